@@ -1,4 +1,4 @@
-use semtext::input::Action;
+use semtext::input::{Action, WidgetId};
 use semtext::widget::Label;
 use semtext::{grid_area, Screen, Widget};
 use std::error::Error;
@@ -16,7 +16,7 @@ async fn async_main() -> Result<(), Box<dyn Error>> {
     let i = Label::new("I").into_button();
     let j = Label::new("J").into_button();
     let k = Label::new("K").into_button();
-    let l = Label::new("L").into_button();
+    let l = Label::new("🦀 Run").into_button();
     let grid = grid_area!(
         [. a e i .]
         [. b f j .]
@@ -24,7 +24,36 @@ async fn async_main() -> Result<(), Box<dyn Error>> {
         [. d h l .]
         [. . . . .]
     )?;
-    while screen.step(&grid).await? != Action::Quit() {}
+    let buttons = [
+        (&a, "A"),
+        (&b, "B"),
+        (&c, "C"),
+        (&d, "D"),
+        (&e, "E"),
+        (&f, "F"),
+        (&g, "G"),
+        (&h, "H"),
+        (&i, "I"),
+        (&j, "J"),
+        (&k, "K"),
+        (&l, "Run"),
+    ];
+    let mut pressed = vec![];
+    loop {
+        match screen.step(&grid).await? {
+            Action::Quit() => break,
+            Action::Activated(id) => {
+                let button =
+                    buttons.iter().find(|(b, _)| WidgetId::of(*b) == id);
+                if let Some((_, name)) = button {
+                    pressed.push(*name);
+                }
+            }
+            _ => (),
+        }
+    }
+    drop(screen);
+    println!("pressed: {}", pressed.join(", "));
     Ok(())
 }
 
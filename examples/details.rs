@@ -0,0 +1,29 @@
+use semtext::input::Action;
+use semtext::widget::{KeyValue, Label};
+use semtext::{grid_area, Screen, Widget};
+use std::error::Error;
+
+async fn async_main() -> Result<(), Box<dyn Error>> {
+    let mut screen = Screen::new()?;
+    screen.set_title("Details")?;
+    let a = Label::new("screen.rs").into_border();
+    let b = KeyValue::new(vec![
+        ("Name".into(), "screen.rs".into()),
+        ("Size".into(), "12 KB".into()),
+        ("Modified".into(), "2022-03-14 09:26".into()),
+        ("Owner".into(), "".into()),
+    ])
+    .with_placeholder("(unknown)")
+    .into_border();
+    let grid = grid_area!(
+        [. . . .]
+        [. a b .]
+        [. . . .]
+    )?;
+    while screen.step(&grid).await? != Action::Quit() {}
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    futures::executor::block_on(async_main())
+}
@@ -0,0 +1,66 @@
+use semtext::input::Action;
+use semtext::widget::{KeyValue, Label};
+use semtext::{grid_area, Screen, Widget};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Length of each slice of work
+const SLICE: Duration = Duration::from_millis(50);
+
+/// Scan directories for one slice, returning the number of files found
+fn scan_slice(dirs: &mut Vec<PathBuf>) -> usize {
+    let start = Instant::now();
+    let mut files = 0;
+    while start.elapsed() < SLICE {
+        let dir = match dirs.pop() {
+            Some(dir) => dir,
+            None => break,
+        };
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                match entry.file_type() {
+                    Ok(tp) if tp.is_dir() => dirs.push(entry.path()),
+                    Ok(_) => files += 1,
+                    Err(_) => (),
+                }
+            }
+        }
+    }
+    files
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = std::env::args().nth(1).unwrap_or_else(|| ".".into());
+    let mut screen = Screen::new()?;
+    screen.set_title("Scan")?;
+    let a = KeyValue::new(vec![
+        ("Files".into(), "0".into()),
+        ("Scanning".into(), root.clone()),
+    ])
+    .into_border();
+    let b = Label::new("Press Esc to cancel");
+    let grid = grid_area!(
+        [. . .]
+        [. a .]
+        [. b .]
+        [. . .]
+    )?;
+    let mut dirs = vec![PathBuf::from(root)];
+    let mut files = 0;
+    // Interleave slices of work with input handling
+    while let Some(dir) = dirs.last() {
+        a.wrapped().set_value("Scanning", &dir.to_string_lossy());
+        files += scan_slice(&mut dirs);
+        a.wrapped().set_value("Files", &files.to_string());
+        if screen.pump(&grid, SLICE)?.contains(&Action::Quit()) {
+            return Ok(());
+        }
+    }
+    a.wrapped().set_value("Scanning", "(done)");
+    futures::executor::block_on(async {
+        while screen.step(&grid).await? != Action::Quit() {}
+        Ok(())
+    })
+}
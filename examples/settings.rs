@@ -0,0 +1,46 @@
+use semtext::input::{Action, WidgetId};
+use semtext::widget::{FieldKind, FieldValue, Form};
+use semtext::{grid_area, Screen, Widget};
+
+async fn async_main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut screen = Screen::new()?;
+    let form = Form::new()
+        .with_field("Name", FieldKind::Text("localhost".into()))
+        .with_validator(|v| match v {
+            FieldValue::Text(t) if t.trim().is_empty() => {
+                Err("Name is required".into())
+            }
+            _ => Ok(()),
+        })
+        .with_field("Port", FieldKind::Number(1..=65535, 8080))
+        .with_field("Enabled", FieldKind::Checkbox(true))
+        .with_buttons(&["OK", "Cancel"])
+        .into_border();
+    let grid = grid_area!(
+        [. . .]
+        [. form .]
+        [. . .]
+    )?;
+    let id = WidgetId::of(form.wrapped());
+    loop {
+        match screen.step(&grid).await? {
+            Action::Quit() => break,
+            Action::Changed(w) if w == id => match form.wrapped().activated() {
+                Some("OK") | Some("Cancel") => break,
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+    drop(screen);
+    if let Some(values) = form.wrapped().values() {
+        println!("Name: {:?}", values.text("Name"));
+        println!("Port: {:?}", values.number("Port"));
+        println!("Enabled: {:?}", values.checkbox("Enabled"));
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    futures::executor::block_on(async_main())
+}
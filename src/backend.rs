@@ -0,0 +1,114 @@
+// backend.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{Action, Event};
+use crate::layout::{Dim, GridArea};
+use crate::text::{TextStyle, Theme};
+use crate::{Result, Screen};
+
+/// Test backend, which draws into memory instead of a terminal
+///
+/// This wraps a [headless](Screen::headless) screen, making the drawn
+/// cells available for layout and snapshot tests.  Widgets draw exactly as
+/// they would on a terminal.
+///
+/// ```rust
+/// # use semtext::layout::Dim;
+/// # use semtext::widget::Label;
+/// # use semtext::{grid_area, TestBackend, Widget};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut backend = TestBackend::new(Dim::new(4, 3));
+/// let a = Label::new("Hi").into_border();
+/// let grid = grid_area!([a])?;
+/// backend.render(&grid)?;
+/// assert_eq!(backend.symbol(0, 0), Some("┌"));
+/// assert_eq!(backend.contents(), "┌──┐\n│Hi│\n└──┘\n");
+/// # Ok(())
+/// # }
+/// ```
+pub struct TestBackend {
+    /// Headless screen
+    screen: Screen,
+}
+
+impl TestBackend {
+    /// Create a new test backend
+    ///
+    /// * `dim`: Dimensions in text cells
+    pub fn new(dim: Dim) -> Self {
+        let screen = Screen::headless(dim, Theme::default());
+        TestBackend { screen }
+    }
+
+    /// Set the style theme
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.screen.set_theme(theme);
+        self
+    }
+
+    /// Get the headless screen
+    pub fn screen(&mut self) -> &mut Screen {
+        &mut self.screen
+    }
+
+    /// Render a grid area
+    pub fn render(&mut self, area: &GridArea<'_>) -> Result<()> {
+        self.screen.render(area)
+    }
+
+    /// Render a grid area and process a sequence of events
+    ///
+    /// See [Screen::step_with_events].
+    pub fn step_with_events(
+        &mut self,
+        area: &GridArea<'_>,
+        events: impl IntoIterator<Item = Event>,
+    ) -> Result<Vec<Action>> {
+        self.screen.step_with_events(area, events)
+    }
+
+    /// Get the plain text of all cells
+    ///
+    /// Each row ends with a newline.
+    pub fn contents(&self) -> String {
+        self.screen.text()
+    }
+
+    /// Get the symbol drawn at a cell
+    ///
+    /// The second half of a wide glyph is an empty string.
+    pub fn symbol(&self, col: u16, row: u16) -> Option<&str> {
+        self.screen.snapshot().cell(col, row).map(|c| c.text())
+    }
+
+    /// Get the text style of a cell
+    pub fn style(&self, col: u16, row: u16) -> Option<TextStyle> {
+        self.screen.snapshot().cell(col, row).map(|c| c.style())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid_area;
+    use crate::text::Color;
+    use crate::widget::Label;
+
+    #[test]
+    fn cells() -> Result<()> {
+        let style = TextStyle::default().with_foreground(Color::Rgb(1, 2, 3));
+        let mut backend = TestBackend::new(Dim::new(3, 1));
+        let a = Label::new("ｗb").with_style(style);
+        let grid = grid_area!([a])?;
+        backend.render(&grid)?;
+        assert_eq!(backend.contents(), "ｗb\n");
+        assert_eq!(backend.symbol(0, 0), Some("ｗ"));
+        assert_eq!(backend.symbol(1, 0), Some(""));
+        assert_eq!(backend.symbol(2, 0), Some("b"));
+        assert_eq!(backend.symbol(3, 0), None);
+        let fg = backend.style(2, 0).unwrap().foreground();
+        assert_eq!(fg, Color::Rgb(1, 2, 3));
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+// cast.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{Event, KeyPress, ModKeys, NavKey};
+use crate::layout::Dim;
+use crate::Result;
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::rc::{Rc, Weak};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Asciicast v2 writer
+struct Cast {
+    /// Cast file writer
+    writer: Box<dyn Write>,
+    /// Time recording started
+    start: Instant,
+    /// Input event recording flag
+    input: bool,
+    /// Recording finished flag
+    finished: bool,
+}
+
+/// Screen recorder, writing [asciicast v2] files
+///
+/// Created by [Screen::record_cast].  Everything written to the terminal is
+/// recorded, with one output event per flush.  Recording stops when the
+/// recorder is dropped, or [finish]ed.
+///
+/// [asciicast v2]: https://docs.asciinema.org/manual/asciicast/v2/
+/// [finish]: CastRecorder::finish
+/// [Screen::record_cast]: crate::Screen::record_cast
+pub struct CastRecorder {
+    /// Shared cast writer
+    cast: Rc<RefCell<Cast>>,
+}
+
+/// Terminal output, optionally recorded
+pub(crate) struct Output<W: Write> {
+    /// Terminal writer
+    inner: W,
+    /// Bytes written since last flush
+    pending: Vec<u8>,
+    /// Cast being recorded
+    cast: Weak<RefCell<Cast>>,
+}
+
+/// Append a JSON string literal
+fn push_json_str(out: &mut String, text: &str) {
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\x00'..='\x1F' | '\x7F' => {
+                let _ = write!(out, "\\u{:04x}", u32::from(ch));
+            }
+            _ => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+/// Get the input bytes of a key press
+fn key_text(key: KeyPress, mods: ModKeys) -> Option<String> {
+    let text = match (key, mods) {
+        (KeyPress::Character(ch), ModKeys::CONTROL)
+            if ch.is_ascii_alphabetic() =>
+        {
+            char::from(ch.to_ascii_lowercase() as u8 - b'a' + 1).to_string()
+        }
+        (KeyPress::Character(ch), _) => ch.to_string(),
+        (KeyPress::Navigation(NavKey::Enter), _) => "\r".to_string(),
+        (KeyPress::Navigation(NavKey::Tab), _) => "\t".to_string(),
+        (KeyPress::Navigation(NavKey::Backspace), _) => "\x7f".to_string(),
+        (KeyPress::Navigation(NavKey::Esc), _) => "\x1b".to_string(),
+        (KeyPress::Navigation(NavKey::Up), _) => "\x1b[A".to_string(),
+        (KeyPress::Navigation(NavKey::Down), _) => "\x1b[B".to_string(),
+        (KeyPress::Navigation(NavKey::Right), _) => "\x1b[C".to_string(),
+        (KeyPress::Navigation(NavKey::Left), _) => "\x1b[D".to_string(),
+        _ => return None,
+    };
+    Some(text)
+}
+
+impl Cast {
+    /// Create a new cast and write its header
+    ///
+    /// * `writer`: Cast file writer
+    /// * `dim`: Terminal dimensions
+    fn new(mut writer: Box<dyn Write>, dim: Dim) -> Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        writeln!(
+            writer,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}}}",
+            dim.width, dim.height, timestamp
+        )?;
+        let start = Instant::now();
+        Ok(Cast {
+            writer,
+            start,
+            input: false,
+            finished: false,
+        })
+    }
+
+    /// Write one event
+    ///
+    /// * `code`: Event type code
+    /// * `data`: Event data
+    fn event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        let secs = self.start.elapsed().as_secs_f64();
+        let mut line = format!("[{:.6}, \"{}\", ", secs, code);
+        push_json_str(&mut line, data);
+        line.push_str("]\n");
+        self.writer.write_all(line.as_bytes())
+    }
+
+    /// Write an output event
+    fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.event("o", &String::from_utf8_lossy(bytes))
+    }
+
+    /// Record an input event
+    fn input(&mut self, ev: Event) -> io::Result<()> {
+        match ev {
+            Event::Resize(dim) => {
+                self.event("r", &format!("{}x{}", dim.width, dim.height))
+            }
+            Event::Key(key, mods) | Event::KeyRepeat(key, mods)
+                if self.input =>
+            {
+                match key_text(key, mods) {
+                    Some(text) => self.event("i", &text),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Finish recording
+    fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        self.writer.flush()
+    }
+}
+
+impl CastRecorder {
+    /// Set whether input key events are recorded
+    ///
+    /// Input is not recorded by default.  Only keys with a simple terminal
+    /// encoding are recorded.
+    pub fn set_record_input(&self, enable: bool) {
+        self.cast.borrow_mut().input = enable;
+    }
+
+    /// Finish recording and flush the cast file
+    pub fn finish(self) -> Result<()> {
+        self.cast.borrow_mut().finish()?;
+        Ok(())
+    }
+}
+
+impl Drop for CastRecorder {
+    fn drop(&mut self) {
+        let _ = self.cast.borrow_mut().finish();
+    }
+}
+
+impl<W: Write> Output<W> {
+    /// Create terminal output
+    pub(crate) fn new(inner: W) -> Self {
+        let pending = vec![];
+        let cast = Weak::new();
+        Output {
+            inner,
+            pending,
+            cast,
+        }
+    }
+
+    /// Start recording a cast
+    ///
+    /// * `writer`: Cast file writer
+    /// * `dim`: Terminal dimensions
+    pub(crate) fn record(
+        &mut self,
+        writer: Box<dyn Write>,
+        dim: Dim,
+    ) -> Result<CastRecorder> {
+        let cast = Rc::new(RefCell::new(Cast::new(writer, dim)?));
+        self.pending.clear();
+        self.cast = Rc::downgrade(&cast);
+        Ok(CastRecorder { cast })
+    }
+
+    /// Record an input event
+    pub(crate) fn record_input(&mut self, ev: Event) -> Result<()> {
+        if let Some(cast) = self.cast.upgrade() {
+            cast.borrow_mut().input(ev)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        if self.cast.strong_count() > 0 {
+            self.pending.extend_from_slice(&buf[..len]);
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(cast) = self.cast.upgrade() {
+            if !self.pending.is_empty() {
+                cast.borrow_mut().output(&self.pending)?;
+            }
+        }
+        self.pending.clear();
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::{cursor, queue, style};
+    use serde_json::Value;
+
+    /// Shared cast file buffer
+    #[derive(Clone, Default)]
+    struct Sink(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Parse a cast file into header and events
+    fn parse(cast: &[u8]) -> (Value, Vec<Value>) {
+        let text = std::str::from_utf8(cast).unwrap();
+        let mut lines = text.lines();
+        let header = serde_json::from_str(lines.next().unwrap()).unwrap();
+        let events = lines.map(|l| serde_json::from_str(l).unwrap()).collect();
+        (header, events)
+    }
+
+    #[test]
+    fn json_str() {
+        let mut out = String::new();
+        push_json_str(&mut out, "a\"b\\c\n\x1b[1m\x7f日");
+        assert_eq!(out, "\"a\\\"b\\\\c\\n\\u001b[1m\\u007f日\"");
+    }
+
+    #[test]
+    fn session() -> Result<()> {
+        let sink = Sink::default();
+        let mut out = Output::new(vec![]);
+        queue!(out, style::Print("not recorded"))?;
+        let rec = out.record(Box::new(sink.clone()), Dim::new(80, 24))?;
+        rec.set_record_input(true);
+        queue!(out, cursor::MoveTo(2, 3), style::Print("Hello, \"cast\"!"))?;
+        out.flush()?;
+        out.record_input(Event::Key(KeyPress::Character('q'), ModKeys::EMPTY))?;
+        queue!(out, style::SetForegroundColor(style::Color::Red))?;
+        queue!(out, style::Print("▶ 日本\n"), cursor::Hide)?;
+        out.flush()?;
+        // Flush with nothing written produces no event
+        out.flush()?;
+        out.record_input(Event::Resize(Dim::new(100, 30)))?;
+        out.record_input(Event::Key(
+            KeyPress::Function(crate::input::FunKey::F1),
+            ModKeys::EMPTY,
+        ))?;
+        rec.finish()?;
+        queue!(out, style::Print("after finish"))?;
+        out.flush()?;
+        let terminal = out.inner.clone();
+        let (header, events) = parse(&sink.0.borrow());
+        assert_eq!(header["version"], 2);
+        assert_eq!(header["width"], 80);
+        assert_eq!(header["height"], 24);
+        assert!(header["timestamp"].is_u64());
+        let mut time = 0.0;
+        let mut output = String::new();
+        let mut codes = String::new();
+        for ev in &events {
+            let ev = ev.as_array().unwrap();
+            assert_eq!(ev.len(), 3);
+            let t = ev[0].as_f64().unwrap();
+            assert!(t >= time);
+            time = t;
+            let code = ev[1].as_str().unwrap();
+            codes.push_str(code);
+            if code == "o" {
+                output.push_str(ev[2].as_str().unwrap());
+            }
+        }
+        assert_eq!(codes, "oior");
+        assert_eq!(events[1][2], "q");
+        assert_eq!(events[3][2], "100x30");
+        // Output events match the terminal bytes after recording started
+        let terminal = String::from_utf8(terminal).unwrap();
+        let recorded = terminal
+            .strip_prefix("not recorded")
+            .unwrap()
+            .strip_suffix("after finish")
+            .unwrap();
+        assert_eq!(output, recorded);
+        Ok(())
+    }
+
+    #[test]
+    fn dropped() -> Result<()> {
+        let sink = Sink::default();
+        let mut out = Output::new(vec![]);
+        let rec = out.record(Box::new(sink.clone()), Dim::new(10, 5))?;
+        queue!(out, style::Print("one"))?;
+        out.flush()?;
+        out.record_input(Event::Key(KeyPress::Character('x'), ModKeys::EMPTY))?;
+        drop(rec);
+        queue!(out, style::Print("two"))?;
+        out.flush()?;
+        let (_header, events) = parse(&sink.0.borrow());
+        // Input is not recorded by default
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0][2], "one");
+        assert!(out.pending.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn keys() {
+        let key = |k, m| key_text(k, m);
+        assert_eq!(key(KeyPress::Character('a'), ModKeys::EMPTY).unwrap(), "a");
+        assert_eq!(
+            key(KeyPress::Character('c'), ModKeys::CONTROL).unwrap(),
+            "\x03"
+        );
+        assert_eq!(
+            key(KeyPress::Navigation(NavKey::Up), ModKeys::EMPTY).unwrap(),
+            "\x1b[A"
+        );
+        assert_eq!(
+            key(KeyPress::Navigation(NavKey::PageUp), ModKeys::EMPTY),
+            None
+        );
+    }
+}
@@ -17,6 +17,17 @@ pub enum Error {
     /// Invalid grid area layout
     InvalidGridArea(),
 
+    /// Grid area too large, with its rows and columns
+    ///
+    /// Grids can have at most 65535 rows and columns.
+    GridTooLarge(usize, usize),
+
+    /// Terminal event stream closed
+    ///
+    /// No more input events can be read, such as when the terminal is
+    /// closed.
+    EventStreamClosed(),
+
     /// I/O error
     Io(io::Error),
 }
@@ -33,6 +44,12 @@ impl fmt::Display for Error {
             Error::InvalidGridArea() => {
                 write!(fmt, "Invalid grid: all widgets must be rectangular")
             }
+            Error::GridTooLarge(rows, cols) => {
+                write!(fmt, "Grid too large: {} rows, {} columns", rows, cols)
+            }
+            Error::EventStreamClosed() => {
+                write!(fmt, "Terminal event stream closed")
+            }
             Error::Io(ref err) => err.fmt(fmt),
         }
     }
@@ -0,0 +1,389 @@
+// export.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+//! Export rendered grid areas as HTML or SVG
+//!
+//! A grid area is rendered once to a headless screen, without using a
+//! terminal.  The exported cells are exactly what would be displayed.
+use crate::layout::{Dim, GridArea};
+use crate::snapshot::Snapshot;
+use crate::text::{display_width, Color, TextStyle, Theme, Weight};
+use crate::{Result, Screen};
+use std::fmt::Write;
+
+/// SVG export style
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgStyle {
+    /// Width of one cell in pixels
+    cell_width: u16,
+    /// Height of one cell in pixels
+    cell_height: u16,
+    /// Font family attribute
+    font_family: String,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle {
+            cell_width: 8,
+            cell_height: 16,
+            font_family: "monospace".to_string(),
+        }
+    }
+}
+
+impl SvgStyle {
+    /// Set the cell size in pixels
+    pub fn with_cell_size(mut self, width: u16, height: u16) -> Self {
+        self.cell_width = width;
+        self.cell_height = height;
+        self
+    }
+
+    /// Set the font family attribute
+    pub fn with_font_family(mut self, font_family: &str) -> Self {
+        self.font_family = font_family.to_string();
+        self
+    }
+}
+
+/// Render a grid area to a snapshot
+fn render(area: &GridArea, dim: Dim, theme: &Theme) -> Result<Snapshot> {
+    let mut screen = Screen::headless(dim, theme.clone());
+    screen.render(area)?;
+    Ok(screen.snapshot().clone())
+}
+
+/// Get the CSS hex value of a color
+fn hex(clr: Color) -> String {
+    let (r, g, b) = clr.approx_rgb();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Get the displayed foreground and background colors of a style
+fn colors(style: TextStyle) -> (String, String) {
+    let fg = hex(style.foreground());
+    let bg = hex(style.background());
+    if style.appearance().reverse() {
+        (bg, fg)
+    } else {
+        (fg, bg)
+    }
+}
+
+/// Get the `text-decoration` value of a style
+fn decoration(style: TextStyle) -> Option<&'static str> {
+    let app = style.appearance();
+    match (app.underline(), app.strikethrough()) {
+        (true, true) => Some("underline line-through"),
+        (true, false) => Some("underline"),
+        (false, true) => Some("line-through"),
+        (false, false) => None,
+    }
+}
+
+/// Get the `font-weight` value of a style
+fn font_weight(style: TextStyle) -> Option<&'static str> {
+    match style.appearance().weight() {
+        Weight::Normal => None,
+        Weight::Bold => Some("bold"),
+        Weight::Thin => Some("lighter"),
+    }
+}
+
+/// Escape text for HTML or XML
+fn escape(out: &mut String, text: &str) {
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(ch),
+        }
+    }
+}
+
+/// Get the inline CSS of a style
+fn css(style: TextStyle) -> String {
+    let (fg, bg) = colors(style);
+    let mut css = format!("color:{};background-color:{}", fg, bg);
+    if let Some(weight) = font_weight(style) {
+        let _ = write!(css, ";font-weight:{}", weight);
+    }
+    if style.appearance().italic() {
+        css.push_str(";font-style:italic");
+    }
+    if let Some(decoration) = decoration(style) {
+        let _ = write!(css, ";text-decoration:{}", decoration);
+    }
+    css
+}
+
+/// Render a grid area to plain text
+///
+/// * `area`: Grid area to render
+/// * `dim`: Dimensions in text cells
+/// * `theme`: Style theme
+///
+/// ## Return
+///
+/// Text of each row, followed by a newline.
+pub fn to_text(area: &GridArea, dim: Dim, theme: &Theme) -> Result<String> {
+    Ok(render(area, dim, theme)?.to_plain())
+}
+
+/// Render a grid area to an HTML fragment
+///
+/// * `area`: Grid area to render
+/// * `dim`: Dimensions in text cells
+/// * `theme`: Style theme
+///
+/// ## Return
+///
+/// A `<pre>` element, containing one `<span>` with inline styles for each
+/// run of cells with the same style.
+pub fn to_html(area: &GridArea, dim: Dim, theme: &Theme) -> Result<String> {
+    let snapshot = render(area, dim, theme)?;
+    let mut html = String::from("<pre>");
+    for row in snapshot.rows() {
+        for (style, text) in Snapshot::runs(row) {
+            let _ = write!(html, "<span style=\"{}\">", css(style));
+            escape(&mut html, &text);
+            html.push_str("</span>");
+        }
+        html.push('\n');
+    }
+    html.push_str("</pre>\n");
+    Ok(html)
+}
+
+/// Render a grid area to an SVG image
+///
+/// * `area`: Grid area to render
+/// * `dim`: Dimensions in text cells
+/// * `theme`: Style theme
+/// * `svg_style`: Cell size and font family
+///
+/// ## Return
+///
+/// An `<svg>` element, with background rectangles and one `<text>` row of
+/// styled `<tspan>` runs for each row of cells.
+pub fn to_svg(
+    area: &GridArea,
+    dim: Dim,
+    theme: &Theme,
+    svg_style: &SvgStyle,
+) -> Result<String> {
+    let snapshot = render(area, dim, theme)?;
+    let dim = snapshot.dim();
+    let cw = u32::from(svg_style.cell_width);
+    let ch = u32::from(svg_style.cell_height);
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"",
+        cw * u32::from(dim.width),
+        ch * u32::from(dim.height),
+    );
+    escape(&mut svg, &svg_style.font_family);
+    let _ = writeln!(svg, "\" font-size=\"{}\">", ch * 3 / 4);
+    for (r, row) in snapshot.rows().enumerate() {
+        let y = ch * r as u32;
+        let runs = Snapshot::runs(row);
+        let mut col = 0;
+        for (style, text) in &runs {
+            let cols = display_width(text) as u32;
+            let (_fg, bg) = colors(*style);
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" \
+                 fill=\"{}\"/>",
+                cw * col,
+                y,
+                cw * cols,
+                ch,
+                bg,
+            );
+            col += cols;
+        }
+        let _ = write!(
+            svg,
+            "<text y=\"{}\" xml:space=\"preserve\">",
+            y + ch * 3 / 4
+        );
+        let mut col = 0;
+        for (style, text) in &runs {
+            let (fg, _bg) = colors(*style);
+            let _ = write!(svg, "<tspan x=\"{}\" fill=\"{}\"", cw * col, fg);
+            if let Some(weight) = font_weight(*style) {
+                let _ = write!(svg, " font-weight=\"{}\"", weight);
+            }
+            if style.appearance().italic() {
+                svg.push_str(" font-style=\"italic\"");
+            }
+            if let Some(decoration) = decoration(*style) {
+                let _ = write!(svg, " text-decoration=\"{}\"", decoration);
+            }
+            svg.push('>');
+            escape(&mut svg, text);
+            svg.push_str("</tspan>");
+            col += display_width(text) as u32;
+        }
+        svg.push_str("</text>\n");
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::text::{Appearance, Intensity};
+    use crate::widget::Label;
+    use crate::{grid_area, Widget};
+
+    /// Strip tags and unescape entities of marked-up text
+    fn strip(markup: &str) -> String {
+        let mut out = String::new();
+        let mut tag = false;
+        for ch in markup.chars() {
+            match ch {
+                '<' => tag = true,
+                '>' if tag => tag = false,
+                _ if !tag => out.push(ch),
+                _ => (),
+            }
+        }
+        out.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&")
+    }
+
+    #[test]
+    fn html_text() -> Result<()> {
+        let a = Label::new("<a & \"b\">").into_button();
+        let b = Label::new("日本").into_button();
+        let grid = grid_area!(
+            [. . .]
+            [. a .]
+            [. b .]
+        )?;
+        let dim = Dim::new(20, 8);
+        let theme = Theme::default();
+        let text = to_text(&grid, dim, &theme)?;
+        assert_eq!(text.lines().count(), 8);
+        assert!(text.contains("<a & \"b\">"));
+        assert!(text.contains("日本"));
+        let html = to_html(&grid, dim, &theme)?;
+        assert!(html.starts_with("<pre>"));
+        assert!(html.contains("&lt;a &amp; &quot;b&quot;&gt;"));
+        assert_eq!(strip(&html), text + "\n");
+        Ok(())
+    }
+
+    #[test]
+    fn svg_text() -> Result<()> {
+        let a = Label::new("x < y").into_button();
+        let grid = grid_area!(
+            [. .]
+            [. a]
+        )?;
+        let dim = Dim::new(12, 4);
+        let theme = Theme::default();
+        let style = SvgStyle::default()
+            .with_cell_size(10, 20)
+            .with_font_family("Fira Mono");
+        let svg = to_svg(&grid, dim, &theme, &style)?;
+        assert!(svg.contains("width=\"120\" height=\"80\""));
+        assert!(svg.contains("font-family=\"Fira Mono\""));
+        let rows: Vec<String> = svg
+            .lines()
+            .filter(|l| l.starts_with("<text"))
+            .map(strip)
+            .collect();
+        let text = to_text(&grid, dim, &theme)?;
+        assert_eq!(rows, text.lines().collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn styles() {
+        let st = TextStyle::default()
+            .with_foreground(Color::Rgb(1, 2, 3))
+            .with_background(Color::Blue(Intensity::Normal));
+        assert_eq!(css(st), "color:#010203;background-color:#0000ee");
+        let app = Appearance::default()
+            .with_weight(Weight::Bold)
+            .with_italic(true)
+            .with_underline(true)
+            .with_strikethrough(true)
+            .with_reverse(true);
+        assert_eq!(
+            css(st.with_appearance(app)),
+            "color:#0000ee;background-color:#010203;font-weight:bold;\
+             font-style:italic;text-decoration:underline line-through"
+        );
+        // Palette values are approximated
+        let st = TextStyle::default()
+            .with_foreground(Color::AnsiValue(198))
+            .with_background(Color::AnsiValue(4));
+        assert_eq!(css(st), "color:#ff0087;background-color:#0000ee");
+    }
+
+    #[test]
+    fn buttons_example() -> Result<()> {
+        let a = Label::new("A").into_button();
+        let b = Label::new("B").into_button();
+        let c = Label::new("C").into_button();
+        let d = Label::new("D").into_button();
+        let e = Label::new("E Wider").into_button();
+        let f = Label::new("F").into_button();
+        let g = Label::new("G").into_button();
+        let h = Label::new("H").into_button();
+        let i = Label::new("I").into_button();
+        let j = Label::new("J").into_button();
+        let k = Label::new("K").into_button();
+        let l = Label::new("🦀 Run").into_button();
+        let grid = grid_area!(
+            [. a e i .]
+            [. b f j .]
+            [. c g k .]
+            [. d h l .]
+            [. . . . .]
+        )?;
+        let dim = Dim::new(40, 16);
+        let theme = Theme::default();
+        let text = to_text(&grid, dim, &theme)?;
+        assert!(text.contains("E Wider"));
+        assert!(text.contains("🦀 Run"));
+        let html = to_html(&grid, dim, &theme)?;
+        assert_eq!(strip(&html), text.clone() + "\n");
+        let svg = to_svg(&grid, dim, &theme, &SvgStyle::default())?;
+        assert_eq!(svg.lines().filter(|l| l.starts_with("<text")).count(), 16);
+        Ok(())
+    }
+
+    #[test]
+    fn ambiguous_width() -> Result<()> {
+        use crate::text::AmbiguousWidth;
+        let a = Label::new("±·α");
+        let b = Label::new("bc");
+        let grid = grid_area!([a b])?;
+        let dim = Dim::new(8, 1);
+        let theme = Theme::default();
+        assert_eq!(a.width_bounds(&theme).minimum(), 3);
+        let narrow = to_text(&grid, dim, &theme)?;
+        let wide = AmbiguousWidth::Wide.with(|| {
+            assert_eq!(a.width_bounds(&theme).minimum(), 6);
+            to_text(&grid, dim, &theme)
+        })?;
+        assert_eq!(narrow, "±·α bc  \n");
+        // Each ambiguous character fills two cells
+        assert_eq!(wide, "±·αbc\n");
+        Ok(())
+    }
+}
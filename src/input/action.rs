@@ -4,13 +4,35 @@
 //
 use crate::input::{KeyPress, ModKeys, NavKey};
 use crate::layout::Dim;
+use crate::Widget;
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Widget identifier
+///
+/// Identifies a widget by its address, which is stable while the widget is
+/// borrowed by a [GridArea].
+///
+/// [GridArea]: ../layout/struct.GridArea.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct WidgetId(usize);
+
+impl WidgetId {
+    /// Get the identifier of a widget
+    pub fn of<W: Widget + ?Sized>(widget: &W) -> Self {
+        WidgetId(widget as *const W as *const () as usize)
+    }
+}
 
 /// Screen actions
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Action {
     /// Terminal resized
+    ///
+    /// Only returned if enabled with [Screen::set_notify_resize].
+    ///
+    /// [Screen::set_notify_resize]: ../struct.Screen.html#method.set_notify_resize
     Resize(Dim),
 
     /// Redraw required
@@ -18,32 +40,478 @@ pub enum Action {
 
     /// Quit application
     Quit(),
+
+    /// Widget value changed by user input
+    ///
+    /// Editable widgets produce at most one of these per input event.
+    /// Changing a value programmatically does not produce one.
+    Changed(WidgetId),
+
+    /// Widget activated by user input, such as a button press
+    Activated(WidgetId),
+
+    /// Rows of a widget reordered by user input
+    ///
+    /// The row at the first index was moved to the second index, so that an
+    /// application can apply the same move to its model.
+    Reordered(WidgetId, usize, usize),
+
+    /// Popup dismissed by a mouse click outside of it
+    ///
+    /// Only returned if enabled with [Popup::with_dismiss].
+    ///
+    /// [Popup::with_dismiss]: ../struct.Popup.html#method.with_dismiss
+    Dismissed(),
+
+    /// Timeout elapsed with no other action
+    ///
+    /// Returned by [Screen::step_timeout], for animation.
+    ///
+    /// [Screen::step_timeout]: ../struct.Screen.html#method.step_timeout
+    Tick(),
+
+    /// Application-defined action, identified by a code
+    Custom(u32),
+}
+
+/// Key binding which must be pressed twice
+struct Confirm {
+    /// Action to confirm
+    action: Action,
+    /// Prompt displayed while armed
+    prompt: String,
+    /// Time allowed for second press
+    timeout: Duration,
+}
+
+/// Armed confirmation
+struct Armed {
+    /// Key press which armed the confirmation
+    key: (KeyPress, ModKeys),
+    /// Time when confirmation disarms
+    deadline: Instant,
 }
 
 /// Key / Action mapping
 ///
 /// A mapping of [KeyPress] events to [Action]s, used for [Screen] hotkeys.
 ///
+/// The default map binds `Esc` to [Action::Quit]; [KeyMap::new] starts
+/// with no bindings.  Keys are offered to the focused widget first, so a
+/// binding only applies when that widget does not handle the key.
+///
+/// Destructive actions can be bound with [bind_confirm], requiring a second
+/// press of the same key to take effect.
+///
+/// ```rust
+/// use semtext::input::{Action, FunKey, KeyMap, KeyPress, ModKeys};
+///
+/// let keymap = KeyMap::new()
+///     .with(KeyPress::Function(FunKey::F1), ModKeys::EMPTY, Action::Custom(1))
+///     .with_char('q', ModKeys::CONTROL, Action::Quit());
+/// ```
+///
 /// [Action]: enum.Action.html
+/// [bind_confirm]: struct.KeyMap.html#method.bind_confirm
 /// [KeyPress]: enum.KeyPress.html
 /// [Screen]: ../struct.Screen.html#method.set_keymap
 pub struct KeyMap {
     /// Mapping of key presses to actions
     map: HashMap<(KeyPress, ModKeys), Action>,
+    /// Mapping of key presses to confirmed actions
+    confirms: HashMap<(KeyPress, ModKeys), Confirm>,
+    /// Currently armed confirmation
+    armed: Option<Armed>,
 }
 
 impl Default for KeyMap {
     fn default() -> Self {
-        let mut map = HashMap::new();
-        let key = (KeyPress::Navigation(NavKey::Esc), ModKeys::Empty);
-        map.insert(key, Action::Quit());
-        Self { map }
+        let esc = KeyPress::Navigation(NavKey::Esc);
+        KeyMap::new().with(esc, ModKeys::EMPTY, Action::Quit())
     }
 }
 
 impl KeyMap {
+    /// Create a new empty key map
+    pub fn new() -> Self {
+        KeyMap {
+            map: HashMap::new(),
+            confirms: HashMap::new(),
+            armed: None,
+        }
+    }
+
+    /// Add a binding
+    ///
+    /// * `key`: Key press
+    /// * `mods`: Modifier keys
+    /// * `action`: Action to produce
+    pub fn with(
+        mut self,
+        key: KeyPress,
+        mods: ModKeys,
+        action: Action,
+    ) -> Self {
+        self.bind(key, mods, action);
+        self
+    }
+
+    /// Add a binding for a character key
+    ///
+    /// * `ch`: Character
+    /// * `mods`: Modifier keys
+    /// * `action`: Action to produce
+    pub fn with_char(self, ch: char, mods: ModKeys, action: Action) -> Self {
+        self.with(KeyPress::Character(ch), mods, action)
+    }
+
+    /// Bind a key to an action, replacing any previous binding
+    ///
+    /// * `key`: Key press
+    /// * `mods`: Modifier keys
+    /// * `action`: Action to produce
+    pub fn bind(&mut self, key: KeyPress, mods: ModKeys, action: Action) {
+        self.confirms.remove(&(key, mods));
+        self.map.insert((key, mods), action);
+    }
+
+    /// Bind a character key to an action, replacing any previous binding
+    ///
+    /// * `ch`: Character
+    /// * `mods`: Modifier keys
+    /// * `action`: Action to produce
+    pub fn bind_char(&mut self, ch: char, mods: ModKeys, action: Action) {
+        self.bind(KeyPress::Character(ch), mods, action);
+    }
+
+    /// Remove a key binding, including a confirmed one
+    ///
+    /// ## Return
+    ///
+    /// The previously bound action, if any.
+    pub fn unbind(&mut self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        let key = (key, mods);
+        if self.armed.as_ref().is_some_and(|a| a.key == key) {
+            self.armed = None;
+        }
+        let confirm = self.confirms.remove(&key).map(|c| c.action);
+        self.map.remove(&key).or(confirm)
+    }
+
     /// Lookup an [Action] from a key event
     pub fn lookup(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
         self.map.get(&(key, mods)).cloned()
     }
+
+    /// Bind a key to an action which must be confirmed
+    ///
+    /// * `key`: Key press
+    /// * `mods`: Modifier keys
+    /// * `action`: Action to confirm
+    /// * `prompt`: Prompt displayed while armed
+    /// * `timeout`: Time allowed for the second press
+    ///
+    /// The first press arms the confirmation, and a second press within the
+    /// timeout produces the action.  Any other key, or the timeout elapsing,
+    /// disarms it.
+    ///
+    /// ```rust
+    /// use semtext::input::{Action, KeyMap, KeyPress, ModKeys};
+    /// use std::time::Duration;
+    ///
+    /// let mut keymap = KeyMap::default();
+    /// keymap.bind_confirm(
+    ///     KeyPress::Character('q'),
+    ///     ModKeys::EMPTY,
+    ///     Action::Quit(),
+    ///     "Press q again to quit",
+    ///     Duration::from_secs(2),
+    /// );
+    /// ```
+    pub fn bind_confirm(
+        &mut self,
+        key: KeyPress,
+        mods: ModKeys,
+        action: Action,
+        prompt: &str,
+        timeout: Duration,
+    ) {
+        let prompt = prompt.to_string();
+        let confirm = Confirm {
+            action,
+            prompt,
+            timeout,
+        };
+        self.map.remove(&(key, mods));
+        self.confirms.insert((key, mods), confirm);
+    }
+
+    /// Handle a key press
+    ///
+    /// * `key`: Key press
+    /// * `mods`: Modifier keys
+    /// * `now`: Current time
+    ///
+    /// Arming or disarming a confirmation produces a redraw, so that its
+    /// prompt can be shown or hidden.
+    pub(crate) fn press(
+        &mut self,
+        key: KeyPress,
+        mods: ModKeys,
+        now: Instant,
+    ) -> Option<Action> {
+        let key = (key, mods);
+        let disarmed = match self.armed.take() {
+            Some(armed) if armed.key == key && now < armed.deadline => {
+                return self.confirms.get(&key).map(|c| c.action);
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if let Some(confirm) = self.confirms.get(&key) {
+            let deadline = now + confirm.timeout;
+            self.armed = Some(Armed { key, deadline });
+            return Some(Action::Redraw());
+        }
+        match self.map.get(&key) {
+            Some(action) => Some(*action),
+            None if disarmed => Some(Action::Redraw()),
+            None => None,
+        }
+    }
+
+    /// Get the prompt of the armed confirmation
+    ///
+    /// * `now`: Current time
+    pub(crate) fn prompt(&self, now: Instant) -> Option<&str> {
+        let armed = self.armed.as_ref().filter(|a| now < a.deadline)?;
+        self.confirms.get(&armed.key).map(|c| c.prompt.as_str())
+    }
+
+    /// Get the deadline of the armed confirmation
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.armed.as_ref().map(|a| a.deadline)
+    }
+
+    /// Disarm a confirmation which has timed out
+    ///
+    /// * `now`: Current time
+    pub(crate) fn expire(&mut self, now: Instant) {
+        if self.armed.as_ref().is_some_and(|a| now >= a.deadline) {
+            self.armed = None;
+        }
+    }
+}
+
+/// Key / Command mapping for a widget
+///
+/// Complex widgets define a command enum, and translate [KeyPress] events
+/// through one of these maps before executing a command.  Applications can
+/// replace a widget's map to rebind its keys.
+///
+/// ```rust
+/// # #[cfg(feature = "widgets-extra")]
+/// # {
+/// use semtext::input::{KeyPress, ModKeys, WidgetKeyMap};
+/// use semtext::widget::ScrollCommand;
+///
+/// let keymap = ScrollCommand::keymap()
+///     .with(KeyPress::Character('j'), ModKeys::EMPTY, ScrollCommand::Down)
+///     .with(KeyPress::Character('k'), ModKeys::EMPTY, ScrollCommand::Up);
+/// # }
+/// ```
+///
+/// [KeyPress]: enum.KeyPress.html
+#[derive(Clone, Debug)]
+pub struct WidgetKeyMap<C> {
+    /// Mapping of key presses to commands
+    map: HashMap<(KeyPress, ModKeys), C>,
+}
+
+impl<C> Default for WidgetKeyMap<C> {
+    fn default() -> Self {
+        let map = HashMap::new();
+        Self { map }
+    }
+}
+
+impl<C: Copy> WidgetKeyMap<C> {
+    /// Create a new empty key map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a binding
+    ///
+    /// * `key`: Key press
+    /// * `mods`: Modifier keys
+    /// * `cmd`: Command to execute
+    pub fn with(mut self, key: KeyPress, mods: ModKeys, cmd: C) -> Self {
+        self.bind(key, mods, cmd);
+        self
+    }
+
+    /// Bind a key to a command, replacing any previous binding
+    pub fn bind(&mut self, key: KeyPress, mods: ModKeys, cmd: C) {
+        self.map.insert((key, mods), cmd);
+    }
+
+    /// Remove a key binding
+    ///
+    /// ## Return
+    ///
+    /// The previously bound command, if any.
+    pub fn unbind(&mut self, key: KeyPress, mods: ModKeys) -> Option<C> {
+        self.map.remove(&(key, mods))
+    }
+
+    /// Lookup a command from a key event
+    pub fn lookup(&self, key: KeyPress, mods: ModKeys) -> Option<C> {
+        self.map.get(&(key, mods)).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::input::FunKey;
+    use crate::widget::Label;
+
+    #[test]
+    fn widget_id() {
+        let a = Label::new("A");
+        let b = Label::new("B");
+        let w: &dyn Widget = &a;
+        assert_eq!(WidgetId::of(&a), WidgetId::of(w));
+        assert_ne!(WidgetId::of(&a), WidgetId::of(&b));
+    }
+
+    fn keymap() -> KeyMap {
+        let mut keymap = KeyMap::default();
+        keymap.bind_confirm(
+            KeyPress::Character('d'),
+            ModKeys::EMPTY,
+            Action::Quit(),
+            "Press d again",
+            Duration::from_secs(2),
+        );
+        keymap.bind_confirm(
+            KeyPress::Character('x'),
+            ModKeys::CONTROL,
+            Action::Redraw(),
+            "Press ^x again",
+            Duration::from_secs(1),
+        );
+        keymap
+    }
+
+    #[test]
+    fn confirm() {
+        let mut km = keymap();
+        let d = KeyPress::Character('d');
+        let t = Instant::now();
+        assert_eq!(km.prompt(t), None);
+        assert_eq!(km.press(d, ModKeys::EMPTY, t), Some(Action::Redraw()));
+        assert_eq!(km.prompt(t), Some("Press d again"));
+        let t = t + Duration::from_millis(1500);
+        assert_eq!(km.press(d, ModKeys::EMPTY, t), Some(Action::Quit()));
+        assert_eq!(km.prompt(t), None);
+        // Next press arms again
+        assert_eq!(km.press(d, ModKeys::EMPTY, t), Some(Action::Redraw()));
+    }
+
+    #[test]
+    fn confirm_timeout() {
+        let mut km = keymap();
+        let d = KeyPress::Character('d');
+        let t = Instant::now();
+        km.press(d, ModKeys::EMPTY, t);
+        let t = t + Duration::from_secs(2);
+        assert_eq!(km.prompt(t), None);
+        assert_eq!(km.deadline(), Some(t));
+        km.expire(t - Duration::from_millis(1));
+        assert_eq!(km.deadline(), Some(t));
+        km.expire(t);
+        assert_eq!(km.deadline(), None);
+        // Expired; this press arms again instead of confirming
+        assert_eq!(km.press(d, ModKeys::EMPTY, t), Some(Action::Redraw()));
+        assert_eq!(km.prompt(t), Some("Press d again"));
+    }
+
+    #[test]
+    fn confirm_cancel() {
+        let mut km = keymap();
+        let d = KeyPress::Character('d');
+        let t = Instant::now();
+        km.press(d, ModKeys::EMPTY, t);
+        // Other key disarms
+        let a = KeyPress::Character('a');
+        assert_eq!(km.press(a, ModKeys::EMPTY, t), Some(Action::Redraw()));
+        assert_eq!(km.prompt(t), None);
+        assert_eq!(km.press(d, ModKeys::EMPTY, t), Some(Action::Redraw()));
+        // Same key with modifiers disarms
+        assert_eq!(km.press(d, ModKeys::ALT, t), Some(Action::Redraw()));
+        assert_eq!(km.press(d, ModKeys::EMPTY, t), Some(Action::Redraw()));
+        // Disarming key still produces its own action
+        let esc = KeyPress::Navigation(NavKey::Esc);
+        assert_eq!(km.press(esc, ModKeys::EMPTY, t), Some(Action::Quit()));
+        assert_eq!(km.press(a, ModKeys::EMPTY, t), None);
+    }
+
+    #[test]
+    fn confirm_replace() {
+        let mut km = keymap();
+        let d = KeyPress::Character('d');
+        let x = KeyPress::Character('x');
+        let t = Instant::now();
+        km.press(d, ModKeys::EMPTY, t);
+        // Arming another confirmation cancels the first
+        assert_eq!(km.press(x, ModKeys::CONTROL, t), Some(Action::Redraw()));
+        assert_eq!(km.prompt(t), Some("Press ^x again"));
+        assert_eq!(km.press(d, ModKeys::EMPTY, t), Some(Action::Redraw()));
+        assert_eq!(km.prompt(t), Some("Press d again"));
+        assert_eq!(km.press(d, ModKeys::EMPTY, t), Some(Action::Quit()));
+    }
+
+    #[test]
+    fn bindings() {
+        let esc = KeyPress::Navigation(NavKey::Esc);
+        let f2 = KeyPress::Function(FunKey::F2);
+        let q = KeyPress::Character('q');
+        let mut km = KeyMap::new()
+            .with(f2, ModKeys::EMPTY, Action::Custom(2))
+            .with_char('q', ModKeys::EMPTY, Action::Quit());
+        // Default Esc binding is not present
+        assert_eq!(km.lookup(esc, ModKeys::EMPTY), None);
+        assert_eq!(km.lookup(f2, ModKeys::EMPTY), Some(Action::Custom(2)));
+        assert_eq!(km.lookup(q, ModKeys::EMPTY), Some(Action::Quit()));
+        assert_eq!(km.lookup(q, ModKeys::ALT), None);
+        km.bind(esc, ModKeys::EMPTY, Action::Custom(9));
+        let t = Instant::now();
+        assert_eq!(km.press(esc, ModKeys::EMPTY, t), Some(Action::Custom(9)));
+        assert_eq!(km.unbind(q, ModKeys::EMPTY), Some(Action::Quit()));
+        assert_eq!(km.press(q, ModKeys::EMPTY, t), None);
+        // Binding replaces a confirmation
+        km.bind_confirm(
+            q,
+            ModKeys::EMPTY,
+            Action::Quit(),
+            "q?",
+            Duration::ZERO,
+        );
+        km.bind_char('q', ModKeys::EMPTY, Action::Redraw());
+        assert_eq!(km.press(q, ModKeys::EMPTY, t), Some(Action::Redraw()));
+    }
+
+    #[test]
+    fn widget_keymap() {
+        let a = KeyPress::Character('a');
+        let mut km = WidgetKeyMap::new().with(a, ModKeys::EMPTY, 1);
+        assert_eq!(km.lookup(a, ModKeys::EMPTY), Some(1));
+        assert_eq!(km.lookup(a, ModKeys::CONTROL), None);
+        km.bind(a, ModKeys::EMPTY, 2);
+        assert_eq!(km.lookup(a, ModKeys::EMPTY), Some(2));
+        assert_eq!(km.unbind(a, ModKeys::EMPTY), Some(2));
+        assert_eq!(km.lookup(a, ModKeys::EMPTY), None);
+    }
 }
@@ -2,7 +2,8 @@
 //
 // Copyright (c) 2020  Douglas P Lau
 //
-use crate::layout::{Dim, Pos};
+use crate::layout::{Dim, ScreenPos};
+use bitflags::bitflags;
 use crossterm::event::Event as CtEvent;
 use crossterm::event::MouseButton as CtMouseButton;
 use crossterm::event::MouseEvent as CtMouseEvent;
@@ -18,6 +19,9 @@ pub enum FocusEvent {
     Take,
 
     /// Mouse hover inside widget bounds
+    ///
+    /// Delivered when the pointer moves or a button is released.  A widget
+    /// should only return an action if its visual state changed.
     HoverInside,
 
     /// Mouse hover outside widget bounds
@@ -76,20 +80,35 @@ pub enum MouseEvent {
     ButtonUp(MouseButton),
     ScrollDown(),
     ScrollUp(),
+    ScrollLeft(),
+    ScrollRight(),
     Drag(Option<MouseButton>),
+    /// Second click in the same place, delivered after its `ButtonUp`
+    DoubleClick(MouseButton),
 }
 
-/// Modifier Keys
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
-pub enum ModKeys {
-    Empty,
-    Control,
-    Alt,
-    ControlAlt,
-    Shift,
-    ControlShift,
-    AltShift,
-    ControlAltShift,
+bitflags! {
+    /// Modifier Keys
+    ///
+    /// Modifiers can be combined, such as `ModKeys::CONTROL | ModKeys::SHIFT`.
+    /// In a `match`, combinations should be checked with a guard, since `|`
+    /// in a pattern means either one.
+    #[derive(Default)]
+    pub struct ModKeys: u8 {
+        /// Control key
+        const CONTROL = 0b0001;
+        /// Alt key
+        const ALT = 0b0010;
+        /// Shift key
+        const SHIFT = 0b0100;
+        /// Super key (not reported by all terminals)
+        const SUPER = 0b1000;
+    }
+}
+
+impl ModKeys {
+    /// No modifier keys pressed
+    pub const EMPTY: Self = Self::empty();
 }
 
 /// Key press event
@@ -101,11 +120,27 @@ pub enum KeyPress {
 }
 
 /// Input event
+///
+/// Events are normally read from the terminal, but they can also be fed to
+/// a [Screen] with [Screen::step_with_events].
+///
+/// Key repeat and release events are not reported by the terminal backend
+/// yet, so they are only produced by feeding events.
+///
+/// [Screen]: ../struct.Screen.html
+/// [Screen::step_with_events]: ../struct.Screen.html#method.step_with_events
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Event {
+    /// Terminal resized
     Resize(Dim),
+    /// Key pressed
     Key(KeyPress, ModKeys),
-    Mouse(MouseEvent, ModKeys, Pos),
+    /// Key auto-repeated while held
+    KeyRepeat(KeyPress, ModKeys),
+    /// Key released
+    KeyRelease(KeyPress, ModKeys),
+    /// Mouse event at a screen position
+    Mouse(MouseEvent, ModKeys, ScreenPos),
 }
 
 impl From<KeyCode> for KeyPress {
@@ -165,38 +200,24 @@ impl From<CtMouseEvent> for MouseEvent {
             Moved => Self::Drag(None),
             ScrollDown => Self::ScrollDown(),
             ScrollUp => Self::ScrollUp(),
+            // FIXME: map ScrollLeft / ScrollRight once crossterm reports them
         }
     }
 }
 
 impl From<KeyModifiers> for ModKeys {
     fn from(mods: KeyModifiers) -> Self {
-        // Can't match on bitflags, yuck...
-        if mods == KeyModifiers::CONTROL {
-            Self::Control
-        } else if mods == KeyModifiers::ALT {
-            Self::Alt
-        } else if mods == KeyModifiers::CONTROL | KeyModifiers::ALT {
-            Self::ControlAlt
-        } else if mods == KeyModifiers::SHIFT {
-            Self::Shift
-        } else if mods == KeyModifiers::CONTROL | KeyModifiers::SHIFT {
-            Self::ControlShift
-        } else if mods == KeyModifiers::ALT | KeyModifiers::SHIFT {
-            Self::AltShift
-        } else if mods
-            == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
-        {
-            Self::ControlAltShift
-        } else {
-            Self::Empty
-        }
+        let mut keys = ModKeys::EMPTY;
+        keys.set(ModKeys::CONTROL, mods.contains(KeyModifiers::CONTROL));
+        keys.set(ModKeys::ALT, mods.contains(KeyModifiers::ALT));
+        keys.set(ModKeys::SHIFT, mods.contains(KeyModifiers::SHIFT));
+        keys
     }
 }
 
-impl From<CtMouseEvent> for Pos {
+impl From<CtMouseEvent> for ScreenPos {
     fn from(ev: CtMouseEvent) -> Self {
-        Pos::new(ev.column, ev.row)
+        ScreenPos::new(ev.column, ev.row)
     }
 }
 
@@ -212,8 +233,24 @@ impl From<CtEvent> for Event {
             Mouse(mev) => Self::Mouse(
                 MouseEvent::from(mev),
                 ModKeys::from(mev.modifiers),
-                Pos::from(mev),
+                ScreenPos::from(mev),
             ),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mod_keys() {
+        let mods = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+        let keys = ModKeys::from(mods);
+        assert_eq!(keys, ModKeys::CONTROL | ModKeys::SHIFT);
+        assert!(keys.contains(ModKeys::SHIFT));
+        assert!(!keys.contains(ModKeys::ALT));
+        assert!(ModKeys::from(KeyModifiers::NONE).is_empty());
+        assert_eq!(ModKeys::default(), ModKeys::EMPTY);
+    }
+}
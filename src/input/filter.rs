@@ -0,0 +1,199 @@
+// filter.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use std::fmt;
+use std::rc::Rc;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Filter for characters entered into text input
+#[derive(Clone)]
+pub enum InputFilter {
+    /// ASCII digits only
+    Numeric,
+    /// Alphabetic or numeric characters only
+    Alphanumeric,
+    /// Any characters except whitespace
+    NoWhitespace,
+    /// Characters accepted by a closure
+    Custom(Rc<dyn Fn(char) -> bool>),
+}
+
+/// Rules constraining text input
+///
+/// Rules only apply to inserted text; navigation and editing keys are
+/// never filtered.
+#[derive(Clone, Debug, Default)]
+pub struct InputRules {
+    /// Maximum length in grapheme clusters
+    max_length: Option<usize>,
+    /// Character filter
+    filter: Option<InputFilter>,
+}
+
+/// Result of inserting text
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Insertion {
+    /// Accepted portion of the inserted text
+    pub text: String,
+    /// Some of the text was rejected (ring the bell)
+    pub rejected: bool,
+}
+
+impl fmt::Debug for InputFilter {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputFilter::Numeric => write!(fmt, "Numeric"),
+            InputFilter::Alphanumeric => write!(fmt, "Alphanumeric"),
+            InputFilter::NoWhitespace => write!(fmt, "NoWhitespace"),
+            InputFilter::Custom(_) => write!(fmt, "Custom(..)"),
+        }
+    }
+}
+
+impl InputFilter {
+    /// Create a custom filter from a closure
+    pub fn custom<F: Fn(char) -> bool + 'static>(f: F) -> Self {
+        InputFilter::Custom(Rc::new(f))
+    }
+
+    /// Check whether a character is accepted
+    pub fn accepts(&self, ch: char) -> bool {
+        match self {
+            InputFilter::Numeric => ch.is_ascii_digit(),
+            InputFilter::Alphanumeric => ch.is_alphanumeric(),
+            InputFilter::NoWhitespace => !ch.is_whitespace(),
+            InputFilter::Custom(f) => f(ch),
+        }
+    }
+}
+
+impl InputRules {
+    /// Set the maximum length, in grapheme clusters
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Set the character filter
+    pub fn with_filter(mut self, filter: InputFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Get the maximum length, in grapheme clusters
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Get the number of grapheme clusters remaining before the maximum
+    ///
+    /// * `text`: Current text
+    pub fn remaining(&self, text: &str) -> Option<usize> {
+        self.max_length
+            .map(|max| max.saturating_sub(text.graphemes(true).count()))
+    }
+
+    /// Insert text, applying the rules
+    ///
+    /// * `text`: Current text
+    /// * `at`: Byte index of insertion point
+    /// * `input`: Typed or pasted text
+    ///
+    /// Rejected characters are dropped, and the rest are truncated to fit
+    /// the maximum length.  A combining character which merges into an
+    /// existing cluster does not add to the length.
+    pub fn insert(&self, text: &str, at: usize, input: &str) -> Insertion {
+        let filtered: String = match &self.filter {
+            Some(filter) => {
+                input.chars().filter(|c| filter.accepts(*c)).collect()
+            }
+            None => input.to_string(),
+        };
+        let mut rejected = filtered.len() < input.len();
+        let (before, after) = text.split_at(at);
+        let mut accepted = String::with_capacity(filtered.len());
+        for g in filtered.graphemes(true) {
+            if let Some(max) = self.max_length {
+                let candidate = format!("{}{}{}{}", before, accepted, g, after);
+                if candidate.graphemes(true).count() > max {
+                    rejected = true;
+                    break;
+                }
+            }
+            accepted.push_str(g);
+        }
+        Insertion {
+            text: accepted,
+            rejected,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Type each character of some text at the end
+    fn type_text(rules: &InputRules, text: &mut String, input: &str) -> usize {
+        let mut bells = 0;
+        for ch in input.chars() {
+            let ins = rules.insert(text, text.len(), &ch.to_string());
+            if ins.rejected {
+                bells += 1;
+            }
+            text.push_str(&ins.text);
+        }
+        bells
+    }
+
+    #[test]
+    fn typing_past_limit() {
+        let rules = InputRules::default().with_max_length(5);
+        let mut text = String::new();
+        assert_eq!(rules.remaining(&text), Some(5));
+        assert_eq!(type_text(&rules, &mut text, "ab👍🏽c"), 0);
+        // Emoji with skin tone modifier is one cluster
+        assert_eq!(rules.remaining(&text), Some(1));
+        assert_eq!(type_text(&rules, &mut text, "def"), 2);
+        assert_eq!(text, "ab👍🏽cd");
+        assert_eq!(rules.remaining(&text), Some(0));
+        // Combining mark joins the last cluster
+        assert_eq!(type_text(&rules, &mut text, "\u{301}"), 0);
+        assert_eq!(text, "ab👍🏽cd\u{301}");
+    }
+
+    #[test]
+    fn paste_mixed() {
+        let rules = InputRules::default()
+            .with_max_length(6)
+            .with_filter(InputFilter::Numeric);
+        let text = "12";
+        let ins = rules.insert(text, 1, "a3 4-5b6789");
+        assert_eq!(ins.text, "3456");
+        assert!(ins.rejected);
+        let text = format!("1{}2", ins.text);
+        assert_eq!(text, "134562");
+        assert_eq!(rules.remaining(&text), Some(0));
+        // Everything valid and fits
+        let rules =
+            InputRules::default().with_filter(InputFilter::NoWhitespace);
+        let ins = rules.insert("", 0, "no-spaces");
+        assert_eq!(ins.text, "no-spaces");
+        assert!(!ins.rejected);
+        assert_eq!(rules.remaining("no-spaces"), None);
+    }
+
+    #[test]
+    fn custom_filter() {
+        let vowels = InputFilter::custom(|c| "aeiou".contains(c));
+        let rules =
+            InputRules::default().with_filter(vowels).with_max_length(3);
+        let mut text = String::new();
+        assert_eq!(type_text(&rules, &mut text, "education"), 6);
+        assert_eq!(text, "eua");
+        assert_eq!(rules.remaining(&text), Some(0));
+        assert!(InputFilter::Alphanumeric.accepts('日'));
+        assert!(!InputFilter::Alphanumeric.accepts('!'));
+    }
+}
@@ -6,9 +6,15 @@
 
 mod action;
 mod event;
+mod filter;
+mod reorder;
+mod selection;
 
-pub use action::{Action, KeyMap};
-pub(crate) use event::Event;
+pub use action::{Action, KeyMap, WidgetId, WidgetKeyMap};
 pub use event::{
-    FocusEvent, FunKey, KeyPress, ModKeys, MouseButton, MouseEvent, NavKey,
+    Event, FocusEvent, FunKey, KeyPress, ModKeys, MouseButton, MouseEvent,
+    NavKey,
 };
+pub use filter::{InputFilter, InputRules, Insertion};
+pub use reorder::{move_item, DragReorder};
+pub use selection::{SelectMode, Selection};
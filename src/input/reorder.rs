@@ -0,0 +1,324 @@
+// reorder.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{KeyPress, ModKeys, MouseButton, MouseEvent, NavKey};
+use crate::text::StyleGroup;
+
+/// Row being dragged
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Drag {
+    /// Index of lifted row
+    from: usize,
+    /// Index the row would be moved to
+    to: usize,
+    /// Pointer row within the view
+    pointer: u16,
+    /// Pointer has moved since the press
+    moved: bool,
+}
+
+/// Drag-and-drop reordering model for list-like widgets
+///
+/// A left button press on a row followed by a drag lifts it; releasing the
+/// button moves it to the row under the pointer.  While lifted, the pointer
+/// resting on the top or bottom row of the view scrolls it by one row per
+/// [tick](DragReorder::tick).  `Esc` cancels the drag.
+///
+/// `Ctrl+Up` / `Ctrl+Down` move a row by one for keyboard users.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DragReorder {
+    /// Number of rows
+    len: usize,
+    /// Current drag
+    drag: Option<Drag>,
+}
+
+/// Move an item within a `Vec`
+///
+/// * `items`: Items to reorder
+/// * `from`: Index of item to move
+/// * `to`: Index of item after moving
+pub fn move_item<T>(items: &mut Vec<T>, from: usize, to: usize) {
+    if from < items.len() && to < items.len() {
+        let item = items.remove(from);
+        items.insert(to, item);
+    }
+}
+
+impl DragReorder {
+    /// Create a new reordering model
+    ///
+    /// * `len`: Number of rows
+    pub fn new(len: usize) -> Self {
+        DragReorder { len, drag: None }
+    }
+
+    /// Set the number of rows
+    ///
+    /// Any drag in progress is cancelled.
+    pub fn set_len(&mut self, len: usize) {
+        self.len = len;
+        self.drag = None;
+    }
+
+    /// Check if a row is lifted
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some_and(|d| d.moved)
+    }
+
+    /// Get the style group for drawing a row
+    ///
+    /// The lifted row is `Interacted`.
+    pub fn row_style(&self, row: usize) -> Option<StyleGroup> {
+        match self.drag {
+            Some(d) if d.moved && d.from == row => Some(StyleGroup::Interacted),
+            _ => None,
+        }
+    }
+
+    /// Get the index the lifted row would be moved to
+    pub fn target(&self) -> Option<usize> {
+        self.drag.filter(|d| d.moved).map(|d| d.to)
+    }
+
+    /// Get the insertion indicator position
+    ///
+    /// ## Return
+    ///
+    /// Index of the row which the indicator line is drawn above (equal to
+    /// the number of rows for below the last), or `None` if the row would
+    /// not move.
+    pub fn indicator(&self) -> Option<usize> {
+        let d = self.drag.filter(|d| d.moved)?;
+        match d.to {
+            to if to < d.from => Some(to),
+            to if to > d.from => Some(to + 1),
+            _ => None,
+        }
+    }
+
+    /// Get the index of the row under a pointer
+    fn row_at(&self, pointer: u16, offset: u16) -> usize {
+        let row = usize::from(offset) + usize::from(pointer);
+        row.min(self.len.saturating_sub(1))
+    }
+
+    /// Handle a mouse event
+    ///
+    /// * `mev`: Mouse event
+    /// * `pointer`: Pointer row within the view
+    /// * `offset`: Row scroll offset of the view
+    ///
+    /// ## Return
+    ///
+    /// Indices `(from, to)` of a completed move.
+    pub fn mouse_event(
+        &mut self,
+        mev: MouseEvent,
+        pointer: u16,
+        offset: u16,
+    ) -> Option<(usize, usize)> {
+        match mev {
+            MouseEvent::ButtonDown(MouseButton::Left) => {
+                let row = usize::from(offset) + usize::from(pointer);
+                self.drag = (row < self.len).then_some(Drag {
+                    from: row,
+                    to: row,
+                    pointer,
+                    moved: false,
+                });
+                None
+            }
+            MouseEvent::Drag(Some(MouseButton::Left)) => {
+                let to = self.row_at(pointer, offset);
+                if let Some(d) = &mut self.drag {
+                    d.moved = d.moved || to != d.from;
+                    d.to = to;
+                    d.pointer = pointer;
+                }
+                None
+            }
+            MouseEvent::ButtonUp(MouseButton::Left) => {
+                let d = self.drag.take()?;
+                (d.moved && d.to != d.from).then_some((d.from, d.to))
+            }
+            _ => None,
+        }
+    }
+
+    /// Auto-scroll while the pointer rests at an edge of the view
+    ///
+    /// * `offset`: Row scroll offset of the view
+    /// * `height`: Height of the view
+    ///
+    /// This should be called periodically during a drag.
+    ///
+    /// ## Return
+    ///
+    /// New scroll offset, if it changed.
+    pub fn tick(&mut self, offset: u16, height: u16) -> Option<u16> {
+        let d = self.drag.filter(|d| d.moved)?;
+        let bottom = usize::from(offset) + usize::from(height);
+        let offset = if d.pointer == 0 && offset > 0 {
+            offset - 1
+        } else if d.pointer + 1 >= height && bottom < self.len {
+            offset + 1
+        } else {
+            return None;
+        };
+        let to = self.row_at(d.pointer, offset);
+        if let Some(d) = &mut self.drag {
+            d.to = to;
+        }
+        Some(offset)
+    }
+
+    /// Cancel a drag
+    ///
+    /// ## Return
+    ///
+    /// `true` if a row was lifted.
+    pub fn cancel(&mut self) -> bool {
+        self.drag.take().is_some_and(|d| d.moved)
+    }
+
+    /// Handle a key event
+    ///
+    /// * `key`: Key pressed
+    /// * `mods`: Modifier keys
+    /// * `row`: Selected row
+    ///
+    /// ## Return
+    ///
+    /// Indices `(from, to)` of a completed move.
+    pub fn key_event(
+        &mut self,
+        key: KeyPress,
+        mods: ModKeys,
+        row: usize,
+    ) -> Option<(usize, usize)> {
+        if row >= self.len {
+            return None;
+        }
+        match (key, mods) {
+            (KeyPress::Navigation(NavKey::Esc), ModKeys::EMPTY) => {
+                self.cancel();
+                None
+            }
+            (KeyPress::Navigation(NavKey::Up), ModKeys::CONTROL) if row > 0 => {
+                Some((row, row - 1))
+            }
+            (KeyPress::Navigation(NavKey::Down), ModKeys::CONTROL)
+                if row + 1 < self.len =>
+            {
+                Some((row, row + 1))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DOWN: MouseEvent = MouseEvent::ButtonDown(MouseButton::Left);
+    const DRAG: MouseEvent = MouseEvent::Drag(Some(MouseButton::Left));
+    const UP: MouseEvent = MouseEvent::ButtonUp(MouseButton::Left);
+
+    /// Run a script of mouse events at pointer rows (with offset)
+    fn script(
+        dr: &mut DragReorder,
+        events: &[(MouseEvent, u16)],
+        offset: u16,
+    ) -> Option<(usize, usize)> {
+        let mut res = None;
+        for (mev, pointer) in events {
+            res = dr.mouse_event(*mev, *pointer, offset);
+        }
+        res
+    }
+
+    #[test]
+    fn drag_with_offset() {
+        let mut items: Vec<_> = (0..10).collect();
+        let mut dr = DragReorder::new(items.len());
+        // View scrolled down by 3: pointer row 1 is item 4
+        let mv = script(&mut dr, &[(DOWN, 1), (DRAG, 2), (DRAG, 0)], 3);
+        assert_eq!(mv, None);
+        assert!(dr.is_dragging());
+        assert_eq!(dr.row_style(4), Some(StyleGroup::Interacted));
+        assert_eq!(dr.row_style(3), None);
+        assert_eq!(dr.indicator(), Some(3));
+        let (from, to) = script(&mut dr, &[(DRAG, 4), (UP, 4)], 3).unwrap();
+        assert_eq!((from, to), (4, 7));
+        move_item(&mut items, from, to);
+        assert_eq!(items, [0, 1, 2, 3, 5, 6, 7, 4, 8, 9]);
+        // Press and release without moving is a click
+        assert_eq!(script(&mut dr, &[(DOWN, 0), (DRAG, 0), (UP, 0)], 3), None);
+        // Dragging back to the start row does not move it
+        let events = [(DOWN, 0), (DRAG, 1), (DRAG, 0), (UP, 0)];
+        assert_eq!(script(&mut dr, &events, 0), None);
+    }
+
+    #[test]
+    fn indicator() {
+        let mut dr = DragReorder::new(4);
+        script(&mut dr, &[(DOWN, 1), (DRAG, 3)], 0);
+        assert_eq!(dr.indicator(), Some(4));
+        script(&mut dr, &[(DRAG, 1)], 0);
+        assert_eq!(dr.indicator(), None);
+        // Past the last row
+        script(&mut dr, &[(DRAG, 9)], 0);
+        assert_eq!(dr.indicator(), Some(4));
+        assert_eq!(script(&mut dr, &[(UP, 9)], 0), Some((1, 3)));
+    }
+
+    #[test]
+    fn auto_scroll() {
+        let mut dr = DragReorder::new(10);
+        let mut offset = 0;
+        script(&mut dr, &[(DOWN, 1), (DRAG, 2), (DRAG, 3)], offset);
+        // Pointer at bottom row of a 4-row view
+        while let Some(off) = dr.tick(offset, 4) {
+            offset = off;
+        }
+        assert_eq!(offset, 6);
+        assert_eq!(dr.indicator(), Some(10));
+        // Back to the top row
+        script(&mut dr, &[(DRAG, 0)], offset);
+        assert_eq!(dr.tick(offset, 4), Some(5));
+        assert_eq!(dr.tick(5, 4), Some(4));
+        assert_eq!(script(&mut dr, &[(UP, 0)], 4), Some((1, 4)));
+        // No scrolling when idle
+        assert_eq!(dr.tick(4, 4), None);
+    }
+
+    #[test]
+    fn cancel() {
+        let mut items: Vec<_> = (0..5).collect();
+        let mut dr = DragReorder::new(items.len());
+        script(&mut dr, &[(DOWN, 0), (DRAG, 3)], 0);
+        let esc = KeyPress::Navigation(NavKey::Esc);
+        assert_eq!(dr.key_event(esc, ModKeys::EMPTY, 0), None);
+        assert!(!dr.is_dragging());
+        assert_eq!(dr.indicator(), None);
+        if let Some((from, to)) = script(&mut dr, &[(UP, 3)], 0) {
+            move_item(&mut items, from, to);
+        }
+        assert_eq!(items, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn keyboard() {
+        let mut dr = DragReorder::new(3);
+        let up = KeyPress::Navigation(NavKey::Up);
+        let down = KeyPress::Navigation(NavKey::Down);
+        assert_eq!(dr.key_event(up, ModKeys::CONTROL, 1), Some((1, 0)));
+        assert_eq!(dr.key_event(up, ModKeys::CONTROL, 0), None);
+        assert_eq!(dr.key_event(down, ModKeys::CONTROL, 1), Some((1, 2)));
+        assert_eq!(dr.key_event(down, ModKeys::CONTROL, 2), None);
+        assert_eq!(dr.key_event(down, ModKeys::EMPTY, 1), None);
+    }
+}
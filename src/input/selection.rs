@@ -0,0 +1,494 @@
+// selection.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{KeyPress, ModKeys, NavKey};
+use crate::text::StyleGroup;
+use std::collections::BTreeSet;
+
+/// Row selection mode
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SelectMode {
+    /// Only the cursor row is selected
+    #[default]
+    Single,
+    /// Any set of rows can be selected
+    Multiple,
+}
+
+/// Row selection model for list-like widgets
+///
+/// There is always a single cursor row (unless there are no rows).  In
+/// [Multiple](SelectMode::Multiple) mode, a separate set of rows is also
+/// selected:
+///
+/// - `Space` or `Ctrl`+click toggles one row
+/// - `Shift` with a movement key (or click) extends a contiguous range from
+///   the anchor, which is the last row moved to or toggled without `Shift`
+/// - `Ctrl+A` selects all rows
+///
+/// A range adds rows when the anchor is selected, and removes them when it
+/// is not.  Rows outside the range keep the state they had when the anchor
+/// was set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+    /// Selection mode
+    mode: SelectMode,
+    /// Number of rows
+    len: usize,
+    /// Cursor row
+    cursor: Option<usize>,
+    /// Anchor row for range extension
+    anchor: Option<usize>,
+    /// Selected rows
+    selected: BTreeSet<usize>,
+    /// Selected rows when the anchor was set
+    base: BTreeSet<usize>,
+    /// Range from the anchor adds rows (or removes them)
+    adds: bool,
+}
+
+/// Remap a row index after removing a row
+fn remap(idx: usize, row: usize) -> Option<usize> {
+    match idx {
+        i if i < row => Some(i),
+        i if i == row => None,
+        i => Some(i - 1),
+    }
+}
+
+/// Remap a set of row indices after removing a row
+fn remap_set(set: &BTreeSet<usize>, row: usize) -> BTreeSet<usize> {
+    set.iter().filter_map(|&i| remap(i, row)).collect()
+}
+
+/// Remap a row index after moving a row
+fn remap_move(idx: usize, from: usize, to: usize) -> usize {
+    match idx {
+        i if i == from => to,
+        i if from < i && i <= to => i - 1,
+        i if to <= i && i < from => i + 1,
+        i => i,
+    }
+}
+
+impl Selection {
+    /// Create a new selection model
+    ///
+    /// * `len`: Number of rows
+    ///
+    /// The cursor starts on the first row.
+    pub fn new(len: usize) -> Self {
+        let cursor = (len > 0).then_some(0);
+        Selection {
+            len,
+            cursor,
+            anchor: cursor,
+            adds: true,
+            ..Default::default()
+        }
+    }
+
+    /// Set the selection mode
+    pub fn with_mode(mut self, mode: SelectMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Get the selection mode
+    pub fn mode(&self) -> SelectMode {
+        self.mode
+    }
+
+    /// Get the number of rows
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if there are no rows
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the cursor row
+    pub fn cursor(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    /// Get the selected rows, in order
+    ///
+    /// In [Single](SelectMode::Single) mode, this is the cursor row.
+    pub fn selections(&self) -> Vec<usize> {
+        match self.mode {
+            SelectMode::Single => self.cursor.into_iter().collect(),
+            SelectMode::Multiple => self.selected.iter().copied().collect(),
+        }
+    }
+
+    /// Check if a row is selected
+    pub fn is_selected(&self, row: usize) -> bool {
+        match self.mode {
+            SelectMode::Single => self.cursor == Some(row),
+            SelectMode::Multiple => self.selected.contains(&row),
+        }
+    }
+
+    /// Get the style group for drawing a row
+    ///
+    /// The cursor row is `Focused`, and other selected rows are `Primary`.
+    /// Unselected rows have no style of their own.
+    pub fn row_style(&self, row: usize) -> Option<StyleGroup> {
+        if self.cursor == Some(row) {
+            Some(StyleGroup::Focused)
+        } else if self.is_selected(row) {
+            Some(StyleGroup::Primary)
+        } else {
+            None
+        }
+    }
+
+    /// Set the anchor at the cursor
+    fn set_anchor(&mut self) {
+        self.anchor = self.cursor;
+        self.base = self.selected.clone();
+        self.adds = true;
+    }
+
+    /// Move the cursor to a row
+    ///
+    /// * `row`: Row to move to (clamped to the last row)
+    /// * `extend`: Extend a range from the anchor (multiple mode only)
+    pub fn move_to(&mut self, row: usize, extend: bool) {
+        if self.len == 0 {
+            return;
+        }
+        self.cursor = Some(row.min(self.len - 1));
+        if extend && self.mode == SelectMode::Multiple {
+            self.extend_range();
+        } else {
+            self.set_anchor();
+        }
+    }
+
+    /// Extend the range from the anchor to the cursor
+    fn extend_range(&mut self) {
+        let (anchor, cursor) = match (self.anchor, self.cursor) {
+            (Some(anchor), Some(cursor)) => (anchor, cursor),
+            _ => return,
+        };
+        let range = anchor.min(cursor)..=anchor.max(cursor);
+        let mut selected = self.base.clone();
+        if self.adds {
+            selected.extend(range);
+        } else {
+            for row in range {
+                selected.remove(&row);
+            }
+        }
+        self.selected = selected;
+    }
+
+    /// Toggle selection of the cursor row
+    ///
+    /// The cursor row becomes the anchor.  In single mode, nothing happens.
+    pub fn toggle(&mut self) {
+        if let (SelectMode::Multiple, Some(cursor)) = (self.mode, self.cursor) {
+            self.toggle_row(cursor);
+        }
+    }
+
+    /// Toggle selection of a row, moving the cursor to it
+    fn toggle_row(&mut self, row: usize) {
+        self.cursor = Some(row);
+        if !self.selected.remove(&row) {
+            self.selected.insert(row);
+        }
+        // A range from this anchor applies its new state
+        self.anchor = Some(row);
+        self.base = self.selected.clone();
+        self.adds = self.selected.contains(&row);
+    }
+
+    /// Select all rows (multiple mode only)
+    pub fn select_all(&mut self) {
+        if self.mode == SelectMode::Multiple {
+            self.selected = (0..self.len).collect();
+            self.set_anchor();
+        }
+    }
+
+    /// Set the selected rows (multiple mode only)
+    ///
+    /// * `rows`: Rows to select; rows past the last row are ignored
+    pub fn set_selected(&mut self, rows: &[usize]) {
+        if self.mode == SelectMode::Multiple {
+            let len = self.len;
+            self.selected = rows.iter().copied().filter(|&r| r < len).collect();
+            self.set_anchor();
+        }
+    }
+
+    /// Clear the selected rows
+    pub fn clear(&mut self) {
+        self.selected.clear();
+        self.set_anchor();
+    }
+
+    /// Remove a row
+    ///
+    /// Indices after the row are shifted down, and the row is dropped from
+    /// the selection.  If the cursor was on it, the cursor stays at the same
+    /// index (or the new last row).
+    pub fn remove(&mut self, row: usize) {
+        if row >= self.len {
+            return;
+        }
+        self.len -= 1;
+        let last = self.len.checked_sub(1);
+        let stay = |idx: usize| remap(idx, row).or(Some(idx)).min(last);
+        self.cursor = self.cursor.and_then(stay);
+        self.anchor = self.anchor.and_then(stay);
+        self.selected = remap_set(&self.selected, row);
+        self.base = remap_set(&self.base, row);
+    }
+
+    /// Move a row
+    ///
+    /// * `from`: Row to move
+    /// * `to`: Index to move it to
+    ///
+    /// Indices between `from` and `to` are shifted toward `from`, so each
+    /// selected row stays selected.  The moved row becomes the cursor row.
+    pub fn move_row(&mut self, from: usize, to: usize) {
+        if from >= self.len || to >= self.len {
+            return;
+        }
+        let remap = |idx: usize| remap_move(idx, from, to);
+        self.cursor = Some(to);
+        self.anchor = self.anchor.map(remap);
+        self.selected = self.selected.iter().map(|&i| remap(i)).collect();
+        self.base = self.base.iter().map(|&i| remap(i)).collect();
+    }
+
+    /// Set the number of rows
+    ///
+    /// Rows past the new length are dropped from the selection.
+    pub fn set_len(&mut self, len: usize) {
+        self.len = len;
+        let last = len.checked_sub(1);
+        self.cursor = self.cursor.or(Some(0)).min(last);
+        self.anchor = self.anchor.or(self.cursor).min(last);
+        self.selected.retain(|&i| i < len);
+        self.base.retain(|&i| i < len);
+    }
+
+    /// Handle a key event
+    ///
+    /// * `key`: Key pressed
+    /// * `mods`: Modifier keys
+    /// * `page`: Number of rows moved by `PageUp` / `PageDown`
+    ///
+    /// ## Return
+    ///
+    /// `true` if the key was handled.
+    pub fn key_event(
+        &mut self,
+        key: KeyPress,
+        mods: ModKeys,
+        page: usize,
+    ) -> bool {
+        let cursor = match self.cursor {
+            Some(cursor) => cursor,
+            None => return false,
+        };
+        let extend = match mods {
+            ModKeys::EMPTY => false,
+            ModKeys::SHIFT => true,
+            ModKeys::CONTROL => match key {
+                KeyPress::Character('a') => {
+                    self.select_all();
+                    return self.mode == SelectMode::Multiple;
+                }
+                _ => return false,
+            },
+            _ => return false,
+        };
+        let row = match key {
+            KeyPress::Navigation(NavKey::Up) => cursor.saturating_sub(1),
+            KeyPress::Navigation(NavKey::Down) => cursor + 1,
+            KeyPress::Navigation(NavKey::PageUp) => {
+                cursor.saturating_sub(page.max(1))
+            }
+            KeyPress::Navigation(NavKey::PageDown) => cursor + page.max(1),
+            KeyPress::Navigation(NavKey::Home) => 0,
+            KeyPress::Navigation(NavKey::End) => self.len - 1,
+            KeyPress::Character(' ') if !extend => {
+                self.toggle();
+                return self.mode == SelectMode::Multiple;
+            }
+            _ => return false,
+        };
+        self.move_to(row, extend);
+        true
+    }
+
+    /// Handle a click on a row
+    ///
+    /// `Shift` extends a range from the anchor, and `Ctrl` toggles the row
+    /// (multiple mode only).
+    pub fn click(&mut self, row: usize, mods: ModKeys) {
+        if row >= self.len {
+            return;
+        }
+        match (self.mode, mods) {
+            (SelectMode::Multiple, ModKeys::CONTROL) => self.toggle_row(row),
+            (_, ModKeys::SHIFT) => self.move_to(row, true),
+            _ => self.move_to(row, false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Navigation key
+    fn nav(nav: NavKey) -> KeyPress {
+        KeyPress::Navigation(nav)
+    }
+
+    /// Run a script of key presses
+    fn script(sel: &mut Selection, keys: &[(KeyPress, ModKeys)]) {
+        for (key, mods) in keys {
+            assert!(sel.key_event(*key, *mods, 5), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn single() {
+        let mut sel = Selection::new(3);
+        assert_eq!(sel.selections(), [0]);
+        assert!(!sel.key_event(KeyPress::Character(' '), ModKeys::EMPTY, 5));
+        sel.key_event(nav(NavKey::Down), ModKeys::SHIFT, 5);
+        assert_eq!(sel.selections(), [1]);
+        sel.click(2, ModKeys::CONTROL);
+        assert_eq!(sel.selections(), [2]);
+        assert_eq!(sel.row_style(2), Some(StyleGroup::Focused));
+        assert!(Selection::new(0).selections().is_empty());
+    }
+
+    #[test]
+    fn range_page_down() {
+        let mut sel = Selection::new(20).with_mode(SelectMode::Multiple);
+        script(
+            &mut sel,
+            &[
+                (nav(NavKey::Down), ModKeys::EMPTY),
+                (nav(NavKey::Down), ModKeys::SHIFT),
+                (nav(NavKey::PageDown), ModKeys::SHIFT),
+            ],
+        );
+        assert_eq!(sel.cursor(), Some(7));
+        assert_eq!(sel.selections(), (1..=7).collect::<Vec<_>>());
+        // Shrinking the range back past the anchor
+        script(
+            &mut sel,
+            &[
+                (nav(NavKey::PageUp), ModKeys::SHIFT),
+                (nav(NavKey::PageUp), ModKeys::SHIFT),
+            ],
+        );
+        assert_eq!(sel.cursor(), Some(0));
+        assert_eq!(sel.selections(), [0, 1]);
+        // Plain movement keeps the set, and moves the anchor
+        script(
+            &mut sel,
+            &[
+                (nav(NavKey::End), ModKeys::EMPTY),
+                (nav(NavKey::Up), ModKeys::SHIFT),
+            ],
+        );
+        assert_eq!(sel.selections(), [0, 1, 18, 19]);
+        assert_eq!(sel.row_style(18), Some(StyleGroup::Focused));
+        assert_eq!(sel.row_style(19), Some(StyleGroup::Primary));
+        assert_eq!(sel.row_style(10), None);
+        script(&mut sel, &[(KeyPress::Character('a'), ModKeys::CONTROL)]);
+        assert_eq!(sel.selections().len(), 20);
+    }
+
+    #[test]
+    fn toggle_in_range() {
+        let mut sel = Selection::new(10).with_mode(SelectMode::Multiple);
+        sel.click(2, ModKeys::EMPTY);
+        sel.click(6, ModKeys::SHIFT);
+        assert_eq!(sel.selections(), [2, 3, 4, 5, 6]);
+        // Toggling a row off makes a deselecting anchor
+        sel.click(4, ModKeys::CONTROL);
+        assert_eq!(sel.selections(), [2, 3, 5, 6]);
+        script(&mut sel, &[(nav(NavKey::Down), ModKeys::SHIFT)]);
+        assert_eq!(sel.selections(), [2, 3, 6]);
+        script(
+            &mut sel,
+            &[
+                (nav(NavKey::Up), ModKeys::SHIFT),
+                (nav(NavKey::Up), ModKeys::SHIFT),
+            ],
+        );
+        assert_eq!(sel.selections(), [2, 5, 6]);
+        // Toggling back on with Space makes a selecting anchor
+        script(
+            &mut sel,
+            &[
+                (KeyPress::Character(' '), ModKeys::EMPTY),
+                (nav(NavKey::Home), ModKeys::SHIFT),
+            ],
+        );
+        assert_eq!(sel.cursor(), Some(0));
+        assert_eq!(sel.selections(), [0, 1, 2, 3, 5, 6]);
+    }
+
+    #[test]
+    fn remove() {
+        let mut sel = Selection::new(6).with_mode(SelectMode::Multiple);
+        sel.click(1, ModKeys::CONTROL);
+        sel.click(3, ModKeys::CONTROL);
+        sel.click(5, ModKeys::CONTROL);
+        sel.remove(3);
+        assert_eq!(sel.len(), 5);
+        assert_eq!(sel.selections(), [1, 4]);
+        // Cursor was past the removed row
+        assert_eq!(sel.cursor(), Some(4));
+        sel.remove(4);
+        assert_eq!(sel.selections(), [1]);
+        assert_eq!(sel.cursor(), Some(3));
+        // Range from the remapped anchor
+        sel.click(1, ModKeys::EMPTY);
+        sel.remove(0);
+        sel.click(2, ModKeys::SHIFT);
+        assert_eq!(sel.selections(), [0, 1, 2]);
+        sel.set_len(2);
+        assert_eq!(sel.selections(), [0, 1]);
+        assert_eq!(sel.cursor(), Some(1));
+        sel.remove(0);
+        sel.remove(0);
+        assert!(sel.is_empty());
+        assert_eq!(sel.cursor(), None);
+        assert!(sel.selections().is_empty());
+        assert!(!sel.key_event(nav(NavKey::Down), ModKeys::EMPTY, 5));
+    }
+
+    #[test]
+    fn move_row() {
+        let mut sel = Selection::new(6).with_mode(SelectMode::Multiple);
+        sel.set_selected(&[0, 2, 5]);
+        sel.move_row(0, 3);
+        assert_eq!(sel.cursor(), Some(3));
+        assert_eq!(sel.selections(), [1, 3, 5]);
+        sel.move_row(5, 1);
+        assert_eq!(sel.cursor(), Some(1));
+        assert_eq!(sel.selections(), [1, 2, 4]);
+        // Rows past the end are not moved
+        sel.move_row(6, 0);
+        assert_eq!(sel.selections(), [1, 2, 4]);
+    }
+}
@@ -0,0 +1,53 @@
+// align.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+/// Horizontal alignment
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum HAlign {
+    /// Align to the left edge
+    Left,
+    /// Center horizontally (extra column on the right)
+    #[default]
+    Center,
+    /// Align to the right edge
+    Right,
+}
+
+/// Vertical alignment
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum VAlign {
+    /// Align to the top edge
+    Top,
+    /// Center vertically (extra row on the bottom)
+    #[default]
+    Middle,
+    /// Align to the bottom edge
+    Bottom,
+}
+
+impl HAlign {
+    /// Get the offset of aligned content
+    ///
+    /// * `extra`: Columns left over after the content
+    pub fn offset(self, extra: u16) -> u16 {
+        match self {
+            HAlign::Left => 0,
+            HAlign::Center => extra / 2,
+            HAlign::Right => extra,
+        }
+    }
+}
+
+impl VAlign {
+    /// Get the offset of aligned content
+    ///
+    /// * `extra`: Rows left over after the content
+    pub fn offset(self, extra: u16) -> u16 {
+        match self {
+            VAlign::Top => 0,
+            VAlign::Middle => extra / 2,
+            VAlign::Bottom => extra,
+        }
+    }
+}
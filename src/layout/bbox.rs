@@ -136,8 +136,8 @@ impl BBox {
         let row = self.top().max(rhs.top());
         let right = self.right().min(rhs.right());
         let bottom = self.bottom().min(rhs.bottom());
-        let width = if right > col { right - col } else { 0 };
-        let height = if bottom > row { bottom - row } else { 0 };
+        let width = right.saturating_sub(col);
+        let height = bottom.saturating_sub(row);
         BBox::new(col, row, width, height)
     }
 
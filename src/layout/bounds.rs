@@ -67,13 +67,15 @@ impl LengthBound {
     }
 
     /// Get the available amount to increase
+    ///
+    /// This is zero if the maximum is not above the minimum.
     pub fn available(self) -> u16 {
-        self.maximum - self.minimum
+        self.maximum.saturating_sub(self.minimum)
     }
 
     /// Increase minimum bound
     pub fn increase(&mut self, amount: u16) {
-        self.minimum += amount;
+        self.minimum = self.minimum.saturating_add(amount);
         self.maximum = self.maximum.max(self.minimum);
     }
 
@@ -117,4 +119,16 @@ mod test {
         assert_eq!(bnd.minimum, 2);
         assert_eq!(bnd.maximum, 3);
     }
+
+    #[test]
+    fn available() {
+        assert_eq!(LengthBound::new(2..5).available(), 3);
+        // Maximum below minimum
+        let (min, max) = (5, 2);
+        let mut bnd = LengthBound::new(min..max);
+        assert_eq!(bnd.available(), 0);
+        bnd.increase(u16::MAX);
+        assert_eq!(bnd.minimum(), u16::MAX);
+        assert_eq!(bnd.available(), 0);
+    }
 }
@@ -2,14 +2,35 @@
 //
 // Copyright (c) 2020-2022  Douglas P Lau
 //
-use crate::layout::{BBox, Pos};
-use crate::text::{Glyph, TextStyle, Theme};
+use crate::input::WidgetId;
+use crate::layout::{BBox, ContentPos, HAlign};
+use crate::screen::FRAME_INTERVAL;
+use crate::text::{
+    display_width, layout_line, visible_span, Direction, Glyph, StyleOverlay,
+    StyledText, TextStyle, Theme, WrapMode,
+};
+#[cfg(feature = "markdown")]
+use crate::text::{Marked, Marks};
 use crate::{Result, Screen};
-use textwrap::wrap;
+use std::borrow::Cow;
+use std::time::Instant;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Saved clip state
+struct ClipState {
+    /// Bounding box of cells
+    bbox: BBox,
+    /// Bounding box of clip area
+    clip: BBox,
+    /// Number of style overlays
+    overlays: usize,
+}
 
 /// Cells of text on a [Screen]
 ///
-/// The cells are in a rectangular area of the screen.
+/// The cells are in a rectangular area of the screen.  One set of cells is
+/// used for drawing all widgets in a frame, with each widget drawn inside
+/// a pushed clip area.
 pub struct Cells<'a> {
     /// Screen containing cells
     screen: &'a mut Screen,
@@ -17,13 +38,32 @@ pub struct Cells<'a> {
     bbox: BBox,
     /// Bounding box of clip area
     clip: BBox,
+    /// Most recently set text style (before overlays)
+    style: Option<TextStyle>,
+    /// Stack of style overlays
+    overlays: Vec<StyleOverlay>,
+    /// Stack of saved clip states
+    clips: Vec<ClipState>,
+    /// Cursor column (screen coordinates)
+    col: u16,
+    /// Cursor row (screen coordinates)
+    row: u16,
 }
 
 impl<'a> Cells<'a> {
     /// Create cells
     pub fn new(screen: &'a mut Screen, bbox: BBox) -> Self {
         let clip = bbox;
-        Self { screen, bbox, clip }
+        Self {
+            screen,
+            bbox,
+            clip,
+            style: None,
+            overlays: Vec::new(),
+            clips: Vec::new(),
+            col: bbox.left(),
+            row: bbox.top(),
+        }
     }
 
     /// Get the width
@@ -36,6 +76,61 @@ impl<'a> Cells<'a> {
         self.clip.height()
     }
 
+    /// Push a clip area
+    ///
+    /// * `inset`: Area relative to the current clip area
+    ///
+    /// Until it is popped, the area becomes the bounding box of the cells.
+    /// Style overlays pushed within the area are removed when it is popped.
+    pub fn push_clip(&mut self, inset: BBox) {
+        self.clips.push(ClipState {
+            bbox: self.bbox,
+            clip: self.clip,
+            overlays: self.overlays.len(),
+        });
+        let col = self.clip.left() + inset.left();
+        let row = self.clip.top() + inset.top();
+        let bbox = BBox::new(col, row, inset.width(), inset.height());
+        self.bbox = self.clip.clip(bbox);
+        self.clip = self.bbox;
+    }
+
+    /// Pop the most recently pushed clip area
+    pub fn pop_clip(&mut self) {
+        if let Some(state) = self.clips.pop() {
+            self.bbox = state.bbox;
+            self.clip = state.clip;
+            self.overlays.truncate(state.overlays);
+        }
+    }
+
+    /// Begin drawing a widget, for style transitions
+    pub(crate) fn begin_widget(&mut self, id: WidgetId) {
+        self.screen.transitions_mut().begin_widget(id);
+    }
+
+    /// End drawing a widget, for style transitions
+    pub(crate) fn end_widget(&mut self) {
+        self.screen.transitions_mut().end_widget();
+    }
+
+    /// End drawing a frame, for style transitions
+    pub(crate) fn end_frame(&mut self) {
+        self.screen.transitions_mut().end_frame();
+    }
+
+    /// Get the number of pushed clip areas
+    pub(crate) fn clip_depth(&self) -> usize {
+        self.clips.len()
+    }
+
+    /// Pop clip areas until only a number remain pushed
+    pub(crate) fn pop_clips_to(&mut self, depth: usize) {
+        while self.clips.len() > depth {
+            self.pop_clip();
+        }
+    }
+
     /// Clip to bounding box
     pub fn clip(&mut self, inset: Option<BBox>) {
         if let Some(inset) = inset {
@@ -50,58 +145,168 @@ impl<'a> Cells<'a> {
     }
 
     /// Fill the cells with a glyph
+    ///
+    /// With a double-width glyph, a leftover column at the right edge is
+    /// filled with a space.
     pub fn fill(&mut self, glyph: &Glyph) -> Result<()> {
         let bbox = self.clip;
-        let fill_width = bbox.width() / glyph.width() as u16;
-        if bbox.height() > 0 && fill_width > 0 {
-            self.move_to(0, 0)?;
-            for row in 0..bbox.height() {
-                self.move_to(0, row)?;
-                for _ in 0..fill_width {
-                    glyph.print(self.screen)?;
-                }
+        let glyph_width = glyph.width() as u16;
+        let fill_width = bbox.width() / glyph_width;
+        let extra = bbox.width() % glyph_width;
+        for row in 0..bbox.height() {
+            self.move_to(0, row)?;
+            for _ in 0..fill_width {
+                glyph.print(self.screen)?;
             }
+            for _ in 0..extra {
+                self.screen.print_char(' ')?;
+            }
+            self.col = bbox.right();
         }
         Ok(())
     }
 
+    /// Get the clip area, in screen coordinates
+    #[cfg(feature = "widgets-extra")]
+    pub(crate) fn screen_clip(&self) -> BBox {
+        self.clip
+    }
+
+    /// Get the bounding box of the whole screen
+    #[cfg(feature = "widgets-extra")]
+    pub(crate) fn screen_bbox(&self) -> BBox {
+        self.screen.bbox()
+    }
+
     /// Get the screen theme
     pub fn theme(&self) -> &Theme {
         self.screen.theme()
     }
 
     /// Set the text style
+    ///
+    /// Any pushed style overlays are merged over the style.
     pub fn set_style(&mut self, st: TextStyle) -> Result<()> {
+        self.style = Some(st);
+        let st = self.screen.transition_style(st);
+        let st = self.overlays.iter().fold(st, |st, ov| ov.merge(st));
         self.screen.set_style(st)
     }
 
+    /// Push a style overlay
+    ///
+    /// The overlay is merged over all styles set until it is popped.
+    pub fn push_style_overlay(&mut self, overlay: StyleOverlay) -> Result<()> {
+        self.overlays.push(overlay);
+        self.restore_style()
+    }
+
+    /// Pop the most recently pushed style overlay
+    pub fn pop_style_overlay(&mut self) -> Result<Option<StyleOverlay>> {
+        let overlay = self.overlays.pop();
+        self.restore_style()?;
+        Ok(overlay)
+    }
+
+    /// Restore the most recently set style, with current overlays
+    fn restore_style(&mut self) -> Result<()> {
+        match self.style {
+            Some(st) => self.set_style(st),
+            None => Ok(()),
+        }
+    }
+
+    /// Ring the bell for these cells
+    ///
+    /// Depending on the screen's [BellMode], this emits an audible bell,
+    /// flashes the cells, or does nothing.  Only one bell is rung per frame.
+    ///
+    /// [BellMode]: ../enum.BellMode.html
+    pub fn bell(&mut self) {
+        self.screen.request_bell(self.bbox);
+    }
+
+    /// Get the time of the frame being drawn
+    ///
+    /// Animations should use this instead of the system clock, so that
+    /// every widget in a frame is drawn at the same time.
+    pub fn frame_time(&self) -> Instant {
+        self.screen.frame_time()
+    }
+
+    /// Request another frame, for an animation in progress
+    ///
+    /// The frame is drawn shortly after this one, even if no event happens.
+    /// Widgets should request a frame on each draw until the animation is
+    /// done.
+    pub fn request_frame(&mut self) {
+        let at = self.screen.frame_time() + FRAME_INTERVAL;
+        self.screen.request_frame(at);
+    }
+
     /// Move cursor to a cell
     pub fn move_to(&mut self, col: u16, row: u16) -> Result<()> {
-        let col = self.clip.left() + col;
-        let row = self.clip.top() + row;
-        self.screen.move_to(col, row)
+        self.col = self.clip.left().saturating_add(col);
+        self.row = self.clip.top().saturating_add(row);
+        self.screen.move_to(self.col, self.row)
     }
 
     /// Move cursor right by a number of columns
     pub fn move_right(&mut self, col: u16) -> Result<()> {
+        self.col = self.col.saturating_add(col);
         self.screen.move_right(col)
     }
 
     /// Print a char at the cursor location
+    ///
+    /// The char is clipped just like [print_str](Cells::print_str).
     pub fn print_char(&mut self, ch: char) -> Result<()> {
-        // FIXME: check width first
-        self.screen.print_char(ch)
+        self.print_str(ch.encode_utf8(&mut [0; 4]))
     }
 
     /// Print a str at the cursor location
+    ///
+    /// Text outside of the clip area is not printed.  A double-width glyph
+    /// which would cross the right edge is replaced with a space.
     pub fn print_str(&mut self, st: &str) -> Result<()> {
-        // FIXME: check width first
-        self.screen.print_str(st)
+        let right = self.clip.right();
+        if self.row < self.clip.top() || self.row >= self.clip.bottom() {
+            self.col = self.col.saturating_add(display_width(st) as u16);
+            return Ok(());
+        }
+        // Start column and byte index of the current run of visible text
+        let mut run: Option<(u16, usize)> = None;
+        for (i, g) in st.grapheme_indices(true) {
+            let width = display_width(g).min(2) as u16;
+            let col = self.col;
+            self.col = col.saturating_add(width);
+            if width == 0 || (col >= self.clip.left() && self.col <= right) {
+                if run.is_none() && width > 0 {
+                    run = Some((col, i));
+                }
+                continue;
+            }
+            if let Some((start, j)) = run.take() {
+                self.screen.move_to(start, self.row)?;
+                self.screen.print_str(&st[j..i])?;
+            }
+            if col >= self.clip.left() && col < right {
+                // Pad the part of a wide glyph inside the clip area
+                self.screen.move_to(col, self.row)?;
+                self.screen.print_char(' ')?;
+            }
+        }
+        if let Some((start, j)) = run {
+            self.screen.move_to(start, self.row)?;
+            self.screen.print_str(&st[j..])?;
+        }
+        Ok(())
     }
 
     /// Print some text
     ///
-    /// Inline styling using Markdown:
+    /// With the `markdown` feature (enabled by default), inline styling
+    /// uses Markdown:
     ///
     /// Text Style        | Markdown
     /// ------------------|---------
@@ -111,18 +316,391 @@ impl<'a> Cells<'a> {
     /// ~~Strikethrough~~ | `~~Strikethrough~~`
     /// <u>Underline</u>  | `<u>Underline</u>`
     /// `Reverse`         | `` `Reverse` ``
-    pub fn print_text(&mut self, text: &str, offset: Pos) -> Result<()> {
-        assert_eq!(offset.col, 0, "FIXME");
+    ///
+    /// Styles of different kinds can be combined.  A marker without a
+    /// matching closing marker is printed as is, as is a marker escaped
+    /// with a backslash, such as `\*`.  An underscore within a word, like
+    /// `snake_case`, is not a marker.  Markers are not counted when
+    /// wrapping, and styles are not applied to right-to-left lines.
+    ///
+    /// The direction of each line is detected from its first strong
+    /// character.
+    pub fn print_text(&mut self, text: &str, offset: ContentPos) -> Result<()> {
+        self.print_text_dir(text, offset, Direction::Auto)
+    }
+
+    /// Print some text with a base direction
+    ///
+    /// * `text`: Text to print
+    /// * `offset`: Content position of the top-left cell
+    /// * `dir`: Base direction of each line
+    ///
+    /// Each wrapped line is reordered for display, and right-to-left lines
+    /// are aligned right.
+    pub fn print_text_dir(
+        &mut self,
+        text: &str,
+        offset: ContentPos,
+        dir: Direction,
+    ) -> Result<()> {
+        self.print_lines(text, offset, dir, None, WrapMode::Wrap)
+    }
+
+    /// Print some text with a base direction and alignment
+    ///
+    /// * `text`: Text to print
+    /// * `offset`: Content position of the top-left cell
+    /// * `dir`: Base direction of each line
+    /// * `align`: Alignment of each line
+    ///
+    /// Each wrapped line is reordered for display, then aligned within the
+    /// width of the cells, regardless of its direction.
+    pub fn print_text_aligned(
+        &mut self,
+        text: &str,
+        offset: ContentPos,
+        dir: Direction,
+        align: HAlign,
+    ) -> Result<()> {
+        self.print_lines(text, offset, dir, Some(align), WrapMode::Wrap)
+    }
+
+    /// Print wrapped lines of text
+    ///
+    /// Without an alignment, right-to-left lines are aligned right.  Lines
+    /// wider than the cells are truncated with an ellipsis.
+    #[cfg(feature = "markdown")]
+    pub(crate) fn print_lines(
+        &mut self,
+        text: &str,
+        offset: ContentPos,
+        dir: Direction,
+        align: Option<HAlign>,
+        mode: WrapMode,
+    ) -> Result<()> {
+        let marked = Marked::parse(text).expand_tabs(self.theme().tab_width());
+        let text = marked.text();
+        self.print_wrapped(text, offset, dir, align, mode, |cells, line| {
+            match line.start {
+                Some(start) if marked.is_marked() => {
+                    cells.print_marked(&marked, start, line.line, &line.txt)
+                }
+                _ => cells.print_str(&line.txt),
+            }
+        })
+    }
+
+    /// Print wrapped lines of plain text
+    ///
+    /// Without the `markdown` feature, markers are printed as is.
+    #[cfg(not(feature = "markdown"))]
+    pub(crate) fn print_lines(
+        &mut self,
+        text: &str,
+        offset: ContentPos,
+        dir: Direction,
+        align: Option<HAlign>,
+        mode: WrapMode,
+    ) -> Result<()> {
+        let text = self.theme().expand_tabs(text).into_owned();
+        self.print_wrapped(&text, offset, dir, align, mode, |cells, line| {
+            cells.print_str(&line.txt)
+        })
+    }
+
+    /// Print wrapped lines of styled text
+    ///
+    /// The style switches at each span boundary, including within a line.
+    pub(crate) fn print_styled(
+        &mut self,
+        styled: &StyledText,
+        offset: ContentPos,
+        dir: Direction,
+        align: Option<HAlign>,
+        mode: WrapMode,
+    ) -> Result<()> {
+        let styled = styled.expand_tabs(self.theme().tab_width());
+        let text = styled.text();
+        self.print_wrapped(text, offset, dir, align, mode, |cells, line| {
+            match line.start {
+                Some(start) => {
+                    cells.print_spans(&styled, start, line.line, &line.txt)
+                }
+                None => cells.print_str(&line.txt),
+            }
+        })
+    }
+
+    /// Wrap text and print each visible line
+    ///
+    /// * `print`: Function to print a line, after moving to its position
+    fn print_wrapped<F>(
+        &mut self,
+        text: &str,
+        offset: ContentPos,
+        dir: Direction,
+        align: Option<HAlign>,
+        mode: WrapMode,
+        mut print: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&mut Self, Line) -> Result<()>,
+    {
         let top = usize::from(offset.row);
-        let width = usize::from(self.width());
+        let width = self.width();
         let height = usize::from(self.height());
-        for (row, txt) in
-            wrap(text, width).iter().skip(top).take(height).enumerate()
-        {
-            let row = row as u16; // limited to u16 by take(height)
-            self.move_to(0, row)?;
-            self.print_str(txt)?;
+        let lines = self.theme().wrap_with(text, width, mode);
+        // Byte index of the end of the previous line within visible text
+        let mut pos = 0;
+        for (i, line) in lines.iter().enumerate().take(top + height) {
+            let start = text[pos..].find(&**line).map(|s| pos + s);
+            if let Some(start) = start {
+                pos = start + line.len();
+            }
+            if i < top {
+                continue;
+            }
+            let row = (i - top) as u16; // limited to u16 by take(height)
+            if offset.col > 0 {
+                // Scrolled horizontally; print the visible span untruncated
+                let (_, txt) = layout_line(line, display_width(line), dir);
+                let Some((col, span)) = visible_span(&txt, offset.col, width)
+                else {
+                    continue;
+                };
+                // Only logical order lines keep their styles
+                let skip = span.as_ptr() as usize - txt.as_ptr() as usize;
+                let start = start.filter(|_| txt == *line).map(|s| s + skip);
+                self.move_to(col, row)?;
+                let txt = Cow::Borrowed(span);
+                print(
+                    self,
+                    Line {
+                        start,
+                        line: span,
+                        txt,
+                    },
+                )?;
+                continue;
+            }
+            let (col, txt) = layout_line(line, usize::from(width), dir);
+            let col = match align {
+                Some(align) => {
+                    // limited to u16 by width
+                    let extra = width - display_width(&txt) as u16;
+                    align.offset(extra)
+                }
+                None => col as u16, // limited to u16 by width
+            };
+            self.move_to(col, row)?;
+            print(self, Line { start, line, txt })?;
+        }
+        Ok(())
+    }
+
+    /// Print one line of marked text
+    ///
+    /// * `marked`: Marked text
+    /// * `start`: Byte index of the line within the marked text
+    /// * `line`: Wrapped line
+    /// * `txt`: Line laid out for display
+    #[cfg(feature = "markdown")]
+    fn print_marked(
+        &mut self,
+        marked: &Marked,
+        start: usize,
+        line: &str,
+        txt: &str,
+    ) -> Result<()> {
+        let base = match self.style {
+            Some(base) => base,
+            None => return self.print_str(txt),
+        };
+        let (visible, rest) = match logical_prefix(line, txt) {
+            Some(prefix) => prefix,
+            None => return self.print_str(txt),
+        };
+        let mut current = Marks::default();
+        let mut run = 0;
+        for (i, _ch) in visible.char_indices() {
+            let marks = marked.marks(start + i);
+            if marks != current {
+                self.print_str(&visible[run..i])?;
+                let app = marks.apply(base.appearance());
+                self.set_style(base.with_appearance(app))?;
+                current = marks;
+                run = i;
+            }
+        }
+        self.print_str(&visible[run..])?;
+        if current != Marks::default() {
+            self.set_style(base)?;
+        }
+        self.print_str(rest)
+    }
+
+    /// Print one line of styled text
+    ///
+    /// * `styled`: Styled text
+    /// * `start`: Byte index of the line within the styled text
+    /// * `line`: Wrapped line
+    /// * `txt`: Line laid out for display
+    pub(crate) fn print_spans(
+        &mut self,
+        styled: &StyledText,
+        start: usize,
+        line: &str,
+        txt: &str,
+    ) -> Result<()> {
+        let base = match self.style {
+            Some(base) => base,
+            None => return self.print_str(txt),
+        };
+        let (visible, rest) = match logical_prefix(line, txt) {
+            Some(prefix) => prefix,
+            None => return self.print_str(txt),
+        };
+        let mut current = None;
+        let mut run = 0;
+        for (i, _ch) in visible.char_indices() {
+            let style = styled.style_at(start + i);
+            if style != current {
+                self.print_str(&visible[run..i])?;
+                self.set_style(style.unwrap_or(base))?;
+                current = style;
+                run = i;
+            }
+        }
+        self.print_str(&visible[run..])?;
+        if current.is_some() {
+            self.set_style(base)?;
+        }
+        self.print_str(rest)
+    }
+}
+
+/// Wrapped line, laid out for display
+struct Line<'a> {
+    /// Byte index of the line within the visible text
+    start: Option<usize>,
+    /// Wrapped line
+    line: &'a str,
+    /// Line laid out for display
+    txt: Cow<'a, str>,
+}
+
+/// Get the part of a laid out line which can be styled
+///
+/// Only the logical order text (or a truncated prefix) is styled.
+///
+/// ## Return
+///
+/// Styled prefix and unstyled rest, or `None` if the line was reordered.
+fn logical_prefix<'a>(line: &str, txt: &'a str) -> Option<(&'a str, &'a str)> {
+    if txt == line {
+        Some((txt, ""))
+    } else {
+        match txt.strip_suffix('…') {
+            Some(prefix) if line.starts_with(prefix) => Some((prefix, "…")),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::export::to_text;
+    use crate::layout::{Cells, ContentPos, Dim, LengthBound};
+    use crate::text::{IntoGlyph, Theme};
+    use crate::widget::Label;
+    use crate::{grid_area, Result, Widget};
+
+    /// Widget printing text without wrapping, in five columns
+    struct Raw(&'static str);
+
+    impl Widget for Raw {
+        fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+            LengthBound::new(5..=5)
+        }
+
+        fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+            LengthBound::new(2..=2)
+        }
+
+        fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+            cells.move_to(0, 0)?;
+            cells.print_str(self.0)?;
+            cells.move_to(1, 1)?;
+            for ch in self.0.chars() {
+                cells.print_char(ch)?;
+            }
+            cells.move_to(0, 2)?;
+            cells.print_str("below")
+        }
+    }
+
+    /// Widget filled with a glyph, over stale content
+    struct Fill(&'static str);
+
+    impl Widget for Fill {
+        fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+            cells.move_to(0, 0)?;
+            cells.print_str(&"x".repeat(cells.width().into()))?;
+            cells.fill(&self.0.into_glyph()?)
+        }
+    }
+
+    #[test]
+    fn fill_wide() -> Result<()> {
+        let theme = Theme::default();
+        for (width, expected) in [(4, "🦀🦀\n"), (5, "🦀🦀 \n")] {
+            // Stale content is covered, even in an odd trailing column
+            let crab = Fill("🦀");
+            let grid = grid_area!([crab])?;
+            assert_eq!(to_text(&grid, Dim::new(width, 1), &theme)?, expected);
+        }
+        Ok(())
+    }
+
+    /// Widget printing text at a fixed offset
+    #[cfg(feature = "markdown")]
+    struct Offset(&'static str, ContentPos);
+
+    #[cfg(feature = "markdown")]
+    impl Widget for Offset {
+        fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+            LengthBound::new(2..=2)
         }
+
+        fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+            cells.print_text(self.0, self.1)
+        }
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn print_offset() -> Result<()> {
+        let theme = Theme::default();
+        let text = "0123456789\nab**cdef**";
+        let dim = Dim::new(5, 2);
+        let down = Offset(text, ContentPos::new(0, 1));
+        let grid = grid_area!([down])?;
+        assert_eq!(to_text(&grid, dim, &theme)?, "56789\nabcde\n");
+        let right = Offset(text, ContentPos::new(2, 1));
+        let grid = grid_area!([right])?;
+        assert_eq!(to_text(&grid, dim, &theme)?, "789  \ncde  \n");
+        Ok(())
+    }
+
+    #[test]
+    fn clip_wide() -> Result<()> {
+        let theme = Theme::default();
+        let raw = Raw("ｗｗｗｗｗ");
+        let b = Label::new("b");
+        let c = Label::new("cccccc");
+        let grid = grid_area!([raw b][c c])?;
+        let text = to_text(&grid, Dim::new(6, 3), &theme)?;
+        assert_eq!(text, "ｗｗ b\n ｗｗ \ncccccc\n");
         Ok(())
     }
 }
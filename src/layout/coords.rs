@@ -0,0 +1,190 @@
+// coords.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::layout::{BBox, Pos};
+use std::ops::Deref;
+
+/// Position relative to the top-left cell of the screen
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(transparent)]
+pub struct ScreenPos(pub Pos);
+
+/// Position relative to the top-left cell of a widget's bounding box
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(transparent)]
+pub struct WidgetPos(pub Pos);
+
+/// Position relative to the top-left cell of a widget's (scrolled) content
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(transparent)]
+pub struct ContentPos(pub Pos);
+
+impl Deref for ScreenPos {
+    type Target = Pos;
+
+    fn deref(&self) -> &Pos {
+        &self.0
+    }
+}
+
+impl Deref for WidgetPos {
+    type Target = Pos;
+
+    fn deref(&self) -> &Pos {
+        &self.0
+    }
+}
+
+impl Deref for ContentPos {
+    type Target = Pos;
+
+    fn deref(&self) -> &Pos {
+        &self.0
+    }
+}
+
+impl ScreenPos {
+    /// Create a new screen position
+    pub fn new(col: u16, row: u16) -> Self {
+        ScreenPos(Pos::new(col, row))
+    }
+
+    /// Get the screen position of the top-left cell of a bounding box
+    pub fn origin(bbox: BBox) -> Self {
+        ScreenPos::new(bbox.left(), bbox.top())
+    }
+}
+
+impl WidgetPos {
+    /// Create a new widget position
+    pub fn new(col: u16, row: u16) -> Self {
+        WidgetPos(Pos::new(col, row))
+    }
+
+    /// Convert from a screen position
+    ///
+    /// * `pos`: Screen position
+    /// * `widget_origin`: Screen position of widget's top-left cell
+    ///
+    /// Positions above or left of the widget are clamped to its edge.
+    pub fn from_screen(pos: ScreenPos, widget_origin: ScreenPos) -> Self {
+        let col = pos.col.saturating_sub(widget_origin.col);
+        let row = pos.row.saturating_sub(widget_origin.row);
+        WidgetPos::new(col, row)
+    }
+
+    /// Convert to a screen position
+    ///
+    /// * `widget_origin`: Screen position of widget's top-left cell
+    pub fn to_screen(self, widget_origin: ScreenPos) -> ScreenPos {
+        let col = self.col.saturating_add(widget_origin.col);
+        let row = self.row.saturating_add(widget_origin.row);
+        ScreenPos::new(col, row)
+    }
+
+    /// Convert from a content position, for a widget filling the content
+    ///
+    /// A wrapped widget which is laid out over the entire content area of
+    /// its container (such as a [ScrollView]) uses content positions as its
+    /// own widget positions.
+    ///
+    /// [ScrollView]: ../widget/struct.ScrollView.html
+    pub fn from_content(pos: ContentPos) -> Self {
+        WidgetPos(pos.0)
+    }
+
+    /// Convert to the position within a nested widget
+    ///
+    /// * `inset`: Bounding box of nested widget, relative to this widget
+    ///
+    /// Positions outside the nested widget are clamped to its nearest edge.
+    pub fn nested(self, inset: BBox) -> Self {
+        let max_col = inset.width().saturating_sub(1);
+        let max_row = inset.height().saturating_sub(1);
+        let col = self.col.saturating_sub(inset.left()).min(max_col);
+        let row = self.row.saturating_sub(inset.top()).min(max_row);
+        WidgetPos::new(col, row)
+    }
+}
+
+impl ContentPos {
+    /// Create a new content position
+    pub fn new(col: u16, row: u16) -> Self {
+        ContentPos(Pos::new(col, row))
+    }
+
+    /// Convert from a widget position
+    ///
+    /// * `pos`: Widget position
+    /// * `scroll_offset`: Content offset of widget's top-left cell
+    pub fn from_widget(pos: WidgetPos, scroll_offset: Pos) -> Self {
+        let col = pos.col.saturating_add(scroll_offset.col);
+        let row = pos.row.saturating_add(scroll_offset.row);
+        ContentPos::new(col, row)
+    }
+
+    /// Convert to a widget position
+    ///
+    /// * `scroll_offset`: Content offset of widget's top-left cell
+    ///
+    /// Positions scrolled above or left of the widget are clamped to its
+    /// edge.
+    pub fn to_widget(self, scroll_offset: Pos) -> WidgetPos {
+        let col = self.col.saturating_sub(scroll_offset.col);
+        let row = self.row.saturating_sub(scroll_offset.row);
+        WidgetPos::new(col, row)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let origin = ScreenPos::new(10, 5);
+        let pos = ScreenPos::new(12, 9);
+        let wp = WidgetPos::from_screen(pos, origin);
+        assert_eq!(wp, WidgetPos::new(2, 4));
+        assert_eq!(wp.to_screen(origin), pos);
+        let offset = Pos::new(0, 20);
+        let cp = ContentPos::from_widget(wp, offset);
+        assert_eq!(cp, ContentPos::new(2, 24));
+        assert_eq!(cp.to_widget(offset), wp);
+        // Clamped outside of widget
+        assert_eq!(
+            WidgetPos::from_screen(ScreenPos::new(3, 3), origin),
+            WidgetPos::new(0, 0)
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let inset = BBox::new(1, 1, 4, 2);
+        assert_eq!(WidgetPos::new(2, 1).nested(inset), WidgetPos::new(1, 0));
+        assert_eq!(WidgetPos::new(0, 0).nested(inset), WidgetPos::new(0, 0));
+        assert_eq!(WidgetPos::new(5, 3).nested(inset), WidgetPos::new(3, 1));
+    }
+
+    #[test]
+    fn border_in_scroll_view() {
+        // Scroll view at screen (10, 5), scrolled down 20 rows; wrapped
+        // border has a 1 cell outline around a label
+        let origin = ScreenPos::new(10, 5);
+        let offset = Pos::new(0, 20);
+        let inset = BBox::new(1, 1, 6, 40);
+        let pos = ScreenPos::new(13, 7);
+        let view = WidgetPos::from_screen(pos, origin);
+        let border =
+            WidgetPos::from_content(ContentPos::from_widget(view, offset));
+        assert_eq!(border, WidgetPos::new(3, 22));
+        let label = border.nested(inset);
+        assert_eq!(label, WidgetPos::new(2, 21));
+        // Back out to the screen
+        let border =
+            WidgetPos::new(label.col + inset.left(), label.row + inset.top());
+        let view = ContentPos(border.0).to_widget(offset);
+        assert_eq!(view.to_screen(origin), pos);
+    }
+}
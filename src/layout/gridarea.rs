@@ -2,25 +2,72 @@
 //
 // Copyright (c) 2020-2022  Douglas P Lau
 //
-use crate::layout::{BBox, LengthBound};
+use crate::input::{Action, FocusEvent, KeyPress, ModKeys, MouseEvent, NavKey};
+use crate::layout::{
+    BBox, Cells, ContentPos, Dim, HAlign, LengthBound, PassCache, ScreenPos,
+    VAlign, WidgetPos,
+};
+use crate::screen::draw_widget;
 use crate::text::Theme;
 use crate::{Error, Result, Widget};
+use std::cell::{Cell, RefCell};
+use std::time::Instant;
 
 /// An item in a [GridArea]
 pub enum GridItem<'a> {
     /// [Widget] grid item
     Widget(&'a dyn Widget),
-    /// [Spacer] grid item
+    /// [Widget] grid item with an application tag
+    ///
+    /// Tags do not affect layout, but are reported by
+    /// [GridArea::tagged_boxes] and [GridArea::widget_at].
+    TaggedWidget(u64, &'a dyn Widget),
+    /// [Spacer] grid item, with an optional weight
+    ///
+    /// A row or column made only of spacers shares leftover space in
+    /// proportion to its weight (1 if `None`).  With a weight of 0, it only
+    /// grows once every other track is full.
     ///
     /// [Spacer]: ../widget/struct.Spacer.html
     Spacer(Option<u8>),
 }
 
+/// Widget bounds cached for a layout pass, keyed by their inputs
+type BoundsCache<K> = PassCache<(K, Vec<LengthBound>)>;
+
+/// Event routing state of a grid area drawn as a widget
+#[derive(Default)]
+struct Routing {
+    /// Cell bounding boxes of widgets from the last draw
+    boxes: RefCell<Vec<BBox>>,
+    /// Visible bounding boxes of widgets from the last draw
+    shown: RefCell<Vec<BBox>>,
+    /// Index of widget with keyboard focus
+    focused: Cell<Option<usize>>,
+    /// Index of widget where a mouse button was pressed
+    pressed: Cell<Option<usize>>,
+}
+
 /// Grid area layout
 ///
 /// A layout of an area divided into a grid, containing a set of borrowed
 /// [Widget]s.  It is used when calling [Screen::step].
 ///
+/// A grid area is also a `Widget`, so it can be nested within a cell of
+/// another grid area.  It can also be wrapped, such as by a [Border] or
+/// [ScrollView]; its widgets are then drawn within the wrapper, and receive
+/// events routed through it.  Focus moves between them with `Tab` /
+/// `BackTab`, before leaving the wrapper.  Tags within a wrapped grid area
+/// are not reported, and their state is not saved in a session.
+///
+/// Widget bounds are cached for each layout pass (see [Theme::pass_id]), so
+/// the content of a widget must not change between calculating its bounds
+/// and drawing it within a pass.  Changes made by event handlers are fine,
+/// since each event is followed by a new pass.
+///
+/// [Border]: ../widget/struct.Border.html
+/// [ScrollView]: ../widget/struct.ScrollView.html
+/// [Theme::pass_id]: ../text/struct.Theme.html#method.pass_id
 /// [Screen::step]: ../struct.Screen.html#method.step
 pub struct GridArea<'a> {
     /// Grid rows
@@ -29,8 +76,51 @@ pub struct GridArea<'a> {
     cols: u16,
     /// `Widget` references, with no duplicates
     widgets: Vec<&'a dyn Widget>,
+    /// Widget tags
+    tags: Vec<Option<u64>>,
+    /// Widget alignments within their cells
+    aligns: Vec<(HAlign, VAlign)>,
     /// Grid bounding boxes for all widgets
     grid_boxes: Vec<BBox>,
+    /// Weights of grid columns, from spacers or fractions
+    col_weights: Vec<u8>,
+    /// Weights of grid rows, from spacers or fractions
+    row_weights: Vec<u8>,
+    /// Gap between grid tracks (columns, rows)
+    gap: Dim,
+    /// Widget width bounds for an area
+    width_cache: BoundsCache<Dim>,
+    /// Widget height bounds for widget widths and an area
+    height_cache: BoundsCache<(Vec<u16>, Dim)>,
+    /// Event routing state, when drawn as a widget
+    routing: Routing,
+}
+
+impl<'a> GridItem<'a> {
+    /// Get the widget of a grid item
+    fn widget(&self) -> Option<&'a dyn Widget> {
+        match self {
+            GridItem::Widget(widget) => Some(*widget),
+            GridItem::TaggedWidget(_, widget) => Some(*widget),
+            GridItem::Spacer(_) => None,
+        }
+    }
+
+    /// Get the spacer weight of a grid item
+    fn weight(&self) -> Option<u8> {
+        match self {
+            GridItem::Spacer(weight) => Some(weight.unwrap_or(1)),
+            _ => None,
+        }
+    }
+
+    /// Get the tag of a grid item
+    fn tag(&self) -> Option<u64> {
+        match self {
+            GridItem::TaggedWidget(tag, _) => Some(*tag),
+            _ => None,
+        }
+    }
 }
 
 impl<'a> GridArea<'a> {
@@ -45,25 +135,156 @@ impl<'a> GridArea<'a> {
     /// # Errors
     ///
     /// [Error::InvalidGridArea] If the length of `grid` is not a multiple of
-    ///                          `rows`, or if any [GridItem] does not form a
-    ///                          rectangular pattern.
+    ///                          `rows`, if any [GridItem] does not form a
+    ///                          rectangular pattern, or if a widget has more
+    ///                          than one tag.
+    ///
+    /// [Error::GridTooLarge] If there would be more than 65535 columns.
     pub fn new(grid: &[GridItem<'a>], rows: u16) -> Result<Self> {
-        let len = grid.len() as u16; // FIXME
-        let cols = len / rows;
-        if cols * rows != len {
+        let len = grid.len();
+        let rows_len = usize::from(rows);
+        if rows_len == 0 || !len.is_multiple_of(rows_len) {
             return Err(Error::InvalidGridArea());
         }
-        let widgets = widgets_unique(grid);
+        let cols = len / rows_len;
+        let cols = u16::try_from(cols)
+            .map_err(|_| Error::GridTooLarge(rows_len, cols))?;
+        Self::with_dims(grid, rows, cols)
+    }
+
+    /// Create a new grid area layout with checked dimensions
+    ///
+    /// This is used by the [grid_area] macro, which checks the row lengths
+    /// at compile time.
+    ///
+    /// # Errors
+    ///
+    /// [Error::InvalidGridArea] If the length of `grid` is not `rows` times
+    ///                          `cols`, or for any reason listed for [new].
+    ///
+    /// [new]: GridArea::new
+    #[doc(hidden)]
+    pub fn with_dims(
+        grid: &[GridItem<'a>],
+        rows: u16,
+        cols: u16,
+    ) -> Result<Self> {
+        if grid.len() != usize::from(rows) * usize::from(cols) {
+            return Err(Error::InvalidGridArea());
+        }
+        let (widgets, tags) = widgets_unique(grid)?;
+        let aligns = vec![(HAlign::Left, VAlign::Top); widgets.len()];
         let mut area = GridArea {
             rows,
             cols,
             widgets,
+            tags,
+            aligns,
             grid_boxes: vec![],
+            col_weights: vec![],
+            row_weights: vec![],
+            gap: Dim::default(),
+            width_cache: PassCache::new(),
+            height_cache: PassCache::new(),
+            routing: Routing::default(),
         };
+        let col = |i: usize| i % usize::from(cols);
+        let row = |i: usize| i / usize::from(cols);
+        area.col_weights = track_weights(grid, cols, col);
+        area.row_weights = track_weights(grid, rows, row);
         area.grid_boxes = area.calculate_grid_boxes(grid)?;
         Ok(area)
     }
 
+    /// Set the gap between grid tracks
+    ///
+    /// * `cols`: Columns of spacing between grid columns
+    /// * `rows`: Rows of spacing between grid rows
+    ///
+    /// Widgets spanning more than one track include the gaps between them.
+    /// Gaps which do not fit within the area are dropped.
+    pub fn with_gap(mut self, cols: u16, rows: u16) -> Self {
+        self.gap = Dim::new(cols, rows);
+        self
+    }
+
+    /// Set fractions of leftover width for grid columns
+    ///
+    /// * `fractions`: Weight of each column, starting from the left
+    ///
+    /// Once all minimum widths are met, columns grow in proportion to their
+    /// fractions; `[3, 7]` gives the first column 30% of the width.
+    /// Columns past the end of `fractions` keep their spacer weights.
+    pub fn with_column_fractions(mut self, fractions: &[u8]) -> Self {
+        for (w, fr) in self.col_weights.iter_mut().zip(fractions) {
+            *w = *fr;
+        }
+        self
+    }
+
+    /// Set fractions of leftover height for grid rows
+    ///
+    /// * `fractions`: Weight of each row, starting from the top
+    ///
+    /// Rows past the end of `fractions` keep their spacer weights.
+    pub fn with_row_fractions(mut self, fractions: &[u8]) -> Self {
+        for (w, fr) in self.row_weights.iter_mut().zip(fractions) {
+            *w = *fr;
+        }
+        self
+    }
+
+    /// Set the alignment of a widget within its cells
+    ///
+    /// * `widget`: Widget in the grid area
+    /// * `halign`: Horizontal alignment
+    /// * `valign`: Vertical alignment
+    ///
+    /// This only matters when the cells are larger than the widget's maximum
+    /// bounds; by default, widgets are aligned to the top-left.  Centering
+    /// puts any odd column on the right, and any odd row on the bottom.  If
+    /// the widget is not in the grid area, this has no effect.
+    pub fn with_align(
+        mut self,
+        widget: &dyn Widget,
+        halign: HAlign,
+        valign: VAlign,
+    ) -> Self {
+        if let Some(i) =
+            self.widgets.iter().position(|w| widget_is_same(*w, widget))
+        {
+            self.aligns[i] = (halign, valign);
+        }
+        self
+    }
+
+    /// Get the minimum size needed to lay out all widgets
+    ///
+    /// * `theme`: Style theme
+    ///
+    /// Lengths given as a percentage of the area count as zero.
+    pub fn min_size(&self, theme: &Theme) -> Dim {
+        let width = self.width_bounds_in(theme, Dim::default()).minimum();
+        let area = Dim::new(width, 0);
+        let height = self.height_bounds_in(theme, width, area).minimum();
+        Dim::new(width, height)
+    }
+
+    /// Get the gap between tracks which fits within an area
+    fn fit_gap(&self, area: Dim) -> Dim {
+        let fit = |gap: u16, tracks: u16, total: u16| {
+            if gap_total(gap, tracks) <= total {
+                gap
+            } else {
+                0
+            }
+        };
+        Dim::new(
+            fit(self.gap.width, self.cols, area.width),
+            fit(self.gap.height, self.rows, area.height),
+        )
+    }
+
     /// Calculate widget bounding boxes in grid units
     fn calculate_grid_boxes(&self, grid: &[GridItem]) -> Result<Vec<BBox>> {
         let mut grid_boxes = Vec::new();
@@ -85,12 +306,13 @@ impl<'a> GridArea<'a> {
         let mut right = u16::MIN;
         let mut count = 0;
         for (i, item) in grid.iter().enumerate() {
-            if let GridItem::Widget(w) = item {
-                if widget_is_same(*w, widget) {
-                    let row = i as u16 / self.cols;
+            if let Some(w) = item.widget() {
+                if widget_is_same(w, widget) {
+                    // in range, since rows and columns are u16
+                    let row = (i / usize::from(self.cols)) as u16;
                     top = top.min(row);
                     bottom = bottom.max(row);
-                    let col = i as u16 % self.cols;
+                    let col = (i % usize::from(self.cols)) as u16;
                     left = left.min(col);
                     right = right.max(col);
                     count += 1;
@@ -100,7 +322,7 @@ impl<'a> GridArea<'a> {
         if count > 0 {
             let width = right - left + 1;
             let height = bottom - top + 1;
-            if count == width * height {
+            if count == usize::from(width) * usize::from(height) {
                 return Ok(BBox::new(left, top, width, height));
             }
         }
@@ -108,42 +330,169 @@ impl<'a> GridArea<'a> {
     }
 
     /// Calculate bounding boxes for the widgets
+    ///
+    /// An empty bounding box produces an empty layout.
     pub(crate) fn widget_boxes(
         &self,
         bbox: BBox,
         theme: &Theme,
     ) -> Vec<(&'a dyn Widget, BBox)> {
+        if bbox.dim().is_empty() {
+            return vec![];
+        }
         let boxes = self.calculate_cell_boxes(bbox, theme);
         let mut wb = vec![];
         for (widget, bbox) in self.widgets.iter().zip(boxes) {
-            wb.push((*widget, bbox));
+            match widget.nested_area() {
+                Some(area) => wb.extend(area.widget_boxes(bbox, theme)),
+                None => wb.push((*widget, bbox)),
+            }
         }
         wb
     }
 
+    /// Get all widgets in layout order, including those in nested areas
+    pub(crate) fn leaf_widgets(&self) -> Vec<&'a dyn Widget> {
+        let mut leaves = vec![];
+        for widget in &self.widgets {
+            match widget.nested_area() {
+                Some(area) => leaves.extend(area.leaf_widgets()),
+                None => leaves.push(*widget),
+            }
+        }
+        leaves
+    }
+
+    /// Get the index of a widget
+    ///
+    /// * `widget`: Widget to find
+    ///
+    /// Widgets are indexed in layout order, which is also focus order, with
+    /// nested areas flattened.  This can be used with the [WidgetId] of an
+    /// [Action] to tell which widget produced it.
+    ///
+    /// ## Return
+    ///
+    /// The index, or `None` if the widget is not in the grid area.
+    ///
+    /// [Action]: ../input/enum.Action.html
+    /// [WidgetId]: ../input/struct.WidgetId.html
+    pub fn widget_index(&self, widget: &dyn Widget) -> Option<usize> {
+        self.leaf_widgets()
+            .iter()
+            .position(|w| widget_is_same(*w, widget))
+    }
+
+    /// Get the tagged widgets, including those in nested areas
+    pub(crate) fn tagged_widgets(&self) -> Vec<(u64, &'a dyn Widget)> {
+        let mut tagged = vec![];
+        for (widget, tag) in self.widgets.iter().zip(&self.tags) {
+            if let Some(tag) = tag {
+                tagged.push((*tag, *widget));
+            }
+            if let Some(area) = widget.nested_area() {
+                tagged.extend(area.tagged_widgets());
+            }
+        }
+        tagged
+    }
+
+    /// Calculate bounding boxes for tagged widgets
+    ///
+    /// * `bbox`: Bounding box of grid area
+    /// * `theme`: Style theme
+    pub fn tagged_boxes(&self, bbox: BBox, theme: &Theme) -> Vec<(u64, BBox)> {
+        let boxes = self.calculate_cell_boxes(bbox, theme);
+        let mut tagged = vec![];
+        for ((widget, tag), bbox) in
+            self.widgets.iter().zip(&self.tags).zip(boxes)
+        {
+            if let Some(tag) = tag {
+                tagged.push((*tag, bbox));
+            }
+            if let Some(area) = widget.nested_area() {
+                tagged.extend(area.tagged_boxes(bbox, theme));
+            }
+        }
+        tagged
+    }
+
+    /// Find the widget at a position
+    ///
+    /// * `bbox`: Bounding box of grid area
+    /// * `theme`: Style theme
+    /// * `pos`: Screen position to check
+    ///
+    /// ## Return
+    ///
+    /// The widget and its tag (if any)
+    pub fn widget_at(
+        &self,
+        bbox: BBox,
+        theme: &Theme,
+        pos: ScreenPos,
+    ) -> Option<(&'a dyn Widget, Option<u64>)> {
+        let boxes = self.calculate_cell_boxes(bbox, theme);
+        let i = boxes.iter().position(|b| b.contains(*pos))?;
+        match self.widgets[i].nested_area() {
+            Some(area) => area.widget_at(boxes[i], theme, pos),
+            None => Some((self.widgets[i], self.tags[i])),
+        }
+    }
+
     /// Calculate cell bounding boxes for all widgets
     fn calculate_cell_boxes(&self, bx: BBox, theme: &Theme) -> Vec<BBox> {
-        let width_bounds = self.width_bounds(theme);
-        let columns = self.grid_columns(&width_bounds[..], bx);
-        let height_bounds = self.height_bounds(theme, &columns[..]);
-        let rows = self.grid_rows(&height_bounds[..], bx);
+        let gap = self.fit_gap(bx.dim());
+        let width_bounds = self.widget_width_bounds(theme, bx.dim());
+        let width = bx.width() - gap_total(gap.width, self.cols);
+        let columns = self.grid_columns(&width_bounds[..], width);
+        let height_bounds =
+            self.widget_height_bounds(theme, &columns[..], gap.width, bx.dim());
+        let height = bx.height() - gap_total(gap.height, self.rows);
+        let rows = self.grid_rows(&height_bounds[..], height);
         self.grid_boxes
             .iter()
             .zip(width_bounds)
             .zip(height_bounds)
-            .map(|((gb, wb), hb)| {
-                widget_cell_bbox(bx, *gb, wb, &columns[..], hb, &rows[..])
+            .zip(&self.aligns)
+            .map(|(((gb, wb), hb), align)| {
+                let tracks = (&columns[..], &rows[..]);
+                let cbx = widget_cell_bbox(bx, *gb, tracks, gap);
+                align_bbox(cbx, wb, hb, *align)
             })
             .collect()
     }
 
     /// Calculate the width bounds for all widgets
-    fn width_bounds(&self, theme: &Theme) -> Vec<LengthBound> {
-        self.widgets.iter().map(|w| w.width_bounds(theme)).collect()
+    ///
+    /// Bounds are cached for the layout pass, since nested areas are asked
+    /// for their bounds more than once.
+    fn widget_width_bounds(
+        &self,
+        theme: &Theme,
+        area: Dim,
+    ) -> Vec<LengthBound> {
+        self.width_cache
+            .get_or_compute_for(theme.pass_id(), area, || {
+                self.widgets
+                    .iter()
+                    .map(|w| w.width_bounds_in(theme, area))
+                    .collect()
+            })
     }
 
     /// Calculate grid column widths
-    fn grid_columns(&self, width_bounds: &[LengthBound], bx: BBox) -> Vec<u16> {
+    fn grid_columns(
+        &self,
+        width_bounds: &[LengthBound],
+        width: u16,
+    ) -> Vec<u16> {
+        let col_bounds = self.column_bounds(width_bounds);
+        distribute_bounds(col_bounds, &self.col_weights, width)
+    }
+
+    /// Calculate bounds for each grid column
+    fn column_bounds(&self, width_bounds: &[LengthBound]) -> Vec<LengthBound> {
         // Bounds for each grid column
         let mut col_bounds = vec![LengthBound::default(); self.cols.into()];
         let mut done = 0; // number of widgets completed
@@ -160,27 +509,49 @@ impl<'a> GridArea<'a> {
             }
             grid_width += 1;
         }
-        distribute_bounds(col_bounds, bx.width())
+        col_bounds
     }
 
     /// Calculate the height bounds for all widgets
-    fn height_bounds(&self, theme: &Theme, cols: &[u16]) -> Vec<LengthBound> {
+    fn widget_height_bounds(
+        &self,
+        theme: &Theme,
+        cols: &[u16],
+        gap: u16,
+        area: Dim,
+    ) -> Vec<LengthBound> {
         let widths: Vec<u16> = self
             .grid_boxes
             .iter()
             .map(|gb| {
-                cols[gb.left() as usize..gb.right() as usize].iter().sum()
+                let width = span_len(cols, gb.left(), gb.right(), gap);
+                // Widgets wider than the area are clipped to it
+                width.min(area.width)
             })
             .collect();
-        self.widgets
-            .iter()
-            .zip(widths)
-            .map(|(w, wd)| w.height_bounds(theme, wd))
-            .collect()
+        let key = (widths.clone(), area);
+        self.height_cache
+            .get_or_compute_for(theme.pass_id(), key, || {
+                self.widgets
+                    .iter()
+                    .zip(widths)
+                    .map(|(w, wd)| w.height_bounds_in(theme, wd, area))
+                    .collect()
+            })
     }
 
     /// Calculate grid row heights
-    fn grid_rows(&self, height_bounds: &[LengthBound], bx: BBox) -> Vec<u16> {
+    fn grid_rows(
+        &self,
+        height_bounds: &[LengthBound],
+        height: u16,
+    ) -> Vec<u16> {
+        let row_bounds = self.row_bounds(height_bounds);
+        distribute_bounds(row_bounds, &self.row_weights, height)
+    }
+
+    /// Calculate bounds for each grid row
+    fn row_bounds(&self, height_bounds: &[LengthBound]) -> Vec<LengthBound> {
         // Bounds for each grid row
         let mut row_bounds = vec![LengthBound::default(); self.rows.into()];
         let mut done = 0; // number of widgets completed
@@ -197,26 +568,345 @@ impl<'a> GridArea<'a> {
             }
             grid_height += 1;
         }
-        distribute_bounds(row_bounds, bx.height())
+        row_bounds
+    }
+
+    /// Get the index of the focused widget, when drawn as a widget
+    fn focused_index(&self) -> Option<usize> {
+        self.routing
+            .focused
+            .get()
+            .filter(|i| self.widgets.get(*i).is_some_and(|w| w.focusable()))
+    }
+
+    /// Move focus with an unhandled `Tab` / `BackTab` key
+    ///
+    /// * `key`: Key pressed
+    /// * `mods`: Modifier keys
+    ///
+    /// ## Return
+    ///
+    /// `None` if there is no next / previous focusable widget, so that focus
+    /// can leave the area.
+    fn tab_focus(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        let forward = match (key, mods) {
+            (KeyPress::Navigation(NavKey::Tab), ModKeys::EMPTY) => true,
+            (KeyPress::Navigation(NavKey::BackTab), _) => false,
+            _ => return None,
+        };
+        let len = self.widgets.len();
+        let focusable = |i: &usize| self.widgets[*i].focusable();
+        let next = match (self.focused_index(), forward) {
+            (Some(i), true) => (i + 1..len).find(focusable),
+            (Some(i), false) => (0..i).rev().find(focusable),
+            (None, true) => (0..len).find(focusable),
+            (None, false) => (0..len).rev().find(focusable),
+        }?;
+        if let Some(i) = self.focused_index() {
+            self.widgets[i].focus(FocusEvent::Take);
+        }
+        self.widgets[next].focus(FocusEvent::Offer);
+        self.routing.focused.set(Some(next));
+        Some(Action::Redraw())
     }
 }
 
-/// Make a `Vec` of unique widgets
-fn widgets_unique<'a>(grid: &[GridItem<'a>]) -> Vec<&'a dyn Widget> {
-    let mut widgets = Vec::new();
+impl Widget for GridArea<'_> {
+    /// Get the width bounds
+    fn width_bounds(&self, theme: &Theme) -> LengthBound {
+        self.width_bounds_in(theme, Dim::default())
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, theme: &Theme, width: u16) -> LengthBound {
+        self.height_bounds_in(theme, width, Dim::new(width, 0))
+    }
+
+    /// Get the width bounds within a containing area
+    fn width_bounds_in(&self, theme: &Theme, area: Dim) -> LengthBound {
+        let gaps = gap_total(self.gap.width, self.cols);
+        let width_bounds = self.widget_width_bounds(theme, area);
+        self.column_bounds(&width_bounds)
+            .into_iter()
+            .fold(LengthBound::new(gaps..=gaps), |sum, b| sum + b)
+    }
+
+    /// Get the height bounds within a containing area
+    fn height_bounds_in(
+        &self,
+        theme: &Theme,
+        width: u16,
+        area: Dim,
+    ) -> LengthBound {
+        let gap = self.fit_gap(Dim::new(width, area.height));
+        let width_bounds = self.widget_width_bounds(theme, area);
+        let width = width - gap_total(gap.width, self.cols);
+        let columns = self.grid_columns(&width_bounds, width);
+        let height_bounds =
+            self.widget_height_bounds(theme, &columns, gap.width, area);
+        let gaps = gap_total(self.gap.height, self.rows);
+        self.row_bounds(&height_bounds)
+            .into_iter()
+            .fold(LengthBound::new(gaps..=gaps), |sum, b| sum + b)
+    }
+
+    /// Draw the widgets, when wrapped
+    ///
+    /// The area is laid out over the content, at least its minimum size.  A
+    /// widget which fails to draw shows an error placeholder in its cells.
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        let view =
+            BBox::new(offset.col, offset.row, cells.width(), cells.height());
+        let min = self.min_size(cells.theme());
+        let width = min.width.max(view.right());
+        let height = min.height.max(view.bottom());
+        let boxes = self.calculate_cell_boxes(
+            BBox::new(0, 0, width, height),
+            cells.theme(),
+        );
+        let mut shown = Vec::with_capacity(boxes.len());
+        for (widget, bbox) in self.widgets.iter().zip(&boxes) {
+            let vis = bbox.clip(view);
+            let inset = BBox::new(
+                vis.left() - view.left(),
+                vis.top() - view.top(),
+                vis.width(),
+                vis.height(),
+            );
+            if !inset.dim().is_empty() {
+                let offset = ContentPos::new(
+                    vis.left() - bbox.left(),
+                    vis.top() - bbox.top(),
+                );
+                // Other errors were drawn as a placeholder
+                let _placeholder =
+                    draw_widget(cells, *widget, inset, offset, None)?;
+            }
+            shown.push(inset);
+        }
+        self.routing.boxes.replace(boxes);
+        self.routing.shown.replace(shown);
+        Ok(())
+    }
+
+    /// Check whether any widget can accept focus
+    fn focusable(&self) -> bool {
+        self.widgets.iter().any(|w| w.focusable())
+    }
+
+    /// Take bells requested by the widgets
+    fn take_bell(&self) -> bool {
+        // Every widget's bell is taken, not just the first
+        let mut bell = false;
+        for widget in &self.widgets {
+            bell |= widget.take_bell();
+        }
+        bell
+    }
+
+    /// Get the content row of the focused widget's cursor
+    fn cursor_row(&self) -> Option<u16> {
+        let i = self.focused_index()?;
+        let row = self.widgets[i].cursor_row()?;
+        let top = self.routing.boxes.borrow().get(i)?.top();
+        row.checked_add(top)
+    }
+
+    /// Get the position of the focused widget's terminal cursor
+    fn cursor(&self) -> Option<WidgetPos> {
+        let i = self.focused_index()?;
+        let pos = self.widgets[i].cursor()?;
+        let inset = *self.routing.shown.borrow().get(i)?;
+        if pos.col < inset.width() && pos.row < inset.height() {
+            Some(WidgetPos::new(
+                inset.left() + pos.col,
+                inset.top() + pos.row,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Get the nested grid area
+    fn nested_area(&self) -> Option<&GridArea<'_>> {
+        Some(self)
+    }
+
+    /// Handle a focus event
+    ///
+    /// Offered focus goes to the last focused widget, or the first
+    /// focusable one.
+    fn focus(&self, fev: FocusEvent) -> Option<Action> {
+        match fev {
+            FocusEvent::Offer => {
+                let i = self.focused_index().or_else(|| {
+                    self.widgets.iter().position(|w| w.focusable())
+                })?;
+                self.routing.focused.set(Some(i));
+                self.widgets[i].focus(fev)
+            }
+            FocusEvent::Take => {
+                let i = self.focused_index();
+                self.routing.focused.set(None);
+                self.widgets[i?].focus(fev)
+            }
+            // Hovering inside is routed by mouse events
+            FocusEvent::HoverInside => None,
+            FocusEvent::HoverOutside => self
+                .widgets
+                .iter()
+                .fold(None, |action, w| w.focus(fev).or(action)),
+        }
+    }
+
+    /// Handle a key event
+    ///
+    /// The focused widget handles keys first.  Unhandled `Tab` / `BackTab`
+    /// presses or repeats move focus between widgets.
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        if let Some(i) = self.focused_index() {
+            let action = self.widgets[i].key_event(key, mods);
+            if action.is_some() {
+                return action;
+            }
+        }
+        self.tab_focus(key, mods)
+    }
+
+    /// Handle a key repeat event
+    fn key_repeat(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        if let Some(i) = self.focused_index() {
+            let action = self.widgets[i].key_repeat(key, mods);
+            if action.is_some() {
+                return action;
+            }
+        }
+        self.tab_focus(key, mods)
+    }
+
+    /// Handle a key release event
+    fn key_release(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        self.widgets[self.focused_index()?].key_release(key, mods)
+    }
+
+    /// Handle mouse events, routing them to the widget under the pointer
+    ///
+    /// Drags and releases go to the widget where the button was pressed.
+    fn mouse_event(
+        &self,
+        mev: MouseEvent,
+        mods: ModKeys,
+        _dim: Dim,
+        pos: WidgetPos,
+    ) -> Option<Action> {
+        use MouseEvent::*;
+        let boxes = self.routing.boxes.borrow();
+        let hovered = self
+            .widgets
+            .iter()
+            .zip(boxes.iter())
+            .position(|(w, b)| w.hit_testable() && b.contains(*pos));
+        if let ButtonDown(_) = mev {
+            let focused = hovered.filter(|i| self.widgets[*i].focusable());
+            self.routing.focused.set(focused);
+        }
+        let captured = match mev {
+            ButtonDown(_) => {
+                self.routing.pressed.set(hovered);
+                None
+            }
+            Drag(Some(_)) => self.routing.pressed.get(),
+            ButtonUp(_) => self.routing.pressed.take(),
+            _ => None,
+        };
+        let mut action = None;
+        let mut redraw = None;
+        for (i, (widget, bbox)) in
+            self.widgets.iter().zip(boxes.iter()).enumerate()
+        {
+            if !widget.hit_testable() {
+                continue;
+            }
+            let inside = hovered == Some(i);
+            let r = match (mev, inside) {
+                (ButtonDown(_), true) => widget.focus(FocusEvent::Offer),
+                (ButtonDown(_), false) => widget.focus(FocusEvent::Take),
+                (Drag(None), true) => widget.focus(FocusEvent::HoverInside),
+                (Drag(_), false) => widget.focus(FocusEvent::HoverOutside),
+                (ButtonUp(_), true) => widget.focus(FocusEvent::HoverInside),
+                (ButtonUp(_), false) => widget.focus(FocusEvent::HoverOutside),
+                _ => None,
+            };
+            redraw = redraw.or(r);
+            if captured.or(hovered) == Some(i) {
+                let p = pos.nested(*bbox);
+                action = widget.mouse_event(mev, mods, bbox.dim(), p);
+            }
+        }
+        action.or(redraw)
+    }
+
+    /// Get an action for the pressed widget while held
+    fn held_action(&self, now: Instant) -> Option<Action> {
+        let i = self.routing.pressed.get()?;
+        self.widgets[i].held_action(now)
+    }
+}
+
+/// Unique widgets with their tags
+type UniqueWidgets<'a> = (Vec<&'a dyn Widget>, Vec<Option<u64>>);
+
+/// Make a `Vec` of unique widgets, with their tags
+fn widgets_unique<'a>(grid: &[GridItem<'a>]) -> Result<UniqueWidgets<'a>> {
+    let mut widgets: Vec<&'a dyn Widget> = Vec::new();
+    let mut tags = Vec::new();
     for item in grid {
-        match item {
-            GridItem::Widget(widget) => {
-                if !widgets.iter().any(|w| widget_is_same(*w, *widget)) {
-                    widgets.push(*widget);
+        if let Some(widget) = item.widget() {
+            let tag = item.tag();
+            match widgets.iter().position(|w| widget_is_same(*w, widget)) {
+                Some(i) => match (tags[i], tag) {
+                    (Some(a), Some(b)) if a != b => {
+                        return Err(Error::InvalidGridArea());
+                    }
+                    (None, Some(_)) => tags[i] = tag,
+                    _ => (),
+                },
+                None => {
+                    widgets.push(widget);
+                    tags.push(tag);
                 }
             }
-            GridItem::Spacer(_) => {
-                // FIXME: Handle spacing
-            }
         }
     }
-    widgets
+    Ok((widgets, tags))
+}
+
+/// Calculate spacer weights of grid tracks (rows or columns)
+///
+/// * `grid`: Grid items, in row-major order
+/// * `len`: Number of tracks
+/// * `track`: Function to get the track of a grid item index
+///
+/// Tracks containing any widget have a weight of 1.
+fn track_weights(
+    grid: &[GridItem],
+    len: u16,
+    track: impl Fn(usize) -> usize,
+) -> Vec<u8> {
+    let mut weights = vec![None; len.into()];
+    let mut widgets = vec![false; len.into()];
+    for (i, item) in grid.iter().enumerate() {
+        let t = track(i);
+        match item.weight() {
+            Some(w) => weights[t] = Some(weights[t].unwrap_or(0).max(w)),
+            None => widgets[t] = true,
+        }
+    }
+    weights
+        .into_iter()
+        .zip(widgets)
+        .map(|(w, widget)| if widget { 1 } else { w.unwrap_or(1) })
+        .collect()
 }
 
 /// Check if two widgets are at the same memory address
@@ -262,7 +952,7 @@ fn distribute_decrease(bounds: &mut [LengthBound], maximum: u16) {
             unbounded += 1;
         }
     }
-    let extra = if maximum > total { maximum - total } else { 0 };
+    let extra = maximum.saturating_sub(total);
     let (each, bonus) = if extra > 0 && unbounded > 0 {
         (extra / unbounded, extra % unbounded)
     } else {
@@ -309,8 +999,22 @@ fn distribute_increase(
 
 /// Distribute total lengths to a `Vec` of lengths
 ///
-/// NOTE: this uses a woefully inefficient algorithm
-fn distribute_bounds(mut bounds: Vec<LengthBound>, total: u16) -> Vec<u16> {
+/// * `bounds`: Length bounds of each track
+/// * `weights`: Spacer weights of each track
+/// * `total`: Total length to distribute
+///
+/// Lengths grow in proportion to their weights; zero-weight lengths only
+/// grow when no others can.
+///
+/// Each pass levels the candidate tracks in closed form, giving the same
+/// lengths as growing them one cell at a time.  A new pass starts only when
+/// a track reaches its maximum, so there are at most as many passes as
+/// tracks.
+fn distribute_bounds(
+    mut bounds: Vec<LengthBound>,
+    weights: &[u8],
+    total: u16,
+) -> Vec<u16> {
     let minimum = bounds[..].iter().map(|b| b.minimum()).sum::<u16>();
     if minimum < total {
         let maximum = bounds[..]
@@ -318,44 +1022,251 @@ fn distribute_bounds(mut bounds: Vec<LengthBound>, total: u16) -> Vec<u16> {
             .map(|b| b.maximum())
             .fold(0u16, |sum, b| sum.saturating_add(b));
         let maximum = total.min(maximum);
-        let extra = maximum - minimum;
-        let mut added = 0;
-        while added < extra {
-            // find index of bound with max available
-            let (i, _) = bounds[..]
-                .iter()
-                .enumerate()
-                .max_by_key(|&(_, &b)| b.available())
-                .unwrap();
-            bounds[i].increase(1);
-            added += 1;
+        let mut extra = maximum - minimum;
+        while extra > 0 {
+            let candidates: Vec<usize> = (0..bounds.len())
+                .filter(|i| bounds[*i].available() > 0 && weights[*i] > 0)
+                .collect();
+            let weight = candidates.first().map(|i| weights[*i]);
+            let added = if candidates.is_empty() {
+                let all: Vec<usize> = (0..bounds.len()).collect();
+                level_available(&mut bounds, &all, extra)
+            } else if candidates.iter().all(|i| Some(weights[*i]) == weight) {
+                level_available(&mut bounds, &candidates, extra)
+            } else {
+                level_weighted(&mut bounds, weights, &candidates, extra)
+            };
+            if added == 0 {
+                break;
+            }
+            extra -= added;
         }
     }
     bounds[..].iter().map(|b| b.minimum()).collect()
 }
 
-/// Calculate a widget cell bounding box from grid data
+/// Increase bounds with the most available length, leveling them down
+///
+/// * `bounds`: Length bounds of each track
+/// * `indices`: Indices of tracks to increase
+/// * `extra`: Extra length to distribute
+///
+/// Ties go to the last track.
+///
+/// ## Return
+///
+/// Length added, which is less than `extra` only if all tracks are full.
+fn level_available(
+    bounds: &mut [LengthBound],
+    indices: &[usize],
+    extra: u16,
+) -> u16 {
+    let avail: Vec<u16> =
+        indices.iter().map(|i| bounds[*i].available()).collect();
+    let needed = |level: u16| -> u32 {
+        avail
+            .iter()
+            .map(|a| u32::from(a.saturating_sub(level)))
+            .sum()
+    };
+    let amount = extra.min(needed(0).try_into().unwrap_or(u16::MAX));
+    // Find the lowest level which can be reached with `amount`
+    let (mut lo, mut hi) = (0, avail.iter().copied().max().unwrap_or(0));
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if needed(mid) <= u32::from(amount) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    let mut remaining = u32::from(amount) - needed(lo);
+    for (i, a) in indices.iter().zip(&avail) {
+        bounds[*i].increase(a.saturating_sub(lo));
+    }
+    // Break ties just below the level, starting from the last track
+    for (i, a) in indices.iter().zip(&avail).rev() {
+        if remaining > 0 && *a >= lo {
+            bounds[*i].increase(1);
+            remaining -= 1;
+        }
+    }
+    amount
+}
+
+/// Scale of key thresholds in `level_weighted`
+///
+/// Keys with weights up to `u8::MAX` differ by more than `1 / KEY_SCALE`.
+const KEY_SCALE: u64 = 1 << 16;
+
+/// Count increases of a bound with keys up to a threshold
+///
+/// The key of the `n`th increase is `(minimum + n) / weight`.
+///
+/// * `bnd`: Length bound
+/// * `weight`: Track weight (non-zero)
+/// * `num`: Threshold numerator
+/// * `den`: Threshold denominator
+/// * `inclusive`: Include keys equal to the threshold
+fn count_keys(
+    bnd: LengthBound,
+    weight: u8,
+    num: u64,
+    den: u64,
+    inclusive: bool,
+) -> u64 {
+    let scaled = num * u64::from(weight);
+    let below = if inclusive {
+        scaled / den
+    } else {
+        // keys are always positive, so only a zero threshold saturates
+        scaled.div_ceil(den).saturating_sub(1)
+    };
+    below
+        .saturating_sub(u64::from(bnd.minimum()))
+        .min(u64::from(bnd.available()))
+}
+
+/// Increase bounds which are smallest relative to their weights
+///
+/// * `bounds`: Length bounds of each track
+/// * `weights`: Spacer weights of each track
+/// * `candidates`: Indices of tracks to increase, with non-zero weights
+/// * `extra`: Extra length to distribute
+///
+/// Ties go to the first track.  Stops after the first track is full, since
+/// that can change which tracks are candidates.
+///
+/// ## Return
+///
+/// Length added
+fn level_weighted(
+    bounds: &mut [LengthBound],
+    weights: &[u8],
+    candidates: &[usize],
+    extra: u16,
+) -> u16 {
+    let count = |num: u64, den: u64, inclusive: bool| -> Vec<u64> {
+        candidates
+            .iter()
+            .map(|i| count_keys(bounds[*i], weights[*i], num, den, inclusive))
+            .collect()
+    };
+    // Find the first track to become full, by its key (ties go first)
+    let full = |i: usize| {
+        let bnd = bounds[i];
+        (
+            u64::from(bnd.minimum()) + u64::from(bnd.available()),
+            u64::from(weights[i]),
+        )
+    };
+    let first = candidates
+        .iter()
+        .copied()
+        .reduce(|a, b| {
+            let ((na, da), (nb, db)) = (full(a), full(b));
+            if nb * da < na * db {
+                b
+            } else {
+                a
+            }
+        })
+        .unwrap();
+    let (num, den) = full(first);
+    let before = count(num, den, false).iter().sum::<u64>();
+    let ties = candidates
+        .iter()
+        .zip(count(num, den, false).iter().zip(count(num, den, true)))
+        .filter(|(i, (lt, le))| **i <= first && le > *lt)
+        .count();
+    let amount = u64::from(extra).min(before + ties as u64);
+    // Find the lowest threshold which includes `amount` increases
+    let (mut lo, mut hi) = (1, (num + 1) * KEY_SCALE);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if count(mid, KEY_SCALE, true).iter().sum::<u64>() >= amount {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    // All keys between thresholds `lo - 1` and `lo` are equal
+    let below = count(lo - 1, KEY_SCALE, true);
+    let at = count(lo, KEY_SCALE, true);
+    let mut remaining = amount - below.iter().sum::<u64>();
+    for (i, (b, a)) in candidates.iter().zip(below.iter().zip(at)) {
+        let mut n = *b;
+        if remaining > 0 && a > *b {
+            n += 1;
+            remaining -= 1;
+        }
+        bounds[*i].increase(n as u16);
+    }
+    amount as u16
+}
+
+/// Get the length of a span of tracks, including gaps between them
+///
+/// * `lens`: Lengths of all tracks
+/// * `start`: First track of span
+/// * `end`: Track after end of span
+/// * `gap`: Gap between tracks
+fn span_len(lens: &[u16], start: u16, end: u16, gap: u16) -> u16 {
+    let len: u16 = lens[start.into()..end.into()].iter().sum();
+    len.saturating_add(gap_total(gap, end.saturating_sub(start)))
+}
+
+/// Get the total length of the gaps between tracks
+///
+/// * `gap`: Gap between tracks
+/// * `tracks`: Number of tracks
+///
+/// The total is clamped to `u16::MAX`, so that a large gap can be dropped
+/// instead of overflowing.
+fn gap_total(gap: u16, tracks: u16) -> u16 {
+    gap.saturating_mul(tracks.saturating_sub(1))
+}
+
+/// Calculate the cell bounding box of a widget's grid cells
 ///
 /// * `bx`: Cell Bounding box of grid area
 /// * `gb`: Grid bounding box of widget
-/// * `wb`: Width bounds
-/// * `cols`: Widths of all grid columns
-/// * `hb`: Height bounds
-/// * `rows`: Heights of all grid rows
+/// * `(cols, rows)`: Lengths of all grid columns and rows
+/// * `gap`: Gap between grid tracks
 fn widget_cell_bbox(
     bx: BBox,
     gb: BBox,
+    (cols, rows): (&[u16], &[u16]),
+    gap: Dim,
+) -> BBox {
+    let col = bx.left()
+        + cols[..gb.left() as usize].iter().sum::<u16>()
+        + gap_total(gap.width, gb.left() + 1);
+    let row = bx.top()
+        + rows[..gb.top() as usize].iter().sum::<u16>()
+        + gap_total(gap.height, gb.top() + 1);
+    let width = span_len(cols, gb.left(), gb.right(), gap.width);
+    let height = span_len(rows, gb.top(), gb.bottom(), gap.height);
+    BBox::new(col, row, width, height)
+}
+
+/// Align a widget bounding box within its cells
+///
+/// * `cbx`: Cell bounding box of widget's grid cells
+/// * `wb`: Width bounds
+/// * `hb`: Height bounds
+/// * `(halign, valign)`: Widget alignment
+fn align_bbox(
+    cbx: BBox,
     wb: LengthBound,
-    cols: &[u16],
     hb: LengthBound,
-    rows: &[u16],
+    (halign, valign): (HAlign, VAlign),
 ) -> BBox {
-    let col = bx.left() + cols[..gb.left() as usize].iter().sum::<u16>();
-    let row = bx.top() + rows[..gb.top() as usize].iter().sum::<u16>();
-    let width: u16 = cols[gb.left() as usize..gb.right() as usize].iter().sum();
-    let height: u16 =
-        rows[gb.top() as usize..gb.bottom() as usize].iter().sum();
-    BBox::new(col, row, width.min(wb.maximum()), height.min(hb.maximum()))
+    let width = cbx.width().min(wb.maximum());
+    let height = cbx.height().min(hb.maximum());
+    let col = cbx.left() + halign.offset(cbx.width() - width);
+    let row = cbx.top() + valign.offset(cbx.height() - height);
+    BBox::new(col, row, width, height)
 }
 
 /// Lay out [Widget]s into a [GridArea]
@@ -365,15 +1276,22 @@ fn widget_cell_bbox(
 /// ## Arguments
 ///
 /// * `[a …] [b …]`: One or more rows of grid items, enclosed in square
-///                  brackets.  A grid item is either a [Widget] identifier or a
-///                  dot `.`, which is used for spacing.  A `Widget` can appear
-///                  multiple times as long as it occupies a rectangular shape
-///                  in the grid.
+///   brackets.  A grid item is either a [Widget] identifier or a dot `.`,
+///   which is used for spacing.  A number in place of the dot is a spacer
+///   weight: `[a 3 b 1 c]` gives the first gap three times the space of the
+///   second.  A `Widget` can appear multiple times as long
+///   as it occupies a rectangular shape in the grid.  A tagged widget is
+///   written `(#7 a)`; the tag only needs to appear in one of its cells.
 ///
 /// ## Errors
 ///
-/// [Error::InvalidGridArea] If the rows are not all the same length, or if any
-///                          [Widget] does not form a rectangular pattern.
+/// Rows with differing item counts are rejected at compile time, naming the
+/// first mismatched row.  Rows are named by number up to row 64; past that,
+/// a mismatched row is named by its items.  Grids with more than 65535 rows
+/// or columns are also rejected at compile time.
+///
+/// [Error::InvalidGridArea] If any [Widget] does not form a rectangular
+/// pattern.
 ///
 /// ## Example
 /// ```rust
@@ -394,17 +1312,63 @@ fn widget_cell_bbox(
 /// [grid-template-areas]: https://developer.mozilla.org/en-US/docs/Web/CSS/grid-template-areas
 #[macro_export]
 macro_rules! grid_area {
+    // Count the items in a row
+    (@count $($item:tt)+) => { 0usize $( + $crate::grid_area!(@one $item) )+ };
+    (@one $item:tt) => { 1usize };
+    // Check that all rows have the same number of items as the first.
+    // Row numbers (starting from 1) are consumed from a list of literals;
+    // once the list runs out (past row 64), rows are named by their items.
+    (@check $cols:ident [$($idx:literal)*]) => {};
+    (@check $cols:ident [$idx:literal $($rest:literal)*]
+        [ $($item:tt)+ ] $($rows:tt)*
+    ) => {
+        const _: () = assert!(
+            $crate::grid_area!(@count $($item)+) == $cols,
+            concat!(
+                "grid_area!: row ", $idx,
+                " does not have the same number of items as row 1"
+            ),
+        );
+        $crate::grid_area!(@check $cols [$($rest)*] $($rows)*);
+    };
+    (@check $cols:ident [] [ $($item:tt)+ ] $($rows:tt)*) => {
+        const _: () = assert!(
+            $crate::grid_area!(@count $($item)+) == $cols,
+            concat!(
+                "grid_area!: row [", stringify!($($item)+),
+                "] does not have the same number of items as row 1"
+            ),
+        );
+        $crate::grid_area!(@check $cols [] $($rows)*);
+    };
     (.) => { $crate::layout::GridItem::Spacer(None) };
+    ($weight:literal) => { $crate::layout::GridItem::Spacer(Some($weight)) };
+    ((# $tag:literal $widget:ident)) => {
+        $crate::layout::GridItem::TaggedWidget($tag, &$widget)
+    };
     ($widget:ident) => { $crate::layout::GridItem::Widget(&$widget) };
-    ($([ $($item:tt)+ ])+) => {
+    ([ $($first:tt)+ ] $([ $($item:tt)+ ])*) => {
         {
-            let mut ga = Vec::<$crate::layout::GridItem>::new();
-            let mut rows = 0;
-            $(
-                $( ga.push(grid_area!( $item )); )+
-                rows += 1;
-            )+
-            $crate::layout::GridArea::new(&ga[..], rows)
+            const COLS: usize = $crate::grid_area!(@count $($first)+);
+            const ROWS: usize = 1 $( + $crate::grid_area!(@one [$($item)+]) )*;
+            $crate::grid_area!(@check COLS [2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61 62 63 64] $([ $($item)+ ])*);
+            const _: () = assert!(
+                ROWS <= u16::MAX as usize,
+                "grid_area!: more than 65535 rows",
+            );
+            const _: () = assert!(
+                COLS <= u16::MAX as usize,
+                "grid_area!: more than 65535 columns",
+            );
+            let ga = [
+                $( $crate::grid_area!( $first ), )+
+                $( $( $crate::grid_area!( $item ), )+ )*
+            ];
+            $crate::layout::GridArea::with_dims(
+                &ga[..],
+                ROWS as u16,
+                COLS as u16,
+            )
         }
     };
 }
@@ -412,6 +1376,7 @@ macro_rules! grid_area {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::layout::Length;
     use crate::widget::{Label, Spacer};
 
     #[test]
@@ -521,6 +1486,286 @@ mod test {
         assert_eq!(l[2].1, BBox::new(40, 16, 40, 9));
     }
 
+    #[test]
+    fn spacer_percent() {
+        let a = Spacer::default().with_columns_len(Length::Percent(10));
+        let b = Spacer::default();
+        let c = Spacer::default().with_columns_len(Length::Percent(25));
+        let grid = grid_area!([a b c]).unwrap();
+        let theme = Theme::default();
+        let l = grid.widget_boxes(BBox::new(0, 0, 80, 25), &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 8, 25));
+        assert_eq!(l[1].1, BBox::new(8, 0, 52, 25));
+        assert_eq!(l[2].1, BBox::new(60, 0, 20, 25));
+        let l = grid.widget_boxes(BBox::new(0, 0, 200, 60), &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 20, 60));
+        assert_eq!(l[1].1, BBox::new(20, 0, 130, 60));
+        assert_eq!(l[2].1, BBox::new(150, 0, 50, 60));
+    }
+
+    #[test]
+    fn spacer_percent_rows() {
+        let a = Spacer::default().with_rows_len(Length::Min(2, 10));
+        let b = Spacer::default();
+        let grid = grid_area!([a][b]).unwrap();
+        let theme = Theme::default();
+        let l = grid.widget_boxes(BBox::new(0, 0, 80, 15), &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 80, 2));
+        let l = grid.widget_boxes(BBox::new(0, 0, 80, 50), &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 80, 5));
+    }
+
+    #[test]
+    fn spacer_percent_minimum() {
+        // Label minimum width is 10
+        let a = Spacer::default().with_columns_len(Length::Percent(75));
+        let b = Label::new("A label which wraps");
+        let c = Spacer::default().with_columns_len(Length::Percent(10));
+        let grid = grid_area!([a b c]).unwrap();
+        let theme = Theme::default();
+        let l = grid.widget_boxes(BBox::new(0, 0, 100, 25), &theme);
+        let widths: Vec<u16> = l.iter().map(|(_, b)| b.width()).collect();
+        assert_eq!(widths, [75, 15, 10]);
+        // Minimum is kept, even when the spacers take the rest
+        let a = Spacer::default().with_columns_len(Length::Percent(90));
+        let grid = grid_area!([a b c]).unwrap();
+        let l = grid.widget_boxes(BBox::new(0, 0, 100, 25), &theme);
+        assert_eq!(l[1].1.width(), 10);
+    }
+
+    #[test]
+    fn spacer_weights() {
+        let a = Spacer::default().with_columns(5..5).with_rows(1..1);
+        let l = grid_area!([1 a 3])
+            .unwrap()
+            .widget_boxes(BBox::new(0, 0, 80, 25), &Theme::default());
+        assert_eq!(l[0].1, BBox::new(19, 0, 5, 1));
+        let b = Spacer::default().with_columns(4..4).with_rows(1..1);
+        let l = grid_area!([a 1 b 3])
+            .unwrap()
+            .widget_boxes(BBox::new(0, 0, 80, 25), &Theme::default());
+        assert_eq!(l[0].1, BBox::new(0, 0, 5, 1));
+        assert_eq!(l[1].1, BBox::new(23, 0, 4, 1));
+        // Zero weight collapses
+        let l = grid_area!([0 a 1])
+            .unwrap()
+            .widget_boxes(BBox::new(0, 0, 80, 25), &Theme::default());
+        assert_eq!(l[0].1, BBox::new(0, 0, 5, 1));
+        let l = grid_area!([0 a 0])
+            .unwrap()
+            .widget_boxes(BBox::new(0, 0, 80, 25), &Theme::default());
+        assert_eq!(l[0].1, BBox::new(37, 0, 5, 1));
+    }
+
+    #[test]
+    fn distribute() {
+        let bounds = vec![
+            LengthBound::new(2..),
+            LengthBound::new(8..),
+            LengthBound::new(1..=4),
+            LengthBound::new(0..),
+        ];
+        // Unbounded tracks level out; ties go to the last track
+        let lengths = distribute_bounds(bounds.clone(), &[1, 1, 1, 1], 300);
+        assert_eq!(lengths, [99, 100, 1, 100]);
+        // Weighted tracks grow in proportion
+        let lengths = distribute_bounds(bounds.clone(), &[1, 2, 0, 2], 301);
+        assert_eq!(lengths, [60, 120, 1, 120]);
+        // Zero-weight tracks grow only when no others can
+        let lengths = distribute_bounds(bounds.clone(), &[0, 0, 0, 0], 20);
+        assert_eq!(lengths, [5, 8, 1, 6]);
+        let lengths = distribute_bounds(bounds, &[1, 1, 0, 0], 20);
+        assert_eq!(lengths, [9, 10, 1, 0]);
+    }
+
+    #[test]
+    fn nested() {
+        let a = Spacer::default();
+        let b = Spacer::default();
+        let c = Spacer::default();
+        let d = Spacer::default();
+        let e = Spacer::default();
+        let inner = grid_area!(
+            [b c]
+            [d (#4 e)]
+        )
+        .unwrap();
+        let outer = grid_area!([a inner]).unwrap();
+        let bx = BBox::new(0, 0, 80, 25);
+        let theme = Theme::default();
+        let l = outer.widget_boxes(bx, &theme);
+        assert_eq!(l.len(), 5);
+        assert_eq!(data_pointer(l[4].0), data_pointer(&e));
+        assert_eq!(l[0].1, BBox::new(0, 0, 40, 25));
+        assert_eq!(l[1].1, BBox::new(40, 0, 20, 12));
+        assert_eq!(l[2].1, BBox::new(60, 0, 20, 12));
+        assert_eq!(l[3].1, BBox::new(40, 12, 20, 13));
+        assert_eq!(l[4].1, BBox::new(60, 12, 20, 13));
+        assert_eq!(outer.tagged_boxes(bx, &theme), [(4, l[4].1)]);
+        let (w, tag) =
+            outer.widget_at(bx, &theme, ScreenPos::new(61, 20)).unwrap();
+        assert_eq!(data_pointer(w), data_pointer(&e));
+        assert_eq!(tag, Some(4));
+        assert_eq!(outer.widget_index(&a), Some(0));
+        assert_eq!(outer.widget_index(&e), Some(4));
+        assert_eq!(outer.widget_index(&inner), None);
+        assert_eq!(inner.widget_index(&a), None);
+        // Minimums of the inner widgets are kept
+        let f = Spacer::default().with_columns(50..);
+        let inner = grid_area!([b f]).unwrap();
+        let outer = grid_area!([a inner]).unwrap();
+        let l = outer.widget_boxes(bx, &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 30, 25));
+        assert_eq!(l[2].1, BBox::new(30, 0, 50, 25));
+    }
+
+    #[test]
+    fn gap() {
+        let a = Spacer::default();
+        let b = Spacer::default();
+        let c = Spacer::default();
+        let d = Spacer::default();
+        let theme = Theme::default();
+        let l = grid_area!(
+            [a a b]
+            [c d b]
+        )
+        .unwrap()
+        .with_gap(1, 1)
+        .widget_boxes(BBox::new(0, 0, 80, 25), &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 53, 12));
+        assert_eq!(l[1].1, BBox::new(54, 0, 26, 25));
+        assert_eq!(l[2].1, BBox::new(0, 13, 26, 12));
+        assert_eq!(l[3].1, BBox::new(27, 13, 26, 12));
+        // Gaps which do not fit are dropped
+        let l = grid_area!([a b c])
+            .unwrap()
+            .with_gap(50, 30)
+            .widget_boxes(BBox::new(0, 0, 80, 25), &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 26, 25));
+        assert_eq!(l[1].1, BBox::new(26, 0, 27, 25));
+        assert_eq!(l[2].1, BBox::new(53, 0, 27, 25));
+        // Huge gaps do not overflow
+        let grid = grid_area!([a b c]).unwrap().with_gap(u16::MAX, u16::MAX);
+        assert_eq!(grid.min_size(&theme), Dim::new(u16::MAX, 0));
+        let l = grid.widget_boxes(BBox::new(0, 0, 80, 25), &theme);
+        assert_eq!(l[2].1, BBox::new(53, 0, 27, 25));
+    }
+
+    #[test]
+    fn align() {
+        let a = Spacer::default().with_columns(5..5).with_rows(3..3);
+        let b = Spacer::default().with_columns(5..5).with_rows(3..3);
+        let c = Spacer::default().with_columns(5..5).with_rows(3..3);
+        let p = Spacer::default().with_rows(4..);
+        let q = Spacer::default().with_rows(4..);
+        let r = Spacer::default().with_rows(4..);
+        let e = Spacer::default().with_columns(10..);
+        let theme = Theme::default();
+        let l = grid_area!(
+            [a p]
+            [b q]
+            [c r]
+            [e .]
+        )
+        .unwrap()
+        .with_align(&b, HAlign::Center, VAlign::Middle)
+        .with_align(&c, HAlign::Right, VAlign::Bottom)
+        .widget_boxes(BBox::new(0, 0, 20, 16), &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 5, 3));
+        // Odd leftover goes to the right and bottom
+        assert_eq!(l[2].1, BBox::new(2, 4, 5, 3));
+        assert_eq!(l[4].1, BBox::new(5, 9, 5, 3));
+    }
+
+    #[test]
+    fn fractions() {
+        let a = Spacer::default();
+        let b = Spacer::default();
+        let c = Spacer::default().with_columns(30..);
+        let theme = Theme::default();
+        let l = grid_area!([a b])
+            .unwrap()
+            .with_column_fractions(&[3, 7])
+            .widget_boxes(BBox::new(0, 0, 100, 25), &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 30, 25));
+        assert_eq!(l[1].1, BBox::new(30, 0, 70, 25));
+        // Minimums are met first
+        let l = grid_area!([a c])
+            .unwrap()
+            .with_column_fractions(&[3, 1])
+            .widget_boxes(BBox::new(0, 0, 100, 25), &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 70, 25));
+        assert_eq!(l[1].1, BBox::new(70, 0, 30, 25));
+        let l = grid_area!([a][b][.])
+            .unwrap()
+            .with_row_fractions(&[1, 2])
+            .widget_boxes(BBox::new(0, 0, 80, 24), &theme);
+        assert_eq!(l[0].1, BBox::new(0, 0, 80, 6));
+        assert_eq!(l[1].1, BBox::new(0, 6, 80, 12));
+    }
+
+    #[test]
+    fn too_large() {
+        let grid: Vec<GridItem> =
+            (0..70_000).map(|_| GridItem::Spacer(None)).collect();
+        assert!(matches!(
+            GridArea::new(&grid, 1),
+            Err(Error::GridTooLarge(1, 70_000))
+        ));
+        assert!(GridArea::new(&grid, 2).is_ok());
+        assert!(matches!(
+            GridArea::new(&grid, 3),
+            Err(Error::InvalidGridArea())
+        ));
+        assert!(matches!(
+            GridArea::new(&grid, 0),
+            Err(Error::InvalidGridArea())
+        ));
+    }
+
+    #[test]
+    fn dims_mismatch() {
+        let grid = [GridItem::Spacer(None), GridItem::Spacer(None)];
+        assert!(GridArea::with_dims(&grid, 1, 2).is_ok());
+        assert!(matches!(
+            GridArea::with_dims(&grid, 2, 2),
+            Err(Error::InvalidGridArea())
+        ));
+        assert!(matches!(
+            GridArea::with_dims(&grid, 1, 1),
+            Err(Error::InvalidGridArea())
+        ));
+    }
+
+    #[test]
+    fn macro_matches_new() {
+        let a = Label::new("Label").into_button();
+        let b = Spacer::default();
+        let bx = BBox::new(0, 0, 80, 25);
+        let theme = Theme::default();
+        let m = grid_area!(
+            [. a a]
+            [b a a]
+        )
+        .unwrap()
+        .widget_boxes(bx, &theme);
+        let grid = [
+            GridItem::Spacer(None),
+            GridItem::Widget(&a),
+            GridItem::Widget(&a),
+            GridItem::Widget(&b),
+            GridItem::Widget(&a),
+            GridItem::Widget(&a),
+        ];
+        let n = GridArea::new(&grid, 2).unwrap().widget_boxes(bx, &theme);
+        assert_eq!(m.len(), n.len());
+        for ((mw, mb), (nw, nb)) in m.iter().zip(n.iter()) {
+            assert_eq!(data_pointer(*mw), data_pointer(*nw));
+            assert_eq!(mb, nb);
+        }
+    }
+
     #[test]
     fn grid1() {
         let a = Label::new("Label");
@@ -556,6 +1801,104 @@ mod test {
         assert_eq!(l[0].1, BBox::new(40, 24, 40, 1));
     }
 
+    #[test]
+    fn unlaid_themes() {
+        let a = Label::new("a\tb");
+        let grid = grid_area!([a]).unwrap();
+        let theme = Theme::default();
+        assert_eq!(grid.min_size(&theme), Dim::new(5, 1));
+        let wide = Theme::default().with_tab_width(8);
+        assert_eq!(grid.min_size(&wide), Dim::new(10, 1));
+        let bx = BBox::new(0, 0, 12, 2);
+        assert_eq!(grid.widget_boxes(bx, &wide)[0].1.width(), 12);
+        assert_eq!(grid.min_size(&theme), Dim::new(5, 1));
+    }
+
+    #[test]
+    fn wrap_cache_disabled() {
+        let a = Label::new("This is a test label with some text");
+        let b = Label::new("Label");
+        let bx = BBox::new(0, 0, 80, 25);
+        let grid = grid_area!(
+            [. . . .]
+            [a . b .]
+        )
+        .unwrap();
+        let theme = Theme::default();
+        let cached = grid.widget_boxes(bx, &theme);
+        let cached_again = grid.widget_boxes(bx, &theme);
+        let theme = Theme::default().with_wrap_cache(0);
+        let uncached = grid.widget_boxes(bx, &theme);
+        for ((c, ca), u) in cached.iter().zip(&cached_again).zip(&uncached) {
+            assert_eq!(c.1, ca.1);
+            assert_eq!(c.1, u.1);
+        }
+    }
+
+    #[test]
+    fn tagged() {
+        let a = Spacer::default();
+        let b = Spacer::default();
+        let c = Spacer::default();
+        let d = Spacer::default();
+        let grid = [
+            GridItem::TaggedWidget(1, &a),
+            GridItem::TaggedWidget(2, &b),
+            GridItem::Widget(&a),
+            GridItem::Widget(&d),
+            GridItem::TaggedWidget(3, &c),
+            GridItem::Widget(&c),
+        ];
+        let grid = GridArea::new(&grid, 3).unwrap();
+        let bx = BBox::new(0, 0, 80, 24);
+        let theme = Theme::default();
+        assert_eq!(
+            grid.tagged_boxes(bx, &theme),
+            [
+                (1, BBox::new(0, 0, 40, 16)),
+                (2, BBox::new(40, 0, 40, 8)),
+                (3, BBox::new(0, 16, 80, 8)),
+            ]
+        );
+        let at = |col, row| {
+            grid.widget_at(bx, &theme, ScreenPos::new(col, row))
+                .map(|(w, tag)| (data_pointer(w), tag))
+        };
+        assert_eq!(at(5, 5), Some((data_pointer(&a), Some(1))));
+        assert_eq!(at(45, 5), Some((data_pointer(&b), Some(2))));
+        assert_eq!(at(45, 10), Some((data_pointer(&d), None)));
+        assert_eq!(at(79, 23), Some((data_pointer(&c), Some(3))));
+        assert_eq!(at(80, 23), None);
+    }
+
+    #[test]
+    fn tagged_macro() {
+        let a = Spacer::default();
+        let b = Spacer::default();
+        let grid = grid_area!(
+            [(#7 a) b]
+            [a (#9 b)]
+        )
+        .unwrap();
+        let boxes =
+            grid.tagged_boxes(BBox::new(0, 0, 80, 24), &Theme::default());
+        assert_eq!(
+            boxes,
+            [(7, BBox::new(0, 0, 40, 24)), (9, BBox::new(40, 0, 40, 24))]
+        );
+    }
+
+    #[test]
+    fn tag_conflict() {
+        let a = Spacer::default();
+        let grid =
+            [GridItem::TaggedWidget(1, &a), GridItem::TaggedWidget(2, &a)];
+        assert!(GridArea::new(&grid, 1).is_err());
+        let grid =
+            [GridItem::TaggedWidget(1, &a), GridItem::TaggedWidget(1, &a)];
+        assert!(GridArea::new(&grid, 1).is_ok());
+    }
+
     #[test]
     fn grid4() {
         let a = Label::new("This is a test label with some text");
@@ -570,4 +1913,109 @@ mod test {
         assert_eq!(l[0].1, BBox::new(0, 23, 20, 2));
         assert_eq!(l[1].1, BBox::new(40, 23, 20, 2));
     }
+
+    #[test]
+    fn tiny_screen() -> Result<()> {
+        use crate::export::to_text;
+        let theme = Theme::default();
+        let a = Label::new("A").into_button();
+        let b = Label::new("B").into_button();
+        let c = Label::new("C").into_button();
+        let d = Label::new("D").into_button();
+        let e = Label::new("E Wider").into_button();
+        let f = Label::new("F").into_button();
+        let g = Label::new("G").into_button();
+        let h = Label::new("H").into_button();
+        let i = Label::new("I").into_button();
+        let j = Label::new("J").into_button();
+        let k = Label::new("K").into_button();
+        let l = Label::new("🦀 Run").into_button();
+        let grid = grid_area!(
+            [. a e i .]
+            [. b f j .]
+            [. c g k .]
+            [. d h l .]
+            [. . . . .]
+        )?;
+        assert!(grid.widget_boxes(BBox::new(0, 0, 0, 0), &theme).is_empty());
+        assert!(grid.widget_boxes(BBox::new(0, 0, 0, 5), &theme).is_empty());
+        let boxes = grid.widget_boxes(BBox::new(0, 0, 1, 1), &theme);
+        assert_eq!(boxes.len(), 12);
+        assert_eq!(to_text(&grid, Dim::new(0, 0), &theme)?, "");
+        let text = to_text(&grid, Dim::new(1, 1), &theme)?;
+        assert_eq!(text.chars().count(), 2);
+        Ok(())
+    }
+
+    /// Mouse click at a screen position
+    fn click(col: u16, row: u16) -> [crate::input::Event; 2] {
+        use crate::input::{Event, MouseButton};
+        let pos = ScreenPos::new(col, row);
+        [
+            MouseEvent::ButtonDown(MouseButton::Left),
+            MouseEvent::ButtonUp(MouseButton::Left),
+        ]
+        .map(|mev| Event::Mouse(mev, ModKeys::EMPTY, pos))
+    }
+
+    #[test]
+    fn wrapped() -> Result<()> {
+        use crate::input::{Event, KeyPress, WidgetId};
+        use crate::widget::{Border, Button};
+        use crate::Screen;
+        let key = |key| Event::Key(KeyPress::Navigation(key), ModKeys::EMPTY);
+        let space = Event::Key(KeyPress::Character(' '), ModKeys::EMPTY);
+        let a = Button::new(Label::new("A"));
+        let b = Button::new(Label::new("B"));
+        let c = Button::new(Label::new("C"));
+        let inner = grid_area!([a b])?;
+        let border = Border::new(inner);
+        let grid = grid_area!([border c])?;
+        let mut screen = Screen::headless(Dim::new(5, 3), Theme::default());
+        screen.render(&grid)?;
+        assert_eq!(screen.text(), "┌──┐C\n│AB│ \n└──┘ \n");
+        let activated = |w: &dyn Widget| Action::Activated(WidgetId::of(w));
+        let actions = screen.step_with_events(&grid, click(2, 1))?;
+        assert_eq!(actions, [activated(&b)]);
+        // Focus moves within the border before leaving it
+        let actions =
+            screen.step_with_events(&grid, [key(NavKey::BackTab), space])?;
+        assert_eq!(actions.last(), Some(&activated(&a)));
+        let actions =
+            screen.step_with_events(&grid, [key(NavKey::Tab), space])?;
+        assert_eq!(actions.last(), Some(&activated(&b)));
+        let actions =
+            screen.step_with_events(&grid, [key(NavKey::Tab), space])?;
+        assert_eq!(actions.last(), Some(&activated(&c)));
+        let actions =
+            screen.step_with_events(&grid, [key(NavKey::Tab), space])?;
+        assert_eq!(actions.last(), Some(&activated(&a)));
+        Ok(())
+    }
+
+    #[cfg(feature = "widgets-extra")]
+    #[test]
+    fn scrolled() -> Result<()> {
+        use crate::input::{Event, WidgetId};
+        use crate::widget::{Button, ScrollView};
+        use crate::Screen;
+        let down = Event::Mouse(
+            MouseEvent::ScrollDown(),
+            ModKeys::EMPTY,
+            ScreenPos::new(0, 0),
+        );
+        let a = Button::new(Label::new("A"));
+        let b = Button::new(Label::new("B"));
+        let c = Button::new(Label::new("C"));
+        let d = Button::new(Label::new("D"));
+        let sv = ScrollView::new(grid_area!([a][b][c][d])?);
+        let grid = grid_area!([sv])?;
+        let mut screen = Screen::headless(Dim::new(3, 2), Theme::default());
+        screen.step_with_events(&grid, [down, down])?;
+        assert!(screen.text().starts_with("C"));
+        let actions = screen.step_with_events(&grid, click(0, 1))?;
+        let activated = Action::Activated(WidgetId::of(&d));
+        assert_eq!(actions, [activated]);
+        Ok(())
+    }
 }
@@ -0,0 +1,57 @@
+// length.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+/// Length of columns or rows
+///
+/// Relative lengths are resolved against the containing area when a layout
+/// is calculated, rounding down.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Length {
+    /// Fixed number of text cells
+    Cells(u16),
+    /// Percent of the containing area
+    Percent(u8),
+    /// Fixed number of cells, but at least a percent of the containing area
+    Min(u16, u8),
+}
+
+impl Length {
+    /// Resolve the length within a containing area
+    ///
+    /// * `total`: Length of containing area (columns or rows)
+    pub fn resolve(self, total: u16) -> u16 {
+        let percent = |pc: u8| {
+            let len = u32::from(total) * u32::from(pc.min(100)) / 100;
+            len as u16 // limited by total
+        };
+        match self {
+            Length::Cells(cells) => cells,
+            Length::Percent(pc) => percent(pc),
+            Length::Min(cells, pc) => cells.max(percent(pc)),
+        }
+    }
+}
+
+impl From<u16> for Length {
+    fn from(cells: u16) -> Self {
+        Length::Cells(cells)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        assert_eq!(Length::Cells(2).resolve(80), 2);
+        assert_eq!(Length::Percent(10).resolve(80), 8);
+        assert_eq!(Length::Percent(10).resolve(85), 8);
+        assert_eq!(Length::Percent(33).resolve(200), 66);
+        assert_eq!(Length::Percent(150).resolve(80), 80);
+        assert_eq!(Length::Min(4, 2).resolve(80), 4);
+        assert_eq!(Length::Min(4, 2).resolve(400), 8);
+        assert_eq!(Length::Percent(100).resolve(u16::MAX), u16::MAX);
+    }
+}
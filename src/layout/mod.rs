@@ -4,13 +4,21 @@
 //
 //! User interface layout
 
+mod align;
 mod bbox;
 mod bounds;
 mod cells;
+mod coords;
 mod gridarea;
+mod length;
+mod pass;
 
+pub use align::{HAlign, VAlign};
 pub use bbox::BBox;
 pub use bbox::{Dim, Pos};
 pub use bounds::LengthBound;
 pub use cells::Cells;
+pub use coords::{ContentPos, ScreenPos, WidgetPos};
 pub use gridarea::{GridArea, GridItem};
+pub use length::Length;
+pub use pass::PassCache;
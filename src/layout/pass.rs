@@ -0,0 +1,216 @@
+// pass.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use std::cell::RefCell;
+
+/// Value cached for one layout pass
+///
+/// Widgets which derive expensive data while calculating bounds can keep it
+/// here, keyed by the [pass id] of the theme.  The value is computed at most
+/// once per pass, and reused through the draw which follows.  Themes which
+/// have not been used for layout have a pass id of 0; values are computed
+/// on every call for them, and never cached.
+///
+/// ```rust
+/// use semtext::layout::{LengthBound, PassCache};
+/// use semtext::text::Theme;
+///
+/// struct Rows {
+///     count: PassCache<u16>,
+/// }
+///
+/// impl Rows {
+///     fn height_bounds(&self, theme: &Theme) -> LengthBound {
+///         let rows = self.count.get_or_compute(theme.pass_id(), || 42);
+///         LengthBound::new(rows..=rows)
+///     }
+/// }
+/// ```
+///
+/// [pass id]: ../text/struct.Theme.html#method.pass_id
+#[derive(Debug)]
+pub struct PassCache<T: Clone> {
+    /// Pass id and cached value
+    entry: RefCell<Option<(u64, T)>>,
+}
+
+impl<T: Clone> Default for PassCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> PassCache<T> {
+    /// Create a new, empty pass cache
+    pub fn new() -> Self {
+        PassCache {
+            entry: RefCell::new(None),
+        }
+    }
+
+    /// Get the value for a pass, computing it if necessary
+    ///
+    /// * `pass_id`: Layout pass id, from [Theme::pass_id]
+    /// * `compute`: Function to compute the value
+    ///
+    /// [Theme::pass_id]: ../text/struct.Theme.html#method.pass_id
+    pub fn get_or_compute<F>(&self, pass_id: u64, compute: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        if pass_id == 0 {
+            return compute();
+        }
+        if let Some((id, value)) = &*self.entry.borrow() {
+            if *id == pass_id {
+                return value.clone();
+            }
+        }
+        let value = compute();
+        *self.entry.borrow_mut() = Some((pass_id, value.clone()));
+        value
+    }
+
+    /// Clear the cached value
+    ///
+    /// This must be called when the data it was derived from changes.
+    pub fn clear(&self) {
+        self.entry.borrow_mut().take();
+    }
+}
+
+impl<K: Clone + PartialEq, T: Clone> PassCache<(K, T)> {
+    /// Get the value for a pass and key, computing it if necessary
+    ///
+    /// * `pass_id`: Layout pass id, from [Theme::pass_id]
+    /// * `key`: Input the value was derived from, such as a width
+    /// * `compute`: Function to compute the value
+    ///
+    /// Only the most recent key is kept for each pass.
+    ///
+    /// [Theme::pass_id]: ../text/struct.Theme.html#method.pass_id
+    pub fn get_or_compute_for<F>(&self, pass_id: u64, key: K, compute: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        if pass_id == 0 {
+            return compute();
+        }
+        if let Some((id, (k, value))) = &*self.entry.borrow() {
+            if *id == pass_id && *k == key {
+                return value.clone();
+            }
+        }
+        let value = compute();
+        *self.entry.borrow_mut() = Some((pass_id, (key, value.clone())));
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::layout::{Cells, ContentPos, Dim, LengthBound};
+    use crate::text::Theme;
+    use crate::widget::{Label, Spacer};
+    use crate::{grid_area, Result, Screen, Widget};
+    use std::cell::Cell;
+
+    /// Widget which counts computations of its bounds
+    #[derive(Default)]
+    struct Counted {
+        /// Number of computations
+        count: Cell<u32>,
+        /// Cached width
+        width: PassCache<u16>,
+    }
+
+    impl Counted {
+        fn width(&self, theme: &Theme) -> u16 {
+            self.width.get_or_compute(theme.pass_id(), || {
+                self.count.set(self.count.get() + 1);
+                5
+            })
+        }
+    }
+
+    impl Widget for Counted {
+        fn width_bounds(&self, theme: &Theme) -> LengthBound {
+            let w = self.width(theme);
+            LengthBound::new(w..=w)
+        }
+
+        fn height_bounds(&self, theme: &Theme, _width: u16) -> LengthBound {
+            let w = self.width(theme);
+            LengthBound::new(w / 5..=w / 5)
+        }
+
+        fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+            let w = self.width(cells.theme());
+            cells.print_str(&"x".repeat(usize::from(w)))
+        }
+    }
+
+    /// Widget which counts calls of its width bounds
+    #[derive(Default)]
+    struct Calls {
+        /// Number of calls
+        count: Cell<u32>,
+    }
+
+    impl Widget for Calls {
+        fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+            self.count.set(self.count.get() + 1);
+            LengthBound::new(5..)
+        }
+
+        fn draw(&self, _cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn once_per_pass() -> Result<()> {
+        let counted = Counted::default();
+        let grid = grid_area!([counted])?;
+        let mut screen = Screen::headless(Dim::new(10, 4), Theme::default());
+        screen.render(&grid)?;
+        assert_eq!(counted.count.get(), 1);
+        screen.render(&grid)?;
+        assert_eq!(counted.count.get(), 2);
+        counted.width.clear();
+        assert_eq!(counted.width(&Theme::default()), 5);
+        assert_eq!(counted.count.get(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn pass_zero() {
+        let counted = Counted::default();
+        let theme = Theme::default();
+        assert_eq!(theme.pass_id(), 0);
+        counted.width(&theme);
+        counted.width(&theme);
+        assert_eq!(counted.count.get(), 2);
+        let cache = PassCache::new();
+        assert_eq!(cache.get_or_compute_for(0, 1, || 10), 10);
+        assert_eq!(cache.get_or_compute_for(0, 1, || 20), 20);
+        assert_eq!(cache.get_or_compute_for(3, 1, || 30), 30);
+        assert_eq!(cache.get_or_compute_for(3, 1, || 40), 30);
+    }
+
+    #[test]
+    fn nested_bounds() -> Result<()> {
+        let calls = Calls::default();
+        let spacer = Spacer::default();
+        let inner = grid_area!([calls spacer])?;
+        let label = Label::new("label");
+        let grid = grid_area!([inner label])?;
+        let mut screen = Screen::headless(Dim::new(20, 4), Theme::default());
+        screen.render(&grid)?;
+        // Once for the outer area, and once for the inner area
+        assert_eq!(calls.count.get(), 2);
+        Ok(())
+    }
+}
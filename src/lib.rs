@@ -24,18 +24,40 @@
 //!     futures::executor::block_on(async_main())
 //! }
 //! ```
+//!
+//! ## Features
+//!
+//! * `textwrap` (default): wrap text with the [textwrap] crate; without
+//!   it, a simpler greedy wrapper is used
+//! * `markdown` (default): inline Markdown styling of printed text and
+//!   labels; without it, text is plain
+//! * `widgets-extra` (default): additional [widget]s, such as scroll views
+//!   and trees
+//! * `forms` (default): the `CheckBox`, `Form` and `TextInput` widgets
+//! * `serde`: serialization of [SessionState] and [Theme](text::Theme)
+//!
+//! [textwrap]: https://docs.rs/textwrap
 
 #![forbid(unsafe_code)]
 
+mod backend;
+mod cast;
 mod error;
+pub mod export;
 pub mod input;
 pub mod layout;
 mod screen;
+mod session;
+mod snapshot;
 pub mod text;
 mod traits;
+mod transition;
 pub mod widget;
 
+pub use crate::backend::TestBackend;
+pub use crate::cast::CastRecorder;
 pub use crate::error::Error;
 pub(crate) use crate::error::Result;
-pub use crate::screen::Screen;
+pub use crate::screen::{BellMode, ExitRender, Popup, Screen, ScreenBuilder};
+pub use crate::session::{PersistState, SessionState, StateBlob};
 pub use crate::traits::Widget;
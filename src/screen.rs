@@ -2,22 +2,87 @@
 //
 // Copyright (c) 2020  Douglas P Lau
 //
-use crate::input::{Action, Event, FocusEvent, KeyMap, ModKeys, MouseEvent};
-use crate::layout::{BBox, Cells, Dim, GridArea, Pos};
-use crate::text::{Appearance, Color, StyleGroup, TextStyle, Theme};
-use crate::{Result, Widget};
+use crate::cast::{CastRecorder, Output};
+use crate::input::{
+    Action, Event, FocusEvent, KeyMap, KeyPress, ModKeys, MouseButton,
+    MouseEvent, NavKey, WidgetId,
+};
+use crate::layout::{
+    BBox, Cells, ContentPos, Dim, GridArea, ScreenPos, WidgetPos,
+};
+use crate::session::SessionState;
+use crate::snapshot::Snapshot;
+use crate::text::{
+    display_width, truncate_end, AmbiguousWidth, Appearance, Color,
+    ColorSupport, EmojiWidth, IntoGlyph, StyleGroup, StyleOverlay, TextStyle,
+    Theme,
+};
+use crate::transition::Transitions;
+use crate::{Error, Result, Widget};
 use crossterm::event::Event as CtEvent;
 use crossterm::{cursor, event, queue, style, terminal};
 use futures_core::stream::Stream;
-use std::io::{Stdout, Write};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
 use std::{
     future::Future,
     pin::Pin,
-    task::{Context, Poll},
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll, Waker},
 };
 
+/// Source of terminal events
+trait EventSource {
+    /// Poll for the next event, registering the task's waker if pending
+    fn poll_next_event(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<crossterm::Result<CtEvent>>>;
+
+    /// Read the next event if one is ready, without registering a waker
+    fn try_next_event(&mut self) -> Option<crossterm::Result<CtEvent>>;
+}
+
+/// Terminal events from crossterm
+///
+/// The event stream must only be polled with the waker of the task which
+/// awaits it: its wake task keeps the waker from the first pending poll
+/// until an event arrives.
+struct TermEvents(event::EventStream);
+
+impl EventSource for TermEvents {
+    fn poll_next_event(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<crossterm::Result<CtEvent>>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+
+    fn try_next_event(&mut self) -> Option<crossterm::Result<CtEvent>> {
+        match event::poll(Duration::ZERO) {
+            Ok(true) => Some(event::read()),
+            Ok(false) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
 /// Needed in order to await the stream.
-struct EvStreamFut(Box<dyn Stream<Item = crossterm::Result<CtEvent>> + Unpin>);
+struct EvStreamFut(Box<dyn EventSource>);
+
+impl EvStreamFut {
+    /// Create a future for terminal events
+    fn terminal() -> Self {
+        EvStreamFut(Box::new(TermEvents(event::EventStream::new())))
+    }
+
+    /// Read the next event if one is ready, without waiting
+    fn try_next(&mut self) -> Option<crossterm::Result<CtEvent>> {
+        self.0.try_next_event()
+    }
+}
 
 impl Future for EvStreamFut {
     type Output = Option<crossterm::Result<CtEvent>>;
@@ -26,108 +91,1046 @@ impl Future for EvStreamFut {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Self::Output> {
-        Pin::new(&mut self.0).poll_next(cx)
+        self.0.poll_next_event(cx)
+    }
+}
+
+/// Future which polls for an event once
+///
+/// It is ready with `None` instead of pending, so that the task's waker is
+/// registered with the stream even when no event is waiting.
+struct PollOnce<'a>(&'a mut EvStreamFut);
+
+impl Future for PollOnce<'_> {
+    type Output = Option<crossterm::Result<CtEvent>>;
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        match Pin::new(&mut *self.0).poll(cx) {
+            Poll::Ready(ev) => Poll::Ready(ev),
+            Poll::Pending => Poll::Ready(None),
+        }
+    }
+}
+
+/// Duration of a visual bell flash
+const FLASH_DURATION: Duration = Duration::from_millis(80);
+
+/// Interval between frames of an animation
+pub(crate) const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Timer which wakes a task at a deadline
+///
+/// One thread is spawned on first use, and re-armed for each deadline.
+#[derive(Default)]
+struct Timer {
+    /// State shared with the timer thread
+    shared: Option<Arc<TimerShared>>,
+}
+
+/// Timer state shared with the timer thread
+#[derive(Default)]
+struct TimerShared {
+    /// Armed deadline and waker
+    state: Mutex<TimerState>,
+    /// Signalled when the state changes
+    changed: Condvar,
+}
+
+/// State of a timer
+#[derive(Default)]
+struct TimerState {
+    /// Deadline and waker to wake at that time
+    armed: Option<(Instant, Waker)>,
+    /// Timer dropped; thread should exit
+    shutdown: bool,
+}
+
+impl Timer {
+    /// Arm the timer, replacing any previous deadline
+    ///
+    /// * `deadline`: Time to wake the task
+    /// * `waker`: Waker of the task
+    fn arm(&mut self, deadline: Instant, waker: &Waker) {
+        let shared = self.shared.get_or_insert_with(|| {
+            let shared = Arc::new(TimerShared::default());
+            let thread_shared = Arc::clone(&shared);
+            std::thread::spawn(move || thread_shared.run());
+            shared
+        });
+        if let Ok(mut state) = shared.state.lock() {
+            state.armed = Some((deadline, waker.clone()));
+        }
+        shared.changed.notify_one();
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(shared) = &self.shared {
+            if let Ok(mut state) = shared.state.lock() {
+                state.shutdown = true;
+            }
+            shared.changed.notify_one();
+        }
+    }
+}
+
+impl TimerShared {
+    /// Run the timer thread until shut down
+    fn run(&self) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+        while !state.shutdown {
+            let wait = match &state.armed {
+                Some((deadline, _)) => {
+                    deadline.checked_duration_since(Instant::now())
+                }
+                None => Some(Duration::MAX),
+            };
+            state = match wait {
+                Some(wait) if !wait.is_zero() => {
+                    match self.changed.wait_timeout(state, wait) {
+                        Ok((state, _)) => state,
+                        Err(_) => return,
+                    }
+                }
+                _ => {
+                    if let Some((_, waker)) = state.armed.take() {
+                        waker.wake();
+                    }
+                    state
+                }
+            };
+        }
+    }
+}
+
+/// Next event, or `None` at a deadline
+///
+/// Events take precedence over the deadline.
+struct EventOrDelay<'a> {
+    /// Event stream
+    ev_stream: &'a mut EvStreamFut,
+    /// Timer to wake the task at the deadline
+    timer: &'a mut Timer,
+    /// Time when ready
+    deadline: Instant,
+}
+
+impl Future for EventOrDelay<'_> {
+    type Output = Option<crossterm::Result<CtEvent>>;
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut *self.ev_stream).poll(cx) {
+            return Poll::Ready(Some(ev));
+        }
+        // An ended stream has no more events, so just wait
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(None);
+        }
+        let deadline = self.deadline;
+        self.timer.arm(deadline, cx.waker());
+        Poll::Pending
+    }
+}
+
+/// Event source with no events, for headless screens
+struct NoEvents;
+
+impl EventSource for NoEvents {
+    fn poll_next_event(
+        &mut self,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<crossterm::Result<CtEvent>>> {
+        Poll::Ready(None)
+    }
+
+    fn try_next_event(&mut self) -> Option<crossterm::Result<CtEvent>> {
+        None
+    }
+}
+
+/// Rendering left on the normal screen when a [Screen] exits
+#[non_exhaustive]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ExitRender {
+    /// Render nothing (previous screen contents are restored)
+    #[default]
+    Nothing,
+    /// Print the final frame drawn
+    ///
+    /// The frame is printed as plain text, or with colors and attributes
+    /// if `styled`.
+    FinalFrame {
+        /// Include styles as ANSI escape sequences
+        styled: bool,
+    },
+    /// Print custom text
+    Custom(String),
+}
+
+/// Popup layer drawn over a grid area
+///
+/// A popup is a second grid area, such as a dialog or menu, laid out
+/// within a bounding box on top of the base layout.  While present, it
+/// receives all input; the base layer is drawn underneath, but its widgets
+/// do not receive any events.  When a popup first appears, its first
+/// focusable widget is given focus.
+///
+/// ```rust
+/// # use semtext::layout::BBox;
+/// # use semtext::widget::Label;
+/// # use semtext::{grid_area, Popup};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let a = Label::new("Are you sure?");
+/// let dialog = grid_area!([a])?;
+/// let popup = Popup::new(&dialog, BBox::new(10, 5, 20, 3))
+///     .with_dim(true)
+///     .with_dismiss(true);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Popup<'p, 'a> {
+    /// Grid area of popup
+    area: &'p GridArea<'a>,
+    /// Bounding box of popup
+    bbox: BBox,
+    /// Dim the base layer
+    dim: bool,
+    /// Dismiss with a click outside
+    dismiss: bool,
+}
+
+/// Popup layer state, from last layout
+struct PopupLayer {
+    /// Index of first popup widget box
+    start: usize,
+    /// Bounding box of popup
+    bbox: BBox,
+    /// Dim the base layer
+    dim: bool,
+    /// Dismiss with a click outside
+    dismiss: bool,
+    /// Mouse pointer state of popup widgets
+    pointer: Pointer,
+}
+
+/// Terminal modes enabled by a [Screen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TermModes {
+    /// Mouse capture
+    mouse_capture: bool,
+    /// Alternate screen
+    alternate_screen: bool,
+    /// Line wrap disabled
+    no_line_wrap: bool,
+}
+
+impl Default for TermModes {
+    fn default() -> Self {
+        TermModes {
+            mouse_capture: true,
+            alternate_screen: true,
+            no_line_wrap: true,
+        }
+    }
+}
+
+/// Builder for a [Screen]
+///
+/// By default, mouse capture and the alternate screen are enabled, and line
+/// wrap is disabled.  A keyboard-driven application can opt out of mouse
+/// capture to keep native text selection working:
+///
+/// ```no_run
+/// # use semtext::Screen;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let screen = Screen::builder().mouse_capture(false).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScreenBuilder {
+    /// Terminal modes to enable
+    modes: TermModes,
+}
+
+impl ScreenBuilder {
+    /// Set whether mouse events are captured
+    pub fn mouse_capture(mut self, capture: bool) -> Self {
+        self.modes.mouse_capture = capture;
+        self
+    }
+
+    /// Set whether the alternate screen is used
+    ///
+    /// Without it, the screen is drawn over the normal terminal contents.
+    pub fn alternate_screen(mut self, alternate: bool) -> Self {
+        self.modes.alternate_screen = alternate;
+        self
+    }
+
+    /// Set whether line wrap is disabled
+    pub fn disable_line_wrap(mut self, disable: bool) -> Self {
+        self.modes.no_line_wrap = disable;
+        self
+    }
+
+    /// Build the screen
+    ///
+    /// Only the enabled modes are restored when the screen is dropped.
+    pub fn build(self) -> Result<Screen> {
+        Screen::with_modes(self.modes)
     }
 }
 
+/// Bell mode
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BellMode {
+    /// Audible terminal bell
+    #[default]
+    Audible,
+    /// Visual flash (reverse video)
+    Visual,
+    /// No bell
+    None,
+}
+
+/// Bell request state
+#[derive(Default)]
+struct Bell {
+    /// Bell mode
+    mode: BellMode,
+    /// Area of pending bell request
+    pending: Option<BBox>,
+}
+
+/// Default double-click window
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Kind of key event
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum KeyKind {
+    /// Initial press
+    Press,
+    /// Auto-repeat while held
+    Repeat,
+    /// Release
+    Release,
+}
+
+/// Mouse pointer routing state
+struct Pointer {
+    /// Focus follows mouse mode
+    focus_follows: bool,
+    /// Index of widget under the pointer
+    hovered: Option<usize>,
+    /// Index of widget with keyboard focus
+    focused: Option<usize>,
+    /// Index of widget where a mouse button was pressed
+    pressed: Option<usize>,
+    /// Maximum time between clicks of a double-click
+    double_click: Duration,
+    /// Button, position and time of last button release
+    last_up: Option<(MouseButton, ScreenPos, Instant)>,
+}
+
 /// Terminal screen
 pub struct Screen {
     /// Standard Output
-    out: Stdout,
+    out: Output<Box<dyn Write>>,
     /// Dimensions of screen in text cells
     dim: Dim,
     /// Style theme
     theme: Theme,
-    /// Current text style
+    /// Current text style of terminal
     style: Option<TextStyle>,
     /// Key / action map
     keymap: KeyMap,
+    /// Show the prompt of an armed key confirmation
+    show_prompt: bool,
     /// Event stream future.
     ev_stream: EvStreamFut,
+    /// Timer for step timeouts
+    timer: Timer,
+    /// Rendering on exit
+    exit_render: ExitRender,
+    /// Mouse pointer state
+    pointer: Pointer,
+    /// Bell state
+    bell: Bell,
+    /// Clear entire screen on next draw
+    clear_all: bool,
+    /// Widget bounding boxes painted on last draw
+    painted: Vec<BBox>,
+    /// Cells drawn in current frame
+    snapshot: Snapshot,
+    /// Cells displayed on terminal, if known
+    displayed: Option<Snapshot>,
+    /// Headless screen (terminal never touched)
+    headless: bool,
+    /// Widget draw errors from last draw
+    draw_errors: Vec<(usize, Error)>,
+    /// Style transitions
+    transitions: Transitions,
+    /// Time of current frame
+    frame_time: Instant,
+    /// Time of next scheduled frame, if any
+    next_frame: Option<Instant>,
+    /// Fixed clock time, for testing
+    fixed_time: Option<Instant>,
+    /// Depth of nested batches deferring redraws
+    batch_depth: usize,
+    /// Filter for returned actions
+    action_filter: Option<ActionFilter>,
+    /// Session state to restore on next layout
+    session: Option<SessionState>,
+    /// Return resize actions from steps
+    notify_resize: bool,
+    /// Check for a screen smaller than the grid area minimum size
+    check_size: bool,
+    /// Minimum size of grid area, if larger than the screen
+    too_small: Option<Dim>,
+    /// Terminal modes enabled
+    modes: TermModes,
+    /// Terminal released by suspend or close
+    released: bool,
+    /// Position of terminal cursor, if shown
+    cursor: Option<(u16, u16)>,
+    /// Terminal color support
+    color_support: ColorSupport,
+    /// Popup layer, if present
+    popup: Option<PopupLayer>,
+}
+
+/// Filter applied to actions before they are returned
+type ActionFilter = Box<dyn FnMut(Action) -> Option<Action>>;
+
+impl<'p, 'a> Popup<'p, 'a> {
+    /// Create a new popup
+    ///
+    /// * `area`: Grid area of popup
+    /// * `bbox`: Bounding box of popup, clipped to the screen
+    pub fn new(area: &'p GridArea<'a>, bbox: BBox) -> Self {
+        Popup {
+            area,
+            bbox,
+            dim: false,
+            dismiss: false,
+        }
+    }
+
+    /// Set whether the base layer is dimmed
+    pub fn with_dim(mut self, dim: bool) -> Self {
+        self.dim = dim;
+        self
+    }
+
+    /// Set whether a click outside dismisses the popup
+    ///
+    /// When enabled, pressing a mouse button outside of the popup returns
+    /// [Action::Dismissed].  Otherwise, those events are swallowed.
+    ///
+    /// [Action::Dismissed]: input/enum.Action.html#variant.Dismissed
+    pub fn with_dismiss(mut self, dismiss: bool) -> Self {
+        self.dismiss = dismiss;
+        self
+    }
 }
 
 impl Screen {
     /// Create a new Screen
+    ///
+    /// Use a [builder](Screen::builder) to configure terminal modes.
     pub fn new() -> Result<Self> {
+        Screen::builder().build()
+    }
+
+    /// Create a builder to configure terminal modes
+    pub fn builder() -> ScreenBuilder {
+        ScreenBuilder::default()
+    }
+
+    /// Create a new Screen with terminal modes
+    fn with_modes(modes: TermModes) -> Result<Self> {
         let (width, height) = terminal::size()?;
         let dim = Dim::new(width, height);
         let theme = Theme::default();
         let style = None;
         let keymap = KeyMap::default();
         terminal::enable_raw_mode()?;
-        let mut out = std::io::stdout();
-        queue!(
-            out,
-            terminal::EnterAlternateScreen,
-            cursor::Hide,
-            terminal::DisableLineWrap,
-            terminal::Clear(terminal::ClearType::All),
-            event::EnableMouseCapture,
-        )?;
-        let ev_stream = EvStreamFut(Box::new(event::EventStream::new()));
+        let mut out: Output<Box<dyn Write>> =
+            Output::new(Box::new(std::io::stdout()));
+        queue_setup(&mut out, modes)?;
+        let ev_stream = EvStreamFut::terminal();
+        let exit_render = ExitRender::default();
+        let pointer = Pointer::default();
+        let bell = Bell::default();
+        let clear_all = true;
+        let painted = vec![];
+        let snapshot = Snapshot::new(dim);
+        let displayed = None;
+        let headless = false;
+        let draw_errors = vec![];
+        let transitions = Transitions::default();
+        let frame_time = Instant::now();
+        let batch_depth = 0;
+        let action_filter = None;
+        let session = None;
+        let notify_resize = false;
+        let check_size = true;
+        let too_small = None;
         Ok(Screen {
+            modes,
+            released: false,
+            cursor: None,
+            color_support: ColorSupport::detect(),
+            popup: None,
             out,
             dim,
             theme,
             style,
             keymap,
+            show_prompt: true,
             ev_stream,
+            timer: Timer::default(),
+            exit_render,
+            pointer,
+            bell,
+            clear_all,
+            painted,
+            snapshot,
+            displayed,
+            headless,
+            draw_errors,
+            transitions,
+            frame_time,
+            next_frame: None,
+            fixed_time: None,
+            batch_depth,
+            action_filter,
+            session,
+            notify_resize,
+            check_size,
+            too_small,
         })
     }
 
+    /// Create a headless screen, which draws only into memory
+    ///
+    /// * `dim`: Dimensions in text cells
+    /// * `theme`: Style theme
+    ///
+    /// The terminal is never touched, so this can be used for testing.
+    /// Events are fed in with [step_with_events](Screen::step_with_events),
+    /// and the drawn cells are read back with [text](Screen::text).
+    ///
+    /// Unlike a terminal screen, widgets are clipped when the screen is too
+    /// small, unless [checked](Screen::set_check_size).
+    pub fn headless(dim: Dim, theme: Theme) -> Self {
+        Screen {
+            out: Output::new(Box::new(std::io::sink())),
+            dim,
+            theme,
+            style: None,
+            keymap: KeyMap::default(),
+            show_prompt: true,
+            ev_stream: EvStreamFut(Box::new(NoEvents)),
+            timer: Timer::default(),
+            exit_render: ExitRender::default(),
+            pointer: Pointer::default(),
+            bell: Bell::default(),
+            clear_all: true,
+            painted: vec![],
+            snapshot: Snapshot::new(dim),
+            displayed: None,
+            headless: true,
+            draw_errors: vec![],
+            transitions: Transitions::default(),
+            frame_time: Instant::now(),
+            next_frame: None,
+            fixed_time: None,
+            batch_depth: 0,
+            action_filter: None,
+            session: None,
+            notify_resize: false,
+            check_size: false,
+            too_small: None,
+            modes: TermModes::default(),
+            released: false,
+            cursor: None,
+            color_support: ColorSupport::TrueColor,
+            popup: None,
+        }
+    }
+
+    /// Suspend the screen, releasing the terminal
+    ///
+    /// The terminal is restored as if the screen were dropped, but nothing
+    /// is printed for the [exit render](ExitRender).  No events are read
+    /// while suspended, so an external program such as `$EDITOR` can be run
+    /// with the terminal; the screen should not be stepped until it is
+    /// [resumed](Screen::resume).  On Unix, an application can also suspend
+    /// itself by binding a key (like `Ctrl+Z`) to an action which calls
+    /// this before raising `SIGTSTP`.
+    ///
+    /// ```no_run
+    /// # use semtext::Screen;
+    /// # use std::process::Command;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut screen = Screen::new()?;
+    /// screen.suspend()?;
+    /// Command::new("vi").arg("notes.txt").status()?;
+    /// screen.resume()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn suspend(&mut self) -> Result<()> {
+        if self.headless || self.released {
+            return Ok(());
+        }
+        self.ev_stream = EvStreamFut(Box::new(NoEvents));
+        queue_cleanup(&mut self.out, None, self.modes)?;
+        self.out.flush()?;
+        terminal::disable_raw_mode()?;
+        self.released = true;
+        Ok(())
+    }
+
+    /// Resume a suspended screen, taking the terminal back
+    ///
+    /// The terminal modes are enabled again, and the screen is fully
+    /// redrawn on the next step.
+    pub fn resume(&mut self) -> Result<()> {
+        if !self.released {
+            return Ok(());
+        }
+        terminal::enable_raw_mode()?;
+        queue_setup(&mut self.out, self.modes)?;
+        self.out.flush()?;
+        let (width, height) = terminal::size()?;
+        self.dim = Dim::new(width, height);
+        self.ev_stream = EvStreamFut::terminal();
+        self.style = None;
+        self.clear_all = true;
+        self.released = false;
+        self.cursor = None;
+        Ok(())
+    }
+
+    /// Render a grid area once, without waiting for events
+    pub(crate) fn render(&mut self, area: &GridArea<'_>) -> Result<()> {
+        let widget_boxes = self.layout(area);
+        self.draw(&widget_boxes)
+    }
+
+    /// Lay out a grid area in a new layout pass
+    fn layout<'a>(
+        &mut self,
+        area: &GridArea<'a>,
+    ) -> Vec<(&'a dyn Widget, BBox)> {
+        self.layout_popup(area, None)
+    }
+
+    /// Lay out a grid area and popup in a new layout pass
+    ///
+    /// Popup widget boxes follow the base widget boxes.
+    fn layout_popup<'a>(
+        &mut self,
+        area: &GridArea<'a>,
+        popup: Option<&Popup<'_, 'a>>,
+    ) -> Vec<(&'a dyn Widget, BBox)> {
+        let mut widget_boxes = self.layout_base(area);
+        match popup {
+            Some(popup) if self.too_small.is_none() => {
+                let bbox = popup.bbox.clip(self.bbox());
+                let start = widget_boxes.len();
+                widget_boxes.extend(popup.area.widget_boxes(bbox, &self.theme));
+                let pointer = match self.popup.take() {
+                    Some(layer) => layer.pointer,
+                    None => {
+                        let focused = widget_boxes[start..]
+                            .iter()
+                            .position(|(w, _)| w.focusable());
+                        if let Some(i) = focused {
+                            widget_boxes[start + i].0.focus(FocusEvent::Offer);
+                        }
+                        Pointer {
+                            focus_follows: self.pointer.focus_follows,
+                            focused,
+                            double_click: self.pointer.double_click,
+                            ..Default::default()
+                        }
+                    }
+                };
+                self.popup = Some(PopupLayer {
+                    start,
+                    bbox,
+                    dim: popup.dim,
+                    dismiss: popup.dismiss,
+                    pointer,
+                });
+            }
+            _ => self.popup = None,
+        }
+        widget_boxes
+    }
+
+    /// Lay out the base grid area
+    fn layout_base<'a>(
+        &mut self,
+        area: &GridArea<'a>,
+    ) -> Vec<(&'a dyn Widget, BBox)> {
+        if let Some(session) = self.session.take() {
+            session.restore(area);
+            if let Some(i) = session.focused_index(area) {
+                let leaves = area.leaf_widgets();
+                if let Some(w) =
+                    self.pointer.focused.and_then(|f| leaves.get(f))
+                {
+                    w.focus(FocusEvent::Take);
+                }
+                leaves[i].focus(FocusEvent::Offer);
+                self.pointer.focused = Some(i);
+            }
+        }
+        self.theme.next_pass();
+        let too_small = if self.check_size {
+            let min = area.min_size(&self.theme);
+            (self.dim.width < min.width || self.dim.height < min.height)
+                .then_some(min)
+        } else {
+            None
+        };
+        if too_small != self.too_small {
+            self.too_small = too_small;
+            self.clear_all = true;
+        }
+        if too_small.is_some() {
+            return vec![];
+        }
+        area.widget_boxes(self.bbox(), &self.theme)
+    }
+
+    /// Get the snapshot of cells drawn in the last frame
+    pub(crate) fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    /// Get the plain text of cells drawn in the last frame
+    ///
+    /// Each row ends with a newline.
+    pub fn text(&self) -> String {
+        self.snapshot.to_plain()
+    }
+
+    /// Set the width policy for ambiguous-width characters
+    ///
+    /// The policy applies to all width calculations on the current thread,
+    /// and should match the terminal.  [AmbiguousWidth::from_env] can be
+    /// used to guess it from the locale.  The screen is fully redrawn on the
+    /// next draw.
+    pub fn set_ambiguous_width(&mut self, policy: AmbiguousWidth) {
+        policy.set_current();
+        self.clear_all = true;
+    }
+
+    /// Set the width policy for emoji presentation sequences
+    ///
+    /// The policy applies to all width calculations on the current thread.
+    /// It should be `Narrow` only for terminals which draw VS16 sequences
+    /// with the width of the base character.  The screen is fully redrawn on
+    /// the next draw.
+    pub fn set_emoji_width(&mut self, policy: EmojiWidth) {
+        policy.set_current();
+        self.clear_all = true;
+    }
+
+    /// Export the session state of a grid area
+    ///
+    /// The state of every tagged widget which implements [PersistState] is
+    /// saved, keyed by its tag, along with the tag of the focused widget.
+    ///
+    /// [PersistState]: trait.PersistState.html
+    pub fn export_session(&self, area: &GridArea<'_>) -> SessionState {
+        SessionState::save(area, self.pointer.focused)
+    }
+
+    /// Import session state
+    ///
+    /// The state is restored to tagged widgets of the grid area passed to
+    /// the next [step](Screen::step) or [pump](Screen::pump), before it is
+    /// drawn.  State for tags which are not in the grid area is ignored.  If
+    /// the focused tag is a focusable widget, it is given focus.
+    pub fn import_session(&mut self, session: SessionState) {
+        self.session = Some(session);
+    }
+
+    /// Set whether resizes are returned as actions
+    ///
+    /// When the terminal is resized, the layout is always recomputed and
+    /// redrawn before waiting for more input, with a burst of resizes
+    /// redrawn only once.  By default, [Action::Resize] is not returned.
+    ///
+    /// [Action::Resize]: input/enum.Action.html#variant.Resize
+    pub fn set_notify_resize(&mut self, notify: bool) {
+        self.notify_resize = notify;
+    }
+
+    /// Set whether to check that the screen is large enough
+    ///
+    /// When checked, a screen smaller than the [minimum size] of the grid
+    /// area shows a centered "Terminal too small" message instead of
+    /// widgets clipped into each other.  This is enabled by default, except
+    /// for [headless](Screen::headless) screens.
+    ///
+    /// [minimum size]: layout/struct.GridArea.html#method.min_size
+    pub fn set_check_size(&mut self, check: bool) {
+        self.check_size = check;
+    }
+
+    /// Set the terminal color support
+    ///
+    /// By default, support is [detected](ColorSupport::detect) from the
+    /// environment.  Colors which the terminal does not support are mapped
+    /// to the nearest supported color when written; drawn cells keep the
+    /// theme colors.
+    pub fn set_color_support(&mut self, support: ColorSupport) {
+        self.color_support = support;
+        self.clear_all = true;
+    }
+
     /// Set the key / action map
     pub fn set_keymap(&mut self, keymap: KeyMap) {
         self.keymap = keymap;
     }
 
+    /// Set a filter for actions returned from [step](Screen::step) and
+    /// [pump](Screen::pump)
+    ///
+    /// The filter is called with each action just before it would be
+    /// returned.  Returning `None` swallows the action, and returning
+    /// `Some` substitutes another one.  It is only called for actions which
+    /// actually occurred, so it cannot produce an action from nothing.
+    /// Redraws handled internally are not filtered.
+    pub fn set_action_filter(
+        &mut self,
+        filter: Box<dyn FnMut(Action) -> Option<Action>>,
+    ) {
+        self.action_filter = Some(filter);
+    }
+
+    /// Apply the action filter to an action
+    fn filter_action(&mut self, action: Action) -> Option<Action> {
+        match (action, &mut self.action_filter) {
+            (Action::Redraw(), _) | (_, None) => Some(action),
+            (_, Some(filter)) => filter(action),
+        }
+    }
+
+    /// Get the prompt of an armed key confirmation
+    ///
+    /// Confirmations are bound with [KeyMap::bind_confirm].  The screen
+    /// shows the prompt on its bottom row until the confirmation is
+    /// completed, cancelled or times out.  An application which displays it
+    /// elsewhere (in a status bar, for example) can turn that off with
+    /// [set_show_prompt](Screen::set_show_prompt).
+    pub fn confirm_prompt(&self) -> Option<&str> {
+        self.keymap.prompt(self.now())
+    }
+
+    /// Set whether the prompt of an armed key confirmation is shown
+    ///
+    /// It is shown on the bottom row of the screen by default.
+    pub fn set_show_prompt(&mut self, show: bool) {
+        self.show_prompt = show;
+    }
+
+    /// Record the screen to an asciicast v2 file
+    ///
+    /// * `path`: Path of cast file
+    ///
+    /// Event times are relative to the start of recording.  The screen is
+    /// fully redrawn on the next step, so the recording starts complete.
+    ///
+    /// ## Return
+    ///
+    /// A recorder, which stops recording when dropped.
+    pub fn record_cast<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<CastRecorder> {
+        self.out.flush()?;
+        let writer = Box::new(BufWriter::new(File::create(path)?));
+        let recorder = self.out.record(writer, self.dim)?;
+        self.style = None;
+        self.clear_all = true;
+        Ok(recorder)
+    }
+
     /// Set the screen title
     pub fn set_title(&mut self, title: &str) -> Result<()> {
         queue!(self.out, terminal::SetTitle(title))?;
         Ok(())
     }
 
+    /// Set the rendering left on the normal screen after exit
+    pub fn set_exit_render(&mut self, exit_render: ExitRender) {
+        self.exit_render = exit_render;
+    }
+
+    /// Set focus follows mouse mode
+    ///
+    /// When enabled, moving the pointer onto a widget focuses it, just like
+    /// clicking on it.  Moving off of all widgets does not change focus.
+    pub fn set_focus_follows_mouse(&mut self, enable: bool) {
+        self.pointer.focus_follows = enable;
+    }
+
+    /// Set the double-click window
+    ///
+    /// When a mouse button is released twice in the same place within this
+    /// time (400 ms by default), the widget receives a
+    /// [DoubleClick](MouseEvent::DoubleClick) event after the second
+    /// `ButtonUp`.
+    pub fn set_double_click(&mut self, window: Duration) {
+        self.pointer.double_click = window;
+    }
+
+    /// Set the bell mode
+    pub fn set_bell_mode(&mut self, mode: BellMode) {
+        self.bell.mode = mode;
+    }
+
+    /// Ring the bell
+    ///
+    /// The bell is rung after the next frame is drawn.  In
+    /// [Visual](BellMode::Visual) mode, the whole screen is flashed.
+    pub fn bell(&mut self) {
+        self.request_bell(self.bbox());
+    }
+
+    /// Request a bell for an area of the screen
+    pub(crate) fn request_bell(&mut self, area: BBox) {
+        self.bell.request(area);
+    }
+
     /// Set the theme
+    ///
+    /// The screen is fully redrawn with the new theme on the next draw,
+    /// so themes can be swapped at any time, such as from a key binding.
     pub fn set_theme(&mut self, theme: Theme) {
         self.theme = theme;
+        self.clear_all = true;
+    }
+
+    /// Get the theme mutably
+    ///
+    /// The screen is fully redrawn on the next draw, just like with
+    /// [set_theme](Screen::set_theme).
+    pub fn theme_mut(&mut self) -> &mut Theme {
+        self.clear_all = true;
+        &mut self.theme
     }
 
     /// Get the screen bounding box
-    fn bbox(&self) -> BBox {
+    pub(crate) fn bbox(&self) -> BBox {
         BBox::new(0, 0, self.dim.width, self.dim.height)
     }
 
     /// Get the theme
-    pub(crate) fn theme(&self) -> &Theme {
+    pub fn theme(&self) -> &Theme {
         &self.theme
     }
 
-    /// Clear the screen (fill with the space character)
-    fn clear(&mut self) -> Result<()> {
-        queue!(self.out, terminal::Clear(terminal::ClearType::All))?;
-        Ok(())
+    /// Get mutable style transitions
+    pub(crate) fn transitions_mut(&mut self) -> &mut Transitions {
+        &mut self.transitions
     }
 
-    /// Get cells contained by a bounding box
-    fn cells(&mut self, bbox: BBox) -> Option<Cells> {
-        let bbox = self.bbox().clip(bbox);
-        if bbox.dim().is_empty() {
-            None
-        } else {
-            Some(Cells::new(self, bbox))
+    /// Get the current time
+    fn now(&self) -> Instant {
+        self.fixed_time.unwrap_or_else(Instant::now)
+    }
+
+    /// Fix the clock time, for testing
+    #[cfg(test)]
+    pub(crate) fn set_time(&mut self, now: Instant) {
+        self.fixed_time = Some(now);
+    }
+
+    /// Get the time of the frame being drawn
+    pub(crate) fn frame_time(&self) -> Instant {
+        self.frame_time
+    }
+
+    /// Schedule a frame to be drawn
+    ///
+    /// * `at`: Time to draw the frame
+    ///
+    /// Only the earliest scheduled frame is kept.
+    pub(crate) fn request_frame(&mut self, at: Instant) {
+        self.next_frame = Some(self.next_frame.map_or(at, |t| t.min(at)));
+    }
+
+    /// Get the time of the next scheduled frame
+    ///
+    /// Widgets which are animating, such as a smoothly scrolling
+    /// [ScrollView], schedule frames while drawing.  The
+    /// [step](Screen::step) functions draw these frames while waiting for
+    /// events; an application using [pump](Screen::pump) should pump again
+    /// by this time.
+    ///
+    /// [ScrollView]: widget/struct.ScrollView.html
+    pub fn next_frame(&self) -> Option<Instant> {
+        self.next_frame
+    }
+
+    /// Get the displayed style for a style set by a widget
+    pub(crate) fn transition_style(&mut self, st: TextStyle) -> TextStyle {
+        let duration = self.theme.transition();
+        if duration.is_zero() {
+            return st;
+        }
+        self.transitions.style(st, duration, self.frame_time)
+    }
+
+    /// Clear the screen (fill with the space character)
+    ///
+    /// Every cell is written to the terminal on the next flush.
+    fn clear(&mut self) {
+        if self.snapshot.dim() != self.dim {
+            self.snapshot = Snapshot::new(self.dim);
         }
+        self.snapshot.clear();
+        self.displayed = None;
     }
 
     /// Set the background color
+    ///
+    /// Colors are degraded to those supported by the terminal.
     fn set_background_color(&mut self, color: Color) -> Result<()> {
-        if self.style.map_or(true, |s| s.background() != color) {
+        if self.style.is_none_or(|s| s.background() != color) {
+            let color = color.quantize(self.color_support);
             queue!(self.out, style::SetBackgroundColor(color.into()))?;
         }
         Ok(())
     }
 
     /// Set the foreground color
+    ///
+    /// Colors are degraded to those supported by the terminal.
     fn set_foreground_color(&mut self, color: Color) -> Result<()> {
-        if self.style.map_or(true, |s| s.foreground() != color) {
+        if self.style.is_none_or(|s| s.foreground() != color) {
+            let color = color.quantize(self.color_support);
             queue!(self.out, style::SetForegroundColor(color.into()))?;
         }
         Ok(())
@@ -144,8 +1147,8 @@ impl Screen {
         Ok(())
     }
 
-    /// Set the text style
-    pub(crate) fn set_style(&mut self, st: TextStyle) -> Result<()> {
+    /// Set the text style of the terminal
+    fn queue_style(&mut self, st: TextStyle) -> Result<()> {
         self.set_background_color(st.background())?;
         self.set_foreground_color(st.foreground())?;
         self.set_appearance(st.appearance())?;
@@ -153,131 +1156,2574 @@ impl Screen {
         Ok(())
     }
 
+    /// Set the text style
+    pub(crate) fn set_style(&mut self, st: TextStyle) -> Result<()> {
+        self.snapshot.set_style(st);
+        Ok(())
+    }
+
     /// Move cursor to a cell
     pub(crate) fn move_to(&mut self, col: u16, row: u16) -> Result<()> {
-        queue!(self.out, cursor::MoveTo(col, row))?;
+        self.snapshot.move_to(col, row);
         Ok(())
     }
 
     /// Move cursor right by a number of columns
     pub(crate) fn move_right(&mut self, col: u16) -> Result<()> {
-        queue!(self.out, cursor::MoveRight(col))?;
+        self.snapshot.move_right(col);
         Ok(())
     }
 
     /// Print a char at the cursor location
     pub(crate) fn print_char(&mut self, ch: char) -> Result<()> {
-        queue!(self.out, style::Print(ch))?;
+        self.snapshot.print_str(ch.encode_utf8(&mut [0; 4]));
         Ok(())
     }
 
     /// Print a str at the cursor location
     pub(crate) fn print_str(&mut self, st: &str) -> Result<()> {
-        queue!(self.out, style::Print(st))?;
+        self.snapshot.print_str(st);
         Ok(())
     }
 
-    /// Draw a grid area layout
-    fn draw(&mut self, widget_boxes: &[(&dyn Widget, BBox)]) -> Result<()> {
-        let pos = Pos::default();
-        let style = self.theme.style(StyleGroup::Enabled);
-        self.set_style(style)?;
-        self.clear()?;
-        for (widget, bbox) in widget_boxes.iter() {
-            if let Some(mut cells) = self.cells(*bbox) {
-                let style = cells.theme().style(widget.style_group());
-                cells.set_style(style)?;
-                widget.draw(&mut cells, pos)?;
+    /// Write cells changed since the last frame to the terminal
+    ///
+    /// Cursor moves are queued only when the next changed cell does not
+    /// follow the last one printed.
+    fn flush_cells(&mut self) -> Result<()> {
+        let snapshot = std::mem::replace(
+            &mut self.snapshot,
+            Snapshot::new(Dim::default()),
+        );
+        let displayed = self.displayed.take();
+        let mut cursor = None;
+        for (col, row, width, cell) in snapshot.changes(displayed.as_ref()) {
+            if cursor != Some((col, row)) {
+                queue!(self.out, cursor::MoveTo(col, row))?;
             }
+            self.queue_style(cell.style())?;
+            queue!(self.out, style::Print(cell.text()))?;
+            cursor = Some((col + width, row));
         }
-        self.out.flush()?;
+        self.displayed = Some(snapshot.clone());
+        self.snapshot = snapshot;
         Ok(())
     }
 
-    /// Check an event for an action
-    fn event_action(
-        &mut self,
-        ev: Event,
-        widget_boxes: &[(&dyn Widget, BBox)],
-    ) -> Option<Action> {
-        match ev {
-            Event::Resize(dim) => {
-                self.dim = dim;
-                Some(Action::Resize(dim))
-            }
-            Event::Key(key, mods) => {
-                // FIXME: check focused widget first
-                self.keymap.lookup(key, mods)
-            }
-            Event::Mouse(mev, mods, pos) => {
-                mouse_action(mev, mods, pos, widget_boxes)
-            }
-        }
+    /// Take the widget draw errors from the last frame
+    ///
+    /// When a widget fails to draw, its area is filled with an error
+    /// placeholder and the rest of the frame is drawn normally.  Each error
+    /// is paired with the index of the widget in its [GridArea].
+    ///
+    /// I/O errors writing to the terminal are not collected; they abort the
+    /// frame instead.
+    pub fn take_draw_errors(&mut self) -> Vec<(usize, Error)> {
+        std::mem::take(&mut self.draw_errors)
     }
 
-    /// Render a grid area and wait asynchronously for an action
-    pub async fn step(&mut self, area: &GridArea<'_>) -> Result<Action> {
-        let widget_boxes = area.widget_boxes(self.bbox(), &self.theme);
-        self.draw(&widget_boxes)?;
+    /// Defer redraws until [resume_redraw](Screen::resume_redraw)
+    ///
+    /// Nested calls are counted; redraws resume when each has been matched.
+    /// While deferred, [step](Screen::step) does not draw, and
+    /// [pump](Screen::pump) neither draws nor processes input.  Input events
+    /// stay queued until after the next draw.
+    pub fn defer_redraw(&mut self) {
+        self.batch_depth += 1;
+    }
+
+    /// Resume redraws deferred by [defer_redraw](Screen::defer_redraw)
+    ///
+    /// After the outermost call, the next `step` or `pump` draws all
+    /// changes in a single frame.
+    pub fn resume_redraw(&mut self) {
+        self.batch_depth = self.batch_depth.saturating_sub(1);
+    }
+
+    /// Apply a batch of changes with redraws deferred
+    ///
+    /// ```no_run
+    /// # use semtext::widget::{Button, Label};
+    /// # use semtext::{grid_area, Screen, Widget};
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut screen = Screen::new()?;
+    /// let a = Button::new(Label::new("A"));
+    /// let b = Button::new(Label::new("B"));
+    /// let grid = grid_area!([a b])?;
+    /// screen.batch(|screen| {
+    ///     a.disable();
+    ///     screen.pump(&grid, Duration::from_millis(10))?;
+    ///     b.disable();
+    ///     Ok::<_, semtext::Error>(())
+    /// })?;
+    /// // Both buttons are drawn disabled at once
+    /// screen.pump(&grid, Duration::from_millis(10))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn batch<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.defer_redraw();
+        let res = f(self);
+        self.resume_redraw();
+        res
+    }
+
+    /// Draw a grid area layout
+    ///
+    /// Cells which are not painted by a widget are cleared: every widget
+    /// bounding box is filled with blanks before the widget draws, and any
+    /// cells vacated since the last draw are filled with the background.
+    ///
+    /// Nothing is drawn while redraws are deferred.
+    fn draw(&mut self, widget_boxes: &[(&dyn Widget, BBox)]) -> Result<()> {
+        if self.batch_depth > 0 {
+            return Ok(());
+        }
+        self.draw_at(widget_boxes, self.now())
+    }
+
+    /// Draw a grid area layout at a frame time
+    fn draw_at(
+        &mut self,
+        widget_boxes: &[(&dyn Widget, BBox)],
+        now: Instant,
+    ) -> Result<()> {
+        self.frame_time = now;
+        self.next_frame = None;
+        self.keymap.expire(now);
+        let prompt = self
+            .keymap
+            .prompt(now)
+            .filter(|p| self.show_prompt && !p.is_empty())
+            .map(String::from);
+        let style = self.theme.style(StyleGroup::Enabled);
+        self.set_style(style)?;
+        let mut boxes: Vec<BBox> =
+            widget_boxes.iter().map(|(_, b)| *b).collect();
+        let popup = self.popup.as_ref().map(|l| (l.start, l.bbox, l.dim));
+        if let Some((_, bbox, _)) = popup {
+            boxes.push(bbox);
+        }
+        let vacated = if self.clear_all || self.snapshot.dim() != self.dim {
+            self.clear();
+            self.clear_all = false;
+            vec![]
+        } else {
+            vacated(&self.painted, &boxes)
+        };
+        let bell = self.bell.take();
+        let mode = self.bell.mode;
+        let blank = ' '.into_glyph()?;
+        let bbox = self.bbox();
+        let too_small = self.too_small;
+        let focused = match &self.popup {
+            Some(layer) => layer.pointer.focused.map(|i| i + layer.start),
+            None => self.pointer.focused,
+        };
+        // One set of cells is used for the whole frame
+        let mut cells = Cells::new(self, bbox);
+        for bbox in vacated {
+            cells.push_clip(bbox);
+            cells.fill(&blank)?;
+            cells.pop_clip();
+        }
+        if let Some(min) = too_small {
+            draw_too_small(&mut cells, min)?;
+        }
+        let mut errors = vec![];
+        let offset = ContentPos::default();
+        // Base layer widgets are dimmed underneath a popup
+        let start = popup.map_or(widget_boxes.len(), |p| p.0);
+        let overlay = popup
+            .filter(|p| p.2)
+            .map(|_| StyleOverlay::default().with_dim(true));
+        for (i, (widget, bbox)) in widget_boxes[..start].iter().enumerate() {
+            let res = draw_widget(&mut cells, *widget, *bbox, offset, overlay)?;
+            if let Err(err) = res {
+                errors.push((i, err));
+            }
+        }
+        if let Some((_, bbox, _)) = popup {
+            // Cells between popup widgets are blank
+            cells.push_clip(bbox);
+            cells.set_style(style)?;
+            cells.fill(&blank)?;
+            cells.pop_clip();
+        }
+        for (i, (widget, bbox)) in widget_boxes.iter().enumerate().skip(start) {
+            let res = draw_widget(&mut cells, *widget, *bbox, offset, None)?;
+            if let Err(err) = res {
+                errors.push((i, err));
+            }
+        }
+        if let Some(prompt) = &prompt {
+            let style = cells.theme().style(StyleGroup::Primary);
+            cells.set_style(style)?;
+            draw_prompt(&mut cells, prompt)?;
+        }
+        if let (Some(area), BellMode::Visual) = (bell, mode) {
+            let overlay = Some(StyleOverlay::default().with_reverse(true));
+            for (i, (widget, bbox)) in widget_boxes.iter().enumerate() {
+                if errors.iter().any(|(e, _)| *e == i) {
+                    continue;
+                }
+                if !area.clip(*bbox).dim().is_empty() {
+                    let res = draw_widget(
+                        &mut cells, *widget, *bbox, offset, overlay,
+                    )?;
+                    if let Err(err) = res {
+                        errors.push((i, err));
+                    }
+                }
+            }
+        }
+        cells.end_frame();
+        if self.transitions.is_running() {
+            self.request_frame(now + FRAME_INTERVAL);
+        }
+        // An armed confirmation is disarmed when it times out
+        if let Some(deadline) = self.keymap.deadline() {
+            self.request_frame(deadline);
+        }
+        // A visual bell flash is cleared by the next frame
+        if let (Some(_), BellMode::Visual) = (bell, mode) {
+            self.request_frame(now + FLASH_DURATION);
+        }
+        self.draw_errors = errors;
+        self.painted = boxes;
+        let cursor = focused
+            .and_then(|i| widget_boxes.get(i))
+            .and_then(|(widget, bbox)| cursor_pos(*widget, *bbox));
+        // Hidden while printing, to avoid flicker
+        if self.cursor.is_some() {
+            queue!(self.out, cursor::Hide)?;
+        }
+        self.flush_cells()?;
+        if let Some((col, row)) = cursor {
+            queue!(self.out, cursor::MoveTo(col, row), cursor::Show)?;
+        }
+        self.cursor = cursor;
+        if let (Some(_), BellMode::Audible) = (bell, mode) {
+            queue_bell(&mut self.out)?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+
+    /// Check an event for an action
+    ///
+    /// Bells requested by widgets while handling the event are collected,
+    /// and a redraw is needed to ring them.
+    fn event_action(
+        &mut self,
+        ev: Event,
+        widget_boxes: &[(&dyn Widget, BBox)],
+    ) -> Option<Action> {
+        let action = self.input_action(ev, widget_boxes);
+        for (widget, bbox) in widget_boxes {
+            if widget.take_bell() {
+                self.request_bell(*bbox);
+            }
+        }
+        match action {
+            None if self.bell.pending.is_some() => Some(Action::Redraw()),
+            _ => action,
+        }
+    }
+
+    /// Check an input event for an action
+    fn input_action(
+        &mut self,
+        ev: Event,
+        widget_boxes: &[(&dyn Widget, BBox)],
+    ) -> Option<Action> {
+        match ev {
+            Event::Resize(dim) => {
+                self.dim = dim;
+                self.clear_all = true;
+                Some(Action::Resize(dim))
+            }
+            Event::Key(key, mods) => self
+                .key_action(KeyKind::Press, key, mods, widget_boxes)
+                .or_else(|| self.keymap.press(key, mods, self.now())),
+            Event::KeyRepeat(key, mods) => {
+                self.key_action(KeyKind::Repeat, key, mods, widget_boxes)
+            }
+            Event::KeyRelease(key, mods) => {
+                self.key_action(KeyKind::Release, key, mods, widget_boxes)
+            }
+            Event::Mouse(mev, mods, pos) => match &mut self.popup {
+                Some(layer) => {
+                    if layer.dismiss
+                        && matches!(mev, MouseEvent::ButtonDown(_))
+                        && !layer.bbox.contains(*pos)
+                    {
+                        return Some(Action::Dismissed());
+                    }
+                    let boxes = &widget_boxes[layer.start..];
+                    layer.pointer.mouse_action(mev, mods, pos, boxes)
+                }
+                None => self.pointer.mouse_action(mev, mods, pos, widget_boxes),
+            },
+        }
+    }
+
+    /// Handle a key action, with the pointer state of the active layer
+    ///
+    /// Only key presses are looked up in the key map, so that an action is
+    /// not triggered again by repeats.
+    fn key_action(
+        &mut self,
+        kind: KeyKind,
+        key: KeyPress,
+        mods: ModKeys,
+        widget_boxes: &[(&dyn Widget, BBox)],
+    ) -> Option<Action> {
+        match &mut self.popup {
+            Some(layer) => {
+                let boxes = &widget_boxes[layer.start..];
+                layer.pointer.key_action(kind, key, mods, boxes)
+            }
+            None => self.pointer.key_action(kind, key, mods, widget_boxes),
+        }
+    }
+
+    /// Render a grid area and wait asynchronously for an action
+    ///
+    /// Nothing is rendered while redraws are deferred, so this should not
+    /// be awaited within a [batch](Screen::batch).
+    ///
+    /// # Errors
+    ///
+    /// [Error::EventStreamClosed] if the event stream ends, since no more
+    /// actions can happen.  An error reading an event is returned as
+    /// [Error::Io]; the screen can still be stepped again afterwards.
+    ///
+    /// [Error::EventStreamClosed]: enum.Error.html#variant.EventStreamClosed
+    /// [Error::Io]: enum.Error.html#variant.Io
+    pub async fn step(&mut self, area: &GridArea<'_>) -> Result<Action> {
+        self.step_with_popup(area, None).await
+    }
+
+    /// Render a grid area with a popup, and wait asynchronously for an
+    /// action
+    ///
+    /// * `area`: Grid area to render
+    /// * `popup`: Popup drawn over the grid area
+    ///
+    /// Like [step](Screen::step), but while a popup is present, it receives
+    /// all input.  Focus within the base grid area is kept for when the
+    /// popup is gone.
+    /// Errors are the same as for [step](Screen::step).
+    pub async fn step_with_popup<'a>(
+        &mut self,
+        area: &GridArea<'a>,
+        popup: Option<&Popup<'_, 'a>>,
+    ) -> Result<Action> {
+        let mut widget_boxes = self.layout_popup(area, popup);
+        self.draw(&widget_boxes)?;
+        let mut resized = false;
+        loop {
+            let pending =
+                self.pending_event(&mut resized, &widget_boxes).await?;
+            let ev = match pending {
+                Some(ev) => ev,
+                None => match self.next_event(&widget_boxes, None).await? {
+                    Some(ev) => ev,
+                    None => return Err(Error::EventStreamClosed()),
+                },
+            };
+            self.out.record_input(ev)?;
+            let action = self.event_action(ev, &widget_boxes);
+            if let Some(Action::Resize(_)) = action {
+                widget_boxes = self.layout_popup(area, popup);
+                resized = true;
+                if !self.notify_resize {
+                    continue;
+                }
+            }
+            if let Some(action) = action.and_then(|a| self.filter_action(a)) {
+                return Ok(action);
+            }
+        }
+    }
+
+    /// Get an event which is already pending after a resize
+    ///
+    /// * `resized`: Resized since last draw (cleared if drawn)
+    /// * `widget_boxes`: Widget boxes of new layout
+    ///
+    /// If no event is pending, the new layout is drawn, so that a burst of
+    /// resizes is only drawn once.
+    async fn pending_event(
+        &mut self,
+        resized: &mut bool,
+        widget_boxes: &[(&dyn Widget, BBox)],
+    ) -> Result<Option<Event>> {
+        if !*resized {
+            return Ok(None);
+        }
+        let ev = match PollOnce(&mut self.ev_stream).await {
+            Some(ev) => Some(ev?.into()),
+            None => None,
+        };
+        if ev.is_none() {
+            self.draw(widget_boxes)?;
+            *resized = false;
+        }
+        Ok(ev)
+    }
+
+    /// Render a grid area and wait asynchronously for an action, or a
+    /// timeout
+    ///
+    /// * `area`: Grid area to render
+    /// * `timeout`: Time to wait for an action
+    ///
+    /// Like [step](Screen::step), but [Action::Tick] is returned if no other
+    /// action happens before the timeout.  While a mouse button is held on
+    /// a widget, it is checked for a [held action](Widget::held_action)
+    /// instead.  Events which arrive first, such
+    /// as a resize, take precedence.  Calling this in a loop allows widgets
+    /// like a progress bar to be updated without extra threads:
+    ///
+    /// ```no_run
+    /// # use semtext::input::Action;
+    /// # use semtext::widget::Label;
+    /// # use semtext::{grid_area, Screen};
+    /// # use std::time::Duration;
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut screen = Screen::new()?;
+    /// let a = Label::new("Waiting...");
+    /// let grid = grid_area!([a])?;
+    /// let tick = Duration::from_millis(100);
+    /// loop {
+    ///     match screen.step_timeout(&grid, tick).await? {
+    ///         Action::Quit() => break,
+    ///         Action::Tick() => { /* update widgets */ }
+    ///         _ => (),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// If the event stream ends, ticks are still returned after each
+    /// timeout, so that a [headless](Screen::headless) screen can be
+    /// animated.  An error reading an event is returned as [Error::Io].
+    ///
+    /// [Action::Tick]: input/enum.Action.html#variant.Tick
+    /// [Error::Io]: enum.Error.html#variant.Io
+    pub async fn step_timeout(
+        &mut self,
+        area: &GridArea<'_>,
+        timeout: Duration,
+    ) -> Result<Action> {
+        let mut widget_boxes = self.layout(area);
+        self.draw(&widget_boxes)?;
+        let mut deadline = Instant::now() + timeout;
+        let mut resized = false;
         loop {
-            let ev = (&mut self.ev_stream).await.unwrap()?.into();
-            if let Some(action) = self.event_action(ev, &widget_boxes) {
+            let pending =
+                self.pending_event(&mut resized, &widget_boxes).await?;
+            let next = match pending {
+                Some(ev) => Some(ev),
+                None => self.next_event(&widget_boxes, Some(deadline)).await?,
+            };
+            let action = match next {
+                Some(ev) => {
+                    self.out.record_input(ev)?;
+                    let action = self.event_action(ev, &widget_boxes);
+                    if let Some(Action::Resize(_)) = action {
+                        widget_boxes = self.layout(area);
+                        resized = true;
+                        if !self.notify_resize {
+                            continue;
+                        }
+                    }
+                    action
+                }
+                None => {
+                    let now = Instant::now();
+                    deadline = now + timeout;
+                    let held = self
+                        .pointer
+                        .pressed
+                        .and_then(|i| widget_boxes.get(i))
+                        .and_then(|(w, _)| w.held_action(now));
+                    Some(held.unwrap_or(Action::Tick()))
+                }
+            };
+            if let Some(action) = action.and_then(|a| self.filter_action(a)) {
                 return Ok(action);
             }
         }
     }
 
+    /// Wait for the next event, drawing scheduled frames
+    ///
+    /// * `widget_boxes`: Widget boxes of current layout
+    /// * `deadline`: Time to stop waiting, if any
+    ///
+    /// ## Return
+    ///
+    /// The next event, or `None` at the deadline.  Without a deadline,
+    /// `None` means the event stream has ended.
+    async fn next_event(
+        &mut self,
+        widget_boxes: &[(&dyn Widget, BBox)],
+        deadline: Option<Instant>,
+    ) -> Result<Option<Event>> {
+        loop {
+            // Frames are scheduled by the screen clock
+            let frame = self.next_frame.map(|at| {
+                Instant::now() + at.saturating_duration_since(self.now())
+            });
+            let wake = match (deadline, frame) {
+                (Some(deadline), Some(frame)) => Some(deadline.min(frame)),
+                (deadline, frame) => deadline.or(frame),
+            };
+            let ev = match wake {
+                Some(wake) => {
+                    EventOrDelay {
+                        ev_stream: &mut self.ev_stream,
+                        timer: &mut self.timer,
+                        deadline: wake,
+                    }
+                    .await
+                }
+                None => match (&mut self.ev_stream).await {
+                    Some(ev) => Some(ev),
+                    None => return Ok(None),
+                },
+            };
+            match ev {
+                Some(ev) => return Ok(Some(ev?.into())),
+                None if deadline.is_some_and(|d| Instant::now() >= d) => {
+                    return Ok(None);
+                }
+                None => self.draw(widget_boxes)?,
+            }
+        }
+    }
+
+    /// Read a pending event without waiting
+    ///
+    /// The event stream is never polled here, since there is no task to
+    /// wake when an event arrives.
+    fn try_event(&mut self) -> Result<Option<Event>> {
+        match self.ev_stream.try_next() {
+            Some(ev) => Ok(Some(ev?.into())),
+            None => Ok(None),
+        }
+    }
+
+    /// Render a grid area and process all pending events
+    ///
+    /// * `area`: Grid area to render
+    /// * `budget`: Maximum time to spend processing events
+    ///
+    /// Unlike [step](Screen::step), this never waits for an event.  It
+    /// returns as soon as no more events are pending, or the budget is
+    /// spent.  Redraws are handled internally; every other action is
+    /// returned, in order.
+    ///
+    /// While redraws are deferred, this returns no actions without drawing
+    /// or processing input.
+    ///
+    /// This allows long-running work to be interleaved with input handling:
+    ///
+    /// ```no_run
+    /// # use semtext::input::Action;
+    /// # use semtext::widget::Label;
+    /// # use semtext::{grid_area, Screen};
+    /// # use std::time::Duration;
+    /// # fn do_work() -> bool { true }
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut screen = Screen::new()?;
+    /// let a = Label::new("Working...");
+    /// let grid = grid_area!([a])?;
+    /// // Do a slice of work between each pump
+    /// while do_work() {
+    ///     let actions = screen.pump(&grid, Duration::from_millis(50))?;
+    ///     if actions.contains(&Action::Quit()) {
+    ///         break;
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pump(
+        &mut self,
+        area: &GridArea<'_>,
+        budget: Duration,
+    ) -> Result<Vec<Action>> {
+        if self.batch_depth > 0 {
+            return Ok(vec![]);
+        }
+        let start = Instant::now();
+        let mut widget_boxes = self.layout(area);
+        self.draw(&widget_boxes)?;
+        let mut actions = vec![];
+        let mut redraw = false;
+        while start.elapsed() < budget {
+            let ev = match self.try_event()? {
+                Some(ev) => ev,
+                None => break,
+            };
+            redraw |=
+                self.batch_event(ev, area, &mut widget_boxes, &mut actions)?;
+        }
+        if redraw {
+            self.draw(&widget_boxes)?;
+        }
+        Ok(actions)
+    }
+
+    /// Render a grid area and process a sequence of events
+    ///
+    /// * `area`: Grid area to render
+    /// * `events`: Events to process, in order
+    ///
+    /// This is like [pump](Screen::pump), but the events are provided
+    /// instead of read from the terminal.  Combined with a
+    /// [headless](Screen::headless) screen, it allows user interaction to be
+    /// tested:
+    ///
+    /// ```rust
+    /// # use semtext::input::{Action, Event, KeyPress, ModKeys, NavKey};
+    /// # use semtext::layout::Dim;
+    /// # use semtext::text::Theme;
+    /// # use semtext::widget::Label;
+    /// # use semtext::{grid_area, Screen};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut screen = Screen::headless(Dim::new(5, 1), Theme::default());
+    /// let a = Label::new("Hello");
+    /// let grid = grid_area!([a])?;
+    /// let esc = Event::Key(KeyPress::Navigation(NavKey::Esc), ModKeys::EMPTY);
+    /// let actions = screen.step_with_events(&grid, [esc])?;
+    /// assert_eq!(actions, [Action::Quit()]);
+    /// assert_eq!(screen.text(), "Hello\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn step_with_events(
+        &mut self,
+        area: &GridArea<'_>,
+        events: impl IntoIterator<Item = Event>,
+    ) -> Result<Vec<Action>> {
+        let mut widget_boxes = self.layout(area);
+        self.draw(&widget_boxes)?;
+        let mut actions = vec![];
+        let mut redraw = false;
+        for ev in events {
+            redraw |=
+                self.batch_event(ev, area, &mut widget_boxes, &mut actions)?;
+        }
+        if redraw {
+            self.draw(&widget_boxes)?;
+        }
+        Ok(actions)
+    }
+
+    /// Process one event of a batch
+    ///
+    /// ## Return
+    ///
+    /// `true` if a redraw is needed
+    fn batch_event<'a>(
+        &mut self,
+        ev: Event,
+        area: &GridArea<'a>,
+        widget_boxes: &mut Vec<(&'a dyn Widget, BBox)>,
+        actions: &mut Vec<Action>,
+    ) -> Result<bool> {
+        self.out.record_input(ev)?;
+        Ok(match self.event_action(ev, widget_boxes) {
+            Some(Action::Redraw()) => true,
+            Some(Action::Resize(dim)) => {
+                *widget_boxes = self.layout(area);
+                if self.notify_resize {
+                    actions.extend(self.filter_action(Action::Resize(dim)));
+                }
+                true
+            }
+            Some(action) => {
+                actions.extend(self.filter_action(action));
+                false
+            }
+            None => false,
+        })
+    }
+
+    /// Close the screen, restoring the terminal
+    ///
+    /// Dropping a screen also restores the terminal, but any error is
+    /// ignored.  Closing it explicitly allows the error to be handled.
+    pub fn close(mut self) -> Result<()> {
+        let res = self.cleanup();
+        self.released = true;
+        res
+    }
+
     /// Cleanup screen
     fn cleanup(&mut self) -> Result<()> {
-        queue!(
-            self.out,
-            event::DisableMouseCapture,
-            terminal::LeaveAlternateScreen,
-            terminal::EnableLineWrap,
-            cursor::Show,
-            style::ResetColor,
-        )?;
+        if self.headless || self.released {
+            // Terminal was never touched, or already released
+            return Ok(());
+        }
+        let exit_text = self.exit_text();
+        queue_cleanup(&mut self.out, exit_text.as_deref(), self.modes)?;
         self.out.flush()?;
         terminal::disable_raw_mode()?;
         Ok(())
     }
 }
 
+impl Screen {
+    /// Get the text left on the normal screen by the exit render
+    ///
+    /// The final frame is taken from the snapshot of the last draw.
+    fn exit_text(&self) -> Option<String> {
+        match &self.exit_render {
+            ExitRender::Nothing => None,
+            ExitRender::FinalFrame { styled: false } => {
+                Some(self.snapshot.to_plain())
+            }
+            ExitRender::FinalFrame { styled: true } => {
+                Some(self.snapshot.to_ansi(self.color_support))
+            }
+            ExitRender::Custom(text) => Some(text.clone()),
+        }
+    }
+}
+
 impl Drop for Screen {
     fn drop(&mut self) {
-        if let Err(err) = self.cleanup() {
-            // Is this useful?
-            dbg!(err);
-        }
-    }
-}
-
-/// Handle a mouse action
-fn mouse_action(
-    mev: MouseEvent,
-    mods: ModKeys,
-    pos: Pos,
-    widget_boxes: &[(&dyn Widget, BBox)],
-) -> Option<Action> {
-    let mut action = None;
-    let mut redraw = None;
-    for (widget, bbox) in widget_boxes.iter() {
-        use MouseEvent::*;
-        let r = match (mev, bbox.within(pos)) {
-            (ButtonDown(_), Some(_)) => widget.focus(FocusEvent::Offer),
-            (ButtonDown(_), None) => widget.focus(FocusEvent::Take),
-            (Drag(None), Some(_)) => widget.focus(FocusEvent::HoverInside),
-            (Drag(_), None) => widget.focus(FocusEvent::HoverOutside),
-            (ButtonUp(_), Some(_)) => widget.focus(FocusEvent::HoverInside),
-            (ButtonUp(_), None) => widget.focus(FocusEvent::HoverOutside),
+        // Best effort; use close to handle errors
+        let _ = self.cleanup();
+    }
+}
+
+/// Draw a widget within its bounding box
+///
+/// * `cells`: Cells of the screen
+/// * `widget`: Widget to draw
+/// * `bbox`: Bounding box of widget
+/// * `offset`: Content offset of the top-left cell
+/// * `overlay`: Style overlay for the widget
+///
+/// ## Return
+///
+/// An I/O error writing to the terminal aborts drawing.  Any other widget
+/// draw error is returned in the inner result, after an error placeholder
+/// is drawn.
+pub(crate) fn draw_widget(
+    cells: &mut Cells,
+    widget: &dyn Widget,
+    bbox: BBox,
+    offset: ContentPos,
+    overlay: Option<StyleOverlay>,
+) -> Result<std::result::Result<(), Error>> {
+    let depth = cells.clip_depth();
+    cells.begin_widget(WidgetId::of(widget));
+    cells.push_clip(bbox);
+    let res = draw_clipped(cells, widget, offset, overlay);
+    cells.end_widget();
+    // Clip areas left pushed by a failed draw are popped too
+    cells.pop_clips_to(depth);
+    match res {
+        Ok(()) => Ok(Ok(())),
+        Err(Error::Io(err)) => Err(Error::Io(err)),
+        Err(err) => {
+            cells.push_clip(bbox);
+            let res = draw_placeholder(cells, &err);
+            cells.pop_clip();
+            res?;
+            Ok(Err(err))
+        }
+    }
+}
+
+/// Draw a widget within the current clip area
+fn draw_clipped(
+    cells: &mut Cells,
+    widget: &dyn Widget,
+    offset: ContentPos,
+    overlay: Option<StyleOverlay>,
+) -> Result<()> {
+    if cells.width() > 0 && cells.height() > 0 {
+        if let Some(overlay) = overlay {
+            cells.push_style_overlay(overlay)?;
+        }
+        let style = cells.theme().style(widget.style_group());
+        let style = style.with_override(widget.style_override());
+        cells.set_style(style)?;
+        cells.fill(&' '.into_glyph()?)?;
+        widget.draw(cells, offset)?;
+    }
+    Ok(())
+}
+
+/// Get the screen position of a widget's terminal cursor
+///
+/// The cursor must be within the widget's bounding box.
+fn cursor_pos(widget: &dyn Widget, bbox: BBox) -> Option<(u16, u16)> {
+    let pos = widget.cursor()?;
+    let col = bbox.left().checked_add(pos.col)?;
+    let row = bbox.top().checked_add(pos.row)?;
+    (col < bbox.right() && row < bbox.bottom()).then_some((col, row))
+}
+
+/// Draw an error placeholder within the current clip area
+///
+/// The area is filled with `!`, with the error text on the first row.
+fn draw_placeholder(cells: &mut Cells, err: &Error) -> Result<()> {
+    let width = cells.width();
+    if width == 0 || cells.height() == 0 {
+        return Ok(());
+    }
+    let style = cells.theme().style(StyleGroup::Error);
+    cells.set_style(style)?;
+    cells.fill(&'!'.into_glyph()?)?;
+    let text = truncate_end(&err.to_string(), usize::from(width));
+    cells.move_to(0, 0)?;
+    cells.print_str(&text)
+}
+
+/// Draw a centered message that the screen is too small
+///
+/// * `cells`: Cells of the screen
+/// * `min`: Minimum size of grid area
+fn draw_too_small(cells: &mut Cells, min: Dim) -> Result<()> {
+    let msg = format!("Terminal too small (need {}x{})", min.width, min.height);
+    let msg = truncate_end(&msg, usize::from(cells.width()));
+    let width = u16::try_from(display_width(&msg)).unwrap_or(u16::MAX);
+    let col = cells.width().saturating_sub(width) / 2;
+    cells.move_to(col, cells.height() / 2)?;
+    cells.print_str(&msg)
+}
+
+/// Draw the prompt of an armed key confirmation on the bottom row
+fn draw_prompt(cells: &mut Cells, prompt: &str) -> Result<()> {
+    let msg = truncate_end(prompt, usize::from(cells.width()));
+    cells.move_to(0, cells.height().saturating_sub(1))?;
+    cells.print_str(&msg)
+}
+
+/// Queue an audible bell
+fn queue_bell<W: Write>(out: &mut W) -> Result<()> {
+    queue!(out, style::Print('\x07'))?;
+    Ok(())
+}
+
+impl Bell {
+    /// Request a bell for an area
+    ///
+    /// Only the first request before the bell is rung is kept.
+    fn request(&mut self, area: BBox) {
+        if self.mode != BellMode::None && self.pending.is_none() {
+            self.pending = Some(area);
+        }
+    }
+
+    /// Take the pending bell request
+    fn take(&mut self) -> Option<BBox> {
+        self.pending.take()
+    }
+}
+
+/// Queue commands to setup the terminal
+fn queue_setup<W: Write>(out: &mut W, modes: TermModes) -> Result<()> {
+    queue!(out, cursor::SavePosition)?;
+    if modes.alternate_screen {
+        queue!(out, terminal::EnterAlternateScreen)?;
+    }
+    queue!(out, cursor::Hide)?;
+    if modes.no_line_wrap {
+        queue!(out, terminal::DisableLineWrap)?;
+    }
+    queue!(out, terminal::Clear(terminal::ClearType::All))?;
+    if modes.mouse_capture {
+        queue!(out, event::EnableMouseCapture)?;
+    }
+    Ok(())
+}
+
+/// Queue commands to restore the terminal
+///
+/// * `out`: Terminal output
+/// * `exit_text`: Text left on the normal screen, from the exit render
+/// * `modes`: Terminal modes to undo
+fn queue_cleanup<W: Write>(
+    out: &mut W,
+    exit_text: Option<&str>,
+    modes: TermModes,
+) -> Result<()> {
+    if modes.mouse_capture {
+        queue!(out, event::DisableMouseCapture)?;
+    }
+    if modes.alternate_screen {
+        queue!(out, terminal::LeaveAlternateScreen)?;
+    }
+    if modes.no_line_wrap {
+        queue!(out, terminal::EnableLineWrap)?;
+    }
+    queue!(
+        out,
+        cursor::RestorePosition,
+        style::SetAttribute(style::Attribute::Reset),
+        style::ResetColor,
+    )?;
+    if let Some(text) = exit_text {
+        // Raw mode is still enabled, so line feeds need carriage returns
+        queue!(out, style::Print(text.replace('\n', "\r\n")))?;
+    }
+    queue!(out, cursor::Show)?;
+    Ok(())
+}
+
+/// Get the cells vacated between two sets of bounding boxes
+///
+/// * `prev`: Previous bounding boxes
+/// * `next`: Next bounding boxes
+///
+/// ## Return
+///
+/// Single-row boxes covering all cells within `prev` but not `next`.
+fn vacated(prev: &[BBox], next: &[BBox]) -> Vec<BBox> {
+    let mut spans = vec![];
+    for p in prev {
+        for row in p.top()..p.bottom() {
+            let mut covered: Vec<(u16, u16)> = next
+                .iter()
+                .filter(|n| n.top() <= row && row < n.bottom())
+                .map(|n| (n.left(), n.right()))
+                .collect();
+            covered.sort_unstable();
+            let mut col = p.left();
+            for (left, right) in covered {
+                if left > col {
+                    let right = left.min(p.right());
+                    spans.push(BBox::new(col, row, right - col, 1));
+                }
+                col = col.max(right);
+                if col >= p.right() {
+                    break;
+                }
+            }
+            if col < p.right() {
+                spans.push(BBox::new(col, row, p.right() - col, 1));
+            }
+        }
+    }
+    spans
+}
+
+impl Default for Pointer {
+    fn default() -> Self {
+        Pointer {
+            focus_follows: false,
+            hovered: None,
+            focused: None,
+            pressed: None,
+            double_click: DOUBLE_CLICK,
+            last_up: None,
+        }
+    }
+}
+
+impl Pointer {
+    /// Check whether a button release completes a double-click
+    fn double_click(
+        &mut self,
+        mev: MouseEvent,
+        pos: ScreenPos,
+    ) -> Option<MouseButton> {
+        let btn = match mev {
+            MouseEvent::ButtonUp(btn) => btn,
+            MouseEvent::ButtonDown(_) => return None,
+            _ => {
+                self.last_up = None;
+                return None;
+            }
+        };
+        let now = Instant::now();
+        let double = matches!(self.last_up, Some((b, p, t))
+            if b == btn && p == pos && now - t <= self.double_click);
+        self.last_up = if double { None } else { Some((btn, pos, now)) };
+        double.then_some(btn)
+    }
+
+    /// Handle a mouse action
+    fn mouse_action(
+        &mut self,
+        mev: MouseEvent,
+        mods: ModKeys,
+        pos: ScreenPos,
+        widget_boxes: &[(&dyn Widget, BBox)],
+    ) -> Option<Action> {
+        let hovered = widget_boxes
+            .iter()
+            .position(|(w, b)| w.hit_testable() && b.contains(*pos));
+        let follow = self.focus_follows
+            && mev == MouseEvent::Drag(None)
+            && hovered.is_some()
+            && hovered != self.hovered;
+        self.hovered = hovered;
+        let double = self.double_click(mev, pos);
+        if follow || matches!(mev, MouseEvent::ButtonDown(_)) {
+            self.focused = hovered.filter(|i| widget_boxes[*i].0.focusable());
+        }
+        // Drags and releases go to the pressed widget, wherever they are
+        let captured = match mev {
+            MouseEvent::ButtonDown(_) => {
+                self.pressed = hovered;
+                None
+            }
+            MouseEvent::Drag(Some(_)) => self.pressed,
+            MouseEvent::ButtonUp(_) => self.pressed.take(),
             _ => None,
         };
-        redraw = redraw.or(r);
-        // Only widget within bounds receives event
-        if let Some(p) = bbox.within(pos) {
-            let a = widget.mouse_event(mev, mods, bbox.dim(), p);
-            action = action.or(a);
+        let mut action = None;
+        let mut redraw = None;
+        let hit_testable = widget_boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, (w, _))| w.hit_testable());
+        for (i, (widget, bbox)) in hit_testable {
+            use MouseEvent::*;
+            let inside = bbox.contains(*pos);
+            let r = match (mev, inside) {
+                (ButtonDown(_), true) => widget.focus(FocusEvent::Offer),
+                (ButtonDown(_), false) => widget.focus(FocusEvent::Take),
+                (Drag(None), true) if follow => widget.focus(FocusEvent::Offer),
+                (Drag(None), false) if follow => widget.focus(FocusEvent::Take),
+                (Drag(None), true) => widget.focus(FocusEvent::HoverInside),
+                (Drag(_), false) => widget.focus(FocusEvent::HoverOutside),
+                (ButtonUp(_), true) => widget.focus(FocusEvent::HoverInside),
+                (ButtonUp(_), false) => widget.focus(FocusEvent::HoverOutside),
+                _ => None,
+            };
+            redraw = redraw.or(r);
+            // Only widget within bounds (or pressed) receives event
+            let receive = match captured {
+                Some(c) => c == i,
+                None => inside,
+            };
+            if receive {
+                let p = WidgetPos::from_screen(pos, ScreenPos::origin(*bbox));
+                let mut a = widget.mouse_event(mev, mods, bbox.dim(), p);
+                if let Some(btn) = double {
+                    let dbl = MouseEvent::DoubleClick(btn);
+                    a = widget.mouse_event(dbl, mods, bbox.dim(), p).or(a);
+                }
+                action = action.or(a);
+            }
+        }
+        action.or(redraw)
+    }
+
+    /// Handle a key action
+    ///
+    /// The focused widget handles keys first.  Unhandled `Tab` / `BackTab`
+    /// presses or repeats move focus to the next / previous focusable
+    /// widget.
+    fn key_action(
+        &mut self,
+        kind: KeyKind,
+        key: KeyPress,
+        mods: ModKeys,
+        widget_boxes: &[(&dyn Widget, BBox)],
+    ) -> Option<Action> {
+        let focused = self.focused.filter(|i| {
+            widget_boxes.get(*i).is_some_and(|(w, _)| w.focusable())
+        });
+        if let Some(i) = focused {
+            let widget = widget_boxes[i].0;
+            let action = match kind {
+                KeyKind::Press => widget.key_event(key, mods),
+                KeyKind::Repeat => widget.key_repeat(key, mods),
+                KeyKind::Release => widget.key_release(key, mods),
+            };
+            if action.is_some() {
+                return action;
+            }
+        }
+        if kind == KeyKind::Release {
+            return None;
+        }
+        let forward = match (key, mods) {
+            (KeyPress::Navigation(NavKey::Tab), ModKeys::EMPTY) => true,
+            (KeyPress::Navigation(NavKey::BackTab), _) => false,
+            _ => return None,
+        };
+        let len = widget_boxes.len();
+        let next = (1..=len)
+            .map(|n| match (focused, forward) {
+                (Some(i), true) => (i + n) % len,
+                (Some(i), false) => (i + len - n) % len,
+                (None, true) => n - 1,
+                (None, false) => len - n,
+            })
+            .find(|i| widget_boxes[*i].0.focusable())?;
+        if let Some(i) = focused {
+            widget_boxes[i].0.focus(FocusEvent::Take);
+        }
+        widget_boxes[next].0.focus(FocusEvent::Offer);
+        self.focused = Some(next);
+        Some(Action::Redraw())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid_area;
+    use crate::input::MouseButton;
+    use crate::layout::LengthBound;
+    use crate::text::StyleGroup;
+    use crate::widget::{Button, Label, Spacer};
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    /// Widget which counts focus and mouse event calls
+    struct Probe {
+        /// Number of calls
+        calls: Cell<u32>,
+    }
+
+    impl Widget for Probe {
+        fn hit_testable(&self) -> bool {
+            false
+        }
+
+        fn focus(&self, _fev: FocusEvent) -> Option<Action> {
+            self.calls.set(self.calls.get() + 1);
+            None
+        }
+
+        fn mouse_event(
+            &self,
+            _mev: MouseEvent,
+            _mods: ModKeys,
+            _dim: Dim,
+            _pos: WidgetPos,
+        ) -> Option<Action> {
+            self.calls.set(self.calls.get() + 1);
+            None
+        }
+    }
+
+    /// Find the position of a command within output
+    fn find_cmd(out: &[u8], cmd: impl crossterm::Command) -> Option<usize> {
+        let mut seq = vec![];
+        queue!(seq, cmd).unwrap();
+        out.windows(seq.len()).position(|w| w == &seq[..])
+    }
+
+    #[test]
+    fn exit_nothing() {
+        let mut out = vec![];
+        let modes = TermModes::default();
+        queue_cleanup(&mut out, None, modes).unwrap();
+        let leave = find_cmd(&out, terminal::LeaveAlternateScreen).unwrap();
+        let show = find_cmd(&out, cursor::Show).unwrap();
+        assert!(leave < show);
+    }
+
+    #[test]
+    fn exit_custom() {
+        let mut out = vec![];
+        let text = "picked:\nsemtext".to_string();
+        let modes = TermModes::default();
+        queue_cleanup(&mut out, Some(&text), modes).unwrap();
+        let leave = find_cmd(&out, terminal::LeaveAlternateScreen).unwrap();
+        let show = find_cmd(&out, cursor::Show).unwrap();
+        let printed = find_cmd(&out, style::Print("picked:\r\nsemtext"));
+        let printed = printed.unwrap();
+        assert!(leave < printed);
+        assert!(printed < show);
+    }
+
+    #[test]
+    fn exit_final_frame() -> Result<()> {
+        let mut screen = Screen::headless(Dim::new(6, 2), Theme::default());
+        let a = Label::new("Alpha");
+        let b = Label::new("Beta");
+        let grid = grid_area!([a][b])?;
+        screen.render(&grid)?;
+        assert_eq!(screen.exit_text(), None);
+        screen.set_exit_render(ExitRender::FinalFrame { styled: false });
+        let text = screen.exit_text().unwrap();
+        assert_eq!(text, screen.text());
+        assert_eq!(text, "Alpha \nBeta  \n");
+        let mut out = vec![];
+        let modes = TermModes::default();
+        queue_cleanup(&mut out, Some(&text), modes)?;
+        let leave = find_cmd(&out, terminal::LeaveAlternateScreen).unwrap();
+        let printed = find_cmd(&out, style::Print("Alpha \r\nBeta  \r\n"));
+        let printed = printed.unwrap();
+        let show = find_cmd(&out, cursor::Show).unwrap();
+        assert!(leave < printed);
+        assert!(printed < show);
+        // Styled frame has the colors of the snapshot
+        screen.set_exit_render(ExitRender::FinalFrame { styled: true });
+        let ansi = screen.exit_text().unwrap();
+        let row = screen.snapshot().rows().next().unwrap();
+        let (style, run) = Snapshot::runs(row).remove(0);
+        let fg = style::SetForegroundColor(style.foreground().into());
+        let mut expected = String::new();
+        crossterm::Command::write_ansi(&fg, &mut expected).unwrap();
+        expected.push_str(&run);
+        let line = ansi.lines().next().unwrap();
+        assert!(line.contains(&expected), "{:?}", line);
+        assert_eq!(ansi.lines().count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn builder_modes() {
+        let modes = Screen::builder()
+            .mouse_capture(false)
+            .disable_line_wrap(false)
+            .modes;
+        let mut out = vec![];
+        queue_setup(&mut out, modes).unwrap();
+        assert!(find_cmd(&out, terminal::EnterAlternateScreen).is_some());
+        assert!(find_cmd(&out, event::EnableMouseCapture).is_none());
+        assert!(find_cmd(&out, terminal::DisableLineWrap).is_none());
+        // Only enabled modes are undone
+        let mut out = vec![];
+        queue_cleanup(&mut out, None, modes).unwrap();
+        assert!(find_cmd(&out, terminal::LeaveAlternateScreen).is_some());
+        assert!(find_cmd(&out, event::DisableMouseCapture).is_none());
+        assert!(find_cmd(&out, terminal::EnableLineWrap).is_none());
+        let modes = Screen::builder().alternate_screen(false).modes;
+        let mut out = vec![];
+        queue_cleanup(&mut out, None, modes).unwrap();
+        assert!(find_cmd(&out, terminal::LeaveAlternateScreen).is_none());
+        assert!(find_cmd(&out, event::DisableMouseCapture).is_some());
+    }
+
+    #[test]
+    fn cleanup_reset() {
+        let mut out = vec![];
+        let modes = TermModes::default();
+        queue_cleanup(&mut out, None, modes).unwrap();
+        let leave = find_cmd(&out, terminal::LeaveAlternateScreen).unwrap();
+        let restore = find_cmd(&out, cursor::RestorePosition).unwrap();
+        assert!(leave < restore);
+        let reset = style::SetAttribute(style::Attribute::Reset);
+        assert!(find_cmd(&out, reset).is_some());
+        let screen = Screen::headless(Dim::new(4, 1), Theme::default());
+        assert!(screen.close().is_ok());
+    }
+
+    /// Writer into a shared buffer
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn color_support() -> Result<()> {
+        let buf = Rc::new(RefCell::new(vec![]));
+        let mut screen = Screen::headless(Dim::new(4, 1), Theme::default());
+        screen.out = Output::new(Box::new(Shared(Rc::clone(&buf))));
+        let rgb = Color::Rgb(250, 10, 130);
+        screen.set_foreground_color(rgb)?;
+        screen.set_color_support(ColorSupport::Ansi256);
+        screen.set_foreground_color(rgb)?;
+        screen.set_color_support(ColorSupport::Ansi16);
+        screen.set_background_color(rgb)?;
+        screen.out.flush()?;
+        let out = buf.borrow();
+        let fg = |clr| find_cmd(&out, style::SetForegroundColor(clr));
+        let true_color = fg(style::Color::Rgb {
+            r: 250,
+            g: 10,
+            b: 130,
+        })
+        .unwrap();
+        let palette = fg(style::Color::AnsiValue(198)).unwrap();
+        assert!(true_color < palette);
+        let bg = style::SetBackgroundColor(style::Color::DarkMagenta);
+        assert!(find_cmd(&out, bg).unwrap() > palette);
+        Ok(())
+    }
+
+    #[test]
+    fn bell_once() {
+        let mut bell = Bell::default();
+        let mut out = vec![];
+        for _ in 0..3 {
+            bell.request(BBox::new(0, 0, 5, 1));
+        }
+        while bell.take().is_some() {
+            queue_bell(&mut out).unwrap();
+        }
+        assert_eq!(out, b"\x07");
+    }
+
+    #[test]
+    fn bell_first_area() {
+        let mut bell = Bell {
+            mode: BellMode::Visual,
+            ..Default::default()
+        };
+        bell.request(BBox::new(0, 0, 5, 1));
+        bell.request(BBox::new(5, 0, 5, 1));
+        assert_eq!(bell.take(), Some(BBox::new(0, 0, 5, 1)));
+        assert_eq!(bell.take(), None);
+    }
+
+    #[test]
+    fn bell_none() {
+        let mut bell = Bell {
+            mode: BellMode::None,
+            ..Default::default()
+        };
+        bell.request(BBox::new(0, 0, 5, 1));
+        assert_eq!(bell.take(), None);
+    }
+
+    /// Widget which requests the bell for every key
+    #[derive(Default)]
+    struct Grumpy(Cell<bool>);
+
+    impl Widget for Grumpy {
+        fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+            cells.print_str("G")
+        }
+
+        fn focusable(&self) -> bool {
+            true
+        }
+
+        fn key_event(&self, _key: KeyPress, _mods: ModKeys) -> Option<Action> {
+            self.0.set(true);
+            None
+        }
+
+        fn take_bell(&self) -> bool {
+            self.0.replace(false)
+        }
+    }
+
+    /// Key press event
+    fn press(ch: char) -> Event {
+        Event::Key(KeyPress::Character(ch), ModKeys::EMPTY)
+    }
+
+    #[test]
+    fn bell_burst() -> Result<()> {
+        let buf = Rc::new(RefCell::new(vec![]));
+        let mut screen = Screen::headless(Dim::new(4, 1), Theme::default());
+        screen.out = Output::new(Box::new(Shared(Rc::clone(&buf))));
+        let grumpy = Grumpy::default();
+        let grid = grid_area!([grumpy])?;
+        let tab = Event::Key(KeyPress::Navigation(NavKey::Tab), ModKeys::EMPTY);
+        screen.step_with_events(&grid, [tab])?;
+        let bells = |buf: &RefCell<Vec<u8>>| {
+            buf.borrow().iter().filter(|b| **b == 0x07).count()
+        };
+        assert_eq!(bells(&buf), 0);
+        let actions = screen
+            .step_with_events(&grid, [press('a'), press('b'), press('c')])?;
+        assert!(actions.is_empty());
+        assert_eq!(bells(&buf), 1);
+        // Requests are taken, so the next frame is quiet
+        screen.step_with_events(&grid, [])?;
+        assert_eq!(bells(&buf), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn bell_flash_cleared() -> Result<()> {
+        let mut screen = Screen::headless(Dim::new(4, 1), Theme::default());
+        screen.set_bell_mode(BellMode::Visual);
+        let grumpy = Grumpy::default();
+        let grid = grid_area!([grumpy])?;
+        let style = |screen: &Screen| {
+            let row = screen.snapshot().rows().next().unwrap();
+            Snapshot::runs(row)[0].0
+        };
+        let start = Instant::now();
+        screen.set_time(start);
+        let tab = Event::Key(KeyPress::Navigation(NavKey::Tab), ModKeys::EMPTY);
+        screen.step_with_events(&grid, [tab])?;
+        let normal = style(&screen);
+        assert_eq!(screen.next_frame(), None);
+        screen.step_with_events(&grid, [press('a')])?;
+        assert_ne!(style(&screen), normal);
+        assert_eq!(screen.next_frame(), Some(start + FLASH_DURATION));
+        screen.set_time(start + FLASH_DURATION);
+        screen.step_with_events(&grid, [])?;
+        assert_eq!(style(&screen), normal);
+        assert_eq!(screen.next_frame(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn vacated_same() {
+        let boxes = [BBox::new(0, 0, 10, 2), BBox::new(10, 0, 5, 2)];
+        assert!(vacated(&boxes, &boxes).is_empty());
+    }
+
+    #[test]
+    fn vacated_shrink() {
+        // Collapsed panel
+        let prev = [BBox::new(0, 0, 10, 3)];
+        let next = [BBox::new(0, 0, 10, 1)];
+        assert_eq!(
+            vacated(&prev, &next),
+            [BBox::new(0, 1, 10, 1), BBox::new(0, 2, 10, 1)]
+        );
+        // Narrower widget
+        let prev = [BBox::new(2, 0, 10, 1)];
+        let next = [BBox::new(2, 0, 6, 1)];
+        assert_eq!(vacated(&prev, &next), [BBox::new(8, 0, 4, 1)]);
+    }
+
+    #[test]
+    fn vacated_moved() {
+        let prev = [BBox::new(0, 0, 10, 1)];
+        let next = [BBox::new(3, 0, 2, 1), BBox::new(7, 0, 5, 1)];
+        assert_eq!(
+            vacated(&prev, &next),
+            [BBox::new(0, 0, 3, 1), BBox::new(5, 0, 2, 1),]
+        );
+        assert_eq!(vacated(&prev, &[]), prev);
+    }
+
+    /// Move the pointer to a position
+    fn move_to(
+        pointer: &mut Pointer,
+        col: u16,
+        widget_boxes: &[(&dyn Widget, BBox)],
+    ) -> Option<Action> {
+        let mev = MouseEvent::Drag(None);
+        let pos = ScreenPos::new(col, 0);
+        pointer.mouse_action(mev, ModKeys::EMPTY, pos, widget_boxes)
+    }
+
+    #[test]
+    fn focus_follows_mouse() {
+        let a = Button::new(Label::new("A"));
+        let b = Button::new(Label::new("B"));
+        let c = Button::new(Label::new("C"));
+        let wb: [(&dyn Widget, BBox); 3] = [
+            (&a, BBox::new(0, 0, 5, 1)),
+            (&b, BBox::new(5, 0, 5, 1)),
+            (&c, BBox::new(10, 0, 5, 1)),
+        ];
+        let groups = || [a.style_group(), b.style_group(), c.style_group()];
+        let mut pointer = Pointer {
+            focus_follows: true,
+            ..Default::default()
+        };
+        use StyleGroup::*;
+        assert_eq!(move_to(&mut pointer, 2, &wb), Some(Action::Redraw()));
+        assert_eq!(groups(), [Focused, Enabled, Enabled]);
+        assert_eq!(move_to(&mut pointer, 3, &wb), None);
+        assert_eq!(move_to(&mut pointer, 7, &wb), Some(Action::Redraw()));
+        assert_eq!(groups(), [Enabled, Focused, Enabled]);
+        assert_eq!(move_to(&mut pointer, 12, &wb), Some(Action::Redraw()));
+        assert_eq!(groups(), [Enabled, Enabled, Focused]);
+        // Moving off of all widgets keeps focus
+        assert_eq!(move_to(&mut pointer, 20, &wb), None);
+        assert_eq!(groups(), [Enabled, Enabled, Focused]);
+    }
+
+    #[test]
+    fn hover_without_focus_follows() {
+        let a = Button::new(Label::new("A"));
+        let b = Button::new(Label::new("B"));
+        let wb: [(&dyn Widget, BBox); 2] =
+            [(&a, BBox::new(0, 0, 5, 1)), (&b, BBox::new(5, 0, 5, 1))];
+        let mut pointer = Pointer::default();
+        assert_eq!(move_to(&mut pointer, 2, &wb), Some(Action::Redraw()));
+        assert_eq!(a.style_group(), StyleGroup::Hovered);
+        assert_eq!(move_to(&mut pointer, 7, &wb), Some(Action::Redraw()));
+        assert_eq!(a.style_group(), StyleGroup::Enabled);
+        assert_eq!(b.style_group(), StyleGroup::Hovered);
+        // Moving within the same widget changes nothing
+        assert_eq!(move_to(&mut pointer, 8, &wb), None);
+        assert_eq!(move_to(&mut pointer, 12, &wb), Some(Action::Redraw()));
+        assert_eq!(b.style_group(), StyleGroup::Enabled);
+        assert_eq!(move_to(&mut pointer, 14, &wb), None);
+    }
+
+    #[test]
+    fn spacer_not_hit_testable() {
+        assert!(!Spacer::default().hit_testable());
+        assert!(!Spacer::default().with_fill('|').unwrap().hit_testable());
+        assert!(Spacer::default().with_hit_test(true).hit_testable());
+        assert!(!Spacer::default().into_border().hit_testable());
+    }
+
+    #[test]
+    fn skip_not_hit_testable() {
+        let a = Button::new(Label::new("A"));
+        let s = Probe {
+            calls: Cell::new(0),
+        };
+        let b = Button::new(Label::new("B"));
+        let wb: [(&dyn Widget, BBox); 3] = [
+            (&a, BBox::new(0, 0, 5, 1)),
+            (&s, BBox::new(5, 0, 1, 1)),
+            (&b, BBox::new(6, 0, 5, 1)),
+        ];
+        let mut pointer = Pointer::default();
+        for col in 0..11 {
+            move_to(&mut pointer, col, &wb);
+        }
+        let mev = MouseEvent::ButtonDown(MouseButton::Left);
+        let pos = ScreenPos::new(5, 0);
+        pointer.mouse_action(mev, ModKeys::EMPTY, pos, &wb);
+        assert_eq!(s.calls.get(), 0);
+        // Clicking the probe does not hover or focus anything
+        assert_eq!(pointer.hovered, None);
+        assert_eq!(a.style_group(), StyleGroup::Enabled);
+    }
+
+    /// Widget which always fails to draw
+    struct Failing;
+
+    impl Widget for Failing {
+        fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+            LengthBound::new(20..=20)
+        }
+
+        fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+            LengthBound::new(2..=2)
+        }
+
+        fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+            cells.print_str("partial")?;
+            // Leave a clip area pushed
+            cells.push_clip(BBox::new(1, 1, 1, 1));
+            Err(Error::InvalidGlyphWidth(3))
+        }
+    }
+
+    #[test]
+    fn draw_error_placeholder() -> Result<()> {
+        let left = Label::new("left");
+        let right = Label::new("right");
+        let bad = Failing;
+        let grid = crate::grid_area!([left bad right])?;
+        let mut screen = Screen::headless(Dim::new(29, 2), Theme::default());
+        screen.render(&grid)?;
+        let text = screen.snapshot().to_plain();
+        assert_eq!(
+            text,
+            "leftInvalid glyph width…right\n    !!!!!!!!!!!!!!!!!!!!     \n"
+        );
+        let errors = screen.take_draw_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 1);
+        assert!(matches!(errors[0].1, Error::InvalidGlyphWidth(3)));
+        assert!(screen.take_draw_errors().is_empty());
+        Ok(())
+    }
+
+    /// Widget placing the terminal cursor
+    struct Caret(Cell<Option<WidgetPos>>);
+
+    impl Widget for Caret {
+        fn focusable(&self) -> bool {
+            true
+        }
+
+        fn cursor(&self) -> Option<WidgetPos> {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn cursor_focused() -> Result<()> {
+        let caret = Caret(Cell::new(Some(WidgetPos::new(2, 0)))).into_border();
+        let grid = grid_area!([caret])?;
+        let mut screen = Screen::headless(Dim::new(6, 3), Theme::default());
+        screen.render(&grid)?;
+        assert_eq!(screen.cursor, None);
+        // Only the focused widget places the cursor, inside its border
+        screen.pointer.focused = Some(0);
+        screen.render(&grid)?;
+        assert_eq!(screen.cursor, Some((3, 1)));
+        // Outside of the widget, the cursor is hidden
+        caret.wrapped().0.set(Some(WidgetPos::new(5, 0)));
+        screen.render(&grid)?;
+        assert_eq!(screen.cursor, None);
+        caret.wrapped().0.set(Some(WidgetPos::new(0, 0)));
+        screen.render(&grid)?;
+        assert_eq!(screen.cursor, Some((1, 1)));
+        caret.wrapped().0.set(None);
+        screen.render(&grid)?;
+        assert_eq!(screen.cursor, None);
+        Ok(())
+    }
+
+    /// Get the foreground color of the first cell of a headless screen
+    fn first_fg(screen: &Screen) -> Color {
+        let snapshot = screen.snapshot();
+        let row = snapshot.rows().next().unwrap();
+        Snapshot::runs(row)[0].0.foreground()
+    }
+
+    /// Get the background color of the first cell of a headless screen
+    fn first_bg(screen: &Screen) -> Color {
+        let snapshot = screen.snapshot();
+        let row = snapshot.rows().next().unwrap();
+        Snapshot::runs(row)[0].0.background()
+    }
+
+    #[test]
+    fn swap_theme() -> Result<()> {
+        let dark = Theme::default().with_background(Color::Rgb(0, 0, 0));
+        let light = dark.clone().with_background(Color::Rgb(255, 255, 255));
+        let mut screen = Screen::headless(Dim::new(8, 2), dark.clone());
+        let a = Label::new("A");
+        let grid = grid_area!([a])?;
+        screen.render(&grid)?;
+        assert_eq!(first_bg(&screen), Color::Rgb(0, 0, 0));
+        screen.set_theme(light);
+        screen.render(&grid)?;
+        assert_eq!(first_bg(&screen), Color::Rgb(255, 255, 255));
+        let style = screen.theme().style(StyleGroup::Enabled);
+        assert_eq!(style.background(), Color::Rgb(255, 255, 255));
+        *screen.theme_mut() = dark;
+        screen.render(&grid)?;
+        assert_eq!(first_bg(&screen), Color::Rgb(0, 0, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn style_override() -> Result<()> {
+        let red = TextStyle::default()
+            .with_foreground(Color::Rgb(255, 0, 0))
+            .with_background(Color::Rgb(40, 0, 0));
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let button = Button::new(Label::new("Delete")).with_style(red);
+        let grid = grid_area!([button])?;
+        screen.render(&grid)?;
+        assert_eq!(first_fg(&screen), Color::Rgb(255, 0, 0));
+        assert_eq!(first_bg(&screen), Color::Rgb(40, 0, 0));
+        // State appearance from the theme is kept
+        button.focus(FocusEvent::Offer);
+        screen.render(&grid)?;
+        let snapshot = screen.snapshot();
+        let style = Snapshot::runs(snapshot.rows().next().unwrap())[0].0;
+        assert_eq!(style.foreground(), Color::Rgb(255, 0, 0));
+        assert!(style.appearance().reverse());
+        button.disable();
+        screen.render(&grid)?;
+        let disabled = screen.theme().style(StyleGroup::Disabled);
+        assert_eq!(first_fg(&screen), disabled.foreground());
+        Ok(())
+    }
+
+    #[test]
+    fn hover_transition() -> Result<()> {
+        let theme = Theme::default()
+            .with_background(Color::Rgb(0, 0, 0))
+            .with_foreground(Color::Rgb(0, 0, 0))
+            .with_interacting(Color::Rgb(200, 100, 0))
+            .with_transition(Duration::from_millis(100));
+        let mut screen = Screen::headless(Dim::new(8, 1), theme);
+        let button = Button::new(Label::new("OK"));
+        let wb: [(&dyn Widget, BBox); 1] = [(&button, BBox::new(0, 0, 8, 1))];
+        let start = Instant::now();
+        screen.draw_at(&wb, start)?;
+        assert_eq!(first_fg(&screen), Color::Rgb(0, 0, 0));
+        button.focus(FocusEvent::HoverInside);
+        let mut colors = vec![];
+        for ms in [0, 25, 50, 75, 100, 125] {
+            screen.draw_at(&wb, start + Duration::from_millis(ms))?;
+            colors.push(first_fg(&screen));
+        }
+        assert_eq!(
+            colors,
+            [
+                Color::Rgb(0, 0, 0),
+                Color::Rgb(50, 25, 0),
+                Color::Rgb(100, 50, 0),
+                Color::Rgb(150, 75, 0),
+                Color::Rgb(200, 100, 0),
+                Color::Rgb(200, 100, 0),
+            ]
+        );
+        // Final frame is exactly the target style
+        let snapshot = screen.snapshot();
+        let row = snapshot.rows().next().unwrap();
+        let target = screen.theme().style(StyleGroup::Hovered);
+        assert_eq!(Snapshot::runs(row)[0].0, target);
+        // Widgets no longer drawn are dropped
+        screen.draw_at(&[], start)?;
+        assert!(screen.transitions.widgets_is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn transition_frames() -> Result<()> {
+        use crate::input::MouseEvent;
+        let theme = Theme::default()
+            .with_background(Color::Rgb(0, 0, 0))
+            .with_foreground(Color::Rgb(0, 0, 0))
+            .with_interacting(Color::Rgb(200, 100, 0))
+            .with_transition(Duration::from_millis(100));
+        let mut screen = Screen::headless(Dim::new(8, 1), theme);
+        let button = Button::new(Label::new("OK"));
+        let grid = grid_area!([button])?;
+        let start = Instant::now();
+        screen.set_time(start);
+        screen.step_with_events(&grid, [])?;
+        assert_eq!(screen.next_frame(), None);
+        let hover = Event::Mouse(
+            MouseEvent::Drag(None),
+            ModKeys::EMPTY,
+            ScreenPos::new(1, 0),
+        );
+        screen.step_with_events(&grid, [hover])?;
+        // Frames are scheduled while the fade runs
+        let mut colors = vec![];
+        let mut ms = 0;
+        while let Some(at) = screen.next_frame() {
+            assert_eq!(at, start + Duration::from_millis(ms) + FRAME_INTERVAL);
+            ms += 25;
+            screen.set_time(start + Duration::from_millis(ms));
+            screen.step_with_events(&grid, [])?;
+            colors.push(first_fg(&screen));
+        }
+        assert_eq!(
+            colors,
+            [
+                Color::Rgb(50, 25, 0),
+                Color::Rgb(100, 50, 0),
+                Color::Rgb(150, 75, 0),
+                Color::Rgb(200, 100, 0),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn transition_off() -> Result<()> {
+        let theme = Theme::default()
+            .with_background(Color::Rgb(0, 0, 0))
+            .with_foreground(Color::Rgb(0, 0, 0))
+            .with_interacting(Color::Rgb(200, 100, 0));
+        let mut screen = Screen::headless(Dim::new(8, 1), theme);
+        let button = Button::new(Label::new("OK"));
+        let wb: [(&dyn Widget, BBox); 1] = [(&button, BBox::new(0, 0, 8, 1))];
+        let start = Instant::now();
+        screen.draw_at(&wb, start)?;
+        button.focus(FocusEvent::HoverInside);
+        screen.draw_at(&wb, start)?;
+        assert_eq!(first_fg(&screen), Color::Rgb(200, 100, 0));
+        assert!(screen.transitions.widgets_is_empty());
+        Ok(())
+    }
+
+    /// Scripted event source
+    struct Script(std::collections::VecDeque<CtEvent>);
+
+    impl EventSource for Script {
+        fn poll_next_event(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<crossterm::Result<CtEvent>>> {
+            Poll::Ready(self.try_next_event())
+        }
+
+        fn try_next_event(&mut self) -> Option<crossterm::Result<CtEvent>> {
+            self.0.pop_front().map(Ok)
+        }
+    }
+
+    /// Replace the event stream of a screen with scripted events
+    fn script(screen: &mut Screen, events: Vec<CtEvent>) {
+        let script = Script(events.into_iter().collect());
+        screen.ev_stream = EvStreamFut(Box::new(script));
+    }
+
+    /// Scripted mouse click
+    fn click(col: u16, row: u16) -> [CtEvent; 2] {
+        use crossterm::event::{
+            KeyModifiers, MouseButton as Btn, MouseEvent as Mev,
+            MouseEventKind::*,
+        };
+        [Down(Btn::Left), Up(Btn::Left)].map(|kind| {
+            CtEvent::Mouse(Mev {
+                kind,
+                column: col,
+                row,
+                modifiers: KeyModifiers::NONE,
+            })
+        })
+    }
+
+    /// Widget which is activated when clicked
+    struct Clicky;
+
+    impl Widget for Clicky {
+        fn mouse_event(
+            &self,
+            mev: MouseEvent,
+            _mods: ModKeys,
+            _dim: Dim,
+            _pos: WidgetPos,
+        ) -> Option<Action> {
+            match mev {
+                MouseEvent::ButtonUp(_) => {
+                    Some(Action::Changed(WidgetId::of(self)))
+                }
+                _ => None,
+            }
         }
     }
-    action.or(redraw)
+
+    /// Widget which requests frames for a number of draws
+    struct Frames(Cell<u32>);
+
+    impl Widget for Frames {
+        fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+            self.0.set(self.0.get() + 1);
+            if self.0.get() < 5 {
+                cells.request_frame();
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn scheduled_frames() -> Result<()> {
+        use futures::executor::block_on;
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let frames = Frames(Cell::new(0));
+        let grid = grid_area!([frames])?;
+        let timeout = Duration::from_millis(500);
+        let start = Instant::now();
+        let action = block_on(screen.step_timeout(&grid, timeout))?;
+        assert_eq!(action, Action::Tick());
+        assert!(start.elapsed() >= timeout);
+        assert_eq!(frames.0.get(), 5);
+        assert_eq!(screen.next_frame(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_swallow() -> Result<()> {
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let clicky = Clicky;
+        let grid = grid_area!([clicky])?;
+        let mut count = 0;
+        screen.set_action_filter(Box::new(move |action| match action {
+            Action::Changed(_) => {
+                count += 1;
+                (count % 2 == 1).then_some(action)
+            }
+            _ => Some(action),
+        }));
+        let events = (0..5).flat_map(|_| click(1, 0)).collect();
+        script(&mut screen, events);
+        let actions = screen.pump(&grid, Duration::from_secs(1))?;
+        let changed = Action::Changed(WidgetId::of(&clicky));
+        assert_eq!(actions, [changed, changed, changed]);
+        // Swallowed actions keep step waiting for the next one
+        screen.set_notify_resize(true);
+        let mut events = click(1, 0).to_vec();
+        events.push(CtEvent::Resize(4, 2));
+        script(&mut screen, events);
+        let action = futures::executor::block_on(screen.step(&grid))?;
+        assert_eq!(action, Action::Resize(Dim::new(4, 2)));
+        Ok(())
+    }
+
+    #[test]
+    fn filter_substitute() -> Result<()> {
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let clicky = Clicky;
+        let grid = grid_area!([clicky])?;
+        screen.set_action_filter(Box::new(|action| match action {
+            Action::Resize(_) => Some(Action::Custom(7)),
+            _ => Some(action),
+        }));
+        screen.set_notify_resize(true);
+        let mut events = vec![CtEvent::Resize(10, 3)];
+        events.extend(click(9, 2));
+        script(&mut screen, events);
+        let action = futures::executor::block_on(screen.step(&grid))?;
+        assert_eq!(action, Action::Custom(7));
+        // Screen was still resized
+        assert_eq!(screen.bbox().dim(), Dim::new(10, 3));
+        let actions = screen.pump(&grid, Duration::from_secs(1))?;
+        assert_eq!(actions, [Action::Changed(WidgetId::of(&clicky))]);
+        Ok(())
+    }
+
+    #[test]
+    fn resize_relayout() -> Result<()> {
+        use futures::executor::block_on;
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let clicky = Clicky;
+        let grid = grid_area!([clicky])?;
+        // Click lands in the new layout, without returning the resizes
+        let mut events = vec![CtEvent::Resize(9, 2), CtEvent::Resize(10, 3)];
+        events.extend(click(9, 2));
+        script(&mut screen, events);
+        let action = block_on(screen.step(&grid))?;
+        assert_eq!(action, Action::Changed(WidgetId::of(&clicky)));
+        assert_eq!(screen.bbox().dim(), Dim::new(10, 3));
+        let mut events = vec![CtEvent::Resize(4, 1)];
+        events.extend(click(3, 0));
+        script(&mut screen, events);
+        let timeout = Duration::from_secs(5);
+        let action = block_on(screen.step_timeout(&grid, timeout))?;
+        assert_eq!(action, Action::Changed(WidgetId::of(&clicky)));
+        script(&mut screen, vec![CtEvent::Resize(6, 1)]);
+        assert!(screen.pump(&grid, timeout)?.is_empty());
+        assert_eq!(screen.bbox().dim(), Dim::new(6, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn too_small() -> Result<()> {
+        let mut screen = Screen::headless(Dim::new(8, 3), Theme::default());
+        let a = Label::new("Alpha");
+        let b = Spacer::default().with_rows(2..);
+        let c = Label::new("Gamma");
+        let grid = grid_area!([a c][b b])?;
+        assert_eq!(grid.min_size(&Theme::default()), Dim::new(10, 3));
+        screen.render(&grid)?;
+        assert_eq!(screen.text(), "AlphaGam\n        \n        \n");
+        screen.set_check_size(true);
+        screen.render(&grid)?;
+        assert_eq!(screen.text(), "        \nTermina…\n        \n");
+        screen.set_check_size(false);
+        screen.render(&grid)?;
+        assert_eq!(screen.text(), "AlphaGam\n        \n        \n");
+        let mut screen = Screen::headless(Dim::new(39, 1), Theme::default());
+        screen.set_check_size(true);
+        let d = Spacer::default().with_columns(40..);
+        let grid = grid_area!([d])?;
+        screen.render(&grid)?;
+        let text = screen.text();
+        assert_eq!(text, "    Terminal too small (need 40x0)     \n");
+        Ok(())
+    }
+
+    /// Shared cast file buffer
+    #[derive(Clone, Default)]
+    struct Sink(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Sink {
+        /// Count output events recorded
+        fn frames(&self) -> usize {
+            let cast = self.0.borrow();
+            let text = std::str::from_utf8(&cast).unwrap();
+            text.lines().filter(|l| l.contains(", \"o\", ")).count()
+        }
+    }
+
+    /// Scripted key press
+    fn key(code: crossterm::event::KeyCode) -> CtEvent {
+        use crossterm::event::{KeyEvent, KeyModifiers};
+        CtEvent::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn tab_traversal() -> Result<()> {
+        use crossterm::event::KeyCode;
+        let mut screen = Screen::headless(Dim::new(15, 1), Theme::default());
+        let a = Button::new(Label::new("A"));
+        let l = Label::new("L");
+        let b = Button::new(Label::new("B"));
+        let c = Button::new(Label::new("C"));
+        c.disable();
+        let grid = grid_area!([a l b c])?;
+        let groups = || [a.style_group(), b.style_group()];
+        use StyleGroup::*;
+        let budget = Duration::from_secs(1);
+        script(&mut screen, vec![key(KeyCode::Tab)]);
+        screen.pump(&grid, budget)?;
+        assert_eq!(groups(), [Focused, Enabled]);
+        // Labels and disabled buttons are skipped
+        script(&mut screen, vec![key(KeyCode::Tab)]);
+        screen.pump(&grid, budget)?;
+        assert_eq!(groups(), [Enabled, Focused]);
+        script(&mut screen, vec![key(KeyCode::Tab)]);
+        screen.pump(&grid, budget)?;
+        assert_eq!(groups(), [Focused, Enabled]);
+        script(&mut screen, vec![key(KeyCode::BackTab)]);
+        screen.pump(&grid, budget)?;
+        assert_eq!(groups(), [Enabled, Focused]);
+        Ok(())
+    }
+
+    #[test]
+    fn tab_after_click() -> Result<()> {
+        use crossterm::event::KeyCode;
+        let mut screen = Screen::headless(Dim::new(12, 1), Theme::default());
+        let a = Button::new(Label::new("A"));
+        let b = Button::new(Label::new("B"));
+        let c = Button::new(Label::new("C"));
+        let grid = grid_area!([a b c])?;
+        let groups = || [a.style_group(), b.style_group(), c.style_group()];
+        use StyleGroup::*;
+        let budget = Duration::from_secs(1);
+        // Nothing focused: BackTab starts at the last widget
+        script(&mut screen, vec![key(KeyCode::BackTab)]);
+        screen.pump(&grid, budget)?;
+        assert_eq!(groups(), [Enabled, Enabled, Focused]);
+        // Traversal continues from a clicked widget
+        let mut events = click(5, 0).to_vec();
+        events.push(key(KeyCode::BackTab));
+        script(&mut screen, events);
+        screen.pump(&grid, budget)?;
+        assert_eq!(groups(), [Focused, Enabled, Enabled]);
+        Ok(())
+    }
+
+    /// Widget which returns codes for key releases
+    struct Releasy;
+
+    impl Widget for Releasy {
+        fn focusable(&self) -> bool {
+            true
+        }
+
+        fn key_event(&self, _key: KeyPress, _mods: ModKeys) -> Option<Action> {
+            Some(Action::Custom(1))
+        }
+
+        fn key_release(
+            &self,
+            _key: KeyPress,
+            _mods: ModKeys,
+        ) -> Option<Action> {
+            Some(Action::Custom(2))
+        }
+    }
+
+    #[test]
+    fn key_kinds() -> Result<()> {
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let a = Label::new("A");
+        let grid = grid_area!([a])?;
+        let esc = KeyPress::Navigation(NavKey::Esc);
+        // Only presses are looked up in the key map
+        let events = [
+            Event::KeyRepeat(esc, ModKeys::EMPTY),
+            Event::KeyRelease(esc, ModKeys::EMPTY),
+        ];
+        assert!(screen.step_with_events(&grid, events)?.is_empty());
+        let events = [Event::Key(esc, ModKeys::EMPTY)];
+        assert_eq!(screen.step_with_events(&grid, events)?, [Action::Quit()]);
+        let r = Releasy;
+        let grid = grid_area!([r])?;
+        let x = KeyPress::Character('x');
+        let events = [
+            Event::Key(KeyPress::Navigation(NavKey::Tab), ModKeys::EMPTY),
+            Event::Key(x, ModKeys::EMPTY),
+            Event::KeyRepeat(x, ModKeys::EMPTY),
+            Event::KeyRelease(x, ModKeys::EMPTY),
+        ];
+        let actions = screen.step_with_events(&grid, events)?;
+        let codes = [1, 1, 2].map(Action::Custom);
+        assert_eq!(actions, codes);
+        Ok(())
+    }
+
+    #[test]
+    fn confirm_double_press() -> Result<()> {
+        let mut screen = Screen::headless(Dim::new(16, 2), Theme::default());
+        let mut keymap = KeyMap::default();
+        let timeout = Duration::from_secs(2);
+        keymap.bind_confirm(
+            KeyPress::Character('d'),
+            ModKeys::EMPTY,
+            Action::Quit(),
+            "Press d again",
+            timeout,
+        );
+        screen.set_keymap(keymap);
+        let a = Label::new("A");
+        let grid = grid_area!([a])?;
+        let start = Instant::now();
+        screen.set_time(start);
+        // First press arms and shows the prompt
+        assert!(screen.step_with_events(&grid, [press('d')])?.is_empty());
+        assert_eq!(screen.text(), "A               \nPress d again   \n");
+        assert_eq!(screen.next_frame(), Some(start + timeout));
+        // Second press confirms
+        let actions = screen.step_with_events(&grid, [press('d')])?;
+        assert_eq!(actions, [Action::Quit()]);
+        assert_eq!(screen.confirm_prompt(), None);
+        screen.step_with_events(&grid, [])?;
+        assert!(!screen.text().contains("Press"));
+        assert_eq!(screen.next_frame(), None);
+        // Any other key cancels
+        let actions = screen
+            .step_with_events(&grid, [press('d'), press('x'), press('d')])?;
+        assert!(actions.is_empty());
+        assert!(screen.text().contains("Press d again"));
+        // Timeout disarms on the scheduled frame
+        screen.set_time(start + timeout);
+        assert!(screen.step_with_events(&grid, [])?.is_empty());
+        assert!(!screen.text().contains("Press"));
+        assert_eq!(screen.next_frame(), None);
+        assert_eq!(screen.confirm_prompt(), None);
+        assert!(screen.step_with_events(&grid, [press('d')])?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn confirm_timeout_redraw() -> Result<()> {
+        use crossterm::event::KeyCode;
+        use futures::executor::block_on;
+        let mut screen = Screen::headless(Dim::new(16, 1), Theme::default());
+        let mut keymap = KeyMap::default();
+        keymap.bind_confirm(
+            KeyPress::Character('d'),
+            ModKeys::EMPTY,
+            Action::Quit(),
+            "Press d again",
+            Duration::from_millis(50),
+        );
+        screen.set_keymap(keymap);
+        let a = Label::new("A");
+        let grid = grid_area!([a])?;
+        script(&mut screen, vec![key(KeyCode::Char('d'))]);
+        let timeout = Duration::from_millis(200);
+        let action = block_on(screen.step_timeout(&grid, timeout))?;
+        assert_eq!(action, Action::Redraw());
+        let action = block_on(screen.step_timeout(&grid, timeout))?;
+        assert_eq!(action, Action::Tick());
+        assert_eq!(screen.text(), "A               \n");
+        assert_eq!(screen.keymap.deadline(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn focused_key_first() -> Result<()> {
+        use crate::input::KeyPress;
+        use crossterm::event::KeyCode;
+        let mut screen = Screen::headless(Dim::new(10, 1), Theme::default());
+        let mut keymap = KeyMap::default();
+        let space = KeyPress::Character(' ');
+        let timeout = Duration::from_secs(60);
+        keymap.bind_confirm(
+            space,
+            ModKeys::EMPTY,
+            Action::Custom(1),
+            "",
+            timeout,
+        );
+        screen.set_keymap(keymap);
+        let a = Button::new(Label::new("A"));
+        let l = Label::new("L");
+        let grid = grid_area!([a l])?;
+        let budget = Duration::from_secs(1);
+        screen.render(&grid)?;
+        script(
+            &mut screen,
+            vec![key(KeyCode::Tab), key(KeyCode::Char(' '))],
+        );
+        let actions = screen.pump(&grid, budget)?;
+        assert_eq!(actions, [Action::Activated(WidgetId::of(&a))]);
+        // Nothing focused after clicking the label
+        let mut events = click(7, 0).to_vec();
+        events.push(key(KeyCode::Char(' ')));
+        events.push(key(KeyCode::Char(' ')));
+        script(&mut screen, events);
+        let actions = screen.pump(&grid, budget)?;
+        assert_eq!(actions, [Action::Custom(1)]);
+        Ok(())
+    }
+
+    #[test]
+    fn button_activated() -> Result<()> {
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let a = Button::new(Label::new("A"));
+        let b = Button::new(Label::new("B"));
+        let grid = grid_area!([a b])?;
+        let budget = Duration::from_secs(1);
+        screen.render(&grid)?;
+        let mut events = click(1, 0).to_vec();
+        events.extend(click(6, 0));
+        script(&mut screen, events);
+        let actions = screen.pump(&grid, budget)?;
+        let activated = |w: &dyn Widget| Action::Activated(WidgetId::of(w));
+        assert_eq!(actions, [activated(&a), activated(&b)]);
+        // Releasing outside cancels the press
+        let [down, _] = click(1, 0);
+        let [_, up] = click(6, 0);
+        script(&mut screen, vec![down, up]);
+        assert!(screen.pump(&grid, budget)?.is_empty());
+        assert_eq!(a.style_group(), StyleGroup::Focused);
+        assert_eq!(b.style_group(), StyleGroup::Hovered);
+        Ok(())
+    }
+
+    #[test]
+    fn drag_captured() -> Result<()> {
+        use crossterm::event::{
+            KeyModifiers, MouseButton as Btn, MouseEvent as Mev, MouseEventKind,
+        };
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let a = Button::new(Label::new("A"));
+        let clicky = Clicky;
+        let grid = grid_area!([a clicky])?;
+        screen.render(&grid)?;
+        let [down, _] = click(1, 0);
+        let [_, up] = click(6, 0);
+        let drag = CtEvent::Mouse(Mev {
+            kind: MouseEventKind::Drag(Btn::Left),
+            column: 6,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        });
+        script(&mut screen, vec![down, drag, up]);
+        // Release goes to the pressed button, not the widget below it
+        assert!(screen.pump(&grid, Duration::from_secs(1))?.is_empty());
+        assert_eq!(a.style_group(), StyleGroup::Focused);
+        script(&mut screen, click(6, 0).to_vec());
+        let actions = screen.pump(&grid, Duration::from_secs(1))?;
+        assert_eq!(actions, [Action::Changed(WidgetId::of(&clicky))]);
+        Ok(())
+    }
+
+    #[test]
+    fn stream_closed() -> Result<()> {
+        use futures::executor::block_on;
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let a = Label::new("A");
+        let grid = grid_area!([a])?;
+        let res = block_on(screen.step(&grid));
+        assert!(matches!(res, Err(Error::EventStreamClosed())), "{:?}", res);
+        // Events still pending are handled first
+        script(&mut screen, vec![key(crossterm::event::KeyCode::Esc)]);
+        assert_eq!(block_on(screen.step(&grid))?, Action::Quit());
+        Ok(())
+    }
+
+    /// Shared state of a [Lagging] event source
+    #[derive(Default)]
+    struct LagState {
+        events: std::collections::VecDeque<CtEvent>,
+        waker: Option<Waker>,
+    }
+
+    /// Event source which, like crossterm's stream, keeps the waker from
+    /// its first pending poll until an event arrives
+    #[derive(Clone, Default)]
+    struct Lagging(Arc<Mutex<LagState>>);
+
+    impl Lagging {
+        /// Push an event and wake the recorded waker
+        fn push(&self, ev: CtEvent) {
+            let mut state = self.0.lock().unwrap();
+            state.events.push_back(ev);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+
+        /// Check whether a waker has been recorded
+        fn has_waker(&self) -> bool {
+            self.0.lock().unwrap().waker.is_some()
+        }
+    }
+
+    impl EventSource for Lagging {
+        fn poll_next_event(
+            &mut self,
+            cx: &mut Context<'_>,
+        ) -> Poll<Option<crossterm::Result<CtEvent>>> {
+            let mut state = self.0.lock().unwrap();
+            match state.events.pop_front() {
+                Some(ev) => Poll::Ready(Some(Ok(ev))),
+                None => {
+                    if state.waker.is_none() {
+                        state.waker = Some(cx.waker().clone());
+                    }
+                    Poll::Pending
+                }
+            }
+        }
+
+        fn try_next_event(&mut self) -> Option<crossterm::Result<CtEvent>> {
+            self.0.lock().unwrap().events.pop_front().map(Ok)
+        }
+    }
+
+    #[test]
+    fn pump_then_step() -> Result<()> {
+        use crossterm::event::KeyCode;
+        use futures::executor::block_on;
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let a = Label::new("A");
+        let grid = grid_area!([a])?;
+        let source = Lagging::default();
+        screen.ev_stream = EvStreamFut(Box::new(source.clone()));
+        assert!(screen.pump(&grid, Duration::from_millis(5))?.is_empty());
+        assert!(!source.has_waker());
+        let sender = source.clone();
+        let handle = std::thread::spawn(move || {
+            while !sender.has_waker() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            sender.push(key(KeyCode::Esc));
+        });
+        assert_eq!(block_on(screen.step(&grid))?, Action::Quit());
+        handle.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn step_after_resize() -> Result<()> {
+        use crossterm::event::KeyCode;
+        use futures::executor::block_on;
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let a = Label::new("A");
+        let grid = grid_area!([a])?;
+        for timeout in [None, Some(Duration::from_secs(5))] {
+            let source = Lagging::default();
+            source.push(CtEvent::Resize(6, 1));
+            screen.ev_stream = EvStreamFut(Box::new(source.clone()));
+            let sender = source.clone();
+            let handle = std::thread::spawn(move || {
+                while !sender.has_waker() {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                sender.push(key(KeyCode::Esc));
+            });
+            let action = match timeout {
+                Some(timeout) => block_on(screen.step_timeout(&grid, timeout)),
+                None => block_on(screen.step(&grid)),
+            };
+            assert_eq!(action?, Action::Quit());
+            assert_eq!(screen.bbox().dim(), Dim::new(6, 1));
+            handle.join().unwrap();
+        }
+        Ok(())
+    }
+
+    /// Waker which unparks a thread
+    struct Unpark(std::thread::Thread);
+
+    impl std::task::Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    #[test]
+    fn timer_rearm() {
+        let mut timer = Timer::default();
+        let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+        let start = Instant::now();
+        timer.arm(start + Duration::from_secs(10), &waker);
+        let shared = timer.shared.as_ref().map(Arc::as_ptr);
+        timer.arm(start + Duration::from_millis(10), &waker);
+        assert_eq!(timer.shared.as_ref().map(Arc::as_ptr), shared);
+        while start.elapsed() < Duration::from_millis(10) {
+            std::thread::park_timeout(Duration::from_secs(5));
+        }
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let state = timer.shared.as_ref().unwrap().state.lock().unwrap();
+        assert!(state.armed.is_none());
+    }
+
+    #[test]
+    fn step_timeout() -> Result<()> {
+        use crossterm::event::KeyCode;
+        use futures::executor::block_on;
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let a = Label::new("A");
+        let grid = grid_area!([a])?;
+        let timeout = Duration::from_millis(20);
+        let start = Instant::now();
+        let action = block_on(screen.step_timeout(&grid, timeout))?;
+        assert_eq!(action, Action::Tick());
+        assert!(start.elapsed() >= timeout);
+        // The timer thread is re-armed, not spawned again
+        let shared = screen.timer.shared.as_ref().map(Arc::as_ptr);
+        assert!(shared.is_some());
+        let action = block_on(screen.step_timeout(&grid, timeout))?;
+        assert_eq!(action, Action::Tick());
+        assert_eq!(screen.timer.shared.as_ref().map(Arc::as_ptr), shared);
+        // Events arriving first take precedence
+        script(&mut screen, vec![key(KeyCode::Esc)]);
+        let start = Instant::now();
+        let action = block_on(screen.step_timeout(&grid, timeout))?;
+        assert_eq!(action, Action::Quit());
+        assert!(start.elapsed() < timeout);
+        // Events without an action do not stop the tick
+        script(&mut screen, click(7, 0).to_vec());
+        let action = block_on(screen.step_timeout(&grid, timeout))?;
+        assert_eq!(action, Action::Tick());
+        Ok(())
+    }
+
+    /// Widget which is activated when double-clicked
+    struct DoubleClicky;
+
+    impl Widget for DoubleClicky {
+        fn mouse_event(
+            &self,
+            mev: MouseEvent,
+            _mods: ModKeys,
+            _dim: Dim,
+            _pos: WidgetPos,
+        ) -> Option<Action> {
+            match mev {
+                MouseEvent::DoubleClick(MouseButton::Left) => {
+                    Some(Action::Activated(WidgetId::of(self)))
+                }
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn double_click() -> Result<()> {
+        let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+        let dc = DoubleClicky;
+        let grid = grid_area!([dc])?;
+        let mut events = click(2, 0).to_vec();
+        events.extend(click(2, 0));
+        events.extend(click(2, 0));
+        let budget = Duration::from_secs(1);
+        screen.render(&grid)?;
+        script(&mut screen, events.clone());
+        let actions = screen.pump(&grid, budget)?;
+        assert_eq!(actions, [Action::Activated(WidgetId::of(&dc))]);
+        // Clicks at different positions are not double clicks
+        let mut moved = click(4, 0).to_vec();
+        moved.extend(click(5, 0));
+        script(&mut screen, moved);
+        assert!(screen.pump(&grid, budget)?.is_empty());
+        screen.set_double_click(Duration::ZERO);
+        script(&mut screen, events);
+        assert!(screen.pump(&grid, budget)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn batch_one_redraw() -> Result<()> {
+        use crossterm::event::KeyCode;
+        let dim = Dim::new(12, 4);
+        let mut screen = Screen::headless(dim, Theme::default());
+        let sink = Sink::default();
+        let _rec = screen.out.record(Box::new(sink.clone()), dim)?;
+        let a = Button::new(Label::new("A"));
+        let b = Button::new(Label::new("B"));
+        let grid = grid_area!([a][b])?;
+        screen.render(&grid)?;
+        let frames = sink.frames();
+        let budget = Duration::from_millis(10);
+        screen.batch(|screen| -> Result<()> {
+            a.disable();
+            assert!(screen.pump(&grid, budget)?.is_empty());
+            // Input arriving mid-batch is queued
+            script(screen, vec![key(KeyCode::Esc)]);
+            b.disable();
+            // Nested batches coalesce to the outermost
+            screen.batch(|screen| screen.pump(&grid, budget))?;
+            a.enable();
+            assert!(screen.pump(&grid, budget)?.is_empty());
+            Ok(())
+        })?;
+        assert_eq!(sink.frames(), frames);
+        let actions = screen.pump(&grid, budget)?;
+        assert_eq!(actions, [Action::Quit()]);
+        assert_eq!(sink.frames(), frames + 1);
+        Ok(())
+    }
+
+    #[test]
+    fn damage() -> Result<()> {
+        let dim = Dim::new(12, 2);
+        let mut screen = Screen::headless(dim, Theme::default());
+        let sink = Sink::default();
+        screen.out = Output::new(Box::new(sink.clone()));
+        let a = Button::new(Label::new("Alpha"));
+        let b = Button::new(Label::new("Beta"));
+        let grid = grid_area!([a][b])?;
+        let output = |screen: &mut Screen| -> Result<String> {
+            screen.render(&grid)?;
+            let out = sink.0.take();
+            Ok(String::from_utf8(out).unwrap())
+        };
+        let out = output(&mut screen)?;
+        assert!(out.contains("Alpha") && out.contains("Beta"), "{:?}", out);
+        // Nothing changed
+        assert_eq!(output(&mut screen)?, "");
+        b.disable();
+        let out = output(&mut screen)?;
+        assert!(out.contains("Beta") && !out.contains("Alpha"), "{:?}", out);
+        // Resizing repaints everything
+        screen.event_action(Event::Resize(dim), &[]);
+        let out = output(&mut screen)?;
+        assert!(out.contains("Alpha") && out.contains("Beta"), "{:?}", out);
+        Ok(())
+    }
+
+    #[test]
+    fn popup() -> Result<()> {
+        use crate::text::Weight;
+        use futures::executor::block_on;
+        let mut screen = Screen::headless(Dim::new(8, 3), Theme::default());
+        screen.set_notify_resize(true);
+        let base = Clicky;
+        let grid = grid_area!([base])?;
+        let a = Clicky;
+        let b = Label::new("ok");
+        let dialog = grid_area!([a b])?;
+        let bbox = BBox::new(2, 1, 4, 1);
+        let popup = Popup::new(&dialog, bbox).with_dim(true);
+        // Clicks outside the popup are swallowed
+        let mut events = click(0, 0).to_vec();
+        events.extend(click(2, 1));
+        script(&mut screen, events);
+        let action = block_on(screen.step_with_popup(&grid, Some(&popup)))?;
+        assert_eq!(action, Action::Changed(WidgetId::of(&a)));
+        assert_eq!(screen.text(), "        \n    ok  \n        \n");
+        let cell = screen.snapshot().cell(0, 0).unwrap();
+        assert_eq!(cell.style().appearance().weight(), Weight::Thin);
+        let cell = screen.snapshot().cell(4, 1).unwrap();
+        assert_ne!(cell.style().appearance().weight(), Weight::Thin);
+        let popup = popup.with_dismiss(true);
+        script(&mut screen, click(7, 2).to_vec());
+        let action = block_on(screen.step_with_popup(&grid, Some(&popup)))?;
+        assert_eq!(action, Action::Dismissed());
+        // Without the popup, the base layer receives clicks again
+        script(&mut screen, click(7, 2).to_vec());
+        let action = block_on(screen.step(&grid))?;
+        assert_eq!(action, Action::Changed(WidgetId::of(&base)));
+        Ok(())
+    }
 }
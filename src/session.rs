@@ -0,0 +1,311 @@
+// session.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::WidgetId;
+use crate::layout::GridArea;
+use std::collections::BTreeMap;
+
+/// Saved state of one widget
+///
+/// Each widget defines its own compact representation, as a list of values
+/// and some text.  The version must be bumped when the representation
+/// changes, so that blobs saved by older versions are ignored.
+///
+/// Wrapper widgets can save the state of their wrapped widget as a nested
+/// blob.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct StateBlob {
+    /// Representation version
+    version: u32,
+    /// Numeric values
+    values: Vec<u64>,
+    /// Text value
+    text: String,
+    /// Nested blob of a wrapped widget
+    #[cfg_attr(feature = "serde", serde(default))]
+    nested: Option<Box<StateBlob>>,
+}
+
+/// Widget which can save and restore its state
+///
+/// Restoring must not panic when a blob no longer fits the widget, such as
+/// an index past the current number of items.  Widgets should clamp the
+/// values, or ignore the blob.
+pub trait PersistState {
+    /// Save the widget state
+    fn save_state(&self) -> Option<StateBlob>;
+
+    /// Restore the widget state
+    ///
+    /// * `blob`: State saved by [save_state](PersistState::save_state)
+    fn restore_state(&self, blob: &StateBlob);
+}
+
+/// UI session state
+///
+/// The saved state of every tagged widget in a grid area which implements
+/// [PersistState], keyed by tag.  Applications can store it however they
+/// like; with the `serde` feature, it can be serialized.
+///
+/// [Screen::export_session] and [Screen::import_session] save and restore
+/// a session.
+///
+/// [Screen::export_session]: struct.Screen.html#method.export_session
+/// [Screen::import_session]: struct.Screen.html#method.import_session
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SessionState {
+    /// Blobs keyed by widget tag
+    blobs: BTreeMap<u64, StateBlob>,
+    /// Tag of widget with keyboard focus
+    #[cfg_attr(feature = "serde", serde(default))]
+    focused: Option<u64>,
+}
+
+impl StateBlob {
+    /// Create a new state blob
+    ///
+    /// * `version`: Representation version
+    pub fn new(version: u32) -> Self {
+        StateBlob {
+            version,
+            ..Default::default()
+        }
+    }
+
+    /// Set the numeric values
+    pub fn with_values(mut self, values: Vec<u64>) -> Self {
+        self.values = values;
+        self
+    }
+
+    /// Set the text value
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        self
+    }
+
+    /// Set the nested blob of a wrapped widget
+    pub fn with_nested(mut self, nested: StateBlob) -> Self {
+        self.nested = Some(Box::new(nested));
+        self
+    }
+
+    /// Get the representation version
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Get the numeric values
+    pub fn values(&self) -> &[u64] {
+        &self.values
+    }
+
+    /// Get the text value
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the nested blob of a wrapped widget
+    pub fn nested(&self) -> Option<&StateBlob> {
+        self.nested.as_deref()
+    }
+}
+
+impl SessionState {
+    /// Get the number of saved widgets
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    /// Check if no widgets are saved
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+
+    /// Get the blob saved for a tag
+    pub fn get(&self, tag: u64) -> Option<&StateBlob> {
+        self.blobs.get(&tag)
+    }
+
+    /// Insert the blob for a tag
+    pub fn insert(&mut self, tag: u64, blob: StateBlob) {
+        self.blobs.insert(tag, blob);
+    }
+
+    /// Get the tag of the widget with keyboard focus
+    pub fn focused(&self) -> Option<u64> {
+        self.focused
+    }
+
+    /// Set the tag of the widget with keyboard focus
+    pub fn set_focused(&mut self, tag: Option<u64>) {
+        self.focused = tag;
+    }
+
+    /// Save the state of tagged widgets in a grid area
+    ///
+    /// * `area`: Grid area
+    /// * `focused`: Layout index of widget with keyboard focus
+    pub(crate) fn save(area: &GridArea<'_>, focused: Option<usize>) -> Self {
+        let mut session = SessionState::default();
+        let tagged = area.tagged_widgets();
+        for (tag, widget) in &tagged {
+            let blob = widget.persist().and_then(|p| p.save_state());
+            if let Some(blob) = blob {
+                session.insert(*tag, blob);
+            }
+        }
+        let leaves = area.leaf_widgets();
+        if let Some(widget) = focused.and_then(|i| leaves.get(i)) {
+            let id = WidgetId::of(*widget);
+            session.focused = tagged
+                .iter()
+                .find(|(_, w)| WidgetId::of(*w) == id)
+                .map(|(tag, _)| *tag);
+        }
+        session
+    }
+
+    /// Get the layout index of the focused widget in a grid area
+    ///
+    /// ## Return
+    ///
+    /// Index, if the focused tag is a focusable widget in the grid area.
+    pub(crate) fn focused_index(&self, area: &GridArea<'_>) -> Option<usize> {
+        let tag = self.focused?;
+        let (_, widget) =
+            area.tagged_widgets().into_iter().find(|(t, _)| *t == tag)?;
+        let id = WidgetId::of(widget);
+        area.leaf_widgets()
+            .iter()
+            .position(|w| WidgetId::of(*w) == id && w.focusable())
+    }
+
+    /// Restore the state of tagged widgets in a grid area
+    ///
+    /// Blobs for tags which are not in the grid area are ignored.
+    pub(crate) fn restore(&self, area: &GridArea<'_>) {
+        for (tag, widget) in area.tagged_widgets() {
+            if let (Some(blob), Some(p)) = (self.get(tag), widget.persist()) {
+                p.restore_state(blob);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::layout::Dim;
+    use crate::text::Theme;
+    use crate::{grid_area, Result, Screen, Widget};
+    use std::cell::Cell;
+
+    /// Widget which saves a single value
+    #[derive(Default)]
+    struct Counter(Cell<u64>);
+
+    impl PersistState for Counter {
+        fn save_state(&self) -> Option<StateBlob> {
+            Some(StateBlob::new(1).with_values(vec![self.0.get()]))
+        }
+
+        fn restore_state(&self, blob: &StateBlob) {
+            if let (1, [value]) = (blob.version(), blob.values()) {
+                self.0.set(*value);
+            }
+        }
+    }
+
+    impl Widget for Counter {
+        fn persist(&self) -> Option<&dyn PersistState> {
+            Some(self)
+        }
+
+        fn focusable(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let (a, b, c) =
+            (Counter::default(), Counter::default(), Counter::default());
+        a.0.set(3);
+        b.0.set(5);
+        c.0.set(7);
+        let grid = grid_area!([(#1 a) (#2 b) c])?;
+        let mut screen = Screen::headless(Dim::new(6, 1), Theme::default());
+        let session = screen.export_session(&grid);
+        // Untagged widgets are not saved
+        assert_eq!(session.len(), 2);
+        assert_eq!(session.get(2).map(|b| b.values()), Some(&[5][..]));
+        let (x, y) = (Counter::default(), Counter::default());
+        let grid = grid_area!([(#2 x) (#3 y)])?;
+        screen.import_session(session);
+        // Restored before the first draw
+        assert_eq!(x.0.get(), 0);
+        screen.render(&grid)?;
+        assert_eq!(x.0.get(), 5);
+        assert_eq!(y.0.get(), 0);
+        // Imported only once
+        x.0.set(9);
+        screen.render(&grid)?;
+        assert_eq!(x.0.get(), 9);
+        Ok(())
+    }
+
+    #[test]
+    fn stale_version() -> Result<()> {
+        let a = Counter::default();
+        let grid = grid_area!([(#1 a)])?;
+        let mut session = SessionState::default();
+        session.insert(1, StateBlob::new(2).with_values(vec![4]));
+        let mut screen = Screen::headless(Dim::new(6, 1), Theme::default());
+        screen.import_session(session);
+        screen.render(&grid)?;
+        assert_eq!(a.0.get(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn focused() -> Result<()> {
+        use crate::input::{Event, KeyPress, ModKeys, NavKey};
+        let tab = Event::Key(KeyPress::Navigation(NavKey::Tab), ModKeys::EMPTY);
+        let (a, b, c) =
+            (Counter::default(), Counter::default(), Counter::default());
+        let grid = grid_area!([(#1 a) b (#3 c)])?;
+        let mut screen = Screen::headless(Dim::new(6, 1), Theme::default());
+        assert_eq!(screen.export_session(&grid).focused(), None);
+        screen.step_with_events(&grid, [tab])?;
+        assert_eq!(screen.export_session(&grid).focused(), Some(1));
+        // Untagged widgets are not saved
+        screen.step_with_events(&grid, [tab])?;
+        assert_eq!(screen.export_session(&grid).focused(), None);
+        screen.step_with_events(&grid, [tab])?;
+        let session = screen.export_session(&grid);
+        assert_eq!(session.focused(), Some(3));
+        let mut screen = Screen::headless(Dim::new(6, 1), Theme::default());
+        screen.import_session(session);
+        screen.render(&grid)?;
+        assert_eq!(screen.export_session(&grid).focused(), Some(3));
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let mut session = SessionState::default();
+        session.insert(4, StateBlob::new(1).with_values(vec![2, 3]));
+        session.insert(8, StateBlob::new(1).with_text("input"));
+        let nested = StateBlob::new(1).with_values(vec![5]);
+        session.insert(9, StateBlob::new(1).with_nested(nested));
+        session.set_focused(Some(8));
+        let json = serde_json::to_string(&session).unwrap();
+        let de: SessionState = serde_json::from_str(&json).unwrap();
+        assert_eq!(de, session);
+    }
+}
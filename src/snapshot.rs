@@ -0,0 +1,348 @@
+// snapshot.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::layout::Dim;
+use crate::text::{display_width, Appearance, ColorSupport, TextStyle};
+use crossterm::style::{
+    Attribute, ResetColor, SetAttribute, SetAttributes, SetBackgroundColor,
+    SetForegroundColor,
+};
+use crossterm::Command;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// One text cell of a snapshot
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SnapCell {
+    /// Grapheme cluster (empty for the second half of a wide glyph)
+    text: String,
+    /// Text style
+    style: TextStyle,
+}
+
+/// Snapshot of the text cells drawn on a screen
+///
+/// The snapshot tracks the cursor and style just like a terminal, so it
+/// contains exactly what would be displayed.  Screens draw each frame into
+/// a snapshot, then write only the cells which changed to the terminal.
+#[derive(Clone, Debug)]
+pub(crate) struct Snapshot {
+    /// Dimensions in text cells
+    dim: Dim,
+    /// All cells, in row-major order
+    cells: Vec<SnapCell>,
+    /// Cursor column
+    col: u16,
+    /// Cursor row
+    row: u16,
+    /// Current text style
+    style: TextStyle,
+}
+
+impl SnapCell {
+    /// Create a blank cell
+    fn blank(style: TextStyle) -> Self {
+        let text = " ".to_string();
+        SnapCell { text, style }
+    }
+
+    /// Get the grapheme cluster
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the text style
+    pub(crate) fn style(&self) -> TextStyle {
+        self.style
+    }
+}
+
+impl Snapshot {
+    /// Create a new blank snapshot
+    pub(crate) fn new(dim: Dim) -> Self {
+        let style = TextStyle::default();
+        let len = usize::from(dim.width) * usize::from(dim.height);
+        let cells = vec![SnapCell::blank(style); len];
+        Snapshot {
+            dim,
+            cells,
+            col: 0,
+            row: 0,
+            style,
+        }
+    }
+
+    /// Get the dimensions
+    pub(crate) fn dim(&self) -> Dim {
+        self.dim
+    }
+
+    /// Clear all cells using the current style
+    pub(crate) fn clear(&mut self) {
+        let style = self.style;
+        self.cells
+            .iter_mut()
+            .for_each(|c| *c = SnapCell::blank(style));
+    }
+
+    /// Set the current text style
+    pub(crate) fn set_style(&mut self, style: TextStyle) {
+        self.style = style;
+    }
+
+    /// Move the cursor to a cell
+    pub(crate) fn move_to(&mut self, col: u16, row: u16) {
+        self.col = col;
+        self.row = row;
+    }
+
+    /// Move the cursor right by a number of columns
+    pub(crate) fn move_right(&mut self, cols: u16) {
+        self.col = self.col.saturating_add(cols);
+    }
+
+    /// Get the index of a cell
+    fn index(&self, col: u16, row: u16) -> Option<usize> {
+        if col < self.dim.width && row < self.dim.height {
+            Some(
+                usize::from(row) * usize::from(self.dim.width)
+                    + usize::from(col),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Get a cell
+    pub(crate) fn cell(&self, col: u16, row: u16) -> Option<&SnapCell> {
+        self.index(col, row).map(|i| &self.cells[i])
+    }
+
+    /// Print a str at the cursor location
+    ///
+    /// Text past the right edge is dropped, since line wrap is disabled.
+    pub(crate) fn print_str(&mut self, st: &str) {
+        for g in st.graphemes(true) {
+            let width = display_width(g).min(2) as u16;
+            if width == 0 {
+                // Combine with the previous cell
+                if let Some(i) = self
+                    .col
+                    .checked_sub(1)
+                    .and_then(|c| self.index(c, self.row))
+                {
+                    self.cells[i].text.push_str(g);
+                }
+                continue;
+            }
+            if self.col + width > self.dim.width {
+                self.col = self.col.saturating_add(width);
+                continue;
+            }
+            if let Some(i) = self.index(self.col, self.row) {
+                self.split_wide(i, width);
+                let style = self.style;
+                self.cells[i] = SnapCell {
+                    text: g.to_string(),
+                    style,
+                };
+                if width == 2 {
+                    self.cells[i + 1] = SnapCell {
+                        text: String::new(),
+                        style,
+                    };
+                }
+            }
+            self.col += width;
+        }
+    }
+
+    /// Blank any wide glyph partly covered by a new glyph
+    ///
+    /// * `i`: Index of the new glyph
+    /// * `width`: Width of the new glyph
+    ///
+    /// A terminal erases the other half of a wide glyph when one half is
+    /// overwritten.
+    fn split_wide(&mut self, i: usize, width: u16) {
+        if self.cells[i].text.is_empty() && self.col > 0 {
+            let style = self.cells[i - 1].style;
+            self.cells[i - 1] = SnapCell::blank(style);
+        }
+        if let Some(j) = self.index(self.col + width, self.row) {
+            if self.cells[j].text.is_empty() {
+                let style = self.cells[j].style;
+                self.cells[j] = SnapCell::blank(style);
+            }
+        }
+    }
+
+    /// Get the cells which changed since a previous snapshot
+    ///
+    /// * `prev`: Previous snapshot; if `None`, all cells are changed
+    ///
+    /// ## Return
+    ///
+    /// Column, row, width and cell of each change.  The second half of a
+    /// wide glyph is not included, since it is drawn with the first half.
+    pub(crate) fn changes<'a>(
+        &'a self,
+        prev: Option<&'a Snapshot>,
+    ) -> impl Iterator<Item = (u16, u16, u16, &'a SnapCell)> + 'a {
+        let width = usize::from(self.dim.width.max(1));
+        self.cells.iter().enumerate().filter_map(move |(i, cell)| {
+            if cell.text.is_empty()
+                || prev.is_some_and(|p| p.cells.get(i) == Some(cell))
+            {
+                return None;
+            }
+            // limited to u16 by dimensions
+            let col = (i % width) as u16;
+            let row = (i / width) as u16;
+            let wide = col + 1 < self.dim.width
+                && self.cells.get(i + 1).is_some_and(|c| c.text.is_empty());
+            Some((col, row, if wide { 2 } else { 1 }, cell))
+        })
+    }
+
+    /// Get the cells of each row
+    pub(crate) fn rows(&self) -> impl Iterator<Item = &[SnapCell]> {
+        self.cells.chunks(usize::from(self.dim.width.max(1)))
+    }
+
+    /// Get the runs of cells with the same style in a row
+    pub(crate) fn runs(row: &[SnapCell]) -> Vec<(TextStyle, String)> {
+        let mut runs: Vec<(TextStyle, String)> = vec![];
+        for cell in row {
+            match runs.last_mut() {
+                Some((style, text)) if *style == cell.style => {
+                    text.push_str(&cell.text)
+                }
+                _ => runs.push((cell.style, cell.text.clone())),
+            }
+        }
+        runs
+    }
+
+    /// Get the plain text of all rows
+    pub(crate) fn to_plain(&self) -> String {
+        let mut out = String::new();
+        for row in self.rows() {
+            row.iter().for_each(|c| out.push_str(&c.text));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Get the text of all rows, styled with ANSI escape sequences
+    ///
+    /// * `support`: Color support of the terminal
+    ///
+    /// Each row ends with the style reset.
+    pub(crate) fn to_ansi(&self, support: ColorSupport) -> String {
+        let mut out = String::new();
+        for row in self.rows() {
+            for (style, text) in Snapshot::runs(row) {
+                let bg = style.background().quantize(support);
+                let fg = style.foreground().quantize(support);
+                let attrs = style.appearance().changed(Appearance::default());
+                // Writing to a string never fails
+                let _ = SetAttribute(Attribute::Reset).write_ansi(&mut out);
+                let _ = SetBackgroundColor(bg.into()).write_ansi(&mut out);
+                let _ = SetForegroundColor(fg.into()).write_ansi(&mut out);
+                if !attrs.is_empty() {
+                    let _ = SetAttributes(attrs).write_ansi(&mut out);
+                }
+                out.push_str(&text);
+            }
+            let _ = SetAttribute(Attribute::Reset).write_ansi(&mut out);
+            let _ = ResetColor.write_ansi(&mut out);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::text::{Color, Intensity};
+
+    #[test]
+    fn print() {
+        let mut snap = Snapshot::new(Dim::new(6, 2));
+        snap.move_to(1, 0);
+        snap.print_str("ab");
+        snap.move_right(1);
+        snap.print_str("日本");
+        snap.move_to(0, 1);
+        snap.print_str("e\u{301}xyz");
+        assert_eq!(snap.to_plain(), " ab 日\ne\u{301}xyz  \n");
+    }
+
+    #[test]
+    fn split_wide() {
+        let mut snap = Snapshot::new(Dim::new(6, 1));
+        snap.print_str("日本語");
+        snap.move_to(1, 0);
+        snap.print_str("a");
+        snap.move_to(2, 0);
+        snap.print_str("b");
+        assert_eq!(snap.to_plain(), " ab 語\n");
+    }
+
+    #[test]
+    fn changes() {
+        let mut prev = Snapshot::new(Dim::new(6, 2));
+        prev.print_str("ab日");
+        let mut snap = prev.clone();
+        assert_eq!(snap.changes(Some(&prev)).count(), 0);
+        assert_eq!(snap.changes(None).count(), 11);
+        snap.move_to(1, 0);
+        snap.print_str("x");
+        snap.move_to(4, 1);
+        snap.print_str("語");
+        let changes: Vec<_> = snap
+            .changes(Some(&prev))
+            .map(|(col, row, width, cell)| (col, row, width, cell.text()))
+            .collect();
+        assert_eq!(changes, [(1, 0, 1, "x"), (4, 1, 2, "語")]);
+    }
+
+    #[test]
+    fn clear_runs() {
+        let red =
+            TextStyle::default().with_foreground(Color::Red(Intensity::Normal));
+        let mut snap = Snapshot::new(Dim::new(4, 1));
+        snap.set_style(red);
+        snap.clear();
+        snap.set_style(TextStyle::default());
+        snap.move_to(1, 0);
+        snap.print_str("ab");
+        let row = snap.rows().next().unwrap();
+        let runs = Snapshot::runs(row);
+        assert_eq!(
+            runs,
+            [
+                (red, " ".to_string()),
+                (TextStyle::default(), "ab".to_string()),
+                (red, " ".to_string()),
+            ]
+        );
+    }
+    #[test]
+    fn ansi() {
+        let pink =
+            TextStyle::default().with_foreground(Color::Rgb(255, 0, 135));
+        let mut snap = Snapshot::new(Dim::new(3, 2));
+        snap.set_style(pink);
+        snap.print_str("ab");
+        let ansi = snap.to_ansi(ColorSupport::Ansi256);
+        let rows: Vec<&str> = ansi.lines().collect();
+        assert_eq!(rows.len(), 2);
+        // Colors are degraded to those supported
+        assert!(rows[0].contains("\x1b[38;5;198mab"), "{:?}", rows[0]);
+        assert!(rows[0].ends_with("\x1b[0m\x1b[0m"));
+        assert!(!rows[1].contains("38;5;198"));
+    }
+}
@@ -0,0 +1,336 @@
+// bidi.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::text::{display_width, truncate_end};
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Text direction
+///
+/// Lines are reordered for display using a baseline subset of the Unicode
+/// bidirectional algorithm: implicit levels only, with no explicit
+/// embeddings or bracket pairs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Direction {
+    /// Detect from the first strong character of each line
+    #[default]
+    Auto,
+    /// Left-to-right
+    Ltr,
+    /// Right-to-left
+    Rtl,
+}
+
+/// Bidirectional character class
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Class {
+    /// Strong left-to-right
+    L,
+    /// Strong right-to-left
+    R,
+    /// European number
+    En,
+    /// Arabic number
+    An,
+    /// Neutral or whitespace
+    Ni,
+}
+
+impl Class {
+    /// Get the class of a grapheme cluster (from its first character)
+    fn of(g: &str) -> Self {
+        let ch = match g.chars().next() {
+            Some(ch) => ch,
+            None => return Class::Ni,
+        };
+        match ch {
+            '\u{0660}'..='\u{0669}' | '\u{066B}' | '\u{066C}' => Class::An,
+            '\u{0590}'..='\u{08FF}'
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}'
+            | '\u{10800}'..='\u{10FFF}'
+            | '\u{1E800}'..='\u{1EFFF}' => Class::R,
+            _ if ch.is_numeric() => Class::En,
+            _ if ch.is_alphabetic() => Class::L,
+            _ => Class::Ni,
+        }
+    }
+
+    /// Get the strong class of a direction
+    fn strong(dir: Direction) -> Self {
+        match dir {
+            Direction::Rtl => Class::R,
+            _ => Class::L,
+        }
+    }
+}
+
+impl Direction {
+    /// Resolve the base direction of a line
+    ///
+    /// `Auto` resolves to the direction of the first strong character, or
+    /// `Ltr` if there are none.
+    pub fn resolve(self, line: &str) -> Self {
+        match self {
+            Direction::Auto => line
+                .graphemes(true)
+                .find_map(|g| match Class::of(g) {
+                    Class::L => Some(Direction::Ltr),
+                    Class::R => Some(Direction::Rtl),
+                    _ => None,
+                })
+                .unwrap_or(Direction::Ltr),
+            _ => self,
+        }
+    }
+}
+
+/// Resolve embedding levels of each grapheme in a line
+///
+/// * `classes`: Class of each grapheme
+/// * `base`: Base direction (`Ltr` or `Rtl`)
+/// * `graphemes`: Grapheme clusters of the line
+fn resolve_levels(
+    mut classes: Vec<Class>,
+    base: Direction,
+    graphemes: &[&str],
+) -> Vec<u8> {
+    let sos = Class::strong(base);
+    // Numbers following a strong L are treated as L; otherwise they stay
+    // numbers, which act as R for neutrals
+    let mut strong = sos;
+    for cls in classes.iter_mut() {
+        match *cls {
+            Class::L | Class::R => strong = *cls,
+            Class::En if strong == Class::L => *cls = Class::L,
+            _ => (),
+        }
+    }
+    // Resolve neutrals from their surrounding strong characters
+    let mut i = 0;
+    while i < classes.len() {
+        if classes[i] != Class::Ni {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < classes.len() && classes[i] == Class::Ni {
+            i += 1;
+        }
+        let side = |cls: Class| match cls {
+            Class::L => Class::L,
+            _ => Class::R,
+        };
+        let before = start.checked_sub(1).map_or(sos, |j| side(classes[j]));
+        let after = classes.get(i).map_or(sos, |c| side(*c));
+        let cls = if before == after { before } else { sos };
+        classes[start..i].iter_mut().for_each(|c| *c = cls);
+    }
+    let base_level = match base {
+        Direction::Rtl => 1,
+        _ => 0,
+    };
+    let mut levels: Vec<u8> = classes
+        .iter()
+        .map(|cls| match (base_level, cls) {
+            (0, Class::L) | (1, Class::R) => base_level,
+            (0, Class::R) | (1, _) => base_level + 1,
+            _ => base_level + 2,
+        })
+        .collect();
+    // Trailing whitespace is reset to the base level
+    for (lvl, g) in levels.iter_mut().zip(graphemes).rev() {
+        if !g.chars().all(char::is_whitespace) {
+            break;
+        }
+        *lvl = base_level;
+    }
+    levels
+}
+
+/// Get the mirrored form of a grapheme displayed right-to-left
+fn mirror(g: &str) -> &str {
+    match g {
+        "(" => ")",
+        ")" => "(",
+        "[" => "]",
+        "]" => "[",
+        "{" => "}",
+        "}" => "{",
+        "<" => ">",
+        ">" => "<",
+        _ => g,
+    }
+}
+
+/// Reorder a line of text from logical to visual order
+///
+/// * `line`: Line of text (no line breaks)
+/// * `dir`: Base direction
+pub(crate) fn visual_order(line: &str, dir: Direction) -> Cow<'_, str> {
+    let base = dir.resolve(line);
+    let graphemes: Vec<&str> = line.graphemes(true).collect();
+    let classes: Vec<Class> = graphemes.iter().map(|g| Class::of(g)).collect();
+    let rtl = |c: &Class| matches!(c, Class::R | Class::An);
+    if base == Direction::Ltr && !classes.iter().any(rtl) {
+        return Cow::Borrowed(line);
+    }
+    let levels = resolve_levels(classes, base, &graphemes);
+    let mut order: Vec<usize> = (0..graphemes.len()).collect();
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    let lowest_odd = levels.iter().copied().filter(|l| l % 2 == 1).min();
+    if let Some(lowest_odd) = lowest_odd {
+        for level in (lowest_odd..=highest).rev() {
+            let mut i = 0;
+            while i < order.len() {
+                if levels[order[i]] < level {
+                    i += 1;
+                    continue;
+                }
+                let start = i;
+                while i < order.len() && levels[order[i]] >= level {
+                    i += 1;
+                }
+                order[start..i].reverse();
+            }
+        }
+    }
+    let mut out = String::with_capacity(line.len());
+    for i in order {
+        let g = graphemes[i];
+        out.push_str(if levels[i] % 2 == 1 { mirror(g) } else { g });
+    }
+    Cow::Owned(out)
+}
+
+/// Lay out a line of text within a display width
+///
+/// * `line`: Line of text (no line breaks)
+/// * `width`: Display width
+/// * `dir`: Base direction
+///
+/// Right-to-left lines are aligned right, and truncated on the left.
+///
+/// ## Return
+///
+/// Starting column and text in visual order.
+pub(crate) fn layout_line(
+    line: &str,
+    width: usize,
+    dir: Direction,
+) -> (usize, Cow<'_, str>) {
+    let base = dir.resolve(line);
+    // Truncate in logical order, so the ellipsis is at the logical end
+    let text = if display_width(line) > width {
+        Cow::Owned(visual_order(&truncate_end(line, width), base).into_owned())
+    } else {
+        visual_order(line, base)
+    };
+    let col = match base {
+        Direction::Rtl => width.saturating_sub(display_width(&text)),
+        _ => 0,
+    };
+    (col, text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        assert_eq!(Direction::Auto.resolve("hello"), Direction::Ltr);
+        assert_eq!(Direction::Auto.resolve("שלום"), Direction::Rtl);
+        assert_eq!(Direction::Auto.resolve("123 שלום abc"), Direction::Rtl);
+        assert_eq!(Direction::Auto.resolve("abc שלום"), Direction::Ltr);
+        assert_eq!(Direction::Auto.resolve("123 ..."), Direction::Ltr);
+        assert_eq!(Direction::Rtl.resolve("hello"), Direction::Rtl);
+    }
+
+    #[test]
+    fn order_ltr() {
+        assert!(matches!(
+            visual_order("hello", Direction::Auto),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            visual_order("hello, world", Direction::Auto),
+            "hello, world"
+        );
+        // Right-to-left base reverses run order, but not within a run
+        assert_eq!(visual_order("hello world", Direction::Rtl), "hello world");
+    }
+
+    #[test]
+    fn order_rtl() {
+        assert_eq!(visual_order("שלום", Direction::Auto), "םולש");
+        assert_eq!(visual_order("שלום עולם", Direction::Auto), "םלוע םולש");
+        assert_eq!(visual_order("שלום", Direction::Ltr), "םולש");
+        // Numbers keep their order
+        assert_eq!(visual_order("עמוד 12", Direction::Auto), "12 דומע");
+        // Brackets are mirrored
+        assert_eq!(visual_order("(שלום)", Direction::Auto), "(םולש)");
+    }
+
+    #[test]
+    fn order_mixed() {
+        assert_eq!(
+            visual_order("abc שלום עולם def", Direction::Auto),
+            "abc םלוע םולש def"
+        );
+        assert_eq!(
+            visual_order("שלום abc def עולם", Direction::Auto),
+            "םלוע abc def םולש"
+        );
+        assert_eq!(
+            visual_order("שלום abc 12 עולם", Direction::Auto),
+            "םלוע abc 12 םולש"
+        );
+        // Trailing whitespace stays at the logical end
+        assert_eq!(visual_order("שלום abc ", Direction::Auto), " abc םולש");
+    }
+
+    #[test]
+    fn layout_english() {
+        let line = "hello world";
+        assert_eq!(layout_line(line, 12, Direction::Auto), (0, line.into()));
+        assert_eq!(layout_line(line, 11, Direction::Auto), (0, line.into()));
+        assert_eq!(
+            layout_line(line, 8, Direction::Auto),
+            (0, "hello w…".into())
+        );
+    }
+
+    #[test]
+    fn layout_hebrew() {
+        let line = "שלום עולם";
+        assert_eq!(
+            layout_line(line, 12, Direction::Auto),
+            (3, "םלוע םולש".into())
+        );
+        assert_eq!(
+            layout_line(line, 9, Direction::Auto),
+            (0, "םלוע םולש".into())
+        );
+        // Ellipsis is on the left
+        assert_eq!(
+            layout_line(line, 7, Direction::Auto),
+            (0, "…ע םולש".into())
+        );
+    }
+
+    #[test]
+    fn layout_mixed() {
+        let line = "שלום abc";
+        assert_eq!(
+            layout_line(line, 10, Direction::Auto),
+            (2, "abc םולש".into())
+        );
+        assert_eq!(
+            layout_line(line, 10, Direction::Ltr),
+            (0, "םולש abc".into())
+        );
+        assert_eq!(layout_line(line, 6, Direction::Auto), (0, "… םולש".into()));
+    }
+}
@@ -6,6 +6,7 @@ use crossterm::style::Color as Clr;
 
 /// Color intensity
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum Intensity {
     /// Normal (dark) color intensity
     Normal,
@@ -15,11 +16,12 @@ pub enum Intensity {
 
 /// Text Colors
 ///
-/// Colors can be specified using one of the standard 16 ANSI colors, or as
-/// `Rgb` 24-bit *true color*.  In most cases, it is best to use the ANSI
-/// colors, since it allows the user to define their own preferences for all
-/// their terminal apps.
+/// Colors can be specified using one of the standard 16 ANSI colors, an
+/// `AnsiValue` from the 256-color palette, or as `Rgb` 24-bit *true color*.
+/// In most cases, it is best to use the ANSI colors, since it allows the
+/// user to define their own preferences for all their terminal apps.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum Color {
     /// ANSI color 0 *black*, and 8 *dark gray* (bright)
     Black(Intensity),
@@ -39,6 +41,236 @@ pub enum Color {
     White(Intensity),
     /// Red, green, blue *true color*
     Rgb(u8, u8, u8),
+    /// 256-color palette value
+    ///
+    /// Values 0-15 are the ANSI colors, 16-231 are a 6x6x6 color
+    /// [cube](Color::cube), and 232-255 are a [grayscale](Color::grayscale)
+    /// ramp.
+    AnsiValue(u8),
+}
+
+/// Channel levels of the 256-color palette cube
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Typical red, green and blue of the 16 ANSI colors (as in xterm)
+const ANSI_RGB: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xCD, 0x00, 0x00),
+    (0x00, 0xCD, 0x00),
+    (0xCD, 0xCD, 0x00),
+    (0x00, 0x00, 0xEE),
+    (0xCD, 0x00, 0xCD),
+    (0x00, 0xCD, 0xCD),
+    (0xE5, 0xE5, 0xE5),
+    (0x7F, 0x7F, 0x7F),
+    (0xFF, 0x00, 0x00),
+    (0x00, 0xFF, 0x00),
+    (0xFF, 0xFF, 0x00),
+    (0x5C, 0x5C, 0xFF),
+    (0xFF, 0x00, 0xFF),
+    (0x00, 0xFF, 0xFF),
+    (0xFF, 0xFF, 0xFF),
+];
+
+/// Terminal color support
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ColorSupport {
+    /// 16 ANSI colors
+    Ansi16,
+    /// 256-color palette
+    Ansi256,
+    /// 24-bit *true color*
+    #[default]
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Detect color support from the environment
+    ///
+    /// `$COLORTERM` of `truecolor` or `24bit` means true color; otherwise,
+    /// a `$TERM` containing `256color` means the 256-color palette.  Any
+    /// other `$TERM` means 16 colors.  Without `$TERM`, true color is
+    /// assumed.
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").ok();
+        let term = std::env::var("TERM").ok();
+        Self::from_env(colorterm.as_deref(), term.as_deref())
+    }
+
+    /// Get color support from `$COLORTERM` and `$TERM` values
+    fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        match (colorterm, term) {
+            (Some("truecolor" | "24bit"), _) => ColorSupport::TrueColor,
+            (_, Some(term)) if term.contains("256color") => {
+                ColorSupport::Ansi256
+            }
+            (_, Some(_)) => ColorSupport::Ansi16,
+            (_, None) => ColorSupport::TrueColor,
+        }
+    }
+}
+
+/// Get the squared distance between two colors
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| u32::from(x.abs_diff(y)).pow(2);
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// Get the nearest 256-color palette value to a color
+///
+/// Only the cube and grayscale ramp (16-255) are considered, since
+/// terminals define the ANSI colors for themselves.
+pub(crate) fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    (16..=255)
+        .min_by_key(|v| {
+            distance(rgb, Color::palette_rgb(*v).unwrap_or_default())
+        })
+        .unwrap_or(16)
+}
+
+/// Get the nearest ANSI color (0-15) to a color
+pub(crate) fn nearest_16(rgb: (u8, u8, u8)) -> u8 {
+    (0..16)
+        .min_by_key(|v| distance(rgb, ANSI_RGB[usize::from(*v)]))
+        .unwrap_or(0)
+}
+
+/// Linear interpolation between two channel values
+fn lerp(from: u8, to: u8, t: f32) -> u8 {
+    let from = f32::from(from);
+    let to = f32::from(to);
+    (from + (to - from) * t).round() as u8
+}
+
+impl Color {
+    /// Get a color from the 6x6x6 cube of the 256-color palette
+    ///
+    /// * `r`: Red level (0-5)
+    /// * `g`: Green level (0-5)
+    /// * `b`: Blue level (0-5)
+    ///
+    /// ## Return
+    ///
+    /// Palette color, or `None` if any level is out of range.
+    pub fn cube(r: u8, g: u8, b: u8) -> Option<Self> {
+        (r < 6 && g < 6 && b < 6)
+            .then(|| Color::AnsiValue(16 + 36 * r + 6 * g + b))
+    }
+
+    /// Get a gray from the grayscale ramp of the 256-color palette
+    ///
+    /// * `step`: Step from dark to light (0-23)
+    ///
+    /// ## Return
+    ///
+    /// Palette color, or `None` if the step is out of range.
+    pub fn grayscale(step: u8) -> Option<Self> {
+        (step < 24).then(|| Color::AnsiValue(232 + step))
+    }
+
+    /// Blend toward another color
+    ///
+    /// * `to`: Target color
+    /// * `t`: Fraction of the way to the target (0 to 1)
+    ///
+    /// Only `Rgb` colors are blended; otherwise, the target color is
+    /// returned.
+    pub fn blend(self, to: Self, t: f32) -> Self {
+        match (self, to) {
+            (Color::Rgb(r0, g0, b0), Color::Rgb(r1, g1, b1)) => {
+                let t = t.clamp(0.0, 1.0);
+                Color::Rgb(lerp(r0, r1, t), lerp(g0, g1, t), lerp(b0, b1, t))
+            }
+            _ => to,
+        }
+    }
+
+    /// Check if the color is `Rgb`
+    pub fn is_rgb(self) -> bool {
+        matches!(self, Color::Rgb(_, _, _))
+    }
+
+    /// Get the approximate red, green and blue of a palette value
+    ///
+    /// Values 0-15 are ANSI colors, which terminals define for themselves,
+    /// so `None` is returned for them.
+    pub(crate) fn palette_rgb(value: u8) -> Option<(u8, u8, u8)> {
+        match value {
+            0..=15 => None,
+            16..=231 => {
+                let v = value - 16;
+                let level = |l: u8| CUBE_LEVELS[usize::from(l)];
+                Some((level(v / 36), level(v / 6 % 6), level(v % 6)))
+            }
+            _ => {
+                let gray = 8 + 10 * (value - 232);
+                Some((gray, gray, gray))
+            }
+        }
+    }
+
+    /// Get the approximate red, green and blue of the color
+    ///
+    /// ANSI colors are defined by the terminal, so typical values are used.
+    pub(crate) fn approx_rgb(self) -> (u8, u8, u8) {
+        use Color::*;
+        let ansi = |value: usize, intensity| match intensity {
+            Intensity::Normal => ANSI_RGB[value],
+            Intensity::Bright => ANSI_RGB[value + 8],
+        };
+        match self {
+            Black(i) => ansi(0, i),
+            Red(i) => ansi(1, i),
+            Green(i) => ansi(2, i),
+            Yellow(i) => ansi(3, i),
+            Blue(i) => ansi(4, i),
+            Magenta(i) => ansi(5, i),
+            Cyan(i) => ansi(6, i),
+            White(i) => ansi(7, i),
+            Rgb(r, g, b) => (r, g, b),
+            AnsiValue(v) => match Color::palette_rgb(v) {
+                Some(rgb) => rgb,
+                None => ANSI_RGB[usize::from(v)],
+            },
+        }
+    }
+
+    /// Degrade the color to those supported by a terminal
+    ///
+    /// `Rgb` colors are mapped to the nearest palette value, and palette
+    /// values past 15 are mapped to the nearest ANSI color for `Ansi16`.
+    pub fn quantize(self, support: ColorSupport) -> Self {
+        match (self, support) {
+            (_, ColorSupport::TrueColor) => self,
+            (Color::Rgb(r, g, b), ColorSupport::Ansi256) => {
+                Color::AnsiValue(nearest_256((r, g, b)))
+            }
+            (Color::Rgb(..), ColorSupport::Ansi16)
+            | (Color::AnsiValue(16..), ColorSupport::Ansi16) => {
+                Color::ansi(nearest_16(self.approx_rgb()))
+            }
+            _ => self,
+        }
+    }
+
+    /// Get the ANSI color of a palette value (0-15)
+    pub(crate) fn ansi(value: u8) -> Self {
+        let intensity = if value & 8 == 0 {
+            Intensity::Normal
+        } else {
+            Intensity::Bright
+        };
+        match value & 7 {
+            0 => Color::Black(intensity),
+            1 => Color::Red(intensity),
+            2 => Color::Green(intensity),
+            3 => Color::Yellow(intensity),
+            4 => Color::Blue(intensity),
+            5 => Color::Magenta(intensity),
+            6 => Color::Cyan(intensity),
+            _ => Color::White(intensity),
+        }
+    }
 }
 
 impl From<Color> for Clr {
@@ -62,6 +294,92 @@ impl From<Color> for Clr {
             White(Intensity::Normal) => Clr::Grey,
             White(Intensity::Bright) => Clr::White,
             Rgb(r, g, b) => Clr::Rgb { r, g, b },
+            AnsiValue(value) => Clr::AnsiValue(value),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn palette() {
+        assert_eq!(Color::cube(0, 0, 0), Some(Color::AnsiValue(16)));
+        assert_eq!(Color::cube(5, 0, 2), Some(Color::AnsiValue(198)));
+        assert_eq!(Color::cube(5, 5, 5), Some(Color::AnsiValue(231)));
+        assert_eq!(Color::cube(6, 0, 0), None);
+        assert_eq!(Color::grayscale(0), Some(Color::AnsiValue(232)));
+        assert_eq!(Color::grayscale(23), Some(Color::AnsiValue(255)));
+        assert_eq!(Color::grayscale(24), None);
+        assert_eq!(Color::palette_rgb(198), Some((255, 0, 135)));
+        assert_eq!(Color::palette_rgb(244), Some((128, 128, 128)));
+        assert_eq!(Color::palette_rgb(9), None);
+        assert_eq!(Color::ansi(9), Color::Red(Intensity::Bright));
+        assert_eq!(Color::ansi(7), Color::White(Intensity::Normal));
+    }
+
+    #[test]
+    fn nearest() {
+        assert_eq!(nearest_256((0, 0, 0)), 16);
+        assert_eq!(nearest_256((255, 255, 255)), 231);
+        assert_eq!(nearest_256((255, 0, 135)), 198);
+        assert_eq!(nearest_256((250, 10, 130)), 198);
+        assert_eq!(nearest_256((128, 128, 128)), 244);
+        assert_eq!(nearest_256((115, 118, 120)), 243);
+        assert_eq!(nearest_16((0, 0, 0)), 0);
+        assert_eq!(nearest_16((250, 10, 10)), 9);
+        assert_eq!(nearest_16((200, 0, 0)), 1);
+        assert_eq!(nearest_16((128, 128, 128)), 8);
+        assert_eq!(nearest_16((240, 240, 240)), 7);
+        assert_eq!(nearest_16((250, 250, 250)), 15);
+    }
+
+    #[test]
+    fn quantize() {
+        let rgb = Color::Rgb(250, 10, 130);
+        assert_eq!(rgb.quantize(ColorSupport::TrueColor), rgb);
+        assert_eq!(rgb.quantize(ColorSupport::Ansi256), Color::AnsiValue(198));
+        assert_eq!(
+            rgb.quantize(ColorSupport::Ansi16),
+            Color::Magenta(Intensity::Normal)
+        );
+        let gray = Color::grayscale(20).unwrap();
+        assert_eq!(gray.quantize(ColorSupport::Ansi256), gray);
+        assert_eq!(
+            gray.quantize(ColorSupport::Ansi16),
+            Color::White(Intensity::Normal)
+        );
+        let blue = Color::Blue(Intensity::Normal);
+        assert_eq!(blue.quantize(ColorSupport::Ansi16), blue);
+        let low = Color::AnsiValue(3);
+        assert_eq!(low.quantize(ColorSupport::Ansi16), low);
+    }
+
+    #[test]
+    fn support() {
+        use ColorSupport::*;
+        let env = ColorSupport::from_env;
+        assert_eq!(env(Some("truecolor"), Some("xterm")), TrueColor);
+        assert_eq!(env(Some("24bit"), None), TrueColor);
+        assert_eq!(env(None, Some("xterm-256color")), Ansi256);
+        assert_eq!(env(Some("yes"), Some("screen-256color")), Ansi256);
+        assert_eq!(env(None, Some("linux")), Ansi16);
+        assert_eq!(env(None, None), TrueColor);
+    }
+
+    #[test]
+    fn crossterm() {
+        assert_eq!(Clr::from(Color::AnsiValue(0)), Clr::AnsiValue(0));
+        assert_eq!(Clr::from(Color::AnsiValue(208)), Clr::AnsiValue(208));
+        assert_eq!(
+            Clr::from(Color::grayscale(10).unwrap()),
+            Clr::AnsiValue(242)
+        );
+        assert_eq!(Clr::from(Color::Red(Intensity::Bright)), Clr::Red);
+        assert_eq!(
+            Clr::from(Color::Rgb(1, 2, 3)),
+            Clr::Rgb { r: 1, g: 2, b: 3 }
+        );
+    }
+}
@@ -2,8 +2,8 @@
 //
 // Copyright (c) 2020-2022  Douglas P Lau
 //
+use super::width::{char_width, display_width};
 use crate::{Error, Result, Screen};
-use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// Inner enum for glyphs
 #[derive(Clone, Debug, PartialEq)]
@@ -45,7 +45,7 @@ pub trait IntoGlyph {
 impl IntoGlyph for char {
     /// Create a Glyph from a `char`
     fn into_glyph(self) -> Result<Glyph> {
-        let width = self.width().unwrap_or(0);
+        let width = char_width(self);
         if width == 1 || width == 2 {
             let inner = GlyphInner::Char(self);
             return Ok(Glyph { inner, width });
@@ -57,7 +57,7 @@ impl IntoGlyph for char {
 impl IntoGlyph for &str {
     /// Create a Glyphn from a `&str`
     fn into_glyph(self) -> Result<Glyph> {
-        let width = self.width();
+        let width = display_width(self);
         if width == 1 || width == 2 {
             let inner = GlyphInner::Str(self.to_string());
             return Ok(Glyph { inner, width });
@@ -83,3 +83,19 @@ impl Glyph {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn variation_selectors() -> Result<()> {
+        for emoji in ["⚠️", "❤️", "☀️"] {
+            assert_eq!(emoji.into_glyph()?.width(), 2);
+        }
+        assert_eq!("⚠\u{FE0E}".into_glyph()?.width(), 1);
+        assert_eq!('⚠'.into_glyph()?.width(), 1);
+        assert!('\u{FE0F}'.into_glyph().is_err());
+        Ok(())
+    }
+}
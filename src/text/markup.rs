@@ -0,0 +1,405 @@
+// markup.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::text::width::grapheme_width;
+use crate::text::{Appearance, Weight};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Characters which can start a marker (or be escaped)
+const MARKER_CHARS: [char; 6] = ['*', '_', '~', '`', '<', '\\'];
+
+/// Inline style marks of one byte of text
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Marks(u8);
+
+impl Marks {
+    const ITALIC: Marks = Marks(1 << 0);
+    const BOLD: Marks = Marks(1 << 1);
+    const STRIKETHROUGH: Marks = Marks(1 << 2);
+    const UNDERLINE: Marks = Marks(1 << 3);
+    const REVERSE: Marks = Marks(1 << 4);
+
+    /// Check if a mark is set
+    fn has(self, mark: Marks) -> bool {
+        self.0 & mark.0 != 0
+    }
+
+    /// Combine with other marks
+    fn union(self, other: Marks) -> Marks {
+        Marks(self.0 | other.0)
+    }
+
+    /// Apply marks to an appearance
+    pub(crate) fn apply(self, app: Appearance) -> Appearance {
+        let mut app = app;
+        if self.has(Marks::ITALIC) {
+            app = app.with_italic(true);
+        }
+        if self.has(Marks::BOLD) {
+            app = app.with_weight(Weight::Bold);
+        }
+        if self.has(Marks::STRIKETHROUGH) {
+            app = app.with_strikethrough(true);
+        }
+        if self.has(Marks::UNDERLINE) {
+            app = app.with_underline(true);
+        }
+        if self.has(Marks::REVERSE) {
+            app = app.with_reverse(true);
+        }
+        app
+    }
+}
+
+/// Kind of inline marker
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    /// `*`
+    StarItalic,
+    /// `_`
+    UnderItalic,
+    /// `**`
+    StarBold,
+    /// `__`
+    UnderBold,
+    /// `~~`
+    Strike,
+    /// `` ` ``
+    Code,
+    /// `<u>`
+    UnderlineOpen,
+    /// `</u>`
+    UnderlineClose,
+}
+
+impl Kind {
+    /// Get the marks of a marker kind
+    fn marks(self) -> Marks {
+        match self {
+            Kind::StarItalic | Kind::UnderItalic => Marks::ITALIC,
+            Kind::StarBold | Kind::UnderBold => Marks::BOLD,
+            Kind::Strike => Marks::STRIKETHROUGH,
+            Kind::Code => Marks::REVERSE,
+            Kind::UnderlineOpen | Kind::UnderlineClose => Marks::UNDERLINE,
+        }
+    }
+}
+
+/// Token of marked up text
+#[derive(Clone, Copy, Debug)]
+enum Token<'a> {
+    /// Literal text
+    Text(&'a str),
+    /// Inline marker
+    Marker {
+        /// Marker kind
+        kind: Kind,
+        /// Source text
+        src: &'a str,
+        /// Marker can open a span
+        open: bool,
+        /// Marker can close a span
+        close: bool,
+    },
+}
+
+/// Split text into tokens
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut start = 0;
+    let mut i = 0;
+    while let Some(ch) = text[i..].chars().next() {
+        let rest = &text[i..];
+        let (kind, len) = if ch == '\\' {
+            match rest[1..].chars().next() {
+                Some(esc) if MARKER_CHARS.contains(&esc) => {
+                    tokens.push(Token::Text(&text[start..i]));
+                    let end = i + 1 + esc.len_utf8();
+                    tokens.push(Token::Text(&text[i + 1..end]));
+                    i = end;
+                    start = i;
+                    continue;
+                }
+                _ => (None, 1),
+            }
+        } else if rest.starts_with("**") {
+            (Some(Kind::StarBold), 2)
+        } else if rest.starts_with("__") {
+            (Some(Kind::UnderBold), 2)
+        } else if rest.starts_with("~~") {
+            (Some(Kind::Strike), 2)
+        } else if rest.starts_with("<u>") {
+            (Some(Kind::UnderlineOpen), 3)
+        } else if rest.starts_with("</u>") {
+            (Some(Kind::UnderlineClose), 4)
+        } else {
+            match ch {
+                '*' => (Some(Kind::StarItalic), 1),
+                '_' => (Some(Kind::UnderItalic), 1),
+                '`' => (Some(Kind::Code), 1),
+                _ => (None, ch.len_utf8()),
+            }
+        };
+        if let Some(kind) = kind {
+            let before = text[..i].chars().next_back();
+            let after = text[i + len..].chars().next();
+            let under = matches!(kind, Kind::UnderItalic | Kind::UnderBold);
+            // Underscores within a word are literal
+            let word =
+                |c: Option<char>| under && c.is_some_and(char::is_alphanumeric);
+            let open =
+                after.is_some_and(|c| !c.is_whitespace()) && !word(before);
+            let close =
+                before.is_some_and(|c| !c.is_whitespace()) && !word(after);
+            tokens.push(Token::Text(&text[start..i]));
+            tokens.push(Token::Marker {
+                kind,
+                src: &text[i..i + len],
+                open,
+                close,
+            });
+            start = i + len;
+        }
+        i += len;
+    }
+    tokens.push(Token::Text(&text[start..]));
+    tokens.retain(|t| !matches!(t, Token::Text("")));
+    tokens
+}
+
+/// Text with inline style marks
+///
+/// Markers are removed from the text, leaving the visible characters.
+/// Markers of different kinds can be combined, even overlapping.  A marker
+/// without a matching closing marker is literal text, as is any marker
+/// escaped with a backslash.  Within a `` `code` `` span, all markers are
+/// literal.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Marked {
+    /// Visible text
+    text: String,
+    /// Marks of each byte of the text (empty if none are marked)
+    marks: Vec<Marks>,
+}
+
+impl Marked {
+    /// Parse marked up text
+    pub(crate) fn parse(text: &str) -> Self {
+        if !text.contains(&MARKER_CHARS[..]) {
+            return Marked {
+                text: text.to_string(),
+                marks: vec![],
+            };
+        }
+        let tokens = tokenize(text);
+        let mut marked = Marked::default();
+        // Kinds of open spans
+        let mut spans: Vec<Kind> = vec![];
+        let current = |spans: &[Kind]| {
+            spans
+                .iter()
+                .fold(Marks::default(), |m, k| m.union(k.marks()))
+        };
+        let mut i = 0;
+        while i < tokens.len() {
+            let (kind, src, open, close) = match tokens[i] {
+                Token::Text(txt) => {
+                    marked.push(txt, current(&spans));
+                    i += 1;
+                    continue;
+                }
+                Token::Marker {
+                    kind,
+                    src,
+                    open,
+                    close,
+                } => (kind, src, open, close),
+            };
+            let closing = match kind {
+                Kind::UnderlineOpen => Kind::UnderlineClose,
+                k => k,
+            };
+            let closer = |j: usize| match tokens[j] {
+                Token::Marker { kind: k, close, .. } => {
+                    k == closing
+                        && (close
+                            || matches!(k, Kind::Code | Kind::UnderlineClose))
+                }
+                _ => false,
+            };
+            let find_closer = || (i + 1..tokens.len()).find(|j| closer(*j));
+            match kind {
+                Kind::Code => match find_closer() {
+                    Some(j) => {
+                        spans.push(kind);
+                        for tok in &tokens[i + 1..j] {
+                            marked.push(tok.source(), current(&spans));
+                        }
+                        spans.pop();
+                        i = j;
+                    }
+                    None => marked.push(src, current(&spans)),
+                },
+                Kind::UnderlineClose => {
+                    match spans.iter().position(|k| *k == Kind::UnderlineOpen) {
+                        Some(p) => {
+                            spans.remove(p);
+                        }
+                        None => marked.push(src, current(&spans)),
+                    }
+                }
+                _ => match spans.iter().position(|k| *k == kind) {
+                    Some(p) if close => {
+                        spans.remove(p);
+                    }
+                    None if open && find_closer().is_some() => {
+                        spans.push(kind);
+                    }
+                    _ => marked.push(src, current(&spans)),
+                },
+            }
+            i += 1;
+        }
+        if marked.marks.iter().all(|m| *m == Marks::default()) {
+            marked.marks.clear();
+        }
+        marked
+    }
+
+    /// Append some text with marks
+    fn push(&mut self, text: &str, marks: Marks) {
+        self.text.push_str(text);
+        self.marks.extend(std::iter::repeat_n(marks, text.len()));
+    }
+
+    /// Get the visible text
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Check if any text is marked
+    pub(crate) fn is_marked(&self) -> bool {
+        !self.marks.is_empty()
+    }
+
+    /// Get the marks of the byte at an index
+    pub(crate) fn marks(&self, i: usize) -> Marks {
+        self.marks.get(i).copied().unwrap_or_default()
+    }
+
+    /// Expand tabs to spaces, keeping the marks
+    ///
+    /// * `tab_width`: Columns between tab stops
+    pub(crate) fn expand_tabs(self, tab_width: u16) -> Self {
+        if !self.text.contains('\t') {
+            return self;
+        }
+        let mut out = Marked::default();
+        let mut col = 0;
+        for (i, g) in self.text.grapheme_indices(true) {
+            let w = grapheme_width(g, col, tab_width);
+            let marks = self.marks(i);
+            if g == "\t" {
+                out.push(&" ".repeat(w), marks);
+            } else {
+                out.push(g, marks);
+            }
+            col = if g == "\n" || g == "\r\n" { 0 } else { col + w };
+        }
+        if !self.is_marked() {
+            out.marks.clear();
+        }
+        out
+    }
+}
+
+impl Token<'_> {
+    /// Get the source text of a token
+    fn source(&self) -> &str {
+        match self {
+            Token::Text(txt) => txt,
+            Token::Marker { src, .. } => src,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Get the visible text and marked ranges
+    fn spans(text: &str) -> (String, Vec<(String, u8)>) {
+        let marked = Marked::parse(text);
+        let mut spans: Vec<(String, u8)> = vec![];
+        for (i, ch) in marked.text().char_indices() {
+            let m = marked.marks(i).0;
+            match spans.last_mut() {
+                Some((s, last)) if *last == m => s.push(ch),
+                _ => spans.push((ch.to_string(), m)),
+            }
+        }
+        spans.retain(|(_, m)| *m != 0);
+        (marked.text().to_string(), spans)
+    }
+
+    #[test]
+    fn styles() {
+        let (text, sp) = spans("a *b* **c** ~~d~~ <u>e</u> `f` _g_ __h__");
+        assert_eq!(text, "a b c d e f g h");
+        let sp: Vec<_> = sp.iter().map(|(s, m)| (s.as_str(), *m)).collect();
+        assert_eq!(
+            sp,
+            [
+                ("b", 1),
+                ("c", 2),
+                ("d", 4),
+                ("e", 8),
+                ("f", 16),
+                ("g", 1),
+                ("h", 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let (text, sp) = spans("**bold *both* bold**");
+        assert_eq!(text, "bold both bold");
+        let sp: Vec<_> = sp.iter().map(|(s, m)| (s.as_str(), *m)).collect();
+        assert_eq!(sp, [("bold ", 2), ("both", 3), (" bold", 2)]);
+        // Markers within code are literal
+        let (text, sp) = spans("`a *b*`");
+        assert_eq!(text, "a *b*");
+        assert_eq!(sp, [("a *b*".to_string(), 16)]);
+    }
+
+    #[test]
+    fn literal() {
+        for text in [
+            "2 * 3 = 6",
+            "*unterminated",
+            "snake_case_name",
+            "a ** b",
+            "<u>open",
+            "close</u>",
+            "`tick",
+            "C:\\dir",
+        ] {
+            let marked = Marked::parse(text);
+            assert_eq!(marked.text(), text);
+            assert!(!marked.is_marked(), "{}", text);
+        }
+        let (text, sp) = spans("\\*not\\* *yes*");
+        assert_eq!(text, "*not* yes");
+        assert_eq!(sp, [("yes".to_string(), 1)]);
+    }
+
+    #[test]
+    fn tabs() {
+        let marked = Marked::parse("a\t**b**").expand_tabs(4);
+        assert_eq!(marked.text(), "a   b");
+        assert_eq!(marked.marks(4), Marks::BOLD);
+        assert_eq!(marked.marks(3), Marks::default());
+    }
+}
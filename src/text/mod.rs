@@ -4,14 +4,30 @@
 //
 //! Text styles and themes
 
+mod bidi;
 mod color;
 mod glyph;
+#[cfg(feature = "markdown")]
+mod markup;
 mod outline;
 mod style;
+mod styled;
 mod theme;
+mod width;
+mod wrap;
 
-pub use color::{Color, Intensity};
+pub(crate) use bidi::layout_line;
+pub use bidi::Direction;
+pub use color::{Color, ColorSupport, Intensity};
 pub use glyph::{Glyph, IntoGlyph};
+#[cfg(feature = "markdown")]
+pub(crate) use markup::{Marked, Marks};
 pub use outline::{Corner, Outline, Stroke};
-pub use style::{Appearance, TextStyle, Weight};
-pub use theme::{StyleGroup, Theme, WidgetGroup};
+pub use style::{Appearance, StyleOverlay, TextStyle, Weight};
+pub use styled::StyledText;
+pub use theme::{CustomStyle, StyleGroup, Theme, WidgetGroup};
+pub use width::{
+    column_to_index, expand_tabs, index_to_column, AmbiguousWidth, EmojiWidth,
+};
+pub(crate) use width::{display_width, truncate_end, visible_span};
+pub use wrap::WrapMode;
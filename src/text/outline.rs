@@ -5,6 +5,7 @@
 
 /// Outline corner style
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum Corner {
     /// Square corners
     Square,
@@ -14,6 +15,7 @@ pub enum Corner {
 
 /// Outline stroke style
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum Stroke {
     /// Solid outline
     Solid,
@@ -30,6 +32,7 @@ pub enum Stroke {
 /// - **Geometric Shapes** (U+25A0 - U+25FF)
 /// - **Symbols For Legacy Computing** (U+1FB00 - U+1FBFF)
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum Outline {
     /// Empty outline (all spaces)
     ///
@@ -9,9 +9,11 @@ use crossterm::style::{Attribute, Attributes};
 ///
 /// NOTE: Some terminals may treat this as intensity, altering the color rather
 ///       than font weight.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum Weight {
     /// Normal weight (or intensity)
+    #[default]
     Normal,
     /// Bold weight (or increased intensity)
     Bold,
@@ -21,6 +23,11 @@ pub enum Weight {
 
 /// Text appearance
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(from = "AppearanceDef", into = "AppearanceDef")
+)]
 pub struct Appearance {
     /// Crossterm text attributes
     attributes: Attributes,
@@ -30,6 +37,11 @@ pub struct Appearance {
 
 /// Text style
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(default)
+)]
 pub struct TextStyle {
     /// Background color
     background: Color,
@@ -39,10 +51,50 @@ pub struct TextStyle {
     appearance: Appearance,
 }
 
-impl Default for Weight {
-    fn default() -> Self {
-        Weight::Normal
-    }
+/// Serialized text appearance
+#[cfg(feature = "serde")]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct AppearanceDef {
+    /// Font weight
+    weight: Weight,
+    /// Italic text
+    italic: bool,
+    /// Strikethrough text
+    strikethrough: bool,
+    /// Underlined text
+    underline: bool,
+    /// Reverse video
+    reverse: bool,
+}
+
+/// Style overlay
+///
+/// An overlay changes only some properties of a [TextStyle], leaving the rest
+/// untouched.  While an overlay is pushed onto [Cells], it is merged over
+/// every style set for printing.
+///
+/// Property     | Merge rule
+/// -------------|-----------------------------------------------------
+/// Background   | Replaces the background color (if specified)
+/// Underline    | Added to the appearance; never removed
+/// Reverse      | Added to the appearance; never removed
+/// Other        | Foreground, weight and other attributes are kept
+///
+/// When overlays are stacked, they are merged in the order pushed, so the
+/// most recent background color wins.
+///
+/// [Cells]: ../layout/struct.Cells.html#method.push_style_overlay
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StyleOverlay {
+    /// Background color tint
+    background: Option<Color>,
+    /// Add underline
+    underline: bool,
+    /// Force reverse
+    reverse: bool,
+    /// Force thin weight
+    dim: bool,
 }
 
 impl Weight {
@@ -103,6 +155,31 @@ impl Appearance {
         self
     }
 
+    /// Get font weight
+    pub(crate) fn weight(&self) -> Weight {
+        self.weight
+    }
+
+    /// Check for `italic` text appearance
+    pub(crate) fn italic(&self) -> bool {
+        self.attributes.has(Attribute::Italic)
+    }
+
+    /// Check for `strikethrough` text appearance
+    pub(crate) fn strikethrough(&self) -> bool {
+        self.attributes.has(Attribute::CrossedOut)
+    }
+
+    /// Check for `underline` text appearance
+    pub(crate) fn underline(&self) -> bool {
+        self.attributes.has(Attribute::Underlined)
+    }
+
+    /// Check for `reverse` text appearance
+    pub(crate) fn reverse(&self) -> bool {
+        self.attributes.has(Attribute::Reverse)
+    }
+
     /// Get changed attributes
     pub(crate) fn changed(&self, before: Self) -> Attributes {
         let mut attr = self.attributes;
@@ -128,6 +205,76 @@ impl Appearance {
     }
 }
 
+#[cfg(feature = "serde")]
+impl From<AppearanceDef> for Appearance {
+    fn from(def: AppearanceDef) -> Self {
+        Appearance::default()
+            .with_weight(def.weight)
+            .with_italic(def.italic)
+            .with_strikethrough(def.strikethrough)
+            .with_underline(def.underline)
+            .with_reverse(def.reverse)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Appearance> for AppearanceDef {
+    fn from(app: Appearance) -> Self {
+        AppearanceDef {
+            weight: app.weight(),
+            italic: app.italic(),
+            strikethrough: app.strikethrough(),
+            underline: app.underline(),
+            reverse: app.reverse(),
+        }
+    }
+}
+
+impl StyleOverlay {
+    /// Set the background color tint
+    pub fn with_background(mut self, clr: Color) -> Self {
+        self.background = Some(clr);
+        self
+    }
+
+    /// Add `underline` text appearance
+    pub fn with_underline(mut self, enable: bool) -> Self {
+        self.underline = enable;
+        self
+    }
+
+    /// Force `reverse` text appearance
+    pub fn with_reverse(mut self, enable: bool) -> Self {
+        self.reverse = enable;
+        self
+    }
+
+    /// Force `thin` (dim) text weight
+    pub fn with_dim(mut self, enable: bool) -> Self {
+        self.dim = enable;
+        self
+    }
+
+    /// Merge the overlay over a text style
+    pub fn merge(self, st: TextStyle) -> TextStyle {
+        let mut st = st;
+        if let Some(clr) = self.background {
+            st = st.with_background(clr);
+        }
+        let mut app = st.appearance();
+        if self.underline {
+            app = app.with_underline(true);
+        }
+        if self.reverse {
+            app = app.with_reverse(true);
+        }
+        if self.dim {
+            app = app.with_weight(Weight::Thin);
+        }
+        st.with_appearance(app)
+    }
+}
+
 impl Default for TextStyle {
     fn default() -> Self {
         let background = Color::Black(Intensity::Normal);
@@ -174,4 +321,105 @@ impl TextStyle {
     pub fn appearance(&self) -> Appearance {
         self.appearance
     }
+
+    /// Apply a widget style override
+    ///
+    /// * `over`: Override style, if any
+    ///
+    /// Colors are replaced by the override, while appearances are combined.
+    /// This keeps state changes from the theme visible, such as reverse
+    /// video on a focused widget.
+    pub(crate) fn with_override(self, over: Option<Self>) -> Self {
+        let over = match over {
+            Some(over) => over,
+            None => return self,
+        };
+        let mut attributes = self.appearance.attributes;
+        attributes.extend(over.appearance.attributes);
+        let weight = match over.appearance.weight {
+            Weight::Normal => self.appearance.weight,
+            weight => weight,
+        };
+        let appearance = Appearance { attributes, weight };
+        over.with_appearance(appearance)
+    }
+
+    /// Blend colors toward another style
+    ///
+    /// * `to`: Target style
+    /// * `t`: Fraction of the way to the target (0 to 1)
+    ///
+    /// The appearance of the target style is used.
+    pub(crate) fn blend(self, to: Self, t: f32) -> Self {
+        to.with_background(self.background.blend(to.background, t))
+            .with_foreground(self.foreground.blend(to.foreground, t))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn overlay_empty() {
+        let st = TextStyle::default()
+            .with_foreground(Color::Red(Intensity::Bright))
+            .with_appearance(Appearance::default().with_italic(true));
+        assert_eq!(StyleOverlay::default().merge(st), st);
+    }
+
+    #[test]
+    fn overlay_background() {
+        let red = Color::Red(Intensity::Bright);
+        let blue = Color::Blue(Intensity::Normal);
+        let st = TextStyle::default().with_foreground(red);
+        let ov = StyleOverlay::default().with_background(blue);
+        let merged = ov.merge(st);
+        assert_eq!(merged.foreground(), red);
+        assert_eq!(merged.background(), blue);
+        assert_eq!(merged.appearance(), st.appearance());
+    }
+
+    #[test]
+    fn overlay_attributes_combine() {
+        let app = Appearance::default()
+            .with_weight(Weight::Bold)
+            .with_strikethrough(true);
+        let st = TextStyle::default().with_appearance(app);
+        let ov = StyleOverlay::default().with_underline(true);
+        let merged = ov.merge(st);
+        assert_eq!(merged.appearance(), app.with_underline(true));
+        let ov = StyleOverlay::default().with_reverse(true);
+        let app = app.with_underline(true).with_reverse(true);
+        assert_eq!(ov.merge(merged).appearance(), app);
+    }
+
+    #[test]
+    fn overlay_never_removes() {
+        let app = Appearance::default()
+            .with_underline(true)
+            .with_reverse(true);
+        let st = TextStyle::default().with_appearance(app);
+        let ov = StyleOverlay::default()
+            .with_underline(false)
+            .with_reverse(false);
+        assert_eq!(ov.merge(st), st);
+    }
+
+    #[test]
+    fn overlay_stacked() {
+        let green = Color::Green(Intensity::Normal);
+        let cyan = Color::Cyan(Intensity::Normal);
+        let st = TextStyle::default();
+        let a = StyleOverlay::default().with_background(green);
+        let b = StyleOverlay::default()
+            .with_background(cyan)
+            .with_underline(true);
+        let merged = b.merge(a.merge(st));
+        assert_eq!(merged.background(), cyan);
+        assert_eq!(
+            merged.appearance(),
+            Appearance::default().with_underline(true)
+        );
+    }
 }
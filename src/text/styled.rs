@@ -0,0 +1,107 @@
+// styled.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::text::width::grapheme_width;
+use crate::text::TextStyle;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Text composed of styled spans
+///
+/// Each span has its own style, or the style of the widget drawing it.
+/// Unlike Markdown text, nothing is parsed, so any characters can be used.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyledText {
+    /// Visible text
+    text: String,
+    /// Start byte and style of each span (`None` for the base style)
+    spans: Vec<(usize, Option<TextStyle>)>,
+}
+
+impl StyledText {
+    /// Create an empty styled text
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a span with a style
+    pub fn span(self, text: &str, style: TextStyle) -> Self {
+        self.push(text, Some(style))
+    }
+
+    /// Append a span with the base style of the widget
+    pub fn plain(self, text: &str) -> Self {
+        self.push(text, None)
+    }
+
+    /// Append a span
+    fn push(mut self, text: &str, style: Option<TextStyle>) -> Self {
+        if !text.is_empty() {
+            self.spans.push((self.text.len(), style));
+            self.text.push_str(text);
+        }
+        self
+    }
+
+    /// Get the visible text of all spans
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the style of the byte at an index
+    ///
+    /// ## Return
+    ///
+    /// Style of the span, or `None` for the base style.
+    pub(crate) fn style_at(&self, i: usize) -> Option<TextStyle> {
+        let s = self.spans.partition_point(|(start, _)| *start <= i);
+        s.checked_sub(1).and_then(|s| self.spans[s].1)
+    }
+
+    /// Expand tabs to spaces, keeping the span styles
+    ///
+    /// * `tab_width`: Columns between tab stops
+    pub(crate) fn expand_tabs(&self, tab_width: u16) -> Self {
+        if !self.text.contains('\t') {
+            return self.clone();
+        }
+        let mut out = StyledText::new();
+        let mut col = 0;
+        for (i, g) in self.text.grapheme_indices(true) {
+            let w = grapheme_width(g, col, tab_width);
+            let style = self.style_at(i);
+            let g = if g == "\t" {
+                " ".repeat(w)
+            } else {
+                g.to_string()
+            };
+            if out.spans.last().map(|(_, st)| *st) == Some(style) {
+                out.text.push_str(&g);
+            } else {
+                out = out.push(&g, style);
+            }
+            col = if g == "\n" || g == "\r\n" { 0 } else { col + w };
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::text::{Color, Intensity};
+
+    #[test]
+    fn spans() {
+        let red =
+            TextStyle::default().with_foreground(Color::Red(Intensity::Normal));
+        let text = StyledText::new().span("ab", red).plain("").plain("\tc");
+        assert_eq!(text.text(), "ab\tc");
+        assert_eq!(text.style_at(1), Some(red));
+        assert_eq!(text.style_at(2), None);
+        let text = text.expand_tabs(4);
+        assert_eq!(text.text(), "ab  c");
+        assert_eq!(text.style_at(1), Some(red));
+        assert_eq!(text.style_at(3), None);
+    }
+}
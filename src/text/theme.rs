@@ -2,8 +2,18 @@
 //
 // Copyright (c) 2020  Douglas P Lau
 //
-use crate::text::{Appearance, Color, Intensity, Outline, TextStyle};
+use crate::text::width::DEFAULT_TAB_WIDTH;
+use crate::text::wrap::{break_anywhere, wrap_text, WrapCache, WrapMode};
+use crate::text::{
+    expand_tabs, Appearance, Color, Intensity, Outline, TextStyle, Weight,
+};
 use crate::widget::BorderStyle;
+use std::borrow::Cow;
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
 /// Widget group
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -33,10 +43,76 @@ pub enum StyleGroup {
     LightShadow,
     /// Dark shadow style
     DarkShadow,
+    /// Error style
+    Error,
+    /// Custom style defined by the application
+    Custom(CustomStyle),
 }
 
+/// Handle to a custom theme style
+///
+/// Handles are obtained from [Theme::define_custom] or
+/// [Theme::custom_handle], so that styles can be looked up while drawing
+/// without hashing names.  Themes which define the same custom names in the
+/// same order have matching handles.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CustomStyle(usize);
+
+/// Serialized style theme
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct ThemeDef {
+    /// Background color
+    background: Color,
+    /// Foreground text color
+    foreground: Color,
+    /// Primary widget color
+    primary: Color,
+    /// Color for focused elements
+    focused: Color,
+    /// Color for interacting elements
+    interacting: Color,
+    /// Dark shadow color
+    dark_shadow: Color,
+    /// Light shadow color
+    light_shadow: Color,
+    /// Color for error placeholders
+    error: Color,
+    /// Normal border style
+    normal_border: BorderStyle,
+    /// Button border style
+    button_border: BorderStyle,
+    /// Columns between tab stops
+    tab_width: u16,
+    /// Duration of style transitions
+    transition: Duration,
+    /// Custom styles, by name
+    custom: BTreeMap<String, TextStyle>,
+}
+
+/// Last layout pass id
+static LAST_PASS_ID: AtomicU64 = AtomicU64::new(0);
+
 /// Style theme
-#[derive(Clone, Debug, PartialEq)]
+///
+/// With the `serde` feature, a theme can be serialized, such as to a TOML
+/// file.  Custom styles are in a `custom` table, keyed by name:
+///
+/// ```toml
+/// [custom.diff-added]
+/// foreground = { Green = "Bright" }
+/// ```
+///
+/// Missing fields keep their default values.  Deserialized custom styles
+/// have handles in name order, so applications should get handles with
+/// [custom_handle](Theme::custom_handle) after loading a theme.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(from = "ThemeDef", into = "ThemeDef")
+)]
 pub struct Theme {
     /// Background color
     pub background: Color,
@@ -52,10 +128,24 @@ pub struct Theme {
     pub dark_shadow: Color,
     /// Light shadow color
     pub light_shadow: Color,
+    /// Color for error placeholders
+    pub error: Color,
     /// Normal border style
     pub normal_border: BorderStyle,
     /// Button border style
     pub button_border: BorderStyle,
+    /// Columns between tab stops
+    tab_width: u16,
+    /// Duration of style transitions
+    transition: Duration,
+    /// Wrapped text measurement cache
+    wrap_cache: WrapCache,
+    /// Handles of custom styles, by name
+    custom_names: HashMap<Cow<'static, str>, CustomStyle>,
+    /// Custom styles, indexed by handle
+    custom_styles: Vec<TextStyle>,
+    /// Layout pass id
+    pass_id: u64,
 }
 
 impl Default for Theme {
@@ -67,8 +157,15 @@ impl Default for Theme {
         let interacting = Color::Yellow(Intensity::Bright);
         let dark_shadow = Color::Black(Intensity::Bright);
         let light_shadow = Color::White(Intensity::Normal);
+        let error = Color::Red(Intensity::Bright);
         let normal_border = BorderStyle::Simple(Outline::default());
         let button_border = BorderStyle::Bevel(Outline::default());
+        let tab_width = DEFAULT_TAB_WIDTH;
+        let transition = Duration::ZERO;
+        let wrap_cache = WrapCache::default();
+        let custom_names = HashMap::new();
+        let custom_styles = vec![];
+        let pass_id = 0;
         Self {
             background,
             foreground,
@@ -77,12 +174,98 @@ impl Default for Theme {
             interacting,
             dark_shadow,
             light_shadow,
+            error,
             normal_border,
             button_border,
+            tab_width,
+            transition,
+            wrap_cache,
+            custom_names,
+            custom_styles,
+            pass_id,
         }
     }
 }
 
+#[cfg(feature = "serde")]
+impl Default for ThemeDef {
+    fn default() -> Self {
+        Theme::default().into()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<ThemeDef> for Theme {
+    fn from(def: ThemeDef) -> Self {
+        let mut theme = Theme::default()
+            .with_tab_width(def.tab_width)
+            .with_transition(def.transition);
+        theme.background = def.background;
+        theme.foreground = def.foreground;
+        theme.primary = def.primary;
+        theme.focused = def.focused;
+        theme.interacting = def.interacting;
+        theme.dark_shadow = def.dark_shadow;
+        theme.light_shadow = def.light_shadow;
+        theme.error = def.error;
+        theme.normal_border = def.normal_border;
+        theme.button_border = def.button_border;
+        for (name, style) in def.custom {
+            theme.define_custom_name(Cow::Owned(name), style);
+        }
+        theme
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Theme> for ThemeDef {
+    fn from(theme: Theme) -> Self {
+        let custom = theme
+            .custom_names
+            .iter()
+            .map(|(name, handle)| {
+                (name.to_string(), theme.custom_styles[handle.0])
+            })
+            .collect();
+        ThemeDef {
+            background: theme.background,
+            foreground: theme.foreground,
+            primary: theme.primary,
+            focused: theme.focused,
+            interacting: theme.interacting,
+            dark_shadow: theme.dark_shadow,
+            light_shadow: theme.light_shadow,
+            error: theme.error,
+            normal_border: theme.normal_border,
+            button_border: theme.button_border,
+            tab_width: theme.tab_width,
+            transition: theme.transition,
+            custom,
+        }
+    }
+}
+
+impl PartialEq for Theme {
+    /// Themes are equal regardless of the layout pass id
+    fn eq(&self, rhs: &Self) -> bool {
+        self.background == rhs.background
+            && self.foreground == rhs.foreground
+            && self.primary == rhs.primary
+            && self.focused == rhs.focused
+            && self.interacting == rhs.interacting
+            && self.dark_shadow == rhs.dark_shadow
+            && self.light_shadow == rhs.light_shadow
+            && self.error == rhs.error
+            && self.normal_border == rhs.normal_border
+            && self.button_border == rhs.button_border
+            && self.tab_width == rhs.tab_width
+            && self.transition == rhs.transition
+            && self.wrap_cache == rhs.wrap_cache
+            && self.custom_names == rhs.custom_names
+            && self.custom_styles == rhs.custom_styles
+    }
+}
+
 impl Theme {
     /// Set the background color
     pub fn with_background(mut self, clr: Color) -> Self {
@@ -114,6 +297,117 @@ impl Theme {
         self
     }
 
+    /// Set the color for error placeholders
+    pub fn with_error(mut self, clr: Color) -> Self {
+        self.error = clr;
+        self
+    }
+
+    /// Set the capacity of the wrapped text measurement cache
+    ///
+    /// The default capacity is 256 entries; 0 disables the cache.
+    pub fn with_wrap_cache(mut self, capacity: usize) -> Self {
+        self.wrap_cache = WrapCache::new(capacity);
+        self
+    }
+
+    /// Set the number of columns between tab stops
+    ///
+    /// The default tab width is 4.
+    pub fn with_tab_width(mut self, tab_width: u16) -> Self {
+        self.tab_width = tab_width;
+        // Cloning the cache drops all entries
+        self.wrap_cache = self.wrap_cache.clone();
+        self
+    }
+
+    /// Get the number of columns between tab stops
+    pub fn tab_width(&self) -> u16 {
+        self.tab_width
+    }
+
+    /// Set the duration of style transitions
+    ///
+    /// When a widget's style changes between frames, `Rgb` colors fade to
+    /// the new style over the duration.  Transitions are disabled by
+    /// default (zero duration).
+    pub fn with_transition(mut self, transition: Duration) -> Self {
+        self.transition = transition;
+        self
+    }
+
+    /// Get the duration of style transitions
+    pub fn transition(&self) -> Duration {
+        self.transition
+    }
+
+    /// Get the layout pass id
+    ///
+    /// A [Screen] starts a new pass each time it lays out widgets.  The id is
+    /// stable from the first bounds call of a pass through the draw which
+    /// follows, so widgets can cache data derived while calculating bounds
+    /// (see [PassCache]).  Ids increase with each pass, and are never reused
+    /// by any screen.  A theme which has not been used for layout has an id
+    /// of 0, for which nothing is cached.
+    ///
+    /// [PassCache]: ../layout/struct.PassCache.html
+    /// [Screen]: ../struct.Screen.html
+    pub fn pass_id(&self) -> u64 {
+        self.pass_id
+    }
+
+    /// Start a new layout pass
+    pub(crate) fn next_pass(&mut self) {
+        self.pass_id = LAST_PASS_ID.fetch_add(1, Ordering::Relaxed) + 1;
+    }
+
+    /// Expand tabs in text to the next tab stop
+    pub fn expand_tabs<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        expand_tabs(text, self.tab_width)
+    }
+
+    /// Wrap text to a width
+    ///
+    /// Tabs are expanded before wrapping.  Results are cached, so widgets
+    /// can measure and draw the same text repeatedly without wrapping it
+    /// again.
+    pub fn wrap<'t>(&self, text: &'t str, width: u16) -> Vec<Cow<'t, str>> {
+        let width = usize::from(width);
+        match self.expand_tabs(text) {
+            Cow::Borrowed(text) => self.wrap_cache.wrap(text, width),
+            Cow::Owned(text) => wrap_text(&text, width)
+                .into_iter()
+                .map(|line| Cow::Owned(line.into_owned()))
+                .collect(),
+        }
+    }
+
+    /// Wrap text to a width with a wrap mode
+    ///
+    /// Tabs are expanded before wrapping.  With [WrapMode::Truncate], the
+    /// first line is returned as is, to be truncated when drawn.
+    pub fn wrap_with<'t>(
+        &self,
+        text: &'t str,
+        width: u16,
+        mode: WrapMode,
+    ) -> Vec<Cow<'t, str>> {
+        match mode {
+            WrapMode::Wrap => self.wrap(text, width),
+            WrapMode::BreakAnywhere => match self.expand_tabs(text) {
+                Cow::Borrowed(text) => break_anywhere(text, width.into()),
+                Cow::Owned(text) => break_anywhere(&text, width.into())
+                    .into_iter()
+                    .map(|line| Cow::Owned(line.into_owned()))
+                    .collect(),
+            },
+            WrapMode::Truncate => {
+                let line = text.split('\n').next().unwrap_or_default();
+                vec![self.expand_tabs(line)]
+            }
+        }
+    }
+
     /// Get text style
     pub fn style(&self, group: StyleGroup) -> TextStyle {
         let style = TextStyle::default().with_background(self.background);
@@ -129,10 +423,78 @@ impl Theme {
                 .with_appearance(Appearance::default().with_reverse(true)),
             StyleGroup::LightShadow => style.with_foreground(self.light_shadow),
             StyleGroup::DarkShadow => style.with_foreground(self.dark_shadow),
+            StyleGroup::Error => {
+                style.with_foreground(self.error).with_appearance(
+                    Appearance::default().with_weight(Weight::Bold),
+                )
+            }
+            StyleGroup::Custom(handle) => self.custom_style(handle),
             _ => style.with_foreground(self.foreground),
         }
     }
 
+    /// Define a custom style
+    ///
+    /// * `name`: Name of the style, such as `"diff-added"`
+    /// * `style`: Text style
+    ///
+    /// Redefining a name replaces its style, keeping the same handle.
+    pub fn define_custom(
+        &mut self,
+        name: &'static str,
+        style: TextStyle,
+    ) -> CustomStyle {
+        self.define_custom_name(Cow::Borrowed(name), style)
+    }
+
+    /// Define a custom style with a borrowed or owned name
+    fn define_custom_name(
+        &mut self,
+        name: Cow<'static, str>,
+        style: TextStyle,
+    ) -> CustomStyle {
+        match self.custom_names.get(&name) {
+            Some(&handle) => {
+                self.custom_styles[handle.0] = style;
+                handle
+            }
+            None => {
+                let handle = CustomStyle(self.custom_styles.len());
+                self.custom_styles.push(style);
+                self.custom_names.insert(name, handle);
+                handle
+            }
+        }
+    }
+
+    /// Get the handle of a custom style
+    pub fn custom_handle(&self, name: &str) -> Option<CustomStyle> {
+        self.custom_names.get(name).copied()
+    }
+
+    /// Get a custom style by name
+    ///
+    /// Undefined names fall back to the [Enabled](StyleGroup::Enabled)
+    /// style; use [custom_handle](Theme::custom_handle) to check whether a
+    /// name is defined.
+    pub fn custom(&self, name: &str) -> TextStyle {
+        match self.custom_handle(name) {
+            Some(handle) => self.custom_style(handle),
+            None => self.style(StyleGroup::Enabled),
+        }
+    }
+
+    /// Get a custom style by handle
+    ///
+    /// Handles from a theme without the style fall back to the
+    /// [Enabled](StyleGroup::Enabled) style.
+    pub fn custom_style(&self, handle: CustomStyle) -> TextStyle {
+        match self.custom_styles.get(handle.0) {
+            Some(style) => *style,
+            None => self.style(StyleGroup::Enabled),
+        }
+    }
+
     /// Get the border style
     pub fn border_style(&self, group: WidgetGroup) -> BorderStyle {
         match group {
@@ -141,3 +503,89 @@ impl Theme {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrap_tabs() {
+        let text = "日\tb\tc\nx\ty";
+        let theme = Theme::default();
+        assert_eq!(theme.wrap(text, 20), ["日  b   c", "x   y"]);
+        let theme = theme.with_tab_width(8);
+        assert_eq!(theme.tab_width(), 8);
+        assert_eq!(theme.wrap(text, 20), ["日      b       c", "x       y"]);
+        assert_eq!(theme.wrap(text, 10), ["日      b", "c", "x       y"]);
+    }
+
+    #[test]
+    fn eq_pass_id() {
+        let mut theme = Theme::default();
+        theme.next_pass();
+        assert_ne!(theme.pass_id(), 0);
+        assert_eq!(theme, Theme::default());
+        assert_ne!(theme, Theme::default().with_tab_width(8));
+    }
+
+    #[test]
+    fn custom_styles() {
+        let mut theme = Theme::default();
+        let added = TextStyle::default()
+            .with_foreground(Color::Green(Intensity::Bright));
+        let removed =
+            TextStyle::default().with_foreground(Color::Red(Intensity::Normal));
+        let h_added = theme.define_custom("diff-added", added);
+        let h_removed = theme.define_custom("diff-removed", removed);
+        assert_ne!(h_added, h_removed);
+        assert_eq!(theme.custom("diff-added"), added);
+        assert_eq!(theme.custom_style(h_removed), removed);
+        assert_eq!(theme.style(StyleGroup::Custom(h_added)), added);
+        assert_eq!(theme.custom_handle("diff-removed"), Some(h_removed));
+        // Redefining keeps the handle
+        assert_eq!(theme.define_custom("diff-added", removed), h_added);
+        assert_eq!(theme.custom_style(h_added), removed);
+    }
+
+    #[test]
+    fn custom_fallback() {
+        let mut theme = Theme::default();
+        let enabled = theme.style(StyleGroup::Enabled);
+        assert_eq!(theme.custom("warning"), enabled);
+        assert_eq!(theme.custom_handle("warning"), None);
+        // Handle from another theme
+        let mut other = Theme::default();
+        other.define_custom("a", TextStyle::default());
+        let handle = other.define_custom("b", TextStyle::default());
+        theme.define_custom("a", TextStyle::default());
+        assert_eq!(theme.custom_style(handle), enabled);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_custom() {
+        let added = TextStyle::default()
+            .with_foreground(Color::Green(Intensity::Bright))
+            .with_appearance(Appearance::default().with_underline(true));
+        let mut theme = Theme::default()
+            .with_primary(Color::Rgb(10, 20, 30))
+            .with_tab_width(8);
+        theme.define_custom("diff-added", added);
+        let json = serde_json::to_value(&theme).unwrap();
+        assert_eq!(
+            json["custom"]["diff-added"]["foreground"],
+            serde_json::json!({ "Green": "Bright" })
+        );
+        let de: Theme = serde_json::from_value(json).unwrap();
+        assert_eq!(de, theme);
+        assert_eq!(de.custom("diff-added"), added);
+        // Missing fields are defaults
+        let json = r#"{ "custom": { "warning": { "appearance": {
+            "weight": "Bold" } } } }"#;
+        let de: Theme = serde_json::from_str(json).unwrap();
+        assert_eq!(de.background, Theme::default().background);
+        let warning = de.custom("warning");
+        assert_eq!(warning.appearance().weight(), Weight::Bold);
+        assert_eq!(warning.foreground(), TextStyle::default().foreground());
+    }
+}
@@ -0,0 +1,625 @@
+// width.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use std::borrow::Cow;
+use std::cell::Cell;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Ellipsis appended to truncated text
+const ELLIPSIS: char = '…';
+
+/// Default tab width (columns between tab stops)
+pub(crate) const DEFAULT_TAB_WIDTH: u16 = 4;
+
+/// Width policy for East Asian ambiguous-width characters
+///
+/// Characters such as `±`, `·` and Greek letters are drawn one column wide
+/// by most terminals, but two columns wide by terminals configured for CJK
+/// locales.  The policy must match the terminal, or borders shift and text
+/// overlaps.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum AmbiguousWidth {
+    /// One column
+    #[default]
+    Narrow,
+    /// Two columns
+    Wide,
+}
+
+/// Width policy for emoji presentation sequences
+///
+/// A character followed by the emoji presentation selector (VS16), such as
+/// `⚠️`, is drawn two columns wide by most terminals.  Some terminals ignore
+/// the selector, drawing it with the width of the base character instead.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum EmojiWidth {
+    /// Two columns
+    #[default]
+    Wide,
+    /// Width of the base character
+    Narrow,
+}
+
+/// Emoji presentation selector (VS16)
+pub(crate) const VS16: char = '\u{FE0F}';
+
+/// Ambiguous-width letters which unicode-width measures narrow in CJK
+/// context (Latin-1, Greek and Cyrillic)
+const AMBIGUOUS_LETTERS: &[(char, char)] = &[
+    ('\u{C6}', '\u{C6}'),
+    ('\u{D0}', '\u{D0}'),
+    ('\u{D8}', '\u{D8}'),
+    ('\u{DE}', '\u{E1}'),
+    ('\u{E6}', '\u{E6}'),
+    ('\u{E8}', '\u{EA}'),
+    ('\u{EC}', '\u{ED}'),
+    ('\u{F0}', '\u{F0}'),
+    ('\u{F2}', '\u{F3}'),
+    ('\u{F8}', '\u{FA}'),
+    ('\u{FC}', '\u{FC}'),
+    ('\u{FE}', '\u{FE}'),
+    ('\u{391}', '\u{3A1}'),
+    ('\u{3A3}', '\u{3A9}'),
+    ('\u{3B1}', '\u{3C1}'),
+    ('\u{3C3}', '\u{3C9}'),
+    ('\u{401}', '\u{401}'),
+    ('\u{410}', '\u{44F}'),
+    ('\u{451}', '\u{451}'),
+];
+
+thread_local! {
+    /// Ambiguous width policy for the current thread
+    static AMBIGUOUS_WIDTH: Cell<AmbiguousWidth> =
+        const { Cell::new(AmbiguousWidth::Narrow) };
+
+    /// Emoji width policy for the current thread
+    static EMOJI_WIDTH: Cell<EmojiWidth> = const { Cell::new(EmojiWidth::Wide) };
+}
+
+impl AmbiguousWidth {
+    /// Guess the policy from locale environment variables
+    ///
+    /// The first non-empty of `LC_ALL`, `LC_CTYPE` and `LANG` is checked.
+    /// Chinese, Japanese and Korean locales are `Wide`; all others are
+    /// `Narrow`.
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|locale| !locale.is_empty())
+            .map_or(AmbiguousWidth::Narrow, |locale| Self::from_locale(&locale))
+    }
+
+    /// Get the policy for a locale name, such as `ja_JP.UTF-8`
+    fn from_locale(locale: &str) -> Self {
+        let lang = locale.split(['_', '.', '@']).next().unwrap_or("");
+        match lang {
+            "zh" | "ja" | "ko" => AmbiguousWidth::Wide,
+            _ => AmbiguousWidth::Narrow,
+        }
+    }
+
+    /// Get the policy of the current thread
+    pub(crate) fn current() -> Self {
+        AMBIGUOUS_WIDTH.with(Cell::get)
+    }
+
+    /// Set the policy of the current thread
+    pub(crate) fn set_current(self) {
+        AMBIGUOUS_WIDTH.with(|aw| aw.set(self));
+    }
+
+    /// Run a function with this policy, then restore the previous one
+    #[cfg(test)]
+    pub(crate) fn with<R>(self, f: impl FnOnce() -> R) -> R {
+        let prev = Self::current();
+        self.set_current();
+        let res = f();
+        prev.set_current();
+        res
+    }
+}
+
+impl EmojiWidth {
+    /// Get the policy of the current thread
+    pub(crate) fn current() -> Self {
+        EMOJI_WIDTH.with(Cell::get)
+    }
+
+    /// Set the policy of the current thread
+    pub(crate) fn set_current(self) {
+        EMOJI_WIDTH.with(|ew| ew.set(self));
+    }
+
+    /// Run a function with this policy, then restore the previous one
+    #[cfg(test)]
+    pub(crate) fn with<R>(self, f: impl FnOnce() -> R) -> R {
+        let prev = Self::current();
+        self.set_current();
+        let res = f();
+        prev.set_current();
+        res
+    }
+}
+
+/// Check if a character is an ambiguous-width letter
+fn is_ambiguous_letter(ch: char) -> bool {
+    AMBIGUOUS_LETTERS
+        .binary_search_by(|&(lo, hi)| {
+            if hi < ch {
+                std::cmp::Ordering::Less
+            } else if lo > ch {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Get the display width of a character
+///
+/// Control characters have zero width.
+pub(crate) fn char_width(ch: char) -> usize {
+    match AmbiguousWidth::current() {
+        AmbiguousWidth::Narrow => ch.width().unwrap_or(0),
+        AmbiguousWidth::Wide if is_ambiguous_letter(ch) => 2,
+        AmbiguousWidth::Wide => ch.width_cjk().unwrap_or(0),
+    }
+}
+
+/// Get the display width of text
+///
+/// Variation selectors are measured with their base character: VS16 gives
+/// emoji presentation (subject to the [EmojiWidth] policy) and VS15 gives
+/// text presentation.
+pub(crate) fn display_width(text: &str) -> usize {
+    if EmojiWidth::current() == EmojiWidth::Narrow && text.contains(VS16) {
+        return text
+            .graphemes(true)
+            .map(|g| {
+                if g.contains(VS16) {
+                    g.chars().next().map_or(0, char_width)
+                } else {
+                    policy_width(g)
+                }
+            })
+            .sum();
+    }
+    policy_width(text)
+}
+
+/// Get the display width of text with the ambiguous width policy
+fn policy_width(text: &str) -> usize {
+    match AmbiguousWidth::current() {
+        AmbiguousWidth::Narrow => text.width(),
+        AmbiguousWidth::Wide => {
+            let letters = text.chars().filter(|c| is_ambiguous_letter(*c));
+            text.width_cjk() + letters.count()
+        }
+    }
+}
+
+/// Truncate text at the end to fit within a display width
+///
+/// If the text is too wide, it is cut and an ellipsis appended.  A
+/// double-width grapheme which would straddle the cut is dropped; graphemes
+/// are never split, so variation selectors stay with their base.
+pub(crate) fn truncate_end(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    if width == 0 {
+        return out;
+    }
+    let limit = width - 1;
+    let mut used = 0;
+    for g in text.graphemes(true) {
+        let w = display_width(g);
+        if used + w > limit {
+            break;
+        }
+        used += w;
+        out.push_str(g);
+    }
+    out.push(ELLIPSIS);
+    out
+}
+
+/// Get the portion of a line visible in a view
+///
+/// * `line`: Line of text
+/// * `offset`: Column scroll offset of the view
+/// * `width`: Width of the view
+///
+/// Graphemes which are not entirely within the view are skipped.
+///
+/// ## Return
+///
+/// Column of the first visible grapheme relative to the view, and the
+/// visible text; or `None` if nothing is visible.
+pub(crate) fn visible_span(
+    line: &str,
+    offset: u16,
+    width: u16,
+) -> Option<(u16, &str)> {
+    let mut col = 0;
+    let mut span: Option<(u16, usize, usize)> = None;
+    for (i, g) in line.grapheme_indices(true) {
+        let c = col;
+        col += display_width(g) as u16;
+        if c < offset {
+            continue;
+        }
+        if col - offset > width {
+            break;
+        }
+        match &mut span {
+            Some((_, _, end)) => *end = i + g.len(),
+            None => span = Some((c - offset, i, i + g.len())),
+        }
+    }
+    span.map(|(c, start, end)| (c, &line[start..end]))
+}
+
+/// Get the display width of a grapheme cluster
+///
+/// * `g`: Grapheme cluster
+/// * `col`: Column where the grapheme starts
+/// * `tab_width`: Columns between tab stops
+pub(crate) fn grapheme_width(g: &str, col: usize, tab_width: u16) -> usize {
+    if g == "\t" {
+        let tab = usize::from(tab_width.max(1));
+        tab - col % tab
+    } else {
+        // Emoji sequences are drawn in one double-width cell
+        display_width(g).min(2)
+    }
+}
+
+/// Expand tabs to spaces, advancing to the next tab stop
+///
+/// * `text`: Text to expand
+/// * `tab_width`: Columns between tab stops
+///
+/// Tab stops restart at each line break.
+pub fn expand_tabs(text: &str, tab_width: u16) -> Cow<'_, str> {
+    if !text.contains('\t') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut col = 0;
+    for g in text.graphemes(true) {
+        let w = grapheme_width(g, col, tab_width);
+        if g == "\t" {
+            out.extend(std::iter::repeat_n(' ', w));
+        } else {
+            out.push_str(g);
+        }
+        col = if g == "\n" || g == "\r\n" { 0 } else { col + w };
+    }
+    Cow::Owned(out)
+}
+
+/// Convert a display column to a byte index within text
+///
+/// * `text`: Text to search
+/// * `col`: Column relative to the view
+/// * `offset`: Column scroll offset of the view
+/// * `tab_width`: Columns between tab stops
+///
+/// Clicking the left half of a double-width grapheme snaps before it, and
+/// the right half snaps after it.  Columns past the end snap to the end.
+///
+/// ## Return
+///
+/// Byte index of the grapheme boundary and its column relative to the view
+/// (saturating at 0).
+pub fn column_to_index(
+    text: &str,
+    col: u16,
+    offset: u16,
+    tab_width: u16,
+) -> (usize, u16) {
+    let target = usize::from(offset) + usize::from(col);
+    let mut start = 0;
+    for (i, g) in text.grapheme_indices(true) {
+        let w = grapheme_width(g, start, tab_width);
+        if target < start + w {
+            let (i, c) = if (target - start) * 2 < w {
+                (i, start)
+            } else {
+                (i + g.len(), start + w)
+            };
+            return (i, view_column(c, offset));
+        }
+        start += w;
+    }
+    (text.len(), view_column(start, offset))
+}
+
+/// Convert a byte index within text to a display column
+///
+/// * `text`: Text to search
+/// * `index`: Byte index (snapped to the start of its grapheme)
+/// * `offset`: Column scroll offset of the view
+/// * `tab_width`: Columns between tab stops
+///
+/// ## Return
+///
+/// Column relative to the view (saturating at 0).
+pub fn index_to_column(
+    text: &str,
+    index: usize,
+    offset: u16,
+    tab_width: u16,
+) -> u16 {
+    let mut col = 0;
+    for (i, g) in text.grapheme_indices(true) {
+        if index < i + g.len() {
+            break;
+        }
+        col += grapheme_width(g, col, tab_width);
+    }
+    view_column(col, offset)
+}
+
+/// Convert an absolute column to a view column
+fn view_column(col: usize, offset: u16) -> u16 {
+    let col = col.saturating_sub(usize::from(offset));
+    col.min(usize::from(u16::MAX)) as u16
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn truncate() {
+        assert_eq!(truncate_end("semtext", 7), "semtext");
+        assert_eq!(truncate_end("semtext", 8), "semtext");
+        assert_eq!(truncate_end("semtext", 6), "semte…");
+        assert_eq!(truncate_end("semtext", 1), "…");
+        assert_eq!(truncate_end("semtext", 0), "");
+    }
+
+    #[test]
+    fn truncate_wide() {
+        assert_eq!(truncate_end("ｗｗｗ", 6), "ｗｗｗ");
+        assert_eq!(truncate_end("ｗｗｗ", 5), "ｗｗ…");
+        assert_eq!(truncate_end("ｗｗｗ", 4), "ｗ…");
+        assert_eq!(truncate_end("ｗｗｗ", 2), "…");
+    }
+
+    /// Check column_to_index for every column (and one past the end)
+    fn check_columns(text: &str, expected: &[(usize, u16)]) {
+        let width: usize =
+            text.graphemes(true).map(|g| display_width(g).min(2)).sum();
+        assert_eq!(expected.len(), width + 2, "{}", text);
+        for (col, exp) in expected.iter().enumerate() {
+            let col = col as u16;
+            assert_eq!(
+                column_to_index(text, col, 0, 4),
+                *exp,
+                "{} {}",
+                text,
+                col
+            );
+        }
+    }
+
+    #[test]
+    fn columns_ascii() {
+        check_columns("abc", &[(0, 0), (1, 1), (2, 2), (3, 3), (3, 3)]);
+        check_columns("", &[(0, 0), (0, 0)]);
+    }
+
+    #[test]
+    fn columns_cjk() {
+        // Each char is 3 bytes and 2 columns
+        check_columns(
+            "日本",
+            &[(0, 0), (3, 2), (3, 2), (6, 4), (6, 4), (6, 4)],
+        );
+        check_columns(
+            "a日b",
+            &[(0, 0), (1, 1), (4, 3), (4, 3), (5, 4), (5, 4)],
+        );
+    }
+
+    #[test]
+    fn columns_emoji() {
+        // Thumbs up with skin tone modifier: 8 bytes, one grapheme
+        let text = "x👍🏽y";
+        check_columns(
+            text,
+            &[(0, 0), (1, 1), (9, 3), (9, 3), (10, 4), (10, 4)],
+        );
+    }
+
+    #[test]
+    fn columns_combining() {
+        // e + combining acute accent: 3 bytes, one column
+        let text = "e\u{301}a";
+        check_columns(text, &[(0, 0), (3, 1), (4, 2), (4, 2)]);
+        assert_eq!(index_to_column(text, 1, 0, 4), 0);
+        assert_eq!(index_to_column(text, 3, 0, 4), 1);
+    }
+
+    #[test]
+    fn columns_offset() {
+        assert_eq!(column_to_index("abcdef", 0, 2, 4), (2, 0));
+        assert_eq!(column_to_index("abcdef", 2, 2, 4), (4, 2));
+        assert_eq!(column_to_index("abcdef", 9, 2, 4), (6, 4));
+        // Left half of a glyph straddling the view edge
+        assert_eq!(column_to_index("日本", 0, 1, 4), (3, 1));
+        assert_eq!(index_to_column("abcdef", 1, 2, 4), 0);
+        assert_eq!(index_to_column("abcdef", 5, 2, 4), 3);
+    }
+
+    #[test]
+    fn indices() {
+        let text = "a日👍🏽b";
+        assert_eq!(index_to_column(text, 0, 0, 4), 0);
+        assert_eq!(index_to_column(text, 1, 0, 4), 1);
+        assert_eq!(index_to_column(text, 2, 0, 4), 1);
+        assert_eq!(index_to_column(text, 4, 0, 4), 3);
+        assert_eq!(index_to_column(text, 12, 0, 4), 5);
+        assert_eq!(index_to_column(text, 13, 0, 4), 6);
+        for (i, _) in text.grapheme_indices(true) {
+            let col = index_to_column(text, i, 0, 4);
+            assert_eq!(column_to_index(text, col, 0, 4), (i, col));
+        }
+    }
+
+    #[test]
+    fn tabs_expand() {
+        assert_eq!(expand_tabs("a\tb", 4), "a   b");
+        assert_eq!(expand_tabs("a\tb", 8), "a       b");
+        assert_eq!(expand_tabs("日\tb\tc", 4), "日  b   c");
+        assert_eq!(expand_tabs("日\tb\tc", 8), "日      b       c");
+        assert_eq!(expand_tabs("abcd\te\n\tf", 4), "abcd    e\n    f");
+        assert!(matches!(expand_tabs("no tabs", 4), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn tabs_columns() {
+        let text = "日\tb";
+        assert_eq!(column_to_index(text, 2, 0, 4), (3, 2));
+        assert_eq!(column_to_index(text, 3, 0, 4), (4, 4));
+        assert_eq!(column_to_index(text, 4, 0, 4), (4, 4));
+        assert_eq!(column_to_index(text, 5, 0, 4), (5, 5));
+        assert_eq!(column_to_index(text, 4, 0, 8), (3, 2));
+        assert_eq!(column_to_index(text, 5, 0, 8), (4, 8));
+        assert_eq!(column_to_index(text, 8, 0, 8), (4, 8));
+        assert_eq!(index_to_column(text, 4, 0, 4), 4);
+        assert_eq!(index_to_column(text, 4, 0, 8), 8);
+    }
+
+    /// Text with ambiguous-width characters
+    const AMBIGUOUS: &str = "a±·α";
+
+    #[test]
+    fn ambiguous_narrow() {
+        assert_eq!(display_width(AMBIGUOUS), 4);
+        assert_eq!(char_width('α'), 1);
+        assert_eq!(truncate_end(AMBIGUOUS, 4), AMBIGUOUS);
+        assert_eq!(truncate_end(AMBIGUOUS, 3), "a±…");
+        assert_eq!(column_to_index(AMBIGUOUS, 2, 0, 4), (3, 2));
+        assert_eq!(index_to_column(AMBIGUOUS, 5, 0, 4), 3);
+        assert_eq!(visible_span(AMBIGUOUS, 1, 2), Some((0, "±·")));
+    }
+
+    #[test]
+    fn ambiguous_wide() {
+        AmbiguousWidth::Wide.with(|| {
+            assert_eq!(display_width(AMBIGUOUS), 7);
+            assert_eq!(char_width('α'), 2);
+            assert_eq!(truncate_end(AMBIGUOUS, 4), "a±…");
+            assert_eq!(truncate_end(AMBIGUOUS, 3), "a…");
+            check_columns(
+                AMBIGUOUS,
+                &[
+                    (0, 0),
+                    (1, 1),
+                    (3, 3),
+                    (3, 3),
+                    (5, 5),
+                    (5, 5),
+                    (7, 7),
+                    (7, 7),
+                    (7, 7),
+                ],
+            );
+            assert_eq!(index_to_column(AMBIGUOUS, 5, 0, 4), 5);
+            assert_eq!(visible_span(AMBIGUOUS, 1, 2), Some((0, "±")));
+            // Unambiguous characters are unaffected
+            assert_eq!(display_width("ab日"), 4);
+        });
+        assert_eq!(AmbiguousWidth::current(), AmbiguousWidth::Narrow);
+    }
+
+    #[test]
+    fn ambiguous_locale() {
+        use AmbiguousWidth::*;
+        assert_eq!(AmbiguousWidth::from_locale("ja_JP.UTF-8"), Wide);
+        assert_eq!(AmbiguousWidth::from_locale("zh_TW"), Wide);
+        assert_eq!(AmbiguousWidth::from_locale("ko"), Wide);
+        assert_eq!(AmbiguousWidth::from_locale("en_US.UTF-8"), Narrow);
+        assert_eq!(AmbiguousWidth::from_locale("C"), Narrow);
+        assert_eq!(AmbiguousWidth::from_locale(""), Narrow);
+    }
+
+    #[test]
+    fn variation_selectors() {
+        for emoji in ["⚠️", "❤️", "☀️"] {
+            assert_eq!(display_width(emoji), 2, "{}", emoji);
+            assert_eq!(truncate_end(emoji, 2), emoji);
+            let text = format!("{}ab", emoji);
+            assert_eq!(truncate_end(&text, 3), format!("{}…", emoji));
+        }
+        // Text presentation
+        assert_eq!(display_width("⚠\u{FE0E}"), 1);
+        assert_eq!(display_width("⚠️ Warn"), 7);
+        // The selector is never separated from its base
+        assert_eq!(truncate_end("⚠️ Warn", 6), "⚠️ Wa…");
+        assert_eq!(truncate_end("⚠️ Warn", 2), "…");
+        assert_eq!(truncate_end("a⚠️b", 3), "a…");
+        // Sun with VS16: 6 bytes, one grapheme
+        check_columns(
+            "x☀️y",
+            &[(0, 0), (1, 1), (7, 3), (7, 3), (8, 4), (8, 4)],
+        );
+        assert_eq!(index_to_column("x☀️y", 4, 0, 4), 1);
+        assert_eq!(visible_span("☀️ab", 0, 3), Some((0, "☀️a")));
+        assert_eq!(visible_span("☀️ab", 1, 2), Some((1, "a")));
+    }
+
+    #[test]
+    fn emoji_narrow() {
+        EmojiWidth::Narrow.with(|| {
+            assert_eq!(display_width("⚠️"), 1);
+            assert_eq!(display_width("⚠️ Warn"), 6);
+            assert_eq!(truncate_end("⚠️ Warn", 5), "⚠️ Wa…");
+            check_columns("☀️y", &[(0, 0), (6, 1), (7, 2), (7, 2)]);
+            // Wide base characters are unaffected
+            assert_eq!(display_width("😀\u{FE0F}"), 2);
+            assert_eq!(display_width("日本"), 4);
+        });
+        assert_eq!(EmojiWidth::current(), EmojiWidth::Wide);
+    }
+
+    #[test]
+    fn columns_rtl() {
+        // Logical order: each Hebrew letter is 2 bytes and 1 column
+        let text = "שלום abc";
+        check_columns(
+            text,
+            &[
+                (0, 0),
+                (2, 1),
+                (4, 2),
+                (6, 3),
+                (8, 4),
+                (9, 5),
+                (10, 6),
+                (11, 7),
+                (12, 8),
+                (12, 8),
+            ],
+        );
+        for (i, _) in text.grapheme_indices(true) {
+            let col = index_to_column(text, i, 0, 4);
+            assert_eq!(column_to_index(text, col, 0, 4), (i, col));
+        }
+        // Arabic with combining marks
+        let text = "مَرْحَبًا";
+        for col in 0..8 {
+            let (i, _) = column_to_index(text, col, 0, 4);
+            assert!(text.is_char_boundary(i));
+        }
+    }
+}
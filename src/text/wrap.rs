@@ -0,0 +1,558 @@
+// wrap.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+#[cfg(feature = "textwrap")]
+use crate::text::width::VS16;
+use crate::text::width::{display_width, AmbiguousWidth, EmojiWidth};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+#[cfg(feature = "textwrap")]
+use textwrap::core::Fragment;
+#[cfg(feature = "textwrap")]
+use textwrap::word_splitters::split_words;
+#[cfg(feature = "textwrap")]
+use textwrap::wrap_algorithms::{wrap_first_fit, wrap_optimal_fit, Penalties};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Default wrap cache capacity (entries)
+pub(crate) const DEFAULT_CAPACITY: usize = 256;
+
+/// Wrap cache key
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Key {
+    /// Hash of text
+    hash: u64,
+    /// Length of text (bytes)
+    len: usize,
+    /// Wrap width
+    width: usize,
+    /// Ambiguous width policy
+    ambiguous: AmbiguousWidth,
+    /// Emoji width policy
+    emoji: EmojiWidth,
+}
+
+/// Cached wrap entry
+struct Entry {
+    /// Wrapped text, to check for hash collisions
+    text: Box<str>,
+    /// Byte ranges of wrapped lines
+    lines: Vec<Range<usize>>,
+    /// Last use (for eviction)
+    used: u64,
+}
+
+/// Inner wrap cache state
+#[derive(Default)]
+struct Inner {
+    /// Cached entries
+    entries: HashMap<Key, Entry>,
+    /// Use counter
+    counter: u64,
+}
+
+/// Text wrapping mode
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum WrapMode {
+    /// Wrap lines at word boundaries
+    ///
+    /// Words wider than a line are broken between graphemes.
+    #[default]
+    Wrap,
+    /// Wrap lines between any graphemes, filling each row
+    ///
+    /// This suits long unbroken tokens, such as URLs or paths.
+    BreakAnywhere,
+    /// Truncate the first line with an ellipsis, without wrapping
+    Truncate,
+}
+
+/// Wrapped text measurement cache
+///
+/// A least-recently used cache of wrapped line offsets, keyed by text and
+/// width.  Changing either the text or the width results in a different
+/// key, so no explicit invalidation is needed.  Since keys contain only a
+/// hash of the text, each entry also keeps the text, which is compared on
+/// every hit.
+pub(crate) struct WrapCache {
+    /// Maximum number of entries
+    capacity: usize,
+    /// Cache state
+    inner: RefCell<Inner>,
+}
+
+impl Key {
+    /// Create a new cache key
+    fn new(text: &str, width: usize) -> Self {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let hash = hasher.finish();
+        let len = text.len();
+        let ambiguous = AmbiguousWidth::current();
+        let emoji = EmojiWidth::current();
+        Key {
+            hash,
+            len,
+            width,
+            ambiguous,
+            emoji,
+        }
+    }
+}
+
+impl Default for WrapCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl Clone for WrapCache {
+    /// Clone the cache (without cloning entries)
+    fn clone(&self) -> Self {
+        Self::new(self.capacity)
+    }
+}
+
+impl fmt::Debug for WrapCache {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("WrapCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl PartialEq for WrapCache {
+    /// Caches do not affect equality
+    fn eq(&self, _rhs: &Self) -> bool {
+        true
+    }
+}
+
+impl WrapCache {
+    /// Create a new wrap cache
+    ///
+    /// * `capacity`: Maximum number of entries; 0 disables caching
+    pub(crate) fn new(capacity: usize) -> Self {
+        let inner = RefCell::new(Inner::default());
+        Self { capacity, inner }
+    }
+
+    /// Get the number of cached entries
+    pub(crate) fn len(&self) -> usize {
+        self.inner.borrow().entries.len()
+    }
+
+    /// Wrap text to a width
+    pub(crate) fn wrap<'t>(
+        &self,
+        text: &'t str,
+        width: usize,
+    ) -> Vec<Cow<'t, str>> {
+        if self.capacity == 0 {
+            return wrap_text(text, width);
+        }
+        let key = Key::new(text, width);
+        let mut inner = self.inner.borrow_mut();
+        inner.counter += 1;
+        let used = inner.counter;
+        if let Some(entry) = inner.entries.get_mut(&key) {
+            if &*entry.text == text {
+                entry.used = used;
+                let lines = entry.lines.iter().map(|r| text.get(r.clone()));
+                if let Some(lines) = lines.collect::<Option<Vec<_>>>() {
+                    return lines.into_iter().map(Cow::Borrowed).collect();
+                }
+            }
+        }
+        let lines = wrap_text(text, width);
+        if let Some(ranges) = line_ranges(text, &lines) {
+            if inner.entries.len() >= self.capacity
+                && !inner.entries.contains_key(&key)
+            {
+                inner.evict();
+            }
+            let entry = Entry {
+                text: text.into(),
+                lines: ranges,
+                used,
+            };
+            inner.entries.insert(key, entry);
+        }
+        lines
+    }
+}
+
+impl Inner {
+    /// Evict the least recently used entry
+    fn evict(&mut self) {
+        let lru = self
+            .entries
+            .iter()
+            .min_by_key(|(_, e)| e.used)
+            .map(|(k, _)| *k);
+        if let Some(key) = lru {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+/// Word fragment, measured with the ambiguous width policy
+#[cfg(feature = "textwrap")]
+#[derive(Debug)]
+struct Measured<'t> {
+    /// Word text
+    word: &'t str,
+    /// Whitespace following the word
+    whitespace: &'t str,
+    /// Penalty inserted when the word ends a line
+    penalty: &'t str,
+}
+
+#[cfg(feature = "textwrap")]
+impl Fragment for Measured<'_> {
+    fn width(&self) -> f64 {
+        display_width(self.word) as f64
+    }
+
+    fn whitespace_width(&self) -> f64 {
+        display_width(self.whitespace) as f64
+    }
+
+    fn penalty_width(&self) -> f64 {
+        display_width(self.penalty) as f64
+    }
+}
+
+/// Wrap text to a width
+///
+/// textwrap always measures ambiguous-width characters as narrow, and
+/// ignores variation selectors, so words are measured here with the `Wide`
+/// policy or when the text contains VS16.
+#[cfg(feature = "textwrap")]
+pub(crate) fn wrap_text(text: &str, width: usize) -> Vec<Cow<'_, str>> {
+    match AmbiguousWidth::current() {
+        AmbiguousWidth::Narrow if !text.contains(VS16) => {
+            textwrap::wrap(text, width)
+        }
+        _ => text
+            .split('\n')
+            .flat_map(|line| wrap_line(line, width))
+            .collect(),
+    }
+}
+
+/// Wrap text to a width
+///
+/// Without textwrap, lines are filled greedily.
+#[cfg(not(feature = "textwrap"))]
+pub(crate) fn wrap_text(text: &str, width: usize) -> Vec<Cow<'_, str>> {
+    text.split('\n')
+        .flat_map(|line| wrap_greedy(line, width))
+        .collect()
+}
+
+/// Wrap one line of text to a width, filling each line greedily
+///
+/// Lines are broken at spaces, and words wider than a line are broken
+/// between graphemes.
+#[cfg(any(test, not(feature = "textwrap")))]
+fn wrap_greedy(line: &str, width: usize) -> Vec<Cow<'_, str>> {
+    let mut lines = vec![];
+    // Start of current line, end of its last word, and its width
+    let mut current: Option<(usize, usize, usize)> = None;
+    let mut pos = 0;
+    for word in line.split(' ') {
+        let i = pos;
+        pos += word.len() + 1;
+        if word.is_empty() {
+            continue;
+        }
+        let w = display_width(word);
+        match current {
+            Some((start, end, used)) => {
+                let used = used + display_width(&line[end..i]) + w;
+                if used <= width {
+                    current = Some((start, i + word.len(), used));
+                    continue;
+                }
+                lines.push(Cow::Borrowed(&line[start..end]));
+            }
+            None => {
+                // Leading spaces are kept on the first line
+                let used = display_width(&line[..i]) + w;
+                if used <= width {
+                    current = Some((0, i + word.len(), used));
+                    continue;
+                }
+            }
+        }
+        let mut start = i;
+        let mut used = 0;
+        for (j, g) in word.grapheme_indices(true) {
+            let gw = display_width(g);
+            if used + gw > width && used > 0 {
+                lines.push(Cow::Borrowed(&line[start..i + j]));
+                start = i + j;
+                used = 0;
+            }
+            used += gw;
+        }
+        current = Some((start, i + word.len(), used));
+    }
+    match current {
+        Some((start, end, _)) => lines.push(Cow::Borrowed(&line[start..end])),
+        None => lines.push(Cow::Borrowed("")),
+    }
+    lines
+}
+
+/// Wrap text to a width, breaking between any graphemes
+///
+/// Each line is filled completely before breaking, ignoring word
+/// boundaries.  A grapheme wider than the width is put on its own line.
+pub(crate) fn break_anywhere(text: &str, width: usize) -> Vec<Cow<'_, str>> {
+    let mut lines = vec![];
+    for line in text.split('\n') {
+        let mut start = 0;
+        let mut used = 0;
+        for (i, g) in line.grapheme_indices(true) {
+            let gw = display_width(g);
+            if used + gw > width && used > 0 {
+                lines.push(Cow::Borrowed(&line[start..i]));
+                start = i;
+                used = 0;
+            }
+            used += gw;
+        }
+        lines.push(Cow::Borrowed(&line[start..]));
+    }
+    lines
+}
+
+/// Wrap one line of text to a width
+#[cfg(feature = "textwrap")]
+fn wrap_line(line: &str, width: usize) -> Vec<Cow<'_, str>> {
+    let options = textwrap::Options::new(width);
+    let words = options.word_separator.find_words(line);
+    let mut fragments = vec![];
+    for word in split_words(words, &options.word_splitter) {
+        let frag = Measured {
+            word: word.word,
+            whitespace: word.whitespace,
+            penalty: word.penalty,
+        };
+        break_word(frag, width, &mut fragments);
+    }
+    let widths = [width as f64];
+    let wrapped = wrap_optimal_fit(&fragments, &widths, &Penalties::new())
+        .unwrap_or_else(|_| wrap_first_fit(&fragments, &widths));
+    let mut lines = vec![];
+    let mut start = 0;
+    for frags in wrapped {
+        let last = match frags.last() {
+            Some(last) => last,
+            None => {
+                lines.push(Cow::Borrowed(""));
+                continue;
+            }
+        };
+        let len: usize = frags
+            .iter()
+            .map(|f| f.word.len() + f.whitespace.len())
+            .sum::<usize>()
+            - last.whitespace.len();
+        let mut text = Cow::Borrowed(&line[start..start + len]);
+        if !last.penalty.is_empty() {
+            text.to_mut().push_str(last.penalty);
+        }
+        lines.push(text);
+        start += len + last.whitespace.len();
+    }
+    if lines.is_empty() {
+        lines.push(Cow::Borrowed(""));
+    }
+    lines
+}
+
+/// Break a fragment wider than a line into pieces
+#[cfg(feature = "textwrap")]
+fn break_word<'t>(
+    frag: Measured<'t>,
+    width: usize,
+    fragments: &mut Vec<Measured<'t>>,
+) {
+    let word = frag.word;
+    let mut start = 0;
+    let mut used = 0;
+    if display_width(word) > width {
+        for (i, g) in word.grapheme_indices(true) {
+            let w = display_width(g);
+            if used + w > width && i > start {
+                fragments.push(Measured {
+                    word: &word[start..i],
+                    whitespace: "",
+                    penalty: "",
+                });
+                start = i;
+                used = 0;
+            }
+            used += w;
+        }
+    }
+    fragments.push(Measured {
+        word: &word[start..],
+        ..frag
+    });
+}
+
+/// Get byte ranges of wrapped lines within the text
+///
+/// Returns `None` if any line is not borrowed from the text.
+fn line_ranges(text: &str, lines: &[Cow<str>]) -> Option<Vec<Range<usize>>> {
+    let start = text.as_ptr() as usize;
+    lines
+        .iter()
+        .map(|line| match line {
+            Cow::Borrowed(line) => {
+                let begin = (line.as_ptr() as usize).checked_sub(start)?;
+                let end = begin + line.len();
+                (end <= text.len()).then_some(begin..end)
+            }
+            Cow::Owned(_) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEXT: &str = "This is a test label with some text";
+
+    #[test]
+    fn cached_lines() {
+        let cache = WrapCache::new(4);
+        let lines = cache.wrap(TEXT, 10);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.wrap(TEXT, 10), lines);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.wrap(TEXT, 20), wrap_text(TEXT, 20));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn ambiguous() {
+        let text = "±·α ±·α";
+        let cache = WrapCache::new(4);
+        assert_eq!(cache.wrap(text, 8), [text]);
+        AmbiguousWidth::Wide.with(|| {
+            // Each policy has its own cache entry
+            assert_eq!(cache.wrap(text, 8), ["±·α", "±·α"]);
+            assert_eq!(cache.len(), 2);
+            assert_eq!(cache.wrap("±·α±·α", 4), ["±·", "α±", "·α"]);
+            // Same as textwrap without ambiguous characters
+            #[cfg(feature = "textwrap")]
+            for width in 1..40 {
+                assert_eq!(wrap_text(TEXT, width), textwrap::wrap(TEXT, width));
+            }
+            assert_eq!(wrap_text("", 5), [""]);
+            assert_eq!(wrap_text("a\n\nb", 5), ["a", "", "b"]);
+        });
+        assert_eq!(cache.wrap(text, 8), [text]);
+    }
+
+    #[test]
+    fn variation_selectors() {
+        let text = "⚠️ ❤️ ☀️";
+        let cache = WrapCache::new(4);
+        assert_eq!(cache.wrap(text, 5), ["⚠️ ❤️", "☀️"]);
+        EmojiWidth::Narrow.with(|| {
+            assert_eq!(cache.wrap(text, 5), [text]);
+            assert_eq!(cache.len(), 2);
+        });
+    }
+
+    #[test]
+    fn hash_collision() {
+        let cache = WrapCache::new(2);
+        let other = "Some other text";
+        cache.wrap(other, 10);
+        // Entry for other text stored under the key of TEXT
+        let key = Key::new(TEXT, 10);
+        let entry = cache
+            .inner
+            .borrow_mut()
+            .entries
+            .remove(&Key::new(other, 10));
+        cache.inner.borrow_mut().entries.insert(key, entry.unwrap());
+        assert_eq!(cache.wrap(TEXT, 10), wrap_text(TEXT, 10));
+        assert_eq!(cache.len(), 1);
+        // Entry replaced with the right text
+        assert_eq!(&*cache.inner.borrow().entries[&key].text, TEXT);
+        assert_eq!(cache.wrap(TEXT, 10), wrap_text(TEXT, 10));
+    }
+
+    #[test]
+    fn disabled() {
+        let cache = WrapCache::new(0);
+        assert_eq!(cache.wrap(TEXT, 10), wrap_text(TEXT, 10));
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn evict_lru() {
+        let cache = WrapCache::new(2);
+        cache.wrap(TEXT, 10);
+        cache.wrap(TEXT, 20);
+        cache.wrap(TEXT, 10);
+        cache.wrap(TEXT, 30);
+        assert_eq!(cache.len(), 2);
+        let inner = cache.inner.borrow();
+        assert!(inner.entries.contains_key(&Key::new(TEXT, 10)));
+        assert!(!inner.entries.contains_key(&Key::new(TEXT, 20)));
+        assert!(inner.entries.contains_key(&Key::new(TEXT, 30)));
+    }
+
+    #[test]
+    fn greedy() {
+        assert_eq!(
+            wrap_greedy(TEXT, 10),
+            ["This is a", "test label", "with some", "text"]
+        );
+        assert_eq!(wrap_greedy("  indented text", 10), ["  indented", "text"]);
+        assert_eq!(wrap_greedy("abcdefgh ij", 3), ["abc", "def", "gh", "ij"]);
+        assert_eq!(wrap_greedy("日本語 ab", 4), ["日本", "語", "ab"]);
+        assert_eq!(wrap_greedy("a  b", 4), ["a  b"]);
+        assert_eq!(wrap_greedy("", 4), [""]);
+        assert_eq!(wrap_greedy("   ", 4), [""]);
+        AmbiguousWidth::Wide.with(|| {
+            assert_eq!(wrap_greedy("±·α ±·α", 8), ["±·α", "±·α"]);
+        });
+    }
+
+    #[test]
+    fn anywhere() {
+        assert_eq!(
+            break_anywhere("see http://x.io/abc", 8),
+            ["see http", "://x.io/", "abc"]
+        );
+        assert_eq!(break_anywhere("日本語", 5), ["日本", "語"]);
+        assert_eq!(break_anywhere("ab\n\ncd", 4), ["ab", "", "cd"]);
+        assert_eq!(break_anywhere("", 4), [""]);
+    }
+
+    #[test]
+    fn line_breaks() {
+        let text = "first line\nsecond";
+        let cache = WrapCache::new(4);
+        assert_eq!(cache.wrap(text, 40), ["first line", "second"]);
+        assert_eq!(cache.wrap(text, 40), ["first line", "second"]);
+    }
+}
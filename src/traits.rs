@@ -2,11 +2,15 @@
 //
 // Copyright (c) 2020-2021  Douglas P Lau
 //
-use crate::input::{Action, FocusEvent, ModKeys, MouseEvent};
-use crate::layout::{Cells, Dim, LengthBound, Pos};
-use crate::text::{StyleGroup, Theme, WidgetGroup};
-use crate::widget::{Border, Button, ScrollView};
+use crate::input::{Action, FocusEvent, KeyPress, ModKeys, MouseEvent};
+use crate::layout::{Cells, ContentPos, Dim, GridArea, LengthBound, WidgetPos};
+use crate::session::PersistState;
+use crate::text::{StyleGroup, TextStyle, Theme, WidgetGroup};
+#[cfg(feature = "widgets-extra")]
+use crate::widget::ScrollView;
+use crate::widget::{Border, Button};
 use crate::Result;
+use std::time::Instant;
 
 /// User interface component
 ///
@@ -25,6 +29,14 @@ pub trait Widget {
         StyleGroup::Enabled
     }
 
+    /// Get a style override
+    ///
+    /// An override replaces the colors of the theme style for the widget's
+    /// style group, keeping its appearance.
+    fn style_override(&self) -> Option<TextStyle> {
+        None
+    }
+
     /// Get the width bounds
     fn width_bounds(&self, _theme: &Theme) -> LengthBound {
         LengthBound::default()
@@ -35,15 +47,125 @@ pub trait Widget {
         LengthBound::default()
     }
 
+    /// Get the width bounds within a containing area
+    ///
+    /// * `theme`: Style theme
+    /// * `_area`: Dimensions of the containing area
+    ///
+    /// Widgets with relative [Length]s resolve them against the area; by
+    /// default, this is the same as [width_bounds](Widget::width_bounds).
+    ///
+    /// [Length]: layout/enum.Length.html
+    fn width_bounds_in(&self, theme: &Theme, _area: Dim) -> LengthBound {
+        self.width_bounds(theme)
+    }
+
+    /// Get the height bounds within a containing area
+    ///
+    /// * `theme`: Style theme
+    /// * `width`: Width of the widget
+    /// * `_area`: Dimensions of the containing area
+    ///
+    /// By default, this is the same as
+    /// [height_bounds](Widget::height_bounds).
+    fn height_bounds_in(
+        &self,
+        theme: &Theme,
+        width: u16,
+        _area: Dim,
+    ) -> LengthBound {
+        self.height_bounds(theme, width)
+    }
+
+    /// Take the number of rows removed from the top of the content
+    ///
+    /// Rows removed since the last call are counted.  A wrapping scroll view
+    /// uses this to keep the same content visible.
+    fn take_rows_removed(&self) -> u16 {
+        0
+    }
+
+    /// Check whether a wrapping scroll view should follow the end
+    ///
+    /// When true, a scroll view at the end of the content stays there as
+    /// content is added, until it is scrolled up.
+    fn tail(&self) -> bool {
+        false
+    }
+
+    /// Take a bell requested while handling input
+    ///
+    /// A widget which rejects input can request the bell from its event
+    /// handlers, returning `true` here once.  After each event, the screen
+    /// rings the bell for the cells of the first widget with a request, as
+    /// with [Cells::bell].
+    ///
+    /// [Cells::bell]: layout/struct.Cells.html#method.bell
+    fn take_bell(&self) -> bool {
+        false
+    }
+
     /// Draw the widget
     ///
     /// * `_cells`: Text cells to draw onto
-    /// * `_offset`: Offset from top-left position within widget
-    fn draw(&self, _cells: &mut Cells, _offset: Pos) -> Result<()> {
+    /// * `_offset`: Content position of the widget's top-left cell
+    fn draw(&self, _cells: &mut Cells, _offset: ContentPos) -> Result<()> {
         // default implementation draws nothing
         Ok(())
     }
 
+    /// Check whether the widget is hit-testable
+    ///
+    /// Widgets which are not hit-testable are skipped when routing mouse
+    /// events; they receive no hover or focus changes either.
+    fn hit_testable(&self) -> bool {
+        true
+    }
+
+    /// Check whether the widget can accept keyboard focus
+    ///
+    /// Focusable widgets are visited by Tab / BackTab traversal, and
+    /// receive key events while focused.
+    fn focusable(&self) -> bool {
+        false
+    }
+
+    /// Get the content row of the cursor
+    ///
+    /// A [ScrollView] keeps this row visible after the widget handles a
+    /// key event.
+    fn cursor_row(&self) -> Option<u16> {
+        None
+    }
+
+    /// Get the position of the terminal cursor
+    ///
+    /// After drawing, a text entry widget can place the cursor, relative to
+    /// its cells.  Only the cursor of the focused widget is shown; when it
+    /// returns `None`, the cursor is hidden.
+    fn cursor(&self) -> Option<WidgetPos> {
+        None
+    }
+
+    /// Get a nested grid area
+    ///
+    /// A widget which returns a [GridArea] is replaced by the widgets of that
+    /// area when laid out, within its bounding box.  They are drawn, focused
+    /// and receive events as if they were in the outer grid.
+    ///
+    /// [GridArea]: layout/struct.GridArea.html
+    fn nested_area(&self) -> Option<&GridArea<'_>> {
+        None
+    }
+
+    /// Get the persistent state of the widget
+    ///
+    /// Widgets which implement [PersistState] return themselves, so that
+    /// their state is saved in a session when tagged in a grid area.
+    fn persist(&self) -> Option<&dyn PersistState> {
+        None
+    }
+
     /// Handle a focus event
     ///
     /// * `_fev`: The focus event
@@ -55,6 +177,46 @@ pub trait Widget {
         None
     }
 
+    /// Handle a key event
+    ///
+    /// * `_key`: The key pressed
+    /// * `_mods`: Pressed modifier keys
+    ///
+    /// ## Return
+    ///
+    /// If the event triggers an [Action], it is returned.
+    fn key_event(&self, _key: KeyPress, _mods: ModKeys) -> Option<Action> {
+        // ignore by default
+        None
+    }
+
+    /// Handle a key auto-repeated while held
+    ///
+    /// * `key`: The key held
+    /// * `mods`: Pressed modifier keys
+    ///
+    /// By default, repeats are handled like [key_event](Widget::key_event).
+    ///
+    /// ## Return
+    ///
+    /// If the event triggers an [Action], it is returned.
+    fn key_repeat(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        self.key_event(key, mods)
+    }
+
+    /// Handle a key release
+    ///
+    /// * `_key`: The key released
+    /// * `_mods`: Pressed modifier keys
+    ///
+    /// ## Return
+    ///
+    /// If the event triggers an [Action], it is returned.
+    fn key_release(&self, _key: KeyPress, _mods: ModKeys) -> Option<Action> {
+        // ignore by default
+        None
+    }
+
     /// Handle a mouse event
     ///
     /// * `_mev`: The mouse event
@@ -70,12 +232,24 @@ pub trait Widget {
         _mev: MouseEvent,
         _mods: ModKeys,
         _dim: Dim,
-        _pos: Pos,
+        _pos: WidgetPos,
     ) -> Option<Action> {
         // ignore by default
         None
     }
 
+    /// Check for a repeated action while a mouse button is held
+    ///
+    /// * `_now`: Current time
+    ///
+    /// This is polled for the pressed widget on each tick of
+    /// [Screen::step_timeout].
+    ///
+    /// [Screen::step_timeout]: struct.Screen.html#method.step_timeout
+    fn held_action(&self, _now: Instant) -> Option<Action> {
+        None
+    }
+
     /// Wrap the widget with a border
     fn into_border(self) -> Border<Self>
     where
@@ -93,6 +267,7 @@ pub trait Widget {
     }
 
     /// Wrap the widget with a scroll view
+    #[cfg(feature = "widgets-extra")]
     fn into_scroll_view(self) -> ScrollView<Self>
     where
         Self: Sized,
@@ -0,0 +1,150 @@
+// transition.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::WidgetId;
+use crate::text::TextStyle;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Fade of one style
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Fade {
+    /// Style displayed when the fade started
+    from: TextStyle,
+    /// Time the fade started
+    start: Instant,
+}
+
+/// Style set while drawing a widget
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Slot {
+    /// Target style
+    target: TextStyle,
+    /// Displayed style
+    shown: TextStyle,
+    /// Fade in progress
+    fade: Option<Fade>,
+}
+
+/// Style transitions of all widgets
+///
+/// Styles set while drawing a widget are matched with those from the
+/// previous frame by the order they were set.  When a target style changes,
+/// its colors fade from the style previously displayed.
+#[derive(Default)]
+pub(crate) struct Transitions {
+    /// Style slots of each widget
+    widgets: HashMap<WidgetId, Vec<Slot>>,
+    /// Widgets drawn in the current frame
+    drawn: Vec<WidgetId>,
+    /// Widget being drawn, with number of styles set
+    current: Option<(WidgetId, usize)>,
+}
+
+impl Fade {
+    /// Get the displayed style at a point in time
+    fn style(
+        &self,
+        to: TextStyle,
+        duration: Duration,
+        now: Instant,
+    ) -> Option<TextStyle> {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= duration {
+            return None;
+        }
+        let t = elapsed.as_secs_f32() / duration.as_secs_f32();
+        Some(self.from.blend(to, t))
+    }
+}
+
+/// Check if a style change can fade
+fn can_fade(from: TextStyle, to: TextStyle) -> bool {
+    let fg = from.foreground() != to.foreground()
+        && from.foreground().is_rgb()
+        && to.foreground().is_rgb();
+    let bg = from.background() != to.background()
+        && from.background().is_rgb()
+        && to.background().is_rgb();
+    fg || bg
+}
+
+impl Transitions {
+    /// Begin drawing a widget
+    pub(crate) fn begin_widget(&mut self, id: WidgetId) {
+        self.drawn.push(id);
+        self.current = Some((id, 0));
+    }
+
+    /// End drawing a widget
+    pub(crate) fn end_widget(&mut self) {
+        if let Some((id, count)) = self.current.take() {
+            if let Some(slots) = self.widgets.get_mut(&id) {
+                slots.truncate(count);
+            }
+        }
+    }
+
+    /// End a frame, dropping widgets which were not drawn
+    pub(crate) fn end_frame(&mut self) {
+        let drawn = std::mem::take(&mut self.drawn);
+        self.widgets.retain(|id, _| drawn.contains(id));
+        self.current = None;
+    }
+
+    /// Get the displayed style for a target style
+    ///
+    /// * `target`: Style set by the widget
+    /// * `duration`: Duration of transitions
+    /// * `now`: Frame time
+    pub(crate) fn style(
+        &mut self,
+        target: TextStyle,
+        duration: Duration,
+        now: Instant,
+    ) -> TextStyle {
+        let (id, i) = match &mut self.current {
+            Some((id, i)) => {
+                *i += 1;
+                (*id, *i - 1)
+            }
+            None => return target,
+        };
+        let slots = self.widgets.entry(id).or_default();
+        let fade = match slots.get(i) {
+            Some(slot) if slot.target == target => slot.fade,
+            Some(slot) if can_fade(slot.shown, target) => Some(Fade {
+                from: slot.shown,
+                start: now,
+            }),
+            _ => None,
+        };
+        let shown = fade.and_then(|f| f.style(target, duration, now));
+        let slot = Slot {
+            target,
+            shown: shown.unwrap_or(target),
+            fade: shown.and(fade),
+        };
+        if i < slots.len() {
+            slots[i] = slot;
+        } else {
+            slots.push(slot);
+        }
+        slot.shown
+    }
+
+    /// Check if any fade is in progress
+    pub(crate) fn is_running(&self) -> bool {
+        self.widgets
+            .values()
+            .flatten()
+            .any(|slot| slot.fade.is_some())
+    }
+
+    /// Check if no widgets have transition state
+    #[cfg(test)]
+    pub(crate) fn widgets_is_empty(&self) -> bool {
+        self.widgets.is_empty()
+    }
+}
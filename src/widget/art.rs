@@ -0,0 +1,488 @@
+// art.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::layout::{Cells, ContentPos, LengthBound};
+use crate::text::{
+    display_width, Appearance, Color, Intensity, TextStyle, Theme, Weight,
+};
+use crate::{Result, Widget};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Columns between tab stops in art
+const ART_TAB_WIDTH: usize = 8;
+
+/// Style of one art cell
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ArtStyle {
+    /// Foreground color (`None` for default)
+    foreground: Option<Color>,
+    /// Background color (`None` for default)
+    background: Option<Color>,
+    /// Bold weight
+    bold: bool,
+}
+
+/// One cell of art
+#[derive(Clone, Debug, PartialEq)]
+struct ArtCell {
+    /// Grapheme cluster
+    text: String,
+    /// Width in columns (1 or 2)
+    width: u16,
+    /// Cell style
+    style: ArtStyle,
+}
+
+/// Fixed-size text art widget
+///
+/// Art is loaded from plain text, or text containing ANSI escape sequences.
+/// It is always laid out at its exact size, and can be scrolled when
+/// wrapped in a [ScrollView](struct.ScrollView.html).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Art {
+    /// Cells of each row
+    rows: Vec<Vec<ArtCell>>,
+    /// Width in columns
+    width: u16,
+}
+
+/// Get an ANSI color by index (0 to 15)
+fn ansi_color(idx: u8) -> Color {
+    let intensity = if idx < 8 {
+        Intensity::Normal
+    } else {
+        Intensity::Bright
+    };
+    match idx % 8 {
+        0 => Color::Black(intensity),
+        1 => Color::Red(intensity),
+        2 => Color::Green(intensity),
+        3 => Color::Yellow(intensity),
+        4 => Color::Blue(intensity),
+        5 => Color::Magenta(intensity),
+        6 => Color::Cyan(intensity),
+        _ => Color::White(intensity),
+    }
+}
+
+/// Get a 256-color palette color
+fn palette_color(idx: u8) -> Color {
+    match idx {
+        0..=15 => ansi_color(idx),
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = idx - 16;
+            Color::Rgb(level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        _ => {
+            let v = 8 + (idx - 232) * 10;
+            Color::Rgb(v, v, v)
+        }
+    }
+}
+
+/// Parse an extended (256-color or RGB) color
+///
+/// * `params`: Parameters following `38` or `48`
+///
+/// ## Return
+///
+/// Color (if valid) and number of parameters used
+fn extended_color(params: &[Option<u16>]) -> (Option<Color>, usize) {
+    let byte = |i: usize| {
+        params
+            .get(i)
+            .copied()
+            .flatten()
+            .and_then(|v| u8::try_from(v).ok())
+    };
+    match params.first().copied().flatten() {
+        Some(5) => (byte(1).map(palette_color), 2),
+        Some(2) => match (byte(1), byte(2), byte(3)) {
+            (Some(r), Some(g), Some(b)) => (Some(Color::Rgb(r, g, b)), 4),
+            _ => (None, params.len()),
+        },
+        _ => (None, params.len()),
+    }
+}
+
+impl ArtStyle {
+    /// Apply SGR (select graphic rendition) parameters
+    fn apply_sgr(&mut self, params: &[Option<u16>]) {
+        if params.is_empty() {
+            *self = ArtStyle::default();
+            return;
+        }
+        let mut i = 0;
+        while i < params.len() {
+            let p = params[i].unwrap_or(0);
+            i += 1;
+            match p {
+                0 => *self = ArtStyle::default(),
+                1 => self.bold = true,
+                22 => self.bold = false,
+                30..=37 => self.foreground = Some(ansi_color((p - 30) as u8)),
+                39 => self.foreground = None,
+                40..=47 => self.background = Some(ansi_color((p - 40) as u8)),
+                49 => self.background = None,
+                90..=97 => {
+                    self.foreground = Some(ansi_color((p - 90 + 8) as u8))
+                }
+                100..=107 => {
+                    self.background = Some(ansi_color((p - 100 + 8) as u8))
+                }
+                38 | 48 => {
+                    let (clr, used) = extended_color(&params[i..]);
+                    i += used;
+                    if let Some(clr) = clr {
+                        if p == 38 {
+                            self.foreground = Some(clr);
+                        } else {
+                            self.background = Some(clr);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Get the text style over a base style
+    fn text_style(self, base: TextStyle) -> TextStyle {
+        let mut st = base;
+        if let Some(clr) = self.foreground {
+            st = st.with_foreground(clr);
+        }
+        if let Some(clr) = self.background {
+            st = st.with_background(clr);
+        }
+        if self.bold {
+            let app = Appearance::default().with_weight(Weight::Bold);
+            st = st.with_appearance(app);
+        }
+        st
+    }
+}
+
+/// Parse SGR parameters
+fn parse_params(params: &str) -> Vec<Option<u16>> {
+    if params.is_empty() {
+        return vec![];
+    }
+    params.split(';').map(|p| p.parse().ok()).collect()
+}
+
+/// Art parser
+#[derive(Default)]
+struct Parser {
+    /// Completed rows
+    rows: Vec<Vec<ArtCell>>,
+    /// Current row
+    row: Vec<ArtCell>,
+    /// Current row width
+    col: usize,
+    /// Current style
+    style: ArtStyle,
+}
+
+impl Parser {
+    /// Push visible text
+    fn push_text(&mut self, text: &str) {
+        for g in text.graphemes(true) {
+            match g {
+                "\n" | "\r\n" => self.end_row(),
+                "\t" => {
+                    let spaces = ART_TAB_WIDTH - self.col % ART_TAB_WIDTH;
+                    for _ in 0..spaces {
+                        self.push_cell(" ", 1);
+                    }
+                }
+                _ => {
+                    let width = display_width(g);
+                    if width > 0 {
+                        self.push_cell(g, width.min(2));
+                    } else if let Some(cell) = self.row.last_mut() {
+                        // Combining characters join the previous cell
+                        if !g.chars().any(char::is_control) {
+                            cell.text.push_str(g);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Push one cell
+    fn push_cell(&mut self, g: &str, width: usize) {
+        self.row.push(ArtCell {
+            text: g.to_string(),
+            width: width as u16,
+            style: self.style,
+        });
+        self.col += width;
+    }
+
+    /// End the current row
+    fn end_row(&mut self) {
+        self.rows.push(std::mem::take(&mut self.row));
+        self.col = 0;
+    }
+
+    /// Finish parsing
+    fn finish(mut self) -> Art {
+        if !self.row.is_empty() {
+            self.end_row();
+        }
+        let width = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|c| c.width).sum::<u16>())
+            .max()
+            .unwrap_or(0);
+        Art {
+            rows: self.rows,
+            width,
+        }
+    }
+}
+
+impl Art {
+    /// Create art from plain text
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(text: &str) -> Self {
+        let mut parser = Parser::default();
+        parser.push_text(text);
+        parser.finish()
+    }
+
+    /// Create art from text with ANSI escape sequences
+    ///
+    /// SGR sequences are parsed for colors (30-37, 40-47, 90-97, 100-107,
+    /// 38/48;5 and 38/48;2), bold and reset.  Other escape sequences are
+    /// ignored, and malformed sequences print their visible characters.
+    pub fn from_ansi(bytes: &[u8]) -> Self {
+        let text = String::from_utf8_lossy(bytes);
+        let mut parser = Parser::default();
+        let mut rest = &text[..];
+        while let Some(esc) = rest.find('\x1b') {
+            parser.push_text(&rest[..esc]);
+            rest = &rest[esc + 1..];
+            let seq = match rest.strip_prefix('[') {
+                Some(seq) => seq,
+                // Not a CSI sequence: drop the escape
+                None => continue,
+            };
+            let end = seq.find(|c: char| !matches!(c, '\x20'..='\x3F'));
+            match end.map(|e| (e, seq[e..].chars().next())) {
+                Some((e, Some(fin))) if ('\x40'..='\x7E').contains(&fin) => {
+                    if fin == 'm' {
+                        parser.style.apply_sgr(&parse_params(&seq[..e]));
+                    }
+                    rest = &seq[e + fin.len_utf8()..];
+                }
+                Some((e, _)) => {
+                    // Malformed: print the sequence characters
+                    parser.push_text(&rest[..=e]);
+                    rest = &seq[e..];
+                }
+                None => {
+                    // Unterminated at end of input
+                    parser.push_text(rest);
+                    rest = "";
+                }
+            }
+        }
+        parser.push_text(rest);
+        parser.finish()
+    }
+
+    /// Get the width in columns
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Get the height in rows
+    pub fn height(&self) -> u16 {
+        self.rows.len() as u16
+    }
+}
+
+impl Widget for Art {
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        LengthBound::new(self.width..=self.width)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        let rows = self.height();
+        LengthBound::new(rows..=rows)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        let base = cells.theme().style(self.style_group());
+        let width = cells.width();
+        let height = usize::from(cells.height());
+        let rows = self.rows.iter().skip(usize::from(offset.row));
+        for (row, art_row) in rows.take(height).enumerate() {
+            let mut style = None;
+            let mut col = 0;
+            for cell in art_row {
+                let start = col;
+                col += cell.width;
+                // Skip cells which are not entirely visible
+                if start < offset.col {
+                    continue;
+                }
+                let x = start - offset.col;
+                if x + cell.width > width {
+                    break;
+                }
+                if style != Some(cell.style) {
+                    cells.set_style(cell.style.text_style(base))?;
+                    style = Some(cell.style);
+                }
+                cells.move_to(x, row as u16)?;
+                cells.print_str(&cell.text)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Get the text of each row
+    fn rows(art: &Art) -> Vec<String> {
+        art.rows
+            .iter()
+            .map(|row| row.iter().map(|c| c.text.as_str()).collect())
+            .collect()
+    }
+
+    /// Get the style of one cell
+    fn style(art: &Art, row: usize, col: usize) -> ArtStyle {
+        art.rows[row][col].style
+    }
+
+    #[test]
+    fn plain() {
+        let art = Art::from_str(" /\\_/\\\n( o.o )\r\n > ^ <");
+        assert_eq!(rows(&art), [" /\\_/\\", "( o.o )", " > ^ <"]);
+        assert_eq!((art.width(), art.height()), (7, 3));
+        let theme = Theme::default();
+        assert_eq!(art.width_bounds(&theme), LengthBound::new(7..=7));
+        assert_eq!(art.height_bounds(&theme, 7), LengthBound::new(3..=3));
+        let art = Art::from_str("a\tb");
+        assert_eq!(art.width(), 9);
+    }
+
+    #[test]
+    fn wide() {
+        let art = Art::from_ansi("\x1b[31m日本\x1b[0m語\ne\u{301}".as_bytes());
+        assert_eq!(rows(&art), ["日本語", "e\u{301}"]);
+        assert_eq!(art.width(), 6);
+        assert_eq!(art.rows[0][0].width, 2);
+        let red = Some(Color::Red(Intensity::Normal));
+        assert_eq!(style(&art, 0, 1).foreground, red);
+        assert_eq!(style(&art, 0, 2).foreground, None);
+    }
+
+    #[test]
+    fn sgr_basic() {
+        let art = Art::from_ansi(b"\x1b[1;32;44mA\x1b[22mB\x1b[39;49mC\x1b[mD");
+        assert_eq!(rows(&art), ["ABCD"]);
+        let green = Some(Color::Green(Intensity::Normal));
+        let blue = Some(Color::Blue(Intensity::Normal));
+        assert_eq!(
+            style(&art, 0, 0),
+            ArtStyle {
+                foreground: green,
+                background: blue,
+                bold: true
+            }
+        );
+        assert!(!style(&art, 0, 1).bold);
+        assert_eq!(style(&art, 0, 1).foreground, green);
+        assert_eq!(style(&art, 0, 2), ArtStyle::default());
+        let art = Art::from_ansi(b"\x1b[1;97;100mX\x1b[0mY");
+        assert_eq!(
+            style(&art, 0, 0),
+            ArtStyle {
+                foreground: Some(Color::White(Intensity::Bright)),
+                background: Some(Color::Black(Intensity::Bright)),
+                bold: true
+            }
+        );
+        assert_eq!(style(&art, 0, 1), ArtStyle::default());
+    }
+
+    #[test]
+    fn sgr_extended() {
+        let art = Art::from_ansi(
+            b"\x1b[38;5;9ma\x1b[38;5;16mb\x1b[48;5;231mc\x1b[38;5;244md\
+              \x1b[38;2;1;2;3;48;2;250;128;0me",
+        );
+        assert_eq!(rows(&art), ["abcde"]);
+        assert_eq!(
+            style(&art, 0, 0).foreground,
+            Some(Color::Red(Intensity::Bright))
+        );
+        assert_eq!(style(&art, 0, 1).foreground, Some(Color::Rgb(0, 0, 0)));
+        assert_eq!(
+            style(&art, 0, 2).background,
+            Some(Color::Rgb(255, 255, 255))
+        );
+        assert_eq!(
+            style(&art, 0, 3).foreground,
+            Some(Color::Rgb(128, 128, 128))
+        );
+        assert_eq!(style(&art, 0, 4).foreground, Some(Color::Rgb(1, 2, 3)));
+        assert_eq!(style(&art, 0, 4).background, Some(Color::Rgb(250, 128, 0)));
+    }
+
+    #[test]
+    fn malformed() {
+        // Other CSI sequences are ignored
+        let art = Art::from_ansi(b"a\x1b[2Jb\x1b[1;1Hc");
+        assert_eq!(rows(&art), ["abc"]);
+        // Escape without CSI is dropped
+        let art = Art::from_ansi(b"a\x1bb");
+        assert_eq!(rows(&art), ["ab"]);
+        // Invalid character within sequence
+        let art = Art::from_ansi("x\x1b[31\ny".as_bytes());
+        assert_eq!(rows(&art), ["x[31", "y"]);
+        assert_eq!(style(&art, 1, 0), ArtStyle::default());
+        // Unterminated at end of input
+        let art = Art::from_ansi(b"ok\x1b[38;5");
+        assert_eq!(rows(&art), ["ok[38;5"]);
+        let art = Art::from_ansi(b"end\x1b");
+        assert_eq!(rows(&art), ["end"]);
+        // Bad extended colors are skipped
+        let art = Art::from_ansi(b"\x1b[38;7;1mz\x1b[38;2;1mw");
+        assert_eq!(style(&art, 0, 0), ArtStyle::default());
+        assert_eq!(style(&art, 0, 1), ArtStyle::default());
+    }
+
+    #[test]
+    fn draw_offset() -> Result<()> {
+        use crate::layout::{BBox, Dim};
+        use crate::Screen;
+        let art = Art::from_ansi("abcd\n日\x1b[31mxyz\n1234".as_bytes());
+        let mut screen = Screen::headless(Dim::new(3, 2), Theme::default());
+        let mut cells = Cells::new(&mut screen, BBox::new(0, 0, 3, 2));
+        art.draw(&mut cells, ContentPos::new(1, 1))?;
+        drop(cells);
+        let snapshot = screen.snapshot();
+        // Wide character straddling the left edge is skipped
+        assert_eq!(snapshot.to_plain(), " xy\n234\n");
+        let row = snapshot.rows().next().unwrap();
+        let runs = crate::snapshot::Snapshot::runs(row);
+        assert_eq!(runs[1].0.foreground(), Color::Red(Intensity::Normal));
+        Ok(())
+    }
+}
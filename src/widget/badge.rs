@@ -0,0 +1,204 @@
+// badge.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{Action, FocusEvent, KeyPress, ModKeys, MouseEvent};
+use crate::layout::{Cells, ContentPos, Dim, LengthBound, WidgetPos};
+use crate::text::{StyleGroup, TextStyle, Theme, WidgetGroup};
+use crate::{Result, Widget};
+use std::cell::Cell;
+
+/// Largest count displayed without truncation
+const MAX_COUNT: u32 = 99;
+
+/// Badge widget wrapper
+///
+/// Draws a count in the top-right corner of the wrapped widget, using the
+/// `Interacted` style.  Counts above 99 are shown as `99+`, and a count of
+/// zero hides the badge.
+///
+/// ```rust
+/// use semtext::widget::{Badge, Label};
+/// use semtext::Widget;
+///
+/// let inbox = Badge::new(Label::new("Inbox").into_button()).with_count(3);
+/// inbox.set_count(4);
+/// ```
+pub struct Badge<W: Widget> {
+    /// Wrapped widget
+    wrapped: W,
+    /// Badge count
+    count: Cell<u32>,
+}
+
+/// Get the text of a badge
+fn badge_text(count: u32) -> Option<String> {
+    match count {
+        0 => None,
+        1..=MAX_COUNT => Some(format!(" {} ", count)),
+        _ => Some(format!("{}+", MAX_COUNT)),
+    }
+}
+
+/// Get the column of a badge within a width
+fn badge_col(text: &str, width: u16) -> Option<u16> {
+    width.checked_sub(text.len() as u16)
+}
+
+impl<W: Widget> Badge<W> {
+    /// Create a new badge widget
+    pub fn new(wrapped: W) -> Self {
+        let count = Cell::new(0);
+        Self { wrapped, count }
+    }
+
+    /// Set the initial count
+    pub fn with_count(self, count: u32) -> Self {
+        self.count.set(count);
+        self
+    }
+
+    /// Get the count
+    pub fn count(&self) -> u32 {
+        self.count.get()
+    }
+
+    /// Set the count
+    ///
+    /// A count of zero hides the badge.
+    pub fn set_count(&self, count: u32) {
+        self.count.set(count);
+    }
+}
+
+impl<W: Widget> Widget for Badge<W> {
+    /// Get the widget group
+    fn widget_group(&self) -> WidgetGroup {
+        self.wrapped.widget_group()
+    }
+
+    /// Get the style group
+    fn style_group(&self) -> StyleGroup {
+        self.wrapped.style_group()
+    }
+
+    /// Get the style override of the wrapped widget
+    fn style_override(&self) -> Option<TextStyle> {
+        self.wrapped.style_override()
+    }
+
+    /// Get the width bounds
+    fn width_bounds(&self, theme: &Theme) -> LengthBound {
+        self.wrapped.width_bounds(theme)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, theme: &Theme, width: u16) -> LengthBound {
+        self.wrapped.height_bounds(theme, width)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        self.wrapped.draw(cells, offset)?;
+        if cells.height() == 0 {
+            return Ok(());
+        }
+        if let Some(text) = badge_text(self.count.get()) {
+            if let Some(col) = badge_col(&text, cells.width()) {
+                let style = cells.theme().style(StyleGroup::Interacted);
+                cells.set_style(style)?;
+                cells.move_to(col, 0)?;
+                cells.print_str(&text)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether the widget is hit-testable
+    fn hit_testable(&self) -> bool {
+        self.wrapped.hit_testable()
+    }
+
+    /// Check whether the wrapped widget can accept focus
+    fn focusable(&self) -> bool {
+        self.wrapped.focusable()
+    }
+
+    /// Take a bell requested by the wrapped widget
+    fn take_bell(&self) -> bool {
+        self.wrapped.take_bell()
+    }
+
+    /// Handle focus event
+    fn focus(&self, fev: FocusEvent) -> Option<Action> {
+        self.wrapped.focus(fev)
+    }
+
+    /// Handle key events
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        self.wrapped.key_event(key, mods)
+    }
+
+    /// Handle mouse events
+    fn mouse_event(
+        &self,
+        mev: MouseEvent,
+        mods: ModKeys,
+        dim: Dim,
+        pos: WidgetPos,
+    ) -> Option<Action> {
+        self.wrapped.mouse_event(mev, mods, dim, pos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::input::MouseButton;
+    use crate::widget::{Button, Label};
+
+    #[test]
+    fn text() {
+        assert_eq!(badge_text(0), None);
+        assert_eq!(badge_text(7), Some(" 7 ".to_string()));
+        assert_eq!(badge_text(42), Some(" 42 ".to_string()));
+        assert_eq!(badge_text(99), Some(" 99 ".to_string()));
+        assert_eq!(badge_text(120), Some("99+".to_string()));
+    }
+
+    #[test]
+    fn col() {
+        assert_eq!(badge_col(" 7 ", 10), Some(7));
+        assert_eq!(badge_col(" 42 ", 10), Some(6));
+        assert_eq!(badge_col("99+", 10), Some(7));
+        assert_eq!(badge_col(" 42 ", 4), Some(0));
+        assert_eq!(badge_col(" 42 ", 3), None);
+    }
+
+    #[test]
+    fn bounds() {
+        let theme = Theme::default();
+        let label = Label::new("Inbox");
+        let badge = Badge::new(Label::new("Inbox")).with_count(42);
+        assert_eq!(badge.width_bounds(&theme), label.width_bounds(&theme));
+        assert_eq!(
+            badge.height_bounds(&theme, 3),
+            label.height_bounds(&theme, 3)
+        );
+    }
+
+    #[test]
+    fn pass_through() {
+        let badge = Badge::new(Button::new(Label::new("Inbox"))).with_count(7);
+        let action = badge.mouse_event(
+            MouseEvent::ButtonDown(MouseButton::Left),
+            ModKeys::EMPTY,
+            Dim::new(5, 1),
+            WidgetPos::new(4, 0),
+        );
+        assert_eq!(action, Some(Action::Redraw()));
+        assert_eq!(badge.style_group(), StyleGroup::Interacted);
+        badge.set_count(0);
+        assert_eq!(badge.count(), 0);
+    }
+}
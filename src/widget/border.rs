@@ -2,10 +2,16 @@
 //
 // Copyright (c) 2020-2021  Douglas P Lau
 //
-use crate::input::{Action, FocusEvent, ModKeys, MouseEvent};
-use crate::layout::{BBox, Cells, Dim, LengthBound, Pos};
-use crate::text::{Outline, StyleGroup, Theme};
-use crate::{Result, Widget};
+use crate::input::{Action, FocusEvent, KeyPress, ModKeys, MouseEvent};
+use crate::layout::{
+    BBox, Cells, ContentPos, Dim, HAlign, LengthBound, WidgetPos,
+};
+use crate::text::{
+    display_width, truncate_end, Color, Outline, StyleGroup, TextStyle, Theme,
+};
+use crate::{PersistState, Result, Widget};
+use std::cell::Cell;
+use std::time::Instant;
 
 /// Border elevation
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -35,12 +41,17 @@ enum Edge {
 
 /// Border style
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum BorderStyle {
     /// Simple border
     Simple(Outline),
     /// Beveled appearance
     Bevel(Outline),
     /// Drop shadow
+    ///
+    /// A half-tone shadow (`▒`) is drawn one cell right of and below the
+    /// wrapped widget, which has no edges.  With an [Outline::Empty],
+    /// the shadow is drawn with spaces, relying on its background color.
     Shadow(Outline),
     /// Custom outline
     ///
@@ -55,12 +66,26 @@ pub enum BorderStyle {
 
 /// Border widget wrapper
 ///
-/// One or more outline edges are drawn around a wrapped widget.
+/// One or more outline edges are drawn around a wrapped widget.  Where two
+/// edges meet, the top-left and bottom-right corners are drawn in the style
+/// of the top and bottom edges; the others in the style of the left and
+/// right edges.
+///
+/// The border style is resolved when bounds are calculated, and that style
+/// is used for drawing until the next layout.
 pub struct Border<W: Widget> {
     /// Wrapped widget
     wrapped: W,
     /// Border style
     border_style: Option<BorderStyle>,
+    /// Border style resolved during layout
+    layout_style: Cell<Option<BorderStyle>>,
+    /// Title text on the top edge
+    title: Option<String>,
+    /// Title alignment
+    title_align: HAlign,
+    /// Edge colors: `Left`, `Top`, `Right`, `Bottom`
+    edge_colors: [Option<Color>; 4],
 }
 
 impl Elevation {
@@ -148,9 +173,14 @@ impl<W: Widget> Border<W> {
     /// Create a new border
     pub fn new(wrapped: W) -> Self {
         let border_style = None;
+        let layout_style = Cell::new(None);
         Self {
             wrapped,
             border_style,
+            layout_style,
+            title: None,
+            title_align: HAlign::Left,
+            edge_colors: [None; 4],
         }
     }
 
@@ -170,45 +200,174 @@ impl<W: Widget> Border<W> {
         self
     }
 
+    /// Set a title on the top edge
+    ///
+    /// The title is drawn in the wrapped widget's style, and truncated with
+    /// an ellipsis if it does not fit.  Border styles without a top edge
+    /// have no title.
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Set the title alignment (`Left` by default)
+    pub fn with_title_align(mut self, align: HAlign) -> Self {
+        self.title_align = align;
+        self
+    }
+
+    /// Set the edge colors
+    ///
+    /// Each color overrides the foreground of an edge, which is otherwise
+    /// determined by the border style's elevation.
+    pub fn with_edge_colors(
+        mut self,
+        left: Option<Color>,
+        top: Option<Color>,
+        right: Option<Color>,
+        bottom: Option<Color>,
+    ) -> Self {
+        self.edge_colors = [left, top, right, bottom];
+        self
+    }
+
+    /// Get the text style of an edge
+    fn edge_style(
+        &self,
+        theme: &Theme,
+        elevation: Elevation,
+        edge: Edge,
+    ) -> TextStyle {
+        let style = theme.style(elevation.edge_group(edge));
+        let color = match edge {
+            Edge::Left => self.edge_colors[0],
+            Edge::Top => self.edge_colors[1],
+            Edge::Right => self.edge_colors[2],
+            Edge::Bottom => self.edge_colors[3],
+        };
+        match color {
+            Some(clr) => style.with_foreground(clr),
+            None => style,
+        }
+    }
+
+    /// Draw the title on the top edge
+    ///
+    /// * `cells`: Text cells to draw onto
+    /// * `inset`: Bounding box inside the border
+    fn draw_title(&self, cells: &mut Cells, inset: BBox) -> Result<()> {
+        let title = match &self.title {
+            Some(title) if inset.width() > 0 => title,
+            _ => return Ok(()),
+        };
+        let avail = usize::from(inset.width());
+        let text = if avail > 2 {
+            format!(" {} ", truncate_end(title, avail - 2))
+        } else {
+            truncate_end(title, avail)
+        };
+        let width = display_width(&text) as u16; // limited to inset width
+        let col = inset.left() + self.title_align.offset(inset.width() - width);
+        let style = cells.theme().style(self.wrapped.style_group());
+        let style = style.with_override(self.wrapped.style_override());
+        cells.set_style(style)?;
+        cells.move_to(col, 0)?;
+        cells.print_str(&text)
+    }
+
+    /// Draw the wrapped widget inside the border
+    fn draw_wrapped(
+        &self,
+        cells: &mut Cells,
+        inset: BBox,
+        offset: ContentPos,
+    ) -> Result<()> {
+        cells.push_clip(inset);
+        // Set style for wrapped widget draw
+        let style = cells.theme().style(self.wrapped.style_group());
+        let style = style.with_override(self.wrapped.style_override());
+        cells.set_style(style)?;
+        let res = self.wrapped.draw(cells, offset);
+        cells.pop_clip();
+        res
+    }
+
     /// Get the border style
     fn border_style(&self, theme: &Theme) -> BorderStyle {
         self.border_style
             .unwrap_or_else(|| theme.border_style(self.wrapped.widget_group()))
     }
+
+    /// Resolve the border style for a layout pass
+    fn resolve_style(&self, theme: &Theme) -> BorderStyle {
+        let bs = self.border_style(theme);
+        self.layout_style.set(Some(bs));
+        bs
+    }
+
+    /// Get the border style used for layout
+    fn layout_style(&self, theme: &Theme) -> BorderStyle {
+        self.layout_style
+            .get()
+            .unwrap_or_else(|| self.resolve_style(theme))
+    }
+}
+
+/// Draw a drop shadow to the right of and below an inset
+fn draw_shadow(cells: &mut Cells, outline: Outline, inset: BBox) -> Result<()> {
+    let shade = match outline {
+        Outline::Empty => ' ',
+        _ => '▒',
+    };
+    cells.set_style(cells.theme().style(StyleGroup::DarkShadow))?;
+    for row in 1..=inset.height() {
+        cells.move_to(inset.width(), row)?;
+        cells.print_char(shade)?;
+    }
+    cells.move_to(1, inset.height())?;
+    for _ in 1..inset.width() {
+        cells.print_char(shade)?;
+    }
+    Ok(())
 }
 
 impl<W: Widget> Widget for Border<W> {
     /// Get the width bounds
     fn width_bounds(&self, theme: &Theme) -> LengthBound {
-        let bs = self.border_style(theme);
+        // Width bounds are calculated first in each layout pass
+        let bs = self.resolve_style(theme);
         let cols = bs.width();
         LengthBound::new(cols..=cols) + self.wrapped.width_bounds(theme)
     }
 
     /// Get the height bounds
     fn height_bounds(&self, theme: &Theme, width: u16) -> LengthBound {
-        let bs = self.border_style(theme);
+        let bs = self.layout_style(theme);
         let rows = bs.height();
         LengthBound::new(rows..=rows) + self.wrapped.height_bounds(theme, width)
     }
 
     /// Draw the widget
-    fn draw(&self, cells: &mut Cells, offset: Pos) -> Result<()> {
-        assert_eq!(offset, Pos::default(), "FIXME");
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        assert_eq!(offset, ContentPos::default(), "FIXME");
         let width = cells.width();
         let height = cells.height();
         if width == 0 || height == 0 {
             return Ok(());
         }
         let theme = cells.theme();
-        let bs = self.border_style(theme);
+        let bs = self.layout_style(theme);
         let group = self.wrapped.style_group();
         let elevation = Elevation::from_style_group(bs, group);
-        let style_top = theme.style(elevation.edge_group(Edge::Top));
-        let style_left = theme.style(elevation.edge_group(Edge::Left));
-        let style_right = theme.style(elevation.edge_group(Edge::Right));
-        let style_bottom = theme.style(elevation.edge_group(Edge::Bottom));
+        let style_top = self.edge_style(theme, elevation, Edge::Top);
+        let style_left = self.edge_style(theme, elevation, Edge::Left);
+        let style_right = self.edge_style(theme, elevation, Edge::Right);
+        let style_bottom = self.edge_style(theme, elevation, Edge::Bottom);
         let inset = bs.inset(BBox::new(0, 0, width, height));
+        if let BorderStyle::Shadow(outline) = bs {
+            draw_shadow(cells, outline, inset)?;
+            return self.draw_wrapped(cells, inset, offset);
+        }
         let mut row = 0;
         if let Some(top) = bs.outline(Edge::Top) {
             cells.set_style(style_top)?;
@@ -223,6 +382,7 @@ impl<W: Widget> Widget for Border<W> {
                 cells.set_style(style_right)?;
                 cells.print_char(top.top_right(right))?;
             }
+            self.draw_title(cells, inset)?;
             row += 1;
         }
         for _ in 0..inset.height() {
@@ -262,11 +422,27 @@ impl<W: Widget> Widget for Border<W> {
                 cells.print_char(bottom.bottom_right(right))?;
             }
         }
-        cells.clip(Some(inset));
-        // Set style for wrapped widget draw
-        let style = cells.theme().style(group);
-        cells.set_style(style)?;
-        self.wrapped.draw(cells, offset)
+        self.draw_wrapped(cells, inset, offset)
+    }
+
+    /// Check whether the widget is hit-testable
+    fn hit_testable(&self) -> bool {
+        self.wrapped.hit_testable()
+    }
+
+    /// Get the persistent state of the wrapped widget
+    fn persist(&self) -> Option<&dyn PersistState> {
+        self.wrapped.persist()
+    }
+
+    /// Check whether the wrapped widget can accept focus
+    fn focusable(&self) -> bool {
+        self.wrapped.focusable()
+    }
+
+    /// Take a bell requested by the wrapped widget
+    fn take_bell(&self) -> bool {
+        self.wrapped.take_bell()
     }
 
     /// Handle focus event
@@ -274,14 +450,145 @@ impl<W: Widget> Widget for Border<W> {
         self.wrapped.focus(fev)
     }
 
+    /// Handle key events
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        self.wrapped.key_event(key, mods)
+    }
+
+    /// Handle key repeats
+    fn key_repeat(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        self.wrapped.key_repeat(key, mods)
+    }
+
+    /// Handle key releases
+    fn key_release(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        self.wrapped.key_release(key, mods)
+    }
+
+    /// Check for a repeated action of the wrapped widget
+    fn held_action(&self, now: Instant) -> Option<Action> {
+        self.wrapped.held_action(now)
+    }
+
+    /// Get the position of the terminal cursor, inside the border
+    fn cursor(&self) -> Option<WidgetPos> {
+        let pos = self.wrapped.cursor()?;
+        let inset = match self.layout_style.get() {
+            Some(bs) => bs.inset(BBox::new(0, 0, u16::MAX, u16::MAX)),
+            None => BBox::default(),
+        };
+        let col = pos.col.checked_add(inset.left())?;
+        let row = pos.row.checked_add(inset.top())?;
+        Some(WidgetPos::new(col, row))
+    }
+
     /// Handle mouse events
     fn mouse_event(
         &self,
         mev: MouseEvent,
         mods: ModKeys,
         dim: Dim,
-        pos: Pos,
+        pos: WidgetPos,
     ) -> Option<Action> {
-        self.wrapped.mouse_event(mev, mods, dim, pos)
+        // Style is always resolved by layout before events are routed
+        let inset = match self.layout_style.get() {
+            Some(bs) => bs.inset(BBox::new(0, 0, dim.width, dim.height)),
+            None => BBox::new(0, 0, dim.width, dim.height),
+        };
+        if inset.dim().is_empty() {
+            return None;
+        }
+        // Events on the edges are clamped into the wrapped widget
+        let pos = pos.nested(inset);
+        self.wrapped.mouse_event(mev, mods, inset.dim(), pos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::to_text;
+    use crate::grid_area;
+    use crate::widget::Label;
+
+    #[test]
+    fn title() -> Result<()> {
+        let theme = Theme::default();
+        let a = Label::new("x").into_border().with_title("Settings");
+        let grid = grid_area!([a])?;
+        let text = to_text(&grid, Dim::new(12, 3), &theme)?;
+        assert_eq!(text.lines().next(), Some("┌ Settings ┐"));
+        let text = to_text(&grid, Dim::new(8, 3), &theme)?;
+        assert_eq!(text.lines().next(), Some("┌ Set… ┐"));
+        let text = to_text(&grid, Dim::new(4, 3), &theme)?;
+        assert_eq!(text.lines().next(), Some("┌S…┐"));
+        let b = Label::new("x")
+            .into_border()
+            .with_title("ｗｗ")
+            .with_title_align(HAlign::Center);
+        let grid = grid_area!([b])?;
+        let text = to_text(&grid, Dim::new(11, 3), &theme)?;
+        assert_eq!(text.lines().next(), Some("┌─ ｗｗ ──┐"));
+        // No top edge, no title
+        let c = Label::new("x")
+            .into_border()
+            .with_border_style(Some(BorderStyle::Shadow(Outline::default())))
+            .with_title("Title");
+        let grid = grid_area!([c])?;
+        let text = to_text(&grid, Dim::new(8, 2), &theme)?;
+        assert!(!text.contains("Title"), "{:?}", text);
+        Ok(())
+    }
+
+    #[test]
+    fn shadow() -> Result<()> {
+        let mut theme = Theme::default();
+        theme.normal_border = BorderStyle::Shadow(Outline::default());
+        let a = Label::new("ab\ncd").into_border();
+        let grid = grid_area!([a])?;
+        let text = to_text(&grid, Dim::new(3, 3), &theme)?;
+        assert_eq!(text, "ab \ncd▒\n ▒▒\n");
+        Ok(())
+    }
+
+    #[test]
+    fn edge_colors() -> Result<()> {
+        use crate::TestBackend;
+        let (red, blue) = (Color::Rgb(255, 0, 0), Color::Rgb(0, 0, 255));
+        let a = Label::new("x").into_border().with_edge_colors(
+            Some(red),
+            Some(red),
+            Some(blue),
+            Some(blue),
+        );
+        let grid = grid_area!([a])?;
+        let mut backend = TestBackend::new(Dim::new(3, 3));
+        backend.render(&grid)?;
+        let fg = |col, row| backend.style(col, row).unwrap().foreground();
+        assert_eq!(fg(0, 0), red);
+        assert_eq!(fg(1, 0), red);
+        assert_eq!(fg(2, 0), blue);
+        assert_eq!(fg(0, 1), red);
+        assert_eq!(fg(2, 1), blue);
+        assert_eq!(fg(0, 2), red);
+        assert_eq!(fg(1, 2), blue);
+        assert_eq!(fg(2, 2), blue);
+        Ok(())
+    }
+
+    #[test]
+    fn style_from_layout() {
+        let simple = Theme::default();
+        let mut shadow = Theme::default();
+        shadow.normal_border = BorderStyle::Shadow(Outline::default());
+        let border = Label::new("label").into_border();
+        let bounds = border.width_bounds(&simple);
+        assert_eq!(bounds.minimum(), 7);
+        // Theme changed between layout and draw
+        assert_eq!(border.layout_style(&shadow), simple.normal_border);
+        // Next layout uses the new theme
+        let bounds = border.width_bounds(&shadow);
+        assert_eq!(bounds.minimum(), 6);
+        assert_eq!(border.layout_style(&simple), shadow.normal_border);
     }
 }
@@ -2,11 +2,16 @@
 //
 // Copyright (c) 2020-2021  Douglas P Lau
 //
-use crate::input::{Action, FocusEvent, ModKeys, MouseEvent};
-use crate::layout::{Cells, Dim, LengthBound, Pos};
-use crate::text::{IntoGlyph, StyleGroup, Theme, WidgetGroup};
+use crate::input::{
+    Action, FocusEvent, KeyPress, ModKeys, MouseEvent, NavKey, WidgetId,
+};
+use crate::layout::{
+    BBox, Cells, ContentPos, Dim, HAlign, LengthBound, VAlign, WidgetPos,
+};
+use crate::text::{IntoGlyph, StyleGroup, TextStyle, Theme, WidgetGroup};
 use crate::{Result, Widget};
 use std::cell::Cell;
+use std::time::{Duration, Instant};
 
 /// Button state
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -24,18 +29,77 @@ enum State {
 }
 
 /// Button widget
+///
+/// The wrapped widget is centered within the button by default.
+///
+/// Releasing the mouse button inside the button, or pressing `Enter` or
+/// `Space` while it is focused, produces [Action::Activated].  Releasing
+/// outside cancels the press.  A button with [repeat](Button::with_repeat)
+/// is instead activated when pressed, and again while held.
+///
+/// [Action::Activated]: ../input/enum.Action.html#variant.Activated
 pub struct Button<W: Widget> {
     /// Wrapped widget
     wrapped: W,
     /// Button state
     state: Cell<State>,
+    /// Alignment of wrapped widget
+    align: (HAlign, VAlign),
+    /// Style override
+    style: Option<TextStyle>,
+    /// Auto-repeat delay and interval
+    repeat: Option<(Duration, Duration)>,
+    /// Time of press, with count of repeats
+    held: Cell<Option<(Instant, u32)>>,
 }
 
 impl<W: Widget> Button<W> {
     /// Create a new button widget
     pub fn new(wrapped: W) -> Self {
         let state = Cell::new(State::Enabled);
-        Self { wrapped, state }
+        let align = (HAlign::Center, VAlign::Middle);
+        Self {
+            wrapped,
+            state,
+            align,
+            style: None,
+            repeat: None,
+            held: Cell::new(None),
+        }
+    }
+
+    /// Set the alignment of the wrapped widget
+    pub fn with_content_align(
+        mut self,
+        halign: HAlign,
+        valign: VAlign,
+    ) -> Self {
+        self.align = (halign, valign);
+        self
+    }
+
+    /// Set a style, overriding the theme colors
+    ///
+    /// The appearance of each state still comes from the theme, so a
+    /// focused or pressed button is drawn in reverse video.  A disabled
+    /// button uses the theme style.
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    /// Set auto-repeat while the mouse button is held
+    ///
+    /// * `delay`: Time before the first repeat
+    /// * `interval`: Time between later repeats
+    ///
+    /// Repeats are checked on each tick of [Screen::step_timeout], so its
+    /// timeout should be no longer than `interval`.
+    ///
+    /// [Screen::step_timeout]: ../struct.Screen.html#method.step_timeout
+    pub fn with_repeat(mut self, delay: Duration, interval: Duration) -> Self {
+        self.repeat = Some((delay, interval));
+        self
     }
 
     /// Disable the button
@@ -49,6 +113,35 @@ impl<W: Widget> Button<W> {
             self.state.set(State::Enabled);
         }
     }
+
+    /// Get the bounding box of the wrapped widget
+    ///
+    /// * `theme`: Style theme
+    /// * `width`: Width of button
+    /// * `height`: Height of button
+    ///
+    /// The wrapped widget gets the narrowest width which does not need more
+    /// rows than the full button width.  Since it is drawn within that
+    /// width, wide glyphs are never split at the edge of the button.
+    fn content_bbox(&self, theme: &Theme, width: u16, height: u16) -> BBox {
+        let bounds = self.wrapped.width_bounds(theme);
+        let max = bounds.maximum().min(width);
+        let rows = |w| self.wrapped.height_bounds(theme, w).minimum();
+        let full = rows(max);
+        // Minimum width is ignored when it does not fit
+        let mut w = match bounds.minimum() {
+            min if min <= max => min,
+            _ => 1.min(max),
+        };
+        while w < max && rows(w) > full {
+            w += 1;
+        }
+        let h = full.min(height);
+        let (halign, valign) = self.align;
+        let col = halign.offset(width - w);
+        let row = valign.offset(height - h);
+        BBox::new(col, row, w, h)
+    }
 }
 
 impl<W: Widget> Widget for Button<W> {
@@ -68,6 +161,11 @@ impl<W: Widget> Widget for Button<W> {
         }
     }
 
+    /// Get the style override (unless disabled)
+    fn style_override(&self) -> Option<TextStyle> {
+        self.style.filter(|_| self.state.get() != State::Disabled)
+    }
+
     /// Get the width bounds
     fn width_bounds(&self, theme: &Theme) -> LengthBound {
         self.wrapped.width_bounds(theme)
@@ -79,10 +177,20 @@ impl<W: Widget> Widget for Button<W> {
     }
 
     /// Draw the widget
-    fn draw(&self, cells: &mut Cells, offset: Pos) -> Result<()> {
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
         // FIXME: maybe add a print_text variant that fills...
         cells.fill(&' '.into_glyph()?)?;
-        self.wrapped.draw(cells, offset)
+        let bbox =
+            self.content_bbox(cells.theme(), cells.width(), cells.height());
+        cells.push_clip(bbox);
+        let res = self.wrapped.draw(cells, offset);
+        cells.pop_clip();
+        res
+    }
+
+    /// Check whether the button can accept focus
+    fn focusable(&self) -> bool {
+        self.state.get() != State::Disabled
     }
 
     /// Handle focus event
@@ -95,7 +203,6 @@ impl<W: Widget> Widget for Button<W> {
             (Offer, _) => Some(Focused),
             (Take, _) => Some(Enabled),
             (HoverInside, Enabled) => Some(Hovered),
-            (HoverInside, Pressed) => Some(Focused),
             (HoverOutside, Hovered) => Some(Enabled),
             (HoverOutside, Pressed) => Some(Focused),
             _ => None,
@@ -110,18 +217,60 @@ impl<W: Widget> Widget for Button<W> {
         })
     }
 
+    /// Handle key events
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        match (key, mods, self.state.get()) {
+            (_, _, State::Disabled) => None,
+            (KeyPress::Navigation(NavKey::Enter), ModKeys::EMPTY, _)
+            | (KeyPress::Character(' '), ModKeys::EMPTY, _) => {
+                Some(Action::Activated(WidgetId::of(self)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Check for a repeated action while held
+    fn held_action(&self, now: Instant) -> Option<Action> {
+        let (delay, interval) = self.repeat?;
+        let (start, count) = self.held.get()?;
+        if self.state.get() != State::Pressed {
+            self.held.set(None);
+            return None;
+        }
+        if now.saturating_duration_since(start) >= delay + interval * count {
+            self.held.set(Some((start, count + 1)));
+            Some(Action::Activated(WidgetId::of(self)))
+        } else {
+            None
+        }
+    }
+
     /// Handle mouse events
     fn mouse_event(
         &self,
         mev: MouseEvent,
         _mods: ModKeys,
         _dim: Dim,
-        _pos: Pos,
+        _pos: WidgetPos,
     ) -> Option<Action> {
         let state = self.state.get();
         match (mev, state) {
             (_, State::Disabled) => None,
+            (MouseEvent::ButtonDown(_), _) if self.repeat.is_some() => {
+                self.state.set(State::Pressed);
+                self.held.set(Some((Instant::now(), 0)));
+                return Some(Action::Activated(WidgetId::of(self)));
+            }
+            (MouseEvent::ButtonUp(_), State::Pressed)
+                if self.repeat.is_some() =>
+            {
+                Some(State::Focused)
+            }
             (MouseEvent::ButtonDown(_), _) => Some(State::Pressed),
+            (MouseEvent::ButtonUp(_), State::Pressed) => {
+                self.state.set(State::Focused);
+                return Some(Action::Activated(WidgetId::of(self)));
+            }
             _ => None,
         }
         .and_then(|s| {
@@ -134,3 +283,100 @@ impl<W: Widget> Widget for Button<W> {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::to_text;
+    use crate::grid_area;
+    use crate::input::MouseButton;
+    use crate::widget::Label;
+
+    #[test]
+    fn key_press() {
+        let button = Button::new(Label::new("OK"));
+        let pressed = Some(Action::Activated(WidgetId::of(&button)));
+        let enter = KeyPress::Navigation(NavKey::Enter);
+        let space = KeyPress::Character(' ');
+        assert_eq!(button.key_event(enter, ModKeys::EMPTY), pressed);
+        assert_eq!(button.key_event(space, ModKeys::EMPTY), pressed);
+        assert_eq!(button.key_event(space, ModKeys::SHIFT), None);
+        assert_eq!(
+            button.key_event(KeyPress::Character('x'), ModKeys::EMPTY),
+            None
+        );
+        button.disable();
+        assert_eq!(button.key_event(enter, ModKeys::EMPTY), None);
+    }
+
+    #[test]
+    fn repeat() {
+        let ms = Duration::from_millis;
+        let button = Button::new(Label::new("+")).with_repeat(ms(300), ms(50));
+        let activated = Some(Action::Activated(WidgetId::of(&button)));
+        let dim = Dim::new(1, 1);
+        let pos = WidgetPos::new(0, 0);
+        let down = MouseEvent::ButtonDown(MouseButton::Left);
+        let start = Instant::now();
+        assert_eq!(button.held_action(start), None);
+        assert_eq!(
+            button.mouse_event(down, ModKeys::EMPTY, dim, pos),
+            activated
+        );
+        let (start, _) = button.held.get().unwrap();
+        assert_eq!(button.held_action(start + ms(200)), None);
+        assert_eq!(button.held_action(start + ms(300)), activated);
+        assert_eq!(button.held_action(start + ms(320)), None);
+        assert_eq!(button.held_action(start + ms(350)), activated);
+        // Releasing does not activate again
+        let up = MouseEvent::ButtonUp(MouseButton::Left);
+        assert_eq!(
+            button.mouse_event(up, ModKeys::EMPTY, dim, pos),
+            Some(Action::Redraw())
+        );
+        assert_eq!(button.held_action(start + ms(500)), None);
+    }
+
+    #[test]
+    fn wide_label() -> Result<()> {
+        let button = Button::new(Label::new("🦀 Run"));
+        let grid = grid_area!([button])?;
+        let theme = Theme::default();
+        assert_eq!(to_text(&grid, Dim::new(7, 1), &theme)?, "🦀 Run \n");
+        assert_eq!(to_text(&grid, Dim::new(8, 1), &theme)?, " 🦀 Run \n");
+        assert_eq!(to_text(&grid, Dim::new(9, 1), &theme)?, " 🦀 Run  \n");
+        Ok(())
+    }
+
+    #[test]
+    fn variation_selector() -> Result<()> {
+        let button = Button::new(Label::new("⚠️ Warn"));
+        let grid = grid_area!([button])?;
+        let theme = Theme::default();
+        assert_eq!(to_text(&grid, Dim::new(9, 1), &theme)?, " ⚠️ Warn \n");
+        for emoji in ["❤️", "☀️"] {
+            let button = Button::new(Label::new(emoji));
+            let grid = grid_area!([button])?;
+            let text = to_text(&grid, Dim::new(4, 1), &theme)?;
+            assert_eq!(text, format!(" {} \n", emoji));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn content_align() -> Result<()> {
+        let ok = Button::new(Label::new("OK"))
+            .with_content_align(HAlign::Right, VAlign::Top);
+        let grid = grid_area!([ok])?;
+        let theme = Theme::default();
+        assert_eq!(to_text(&grid, Dim::new(6, 1), &theme)?, "    OK\n");
+        // Wrapped to two rows without splitting wide glyphs
+        let button = Button::new(Label::new("日本 語"));
+        let grid = grid_area!([button])?;
+        assert_eq!(
+            to_text(&grid, Dim::new(6, 3), &theme)?,
+            " 日本 \n 語   \n      \n"
+        );
+        Ok(())
+    }
+}
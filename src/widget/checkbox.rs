@@ -0,0 +1,270 @@
+// checkbox.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{Action, FocusEvent, KeyPress, ModKeys, MouseEvent, NavKey};
+use crate::layout::{BBox, Cells, ContentPos, Dim, LengthBound, WidgetPos};
+use crate::text::{display_width, StyleGroup, Theme};
+use crate::widget::Label;
+use crate::{Result, Widget};
+use std::cell::Cell;
+
+/// Default marker for a checked box
+const CHECKED: &str = "[x] ";
+
+/// Default marker for an unchecked box
+const UNCHECKED: &str = "[ ] ";
+
+/// Check box state
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    /// Check box disabled
+    Disabled,
+    /// Check box enabled
+    Enabled,
+    /// Check box hovered
+    Hovered,
+    /// Check box focused
+    Focused,
+    /// Check box pressed
+    Pressed,
+}
+
+/// Check box widget
+///
+/// A marker is drawn before the label text: `[x]` when checked, or `[ ]`
+/// when not.  Releasing the mouse button inside the check box, or pressing
+/// `Enter` or `Space` while it is focused, toggles it.
+pub struct CheckBox {
+    /// Label text
+    label: Label,
+    /// Checked state
+    checked: Cell<bool>,
+    /// Check box state
+    state: Cell<State>,
+    /// Markers for checked and unchecked boxes
+    marks: (&'static str, &'static str),
+}
+
+impl CheckBox {
+    /// Create a new check box widget
+    pub fn new(text: &str) -> Self {
+        CheckBox {
+            label: Label::new(text),
+            checked: Cell::new(false),
+            state: Cell::new(State::Enabled),
+            marks: (CHECKED, UNCHECKED),
+        }
+    }
+
+    /// Set the checked state
+    pub fn with_checked(self, checked: bool) -> Self {
+        self.checked.set(checked);
+        self
+    }
+
+    /// Set the markers
+    ///
+    /// * `checked`: Marker for a checked box, such as `"☑ "`
+    /// * `unchecked`: Marker for an unchecked box, such as `"☐ "`
+    ///
+    /// The default ASCII markers display on any terminal.
+    pub fn with_marks(
+        mut self,
+        checked: &'static str,
+        unchecked: &'static str,
+    ) -> Self {
+        self.marks = (checked, unchecked);
+        self
+    }
+
+    /// Check whether the box is checked
+    pub fn checked(&self) -> bool {
+        self.checked.get()
+    }
+
+    /// Set the checked state
+    pub fn set_checked(&self, checked: bool) {
+        self.checked.set(checked);
+    }
+
+    /// Disable the check box
+    pub fn disable(&self) {
+        self.state.set(State::Disabled);
+    }
+
+    /// Enable the check box
+    pub fn enable(&self) {
+        if self.state.get() == State::Disabled {
+            self.state.set(State::Enabled);
+        }
+    }
+
+    /// Get the width of the markers
+    fn mark_width(&self) -> u16 {
+        let (checked, unchecked) = self.marks;
+        display_width(checked).max(display_width(unchecked)) as u16
+    }
+
+    /// Toggle the checked state
+    fn toggle(&self) -> Option<Action> {
+        self.checked.set(!self.checked.get());
+        Some(Action::Redraw())
+    }
+}
+
+impl Widget for CheckBox {
+    /// Get the style group
+    fn style_group(&self) -> StyleGroup {
+        match self.state.get() {
+            State::Disabled => StyleGroup::Disabled,
+            State::Enabled => StyleGroup::Enabled,
+            State::Focused => StyleGroup::Focused,
+            State::Hovered => StyleGroup::Hovered,
+            State::Pressed => StyleGroup::Interacted,
+        }
+    }
+
+    /// Get the width bounds
+    fn width_bounds(&self, theme: &Theme) -> LengthBound {
+        let mw = self.mark_width();
+        LengthBound::new(mw..=mw) + self.label.width_bounds(theme)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, theme: &Theme, width: u16) -> LengthBound {
+        let width = width.saturating_sub(self.mark_width());
+        self.label.height_bounds(theme, width)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        let mark = if self.checked.get() {
+            self.marks.0
+        } else {
+            self.marks.1
+        };
+        cells.move_to(0, 0)?;
+        cells.print_str(mark)?;
+        let mw = self.mark_width().min(cells.width());
+        let bbox = BBox::new(mw, 0, cells.width() - mw, cells.height());
+        cells.push_clip(bbox);
+        let res = self.label.draw(cells, offset);
+        cells.pop_clip();
+        res
+    }
+
+    /// Check whether the check box can accept focus
+    fn focusable(&self) -> bool {
+        self.state.get() != State::Disabled
+    }
+
+    /// Handle focus event
+    fn focus(&self, fev: FocusEvent) -> Option<Action> {
+        use FocusEvent::*;
+        use State::*;
+        let state = self.state.get();
+        match (fev, state) {
+            (_, Disabled) => None,
+            (Offer, _) => Some(Focused),
+            (Take, _) => Some(Enabled),
+            (HoverInside, Enabled) => Some(Hovered),
+            (HoverOutside, Hovered) => Some(Enabled),
+            (HoverOutside, Pressed) => Some(Focused),
+            _ => None,
+        }
+        .and_then(|st| {
+            if st != state {
+                self.state.set(st);
+                Some(Action::Redraw())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Handle key events
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        match (key, mods, self.state.get()) {
+            (_, _, State::Disabled) => None,
+            (KeyPress::Navigation(NavKey::Enter), ModKeys::EMPTY, _)
+            | (KeyPress::Character(' '), ModKeys::EMPTY, _) => self.toggle(),
+            _ => None,
+        }
+    }
+
+    /// Handle mouse events
+    fn mouse_event(
+        &self,
+        mev: MouseEvent,
+        _mods: ModKeys,
+        _dim: Dim,
+        _pos: WidgetPos,
+    ) -> Option<Action> {
+        match (mev, self.state.get()) {
+            (_, State::Disabled) => None,
+            (MouseEvent::ButtonDown(_), _) => {
+                self.state.set(State::Pressed);
+                Some(Action::Redraw())
+            }
+            (MouseEvent::ButtonUp(_), State::Pressed) => {
+                self.state.set(State::Focused);
+                self.toggle()
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::to_text;
+    use crate::grid_area;
+    use crate::input::MouseButton;
+
+    #[test]
+    fn draw() -> Result<()> {
+        let theme = Theme::default();
+        let a = CheckBox::new("Alpha").with_checked(true);
+        let b = CheckBox::new("Beta");
+        let grid = grid_area!([a][b])?;
+        let text = to_text(&grid, Dim::new(9, 2), &theme)?;
+        assert_eq!(text, "[x] Alpha\n[ ] Beta \n");
+        let c = CheckBox::new("Go").with_marks("☑ ", "☐ ");
+        assert_eq!(c.width_bounds(&theme).minimum(), 4);
+        let grid = grid_area!([c])?;
+        assert_eq!(to_text(&grid, Dim::new(4, 1), &theme)?, "☐ Go\n");
+        Ok(())
+    }
+
+    #[test]
+    fn toggle() {
+        let cb = CheckBox::new("Opt");
+        let space = KeyPress::Character(' ');
+        let enter = KeyPress::Navigation(NavKey::Enter);
+        assert_eq!(cb.key_event(space, ModKeys::EMPTY), Some(Action::Redraw()));
+        assert!(cb.checked());
+        cb.key_event(enter, ModKeys::EMPTY);
+        assert!(!cb.checked());
+        let dim = Dim::new(7, 1);
+        let pos = WidgetPos::new(1, 0);
+        let (down, up) = (
+            MouseEvent::ButtonDown(MouseButton::Left),
+            MouseEvent::ButtonUp(MouseButton::Left),
+        );
+        cb.mouse_event(down, ModKeys::EMPTY, dim, pos);
+        assert_eq!(cb.style_group(), StyleGroup::Interacted);
+        cb.mouse_event(up, ModKeys::EMPTY, dim, pos);
+        assert!(cb.checked());
+        assert_eq!(cb.style_group(), StyleGroup::Focused);
+        // Releasing outside cancels the press
+        cb.mouse_event(down, ModKeys::EMPTY, dim, pos);
+        cb.focus(FocusEvent::HoverOutside);
+        cb.mouse_event(up, ModKeys::EMPTY, dim, pos);
+        assert!(cb.checked());
+        cb.disable();
+        assert_eq!(cb.key_event(space, ModKeys::EMPTY), None);
+        assert!(!cb.focusable());
+    }
+}
@@ -0,0 +1,803 @@
+// form.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{
+    Action, FocusEvent, InputFilter, KeyPress, ModKeys, MouseButton,
+    MouseEvent, NavKey, WidgetId,
+};
+use crate::layout::{BBox, Cells, ContentPos, Dim, LengthBound, WidgetPos};
+use crate::text::{display_width, truncate_end, StyleGroup, Theme};
+use crate::widget::{CheckBox, TextInput};
+use crate::{Result, Widget};
+use std::cell::{Cell, RefCell};
+use std::ops::RangeInclusive;
+
+/// Minimum width of input column
+const INPUT_MIN_WIDTH: usize = 10;
+
+/// Kind of form field, with its default value
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldKind {
+    /// Text entry
+    Text(String),
+    /// Integer entry, limited to a range
+    Number(RangeInclusive<i64>, i64),
+    /// Check box
+    Checkbox(bool),
+}
+
+/// Value of a form field
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    /// Text value
+    Text(String),
+    /// Integer value
+    Number(i64),
+    /// Check box value
+    Checkbox(bool),
+}
+
+/// Values collected from a submitted [Form]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FormValues {
+    /// Field labels and values
+    values: Vec<(String, FieldValue)>,
+}
+
+/// Field validator
+type Validator = Box<dyn Fn(&FieldValue) -> std::result::Result<(), String>>;
+
+/// Input widget of a form field
+enum Input {
+    /// Text entry (text and number fields)
+    Entry(TextInput),
+    /// Check box
+    Check(CheckBox),
+}
+
+/// Form field
+struct Field {
+    /// Field label
+    label: String,
+    /// Field kind
+    kind: FieldKind,
+    /// Input widget
+    input: Input,
+    /// Field validators
+    validators: Vec<Validator>,
+    /// Validation error
+    error: RefCell<Option<String>>,
+}
+
+/// Form widget
+///
+/// A form has one row per field, with right-aligned labels and inputs in a
+/// second column.  Below the fields are a row for validation errors and a
+/// row of buttons.
+///
+/// Text and number fields are edited like a [TextInput], and check boxes
+/// toggled like a [CheckBox].  Number fields only accept digits and `-`.
+///
+/// Tab and BackTab move focus through fields, then buttons.  Fields are
+/// validated on every change, and all fields are validated when the first
+/// button ("OK") is activated; the form is only submitted if every field is
+/// valid.  Enter on a field also activates the first button.
+///
+/// ```rust
+/// use semtext::widget::{FieldKind, Form};
+///
+/// let form = Form::new()
+///     .with_field("Name", FieldKind::Text("".into()))
+///     .with_validator(|v| match v {
+///         semtext::widget::FieldValue::Text(t) if t.is_empty() => {
+///             Err("Name is required".into())
+///         }
+///         _ => Ok(()),
+///     })
+///     .with_field("Port", FieldKind::Number(1..=65535, 8080))
+///     .with_field("Enabled", FieldKind::Checkbox(true))
+///     .with_buttons(&["OK", "Cancel"]);
+/// assert!(form.values().is_none());
+/// ```
+#[derive(Default)]
+pub struct Form {
+    /// Form fields
+    fields: Vec<Field>,
+    /// Button labels
+    buttons: Vec<String>,
+    /// Focus index (fields, then buttons)
+    focus: Cell<usize>,
+    /// Button activated by the last event
+    activated: Cell<Option<usize>>,
+    /// Values from last submit
+    values: RefCell<Option<FormValues>>,
+}
+
+impl FormValues {
+    /// Get the value of a field
+    pub fn get(&self, label: &str) -> Option<&FieldValue> {
+        self.values.iter().find(|(l, _)| l == label).map(|(_, v)| v)
+    }
+
+    /// Get the value of a text field
+    pub fn text(&self, label: &str) -> Option<&str> {
+        match self.get(label)? {
+            FieldValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a number field
+    pub fn number(&self, label: &str) -> Option<i64> {
+        match self.get(label)? {
+            FieldValue::Number(num) => Some(*num),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a check box field
+    pub fn checkbox(&self, label: &str) -> Option<bool> {
+        match self.get(label)? {
+            FieldValue::Checkbox(checked) => Some(*checked),
+            _ => None,
+        }
+    }
+}
+
+impl Field {
+    /// Create a new field
+    fn new(label: &str, kind: FieldKind) -> Self {
+        let input = match &kind {
+            FieldKind::Text(text) => Input::Entry(TextInput::new(text)),
+            FieldKind::Number(range, num) => {
+                // Long enough for either end of the range
+                let len = range
+                    .start()
+                    .to_string()
+                    .len()
+                    .max(range.end().to_string().len());
+                let filter =
+                    InputFilter::custom(|c| c.is_ascii_digit() || c == '-');
+                let input = TextInput::new(&num.to_string())
+                    .with_filter(filter)
+                    .with_max_length(len);
+                Input::Entry(input)
+            }
+            FieldKind::Checkbox(checked) => {
+                Input::Check(CheckBox::new("").with_checked(*checked))
+            }
+        };
+        Field {
+            label: label.to_string(),
+            kind,
+            input,
+            validators: vec![],
+            error: RefCell::new(None),
+        }
+    }
+
+    /// Get the input widget
+    fn widget(&self) -> &dyn Widget {
+        match &self.input {
+            Input::Entry(input) => input,
+            Input::Check(check) => check,
+        }
+    }
+
+    /// Get the entered text and checked state
+    fn entry(&self) -> (String, bool) {
+        match &self.input {
+            Input::Entry(input) => (input.text(), false),
+            Input::Check(check) => (String::new(), check.checked()),
+        }
+    }
+
+    /// Get the width of the input
+    fn input_width(&self) -> usize {
+        match &self.input {
+            Input::Entry(input) => display_width(&input.text()),
+            Input::Check(_) => 3,
+        }
+    }
+
+    /// Get the current value
+    fn value(&self) -> std::result::Result<FieldValue, String> {
+        let (text, checked) = self.entry();
+        match &self.kind {
+            FieldKind::Text(_) => Ok(FieldValue::Text(text)),
+            FieldKind::Number(range, _) => text
+                .parse()
+                .ok()
+                .filter(|num| range.contains(num))
+                .map(FieldValue::Number)
+                .ok_or_else(|| {
+                    format!(
+                        "{} must be {} to {}",
+                        self.label,
+                        range.start(),
+                        range.end()
+                    )
+                }),
+            FieldKind::Checkbox(_) => Ok(FieldValue::Checkbox(checked)),
+        }
+    }
+
+    /// Validate the field
+    ///
+    /// ## Return
+    ///
+    /// `true` if the field is valid.
+    fn validate(&self) -> bool {
+        let res = self
+            .value()
+            .and_then(|v| self.validators.iter().try_for_each(|f| f(&v)));
+        let valid = res.is_ok();
+        *self.error.borrow_mut() = res.err();
+        valid
+    }
+
+    /// Check if the field is invalid
+    fn is_invalid(&self) -> bool {
+        self.error.borrow().is_some()
+    }
+}
+
+impl Form {
+    /// Create a new empty form
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a field
+    ///
+    /// * `label`: Field label, also used to get its value
+    /// * `kind`: Field kind, with its default value
+    pub fn with_field(mut self, label: &str, kind: FieldKind) -> Self {
+        self.fields.push(Field::new(label, kind));
+        self
+    }
+
+    /// Add a validator to the most recently added field
+    ///
+    /// The validator is called with the field value, after it has passed
+    /// built-in checks (such as the range of a number field).  An error
+    /// message is displayed while the field is invalid.
+    pub fn with_validator<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&FieldValue) -> std::result::Result<(), String> + 'static,
+    {
+        if let Some(field) = self.fields.last_mut() {
+            field.validators.push(Box::new(validator));
+        }
+        self
+    }
+
+    /// Set the button labels
+    ///
+    /// The first button submits the form.
+    pub fn with_buttons(mut self, buttons: &[&str]) -> Self {
+        self.buttons = buttons.iter().map(|b| b.to_string()).collect();
+        self
+    }
+
+    /// Get the values from the last successful submit
+    pub fn values(&self) -> Option<FormValues> {
+        self.values.borrow().clone()
+    }
+
+    /// Get the label of the button activated by the last event
+    pub fn activated(&self) -> Option<&str> {
+        self.activated
+            .get()
+            .and_then(|b| self.buttons.get(b))
+            .map(|b| b.as_str())
+    }
+
+    /// Get the validation error of a field
+    pub fn error(&self, label: &str) -> Option<String> {
+        self.field(label).and_then(|f| f.error.borrow().clone())
+    }
+
+    /// Get a field by label
+    fn field(&self, label: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.label == label)
+    }
+
+    /// Get the number of focusable items
+    fn items(&self) -> usize {
+        self.fields.len() + self.buttons.len()
+    }
+
+    /// Get the focused field
+    fn focused_field(&self) -> Option<&Field> {
+        self.fields.get(self.focus.get())
+    }
+
+    /// Move focus to the next or previous item
+    fn move_focus(&self, forward: bool) -> Option<Action> {
+        let items = self.items();
+        if items == 0 {
+            return None;
+        }
+        let focus = self.focus.get();
+        let focus = if forward {
+            (focus + 1) % items
+        } else {
+            (focus + items - 1) % items
+        };
+        self.set_focus(focus)
+    }
+
+    /// Set the focused item, moving focus between field inputs
+    fn set_focus(&self, focus: usize) -> Option<Action> {
+        if let Some(field) = self.focused_field() {
+            field.widget().focus(FocusEvent::Take);
+        }
+        self.focus.set(focus);
+        if let Some(field) = self.focused_field() {
+            field.widget().focus(FocusEvent::Offer);
+        }
+        Some(Action::Redraw())
+    }
+
+    /// Forward an event to the input of a field
+    ///
+    /// * `field`: Form field
+    /// * `event`: Function to handle the event with the input widget
+    ///
+    /// ## Return
+    ///
+    /// [Action::Changed] for the form if the input was edited, otherwise
+    /// the action from the input.
+    fn forward<F>(&self, field: &Field, event: F) -> Option<Action>
+    where
+        F: FnOnce(&dyn Widget) -> Option<Action>,
+    {
+        let entry = field.entry();
+        let action = event(field.widget());
+        if field.entry() != entry {
+            self.changed(field)
+        } else {
+            action
+        }
+    }
+
+    /// Paste text into the focused text or number field
+    ///
+    /// * `text`: Pasted text
+    ///
+    /// Pasted text follows the same rules as typed text.
+    ///
+    /// ## Return
+    ///
+    /// [Action::Changed] if any text was inserted.
+    pub fn paste(&self, text: &str) -> Option<Action> {
+        let field = self.focused_field()?;
+        match &field.input {
+            Input::Entry(input) => self.forward(field, |_| input.paste(text)),
+            Input::Check(_) => None,
+        }
+    }
+
+    /// Check whether any field is invalid
+    fn any_invalid(&self) -> bool {
+        self.fields.iter().any(Field::is_invalid)
+    }
+
+    /// Activate a button
+    fn activate(&self, button: usize) -> Option<Action> {
+        if button >= self.buttons.len() {
+            return None;
+        }
+        self.set_focus(self.fields.len() + button);
+        if button == 0 {
+            // Validate every field, not only up to the first invalid one
+            let valid =
+                self.fields.iter().filter(|f| !f.validate()).count() == 0;
+            if !valid {
+                let invalid = self.fields.iter().position(Field::is_invalid);
+                return self.set_focus(invalid.unwrap_or(0));
+            }
+            let values = self
+                .fields
+                .iter()
+                .filter_map(|f| Some((f.label.clone(), f.value().ok()?)))
+                .collect();
+            *self.values.borrow_mut() = Some(FormValues { values });
+        }
+        self.activated.set(Some(button));
+        Some(Action::Changed(WidgetId::of(self)))
+    }
+
+    /// Handle a change to the focused field
+    fn changed(&self, field: &Field) -> Option<Action> {
+        field.validate();
+        Some(Action::Changed(WidgetId::of(self)))
+    }
+
+    /// Get the width of the label column
+    fn label_width(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|f| display_width(&f.label))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Get the column and width of each button
+    fn button_spans(&self, width: u16) -> Vec<(u16, u16)> {
+        let widths: Vec<u16> = self
+            .buttons
+            .iter()
+            .map(|b| (display_width(b) + 4) as u16)
+            .collect();
+        let total = widths.iter().sum::<u16>() + widths.len() as u16;
+        let mut col = width.saturating_sub(total.saturating_sub(1));
+        widths
+            .into_iter()
+            .map(|w| {
+                let span = (col, w);
+                col += w + 1;
+                span
+            })
+            .collect()
+    }
+
+    /// Get the error message to display
+    fn message(&self) -> Option<String> {
+        let focused = self.focused_field().filter(|f| f.is_invalid());
+        focused
+            .or_else(|| self.fields.iter().find(|f| f.is_invalid()))
+            .and_then(|f| f.error.borrow().clone())
+    }
+}
+
+impl Widget for Form {
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        let input = self
+            .fields
+            .iter()
+            .map(Field::input_width)
+            .max()
+            .unwrap_or(0)
+            .max(INPUT_MIN_WIDTH);
+        let fields = self.label_width() + 1 + input;
+        let buttons = self
+            .buttons
+            .iter()
+            .map(|b| display_width(b) + 5)
+            .sum::<usize>();
+        let width = fields.max(buttons.saturating_sub(1));
+        LengthBound::new(width.try_into().unwrap_or(u16::MAX)..)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        let rows = (self.fields.len() + 2).try_into().unwrap_or(u16::MAX);
+        LengthBound::new(rows..=rows)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+        let width = usize::from(cells.width());
+        let theme = cells.theme();
+        let normal = theme.style(StyleGroup::Enabled);
+        let focused = theme.style(StyleGroup::Focused);
+        let error = theme.style(StyleGroup::Error);
+        let disabled = theme.style(StyleGroup::Disabled);
+        let label_width = self.label_width();
+        let input_width = width.saturating_sub(label_width + 1);
+        let focus = self.focus.get();
+        for (row, field) in self.fields.iter().enumerate() {
+            let row = u16::try_from(row).unwrap_or(u16::MAX);
+            let pad = label_width - display_width(&field.label);
+            cells.set_style(if field.is_invalid() { error } else { normal })?;
+            cells.move_to(pad as u16, row)?;
+            cells.print_str(&field.label)?;
+            let style = if focus == usize::from(row) {
+                focused
+            } else if field.is_invalid() {
+                error
+            } else {
+                normal
+            };
+            cells.set_style(style)?;
+            let cols = u16::try_from(input_width).unwrap_or(u16::MAX);
+            cells.push_clip(BBox::new((label_width + 1) as u16, row, cols, 1));
+            if let Input::Entry(_) = field.input {
+                cells.move_to(0, 0)?;
+                cells.print_str(&" ".repeat(input_width))?;
+            }
+            let res = field.widget().draw(cells, ContentPos::default());
+            cells.pop_clip();
+            res?;
+        }
+        let row = self.fields.len() as u16;
+        if let Some(msg) = self.message() {
+            cells.set_style(error)?;
+            cells.move_to(0, row)?;
+            cells.print_str(&truncate_end(&msg, width))?;
+        }
+        let spans = self.button_spans(cells.width());
+        for (i, (button, (col, _))) in
+            self.buttons.iter().zip(spans).enumerate()
+        {
+            let style = if focus == self.fields.len() + i {
+                focused
+            } else if i == 0 && self.any_invalid() {
+                disabled
+            } else {
+                normal
+            };
+            cells.set_style(style)?;
+            cells.move_to(col, row + 1)?;
+            cells.print_str(&format!("[ {} ]", button))?;
+        }
+        Ok(())
+    }
+
+    /// Check whether the form can accept focus
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    /// Take bells requested by field inputs
+    fn take_bell(&self) -> bool {
+        let mut bell = false;
+        for field in &self.fields {
+            bell |= field.widget().take_bell();
+        }
+        bell
+    }
+
+    /// Get the position of the focused field's terminal cursor
+    fn cursor(&self) -> Option<WidgetPos> {
+        let pos = self.focused_field()?.widget().cursor()?;
+        let col = u16::try_from(self.label_width() + 1).ok()?;
+        let row = u16::try_from(self.focus.get()).ok()?;
+        Some(WidgetPos::new(col.checked_add(pos.col)?, row))
+    }
+
+    /// Handle focus event
+    ///
+    /// Focus is passed to the input of the focused field.
+    fn focus(&self, fev: FocusEvent) -> Option<Action> {
+        match fev {
+            FocusEvent::Offer | FocusEvent::Take => {
+                self.focused_field()?.widget().focus(fev)
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle key events
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        self.activated.set(None);
+        let button = self.focus.get().checked_sub(self.fields.len());
+        match (key, mods) {
+            (KeyPress::Navigation(NavKey::Tab), ModKeys::EMPTY) => {
+                self.move_focus(true)
+            }
+            (KeyPress::Navigation(NavKey::BackTab), _) => {
+                self.move_focus(false)
+            }
+            (KeyPress::Navigation(NavKey::Enter), ModKeys::EMPTY) => {
+                self.activate(button.unwrap_or(0))
+            }
+            (KeyPress::Character(' '), ModKeys::EMPTY) if button.is_some() => {
+                self.activate(button.unwrap_or(0))
+            }
+            _ => {
+                let field = self.focused_field()?;
+                self.forward(field, |w| w.key_event(key, mods))
+            }
+        }
+    }
+
+    /// Handle mouse events
+    ///
+    /// Pressing the left button on a field focuses it; the input handles
+    /// presses within it, and the release which follows.
+    fn mouse_event(
+        &self,
+        mev: MouseEvent,
+        mods: ModKeys,
+        dim: Dim,
+        pos: WidgetPos,
+    ) -> Option<Action> {
+        let col = (self.label_width() + 1) as u16;
+        let inset = BBox::new(col, pos.row, dim.width.saturating_sub(col), 1);
+        if let MouseEvent::ButtonUp(_) = mev {
+            let field = self.focused_field()?;
+            let pos = pos.nested(inset);
+            return self.forward(field, |w| {
+                w.mouse_event(mev, mods, inset.dim(), pos)
+            });
+        }
+        if mev != MouseEvent::ButtonDown(MouseButton::Left) {
+            return None;
+        }
+        self.activated.set(None);
+        let row = usize::from(pos.row);
+        if let Some(field) = self.fields.get(row) {
+            let action = self.set_focus(row);
+            if pos.col < col || inset.dim().is_empty() {
+                return action;
+            }
+            let pos = pos.nested(inset);
+            return self
+                .forward(field, |w| w.mouse_event(mev, mods, inset.dim(), pos))
+                .or(action);
+        }
+        if row == self.fields.len() + 1 {
+            let spans = self.button_spans(dim.width);
+            let button = spans
+                .iter()
+                .position(|(c, w)| pos.col >= *c && pos.col < c + w)?;
+            return self.activate(button);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::to_text;
+    use crate::grid_area;
+
+    /// Settings form fixture
+    fn settings() -> Form {
+        Form::new()
+            .with_field("Name", FieldKind::Text("".into()))
+            .with_validator(|v| match v {
+                FieldValue::Text(t) if t.is_empty() => {
+                    Err("Name is required".into())
+                }
+                _ => Ok(()),
+            })
+            .with_field("Port", FieldKind::Number(1..=65535, 8080))
+            .with_field("Enabled", FieldKind::Checkbox(false))
+            .with_buttons(&["OK", "Cancel"])
+    }
+
+    /// Type text into a form
+    fn type_text(form: &Form, text: &str) {
+        for ch in text.chars() {
+            form.key_event(KeyPress::Character(ch), ModKeys::EMPTY);
+        }
+    }
+
+    /// Press a navigation key
+    fn press(form: &Form, nav: NavKey) -> Option<Action> {
+        form.key_event(KeyPress::Navigation(nav), ModKeys::EMPTY)
+    }
+
+    #[test]
+    fn layout() -> Result<()> {
+        let form = settings();
+        let grid = grid_area!([form])?;
+        let text = to_text(&grid, Dim::new(24, 5), &Theme::default())?;
+        assert_eq!(
+            text,
+            "   Name                 \n   \
+             Port 8080            \n\
+             Enabled [ ]             \n\
+             \x20                       \n\
+             \x20      [ OK ] [ Cancel ]\n"
+        );
+        press(&form, NavKey::Enter);
+        let text = to_text(&grid, Dim::new(24, 5), &Theme::default())?;
+        assert_eq!(text.lines().nth(3), Some("Name is required        "));
+        Ok(())
+    }
+
+    #[test]
+    fn fill_and_submit() {
+        let form = settings();
+        let id = WidgetId::of(&form);
+        // Submitting with an empty name fails validation
+        assert_eq!(press(&form, NavKey::Enter), Some(Action::Redraw()));
+        assert_eq!(form.values(), None);
+        assert_eq!(form.activated(), None);
+        assert_eq!(form.error("Name").as_deref(), Some("Name is required"));
+        // Focus was moved to the invalid field
+        type_text(&form, "srv");
+        assert_eq!(form.error("Name"), None);
+        press(&form, NavKey::Tab);
+        for _ in 0..4 {
+            press(&form, NavKey::Backspace);
+        }
+        assert!(form.error("Port").is_some());
+        type_text(&form, "7x0000");
+        assert_eq!(
+            form.error("Port").as_deref(),
+            Some("Port must be 1 to 65535")
+        );
+        // Invalid field blocks OK
+        press(&form, NavKey::Tab);
+        press(&form, NavKey::Tab);
+        assert_eq!(press(&form, NavKey::Enter), Some(Action::Redraw()));
+        assert_eq!(form.values(), None);
+        press(&form, NavKey::Backspace);
+        press(&form, NavKey::Backspace);
+        assert_eq!(form.error("Port"), None);
+        press(&form, NavKey::Tab);
+        let action = form.key_event(KeyPress::Character(' '), ModKeys::EMPTY);
+        assert_eq!(action, Some(Action::Changed(id)));
+        press(&form, NavKey::Tab);
+        assert_eq!(press(&form, NavKey::Enter), Some(Action::Changed(id)));
+        assert_eq!(form.activated(), Some("OK"));
+        let values = form.values().unwrap();
+        assert_eq!(values.text("Name"), Some("srv"));
+        assert_eq!(values.number("Port"), Some(700));
+        assert_eq!(values.checkbox("Enabled"), Some(true));
+        assert_eq!(values.number("Name"), None);
+    }
+
+    #[test]
+    fn edit() {
+        let form = settings();
+        let id = WidgetId::of(&form);
+        form.focus(FocusEvent::Offer);
+        type_text(&form, "srv");
+        assert_eq!(form.cursor(), Some(WidgetPos::new(11, 0)));
+        press(&form, NavKey::Home);
+        assert_eq!(form.cursor(), Some(WidgetPos::new(8, 0)));
+        assert_eq!(press(&form, NavKey::Right), Some(Action::Redraw()));
+        assert_eq!(press(&form, NavKey::Delete), Some(Action::Changed(id)));
+        type_text(&form, "e");
+        assert_eq!(form.paste("x\ny"), Some(Action::Changed(id)));
+        press(&form, NavKey::End);
+        press(&form, NavKey::Left);
+        press(&form, NavKey::Backspace);
+        assert_eq!(form.fields[0].entry().0, "sexv");
+        // Number fields only accept digits, up to the range length
+        press(&form, NavKey::Tab);
+        assert_eq!(form.paste("1a2"), Some(Action::Changed(id)));
+        assert!(form.take_bell());
+        assert_eq!(form.fields[1].entry().0, "80801");
+        press(&form, NavKey::Backspace);
+        press(&form, NavKey::Backspace);
+        press(&form, NavKey::Home);
+        form.paste("12");
+        assert_eq!(form.fields[1].entry().0, "12808");
+        // Check boxes have no cursor
+        press(&form, NavKey::Tab);
+        assert_eq!(form.cursor(), None);
+        assert_eq!(form.paste("x"), None);
+    }
+
+    #[test]
+    fn mouse() {
+        let form = settings();
+        let dim = Dim::new(24, 5);
+        let down = MouseEvent::ButtonDown(MouseButton::Left);
+        let up = MouseEvent::ButtonUp(MouseButton::Left);
+        let click = |col, row| {
+            let pos = WidgetPos(crate::layout::Pos::new(col, row));
+            form.mouse_event(down, ModKeys::EMPTY, dim, pos)
+        };
+        let release = |col, row| {
+            let pos = WidgetPos(crate::layout::Pos::new(col, row));
+            form.mouse_event(up, ModKeys::EMPTY, dim, pos)
+        };
+        let id = WidgetId::of(&form);
+        // Check box toggles on release
+        assert_eq!(click(9, 2), Some(Action::Redraw()));
+        assert_eq!(release(9, 2), Some(Action::Changed(id)));
+        assert_eq!(form.fields[2].value(), Ok(FieldValue::Checkbox(true)));
+        // Clicking a text field moves its cursor
+        click(9, 1);
+        press(&form, NavKey::Delete);
+        assert_eq!(form.fields[1].entry().0, "880");
+        // Cancel does not submit
+        assert_eq!(click(18, 4), Some(Action::Changed(id)));
+        assert_eq!(form.activated(), Some("Cancel"));
+        assert_eq!(form.values(), None);
+        assert_eq!(click(7, 4), Some(Action::Redraw()));
+        assert!(form.error("Name").is_some());
+    }
+}
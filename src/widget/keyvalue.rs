@@ -0,0 +1,266 @@
+// keyvalue.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::layout::{Cells, ContentPos, LengthBound};
+use crate::text::{display_width, truncate_end, StyleGroup, Theme};
+use crate::{Result, Widget};
+use std::cell::RefCell;
+
+/// Minimum width of value column
+const VALUE_MIN_WIDTH: u16 = 4;
+
+/// Text of one key-value row, fit to a width
+#[derive(Debug, PartialEq)]
+struct RowText {
+    /// Padding before key (for right alignment)
+    pad: usize,
+    /// Key text
+    key: String,
+    /// Separator text
+    sep: String,
+    /// Value text
+    value: String,
+}
+
+/// Key-value widget
+///
+/// Pairs are drawn one per row.  Keys are right-aligned to the width of the
+/// longest key, and values are left-aligned in the remaining width
+/// (truncated with an ellipsis if necessary).
+///
+/// ```rust
+/// use semtext::widget::KeyValue;
+///
+/// let kv = KeyValue::new(vec![
+///     ("Name".into(), "semtext".into()),
+///     ("Size".into(), "12 KB".into()),
+/// ])
+/// .with_separator("  ");
+/// kv.set_value("Size", "14 KB");
+/// ```
+pub struct KeyValue {
+    /// Key / value pairs
+    pairs: RefCell<Vec<(String, String)>>,
+    /// Separator between keys and values
+    separator: String,
+    /// Placeholder for empty values
+    placeholder: Option<String>,
+}
+
+impl RowText {
+    /// Fit a row into a width
+    ///
+    /// * `key_width`: Width of key column
+    /// * `width`: Total width of row
+    fn new(
+        key: &str,
+        sep: &str,
+        value: &str,
+        key_width: usize,
+        width: usize,
+    ) -> Self {
+        let key_width = key_width.min(width);
+        let key = truncate_end(key, key_width);
+        let pad = key_width - display_width(&key);
+        let remaining = width - key_width;
+        let sep = truncate_end(sep, remaining);
+        let remaining = remaining - display_width(&sep);
+        let value = truncate_end(value, remaining);
+        Self {
+            pad,
+            key,
+            sep,
+            value,
+        }
+    }
+}
+
+impl KeyValue {
+    /// Create a new key-value widget
+    pub fn new(pairs: Vec<(String, String)>) -> Self {
+        let pairs = RefCell::new(pairs);
+        let separator = ": ".to_string();
+        let placeholder = None;
+        Self {
+            pairs,
+            separator,
+            placeholder,
+        }
+    }
+
+    /// Set the separator between keys and values
+    pub fn with_separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Set a placeholder to display for empty values
+    ///
+    /// The placeholder is drawn using the `Disabled` style.
+    pub fn with_placeholder(mut self, placeholder: &str) -> Self {
+        self.placeholder = Some(placeholder.to_string());
+        self
+    }
+
+    /// Get the value for a key
+    pub fn value(&self, key: &str) -> Option<String> {
+        self.pairs
+            .borrow()
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    }
+
+    /// Set the value for a key
+    ///
+    /// If the key is not found, a new pair is appended.
+    pub fn set_value(&self, key: &str, value: &str) {
+        let mut pairs = self.pairs.borrow_mut();
+        match pairs.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => pairs.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    /// Get the width of the key column
+    fn key_width(&self) -> u16 {
+        let pairs = self.pairs.borrow();
+        let width = pairs
+            .iter()
+            .map(|(k, _)| display_width(k))
+            .max()
+            .unwrap_or(0);
+        width as u16
+    }
+}
+
+impl Widget for KeyValue {
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        let sep = display_width(&self.separator) as u16;
+        let min = self.key_width() + sep + VALUE_MIN_WIDTH;
+        LengthBound::new(min..)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        let rows = self.pairs.borrow().len() as u16;
+        LengthBound::new(rows..=rows)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        let theme = cells.theme();
+        let key_style = theme.style(StyleGroup::DarkShadow);
+        let value_style = theme.style(self.style_group());
+        let empty_style = theme.style(StyleGroup::Disabled);
+        let width = usize::from(cells.width());
+        let key_width = usize::from(self.key_width());
+        let pairs = self.pairs.borrow();
+        let top = usize::from(offset.row);
+        let height = usize::from(cells.height());
+        for (row, (key, value)) in
+            pairs.iter().skip(top).take(height).enumerate()
+        {
+            let row = row as u16; // limited to u16 by take(height)
+            let placeholder = match &self.placeholder {
+                Some(placeholder) if value.is_empty() => Some(placeholder),
+                _ => None,
+            };
+            let value = placeholder.unwrap_or(value);
+            let txt =
+                RowText::new(key, &self.separator, value, key_width, width);
+            cells.move_to(0, row)?;
+            cells.set_style(key_style)?;
+            cells.print_str(&" ".repeat(txt.pad))?;
+            cells.print_str(&txt.key)?;
+            cells.set_style(value_style)?;
+            cells.print_str(&txt.sep)?;
+            if placeholder.is_some() {
+                cells.set_style(empty_style)?;
+            }
+            cells.print_str(&txt.value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pairs(p: &[(&str, &str)]) -> Vec<(String, String)> {
+        p.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn bounds() {
+        let theme = Theme::default();
+        let kv = KeyValue::new(pairs(&[("Name", "foo"), ("Modified", "")]));
+        assert_eq!(kv.key_width(), 8);
+        assert_eq!(kv.width_bounds(&theme), LengthBound::new(14..));
+        assert_eq!(kv.height_bounds(&theme, 20), LengthBound::new(2..=2));
+        let kv = kv.with_separator("  ");
+        assert_eq!(kv.width_bounds(&theme), LengthBound::new(14..));
+    }
+
+    #[test]
+    fn wide_keys() {
+        let theme = Theme::default();
+        let kv = KeyValue::new(pairs(&[("名前", "foo"), ("Size", "12 KB")]));
+        assert_eq!(kv.key_width(), 4);
+        let kv = KeyValue::new(pairs(&[("サイズ", "12 KB"), ("Name", "")]));
+        assert_eq!(kv.key_width(), 6);
+        assert_eq!(kv.width_bounds(&theme), LengthBound::new(12..));
+    }
+
+    fn row(pad: usize, key: &str, sep: &str, value: &str) -> RowText {
+        RowText {
+            pad,
+            key: key.to_string(),
+            sep: sep.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn row_text() {
+        let r = RowText::new("Size", ": ", "12 KB", 8, 20);
+        assert_eq!(r, row(4, "Size", ": ", "12 KB"));
+        let r = RowText::new("Size", ": ", "12 KB", 8, 14);
+        assert_eq!(r, row(4, "Size", ": ", "12 …"));
+        let r = RowText::new("Modified", ": ", "yesterday", 8, 9);
+        assert_eq!(r, row(0, "Modified", "…", ""));
+        let r = RowText::new("Modified", ": ", "yesterday", 8, 5);
+        assert_eq!(r, row(0, "Modi…", "", ""));
+    }
+
+    #[test]
+    fn row_text_wide() {
+        let r = RowText::new("名前", ": ", "ｗｗｗ", 6, 14);
+        assert_eq!(r, row(2, "名前", ": ", "ｗｗｗ"));
+        let r = RowText::new("名前", ": ", "ｗｗｗ", 6, 13);
+        assert_eq!(r, row(2, "名前", ": ", "ｗｗ…"));
+        let r = RowText::new("名前", ": ", "ｗｗｗ", 6, 12);
+        assert_eq!(r, row(2, "名前", ": ", "ｗ…"));
+        let r = RowText::new("名前", ": ", "ｗｗｗ", 4, 3);
+        assert_eq!(r, row(0, "名…", "", ""));
+        let r = RowText::new("名前", ": ", "ｗｗｗ", 4, 2);
+        assert_eq!(r, row(1, "…", "", ""));
+    }
+
+    #[test]
+    fn set_value() {
+        let theme = Theme::default();
+        let kv = KeyValue::new(pairs(&[("Name", "foo")]));
+        kv.set_value("Name", "bar");
+        assert_eq!(kv.value("Name"), Some("bar".to_string()));
+        assert_eq!(kv.height_bounds(&theme, 20), LengthBound::new(1..=1));
+        kv.set_value("Size", "12 KB");
+        assert_eq!(kv.value("Size"), Some("12 KB".to_string()));
+        assert_eq!(kv.height_bounds(&theme, 20), LengthBound::new(2..=2));
+    }
+}
@@ -2,23 +2,118 @@
 //
 // Copyright (c) 2020-2022  Douglas P Lau
 //
-use crate::layout::{Cells, LengthBound, Pos};
-use crate::text::Theme;
+use crate::layout::{Cells, ContentPos, HAlign, LengthBound, PassCache};
+#[cfg(feature = "markdown")]
+use crate::text::Marked;
+use crate::text::{
+    display_width, Direction, StyledText, TextStyle, Theme, WrapMode,
+};
 use crate::{Result, Widget};
-use textwrap::wrap;
-use unicode_width::UnicodeWidthStr;
 
 /// Text label widget
+///
+/// The text can be styled with inline Markdown, as with [print_text], or
+/// with [StyledText] spans.  Without the `markdown` feature, the text is
+/// plain, and markers are shown as is.
+///
+/// [print_text]: ../layout/struct.Cells.html#method.print_text
+/// [StyledText]: ../text/struct.StyledText.html
 pub struct Label {
     /// Text of label
     text: String,
+    /// Base text direction
+    direction: Direction,
+    /// Line alignment
+    align: Option<HAlign>,
+    /// Wrap mode
+    wrap: WrapMode,
+    /// Visible text, without markup
+    visible: String,
+    /// Styled spans (instead of markup)
+    styled: Option<StyledText>,
+    /// Width of text, with tabs expanded
+    width: PassCache<u16>,
+    /// Wrapped rows for a width
+    rows: PassCache<(u16, u16)>,
+    /// Style override
+    style: Option<TextStyle>,
 }
 
 impl Label {
     /// Create a new label widget
     pub fn new(text: &str) -> Self {
+        #[cfg(feature = "markdown")]
+        let visible = Marked::parse(text).text().to_string();
+        #[cfg(not(feature = "markdown"))]
+        let visible = text.to_string();
         let text = text.to_string();
-        Label { text }
+        let direction = Direction::default();
+        let width = PassCache::new();
+        Label {
+            text,
+            direction,
+            align: None,
+            wrap: WrapMode::default(),
+            visible,
+            styled: None,
+            width,
+            rows: PassCache::new(),
+            style: None,
+        }
+    }
+
+    /// Create a new label widget from styled spans
+    ///
+    /// The text is not parsed for Markdown.  Spans without a style use the
+    /// style of the label.
+    pub fn from_styled(styled: StyledText) -> Self {
+        let text = styled.text().to_string();
+        Label {
+            visible: text.clone(),
+            text,
+            direction: Direction::default(),
+            align: None,
+            wrap: WrapMode::default(),
+            styled: Some(styled),
+            width: PassCache::new(),
+            rows: PassCache::new(),
+            style: None,
+        }
+    }
+
+    /// Set the base text direction
+    ///
+    /// By default, the direction of each line is detected from its first
+    /// strong character.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Set the alignment of each line
+    ///
+    /// Lines are broken at each `\n`, and wrapped to the width of the
+    /// label.  By default, left-to-right lines are aligned left, and
+    /// right-to-left lines are aligned right.
+    pub fn with_align(mut self, align: HAlign) -> Self {
+        self.align = Some(align);
+        self
+    }
+
+    /// Set the wrap mode
+    ///
+    /// With [WrapMode::Truncate], the label is one row tall, and text wider
+    /// than the label ends with an ellipsis.  A wide character which would
+    /// span the edge is dropped.
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Set a style, overriding the theme colors
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = Some(style);
+        self
     }
 
     /// Get label text
@@ -28,9 +123,16 @@ impl Label {
 }
 
 impl Widget for Label {
+    /// Get the style override
+    fn style_override(&self) -> Option<TextStyle> {
+        self.style
+    }
+
     /// Get the width bounds
-    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
-        let w = self.text.width() as u16;
+    fn width_bounds(&self, theme: &Theme) -> LengthBound {
+        let w = self.width.get_or_compute(theme.pass_id(), || {
+            display_width(&theme.expand_tabs(&self.visible)) as u16
+        });
         match w {
             0..=8 => LengthBound::new(w..),
             9..=20 => LengthBound::new(10..),
@@ -39,13 +141,182 @@ impl Widget for Label {
     }
 
     /// Get the height bounds
-    fn height_bounds(&self, _theme: &Theme, width: u16) -> LengthBound {
-        let rows = wrap(&self.text, usize::from(width)).len() as u16;
+    fn height_bounds(&self, theme: &Theme, width: u16) -> LengthBound {
+        let rows = self.rows.get_or_compute_for(theme.pass_id(), width, || {
+            theme.wrap_with(&self.visible, width, self.wrap).len() as u16
+        });
         LengthBound::new(rows..=rows)
     }
 
     /// Draw the widget
-    fn draw(&self, cells: &mut Cells, offset: Pos) -> Result<()> {
-        cells.print_text(&self.text, offset)
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        match &self.styled {
+            Some(styled) => cells.print_styled(
+                styled,
+                offset,
+                self.direction,
+                self.align,
+                self.wrap,
+            ),
+            None => cells.print_lines(
+                &self.text,
+                offset,
+                self.direction,
+                self.align,
+                self.wrap,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::to_text;
+    use crate::grid_area;
+    use crate::layout::Dim;
+
+    #[test]
+    fn unlaid_themes() {
+        let label = Label::new("a\tb");
+        let bounds = label.width_bounds(&Theme::default());
+        assert_eq!(bounds.minimum(), 5);
+        let wide = Theme::default().with_tab_width(8);
+        assert_eq!(label.width_bounds(&wide).minimum(), 10);
+        assert_eq!(label.height_bounds(&wide, 4).minimum(), 2);
+        assert_eq!(label.height_bounds(&wide, 10).minimum(), 1);
+    }
+
+    #[test]
+    fn align() -> Result<()> {
+        let theme = Theme::default();
+        let title = Label::new("Title").with_align(HAlign::Center);
+        let grid = grid_area!([title])?;
+        assert_eq!(to_text(&grid, Dim::new(11, 1), &theme)?, "   Title   \n");
+        let label = Label::new("a\nbbb").with_align(HAlign::Right);
+        assert_eq!(label.height_bounds(&theme, 5).minimum(), 2);
+        let grid = grid_area!([label])?;
+        let text = to_text(&grid, Dim::new(5, 2), &theme)?;
+        assert_eq!(text, "    a\n  bbb\n");
+        // Wrapped lines are aligned too
+        let label = Label::new("one two\nthree").with_align(HAlign::Center);
+        let grid = grid_area!([label])?;
+        let text = to_text(&grid, Dim::new(5, 3), &theme)?;
+        assert_eq!(text, " one \n two \nthree\n");
+        Ok(())
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn markup_wrapped() -> Result<()> {
+        use crate::export::to_html;
+        let theme = Theme::default();
+        let label = Label::new("one **two three** four");
+        assert_eq!(label.width_bounds(&theme).minimum(), 10);
+        assert_eq!(label.height_bounds(&theme, 10).minimum(), 2);
+        let grid = grid_area!([label])?;
+        let dim = Dim::new(10, 2);
+        assert_eq!(to_text(&grid, dim, &theme)?, "one two   \nthree four\n");
+        let html = to_html(&grid, dim, &theme)?;
+        let bold: Vec<_> = html
+            .split("<span style=\"")
+            .filter(|s| s.contains("font-weight:bold"))
+            .map(|s| &s[s.find('>').unwrap() + 1..s.find("</span>").unwrap()])
+            .collect();
+        assert_eq!(bold, ["two", "three"]);
+        Ok(())
+    }
+
+    #[test]
+    fn wide() -> Result<()> {
+        let theme = Theme::default();
+        let wide = Label::new("ｗｗｗｗｗｗ");
+        assert_eq!(wide.height_bounds(&theme, 5).minimum(), 3);
+        let b = Label::new("b");
+        let grid = grid_area!([wide][b])?;
+        let text = to_text(&grid, Dim::new(5, 4), &theme)?;
+        assert_eq!(text, "ｗｗ \nｗｗ \nｗｗ \nb    \n");
+        Ok(())
+    }
+
+    #[test]
+    fn styled() -> Result<()> {
+        use crate::export::to_html;
+        use crate::text::{Color, Intensity};
+        let theme = Theme::default();
+        let red =
+            TextStyle::default().with_foreground(Color::Red(Intensity::Bright));
+        let styled = StyledText::new()
+            .span("ERROR", red)
+            .plain(": disk *full* ")
+            .span("now", red);
+        let label = Label::from_styled(styled);
+        assert_eq!(label.text(), "ERROR: disk *full* now");
+        assert_eq!(label.width_bounds(&theme).minimum(), 12);
+        assert_eq!(label.height_bounds(&theme, 12).minimum(), 2);
+        let grid = grid_area!([label])?;
+        let dim = Dim::new(12, 2);
+        let text = to_text(&grid, dim, &theme)?;
+        assert_eq!(text, "ERROR: disk \n*full* now  \n");
+        let html = to_html(&grid, dim, &theme)?;
+        let red: Vec<_> = html
+            .split("<span style=\"")
+            .filter(|s| s.contains("color:#ff0000"))
+            .map(|s| &s[s.find('>').unwrap() + 1..s.find("</span>").unwrap()])
+            .collect();
+        assert_eq!(red, ["ERROR", "now"]);
+        Ok(())
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn truncate() -> Result<()> {
+        let theme = Theme::default();
+        let label =
+            Label::new("one **two** three").with_wrap(WrapMode::Truncate);
+        assert_eq!(label.height_bounds(&theme, 5), LengthBound::new(1..=1));
+        let grid = grid_area!([label])?;
+        assert_eq!(to_text(&grid, Dim::new(12, 1), &theme)?, "one two thr…\n");
+        // A wide character at the edge is dropped
+        let wide = Label::new("ａｂｃｄｅｆ").with_wrap(WrapMode::Truncate);
+        let grid = grid_area!([wide])?;
+        assert_eq!(to_text(&grid, Dim::new(10, 1), &theme)?, "ａｂｃｄ… \n");
+        Ok(())
+    }
+
+    #[cfg(not(feature = "markdown"))]
+    #[test]
+    fn plain() -> Result<()> {
+        let theme = Theme::default();
+        let label = Label::new("one **two**");
+        assert_eq!(label.width_bounds(&theme).minimum(), 10);
+        let grid = grid_area!([label])?;
+        assert_eq!(to_text(&grid, Dim::new(11, 1), &theme)?, "one **two**\n");
+        Ok(())
+    }
+
+    #[test]
+    fn break_anywhere() -> Result<()> {
+        let theme = Theme::default();
+        let url = "see http://example.com/path";
+        let label = Label::new(url).with_wrap(WrapMode::BreakAnywhere);
+        assert_eq!(label.height_bounds(&theme, 12).minimum(), 3);
+        let grid = grid_area!([label])?;
+        let text = to_text(&grid, Dim::new(12, 3), &theme)?;
+        assert_eq!(text, "see http://e\nxample.com/p\nath         \n");
+        Ok(())
+    }
+
+    #[test]
+    fn variation_selectors() -> Result<()> {
+        let theme = Theme::default();
+        for emoji in ["⚠️", "❤️", "☀️"] {
+            let label = Label::new(&format!("{}ab", emoji));
+            assert_eq!(label.width_bounds(&theme).minimum(), 4);
+            let grid = grid_area!([label])?;
+            let text = to_text(&grid, Dim::new(5, 1), &theme)?;
+            assert_eq!(text, format!("{}ab \n", emoji));
+        }
+        Ok(())
     }
 }
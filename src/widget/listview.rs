@@ -0,0 +1,653 @@
+// listview.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{
+    move_item, Action, DragReorder, KeyPress, ModKeys, MouseButton, MouseEvent,
+    SelectMode, Selection, WidgetId,
+};
+use crate::layout::{Cells, ContentPos, Dim, LengthBound, WidgetPos};
+use crate::text::{
+    display_width, visible_span, StyleGroup, StyleOverlay, StyledText, Theme,
+};
+use crate::{PersistState, Result, StateBlob, Widget};
+use std::cell::{Cell, Ref, RefCell};
+use std::time::{Duration, Instant};
+
+/// Version of saved state representation
+const STATE_VERSION: u32 = 1;
+
+/// Interval between rows scrolled while dragging at an edge of the view
+const SCROLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// List view widget
+///
+/// Each row is one line of text, which can have [StyledText] spans.  The
+/// selected row is highlighted with a background color, keeping the colors
+/// of its spans; `Up`, `Down`, `PageUp`, `PageDown`, `Home` and `End` move
+/// the selection, as does clicking a row.
+///
+/// The list is as tall as its rows, so a long list should be wrapped in a
+/// [ScrollView], which keeps the selected row visible.
+///
+/// With [reordering](ListView::with_reorder), rows can be dragged with the
+/// mouse (see [DragReorder]).  While a row is lifted, an underline is drawn
+/// below the row which it would follow, or the first row is tinted if it
+/// would be moved to the top.  Dragging at an edge of a scroll view scrolls
+/// it.
+///
+/// [DragReorder]: ../input/struct.DragReorder.html
+/// [ScrollView]: struct.ScrollView.html
+/// [StyledText]: ../text/struct.StyledText.html
+pub struct ListView {
+    /// Rows of text
+    rows: RefCell<Vec<String>>,
+    /// Styled rows, if any
+    styled: RefCell<Option<Vec<StyledText>>>,
+    /// Row selection
+    selection: RefCell<Selection>,
+    /// Number of rows drawn, for page movement
+    page: Cell<u16>,
+    /// Row offset of the view, from last draw
+    top: Cell<u16>,
+    /// Drag reordering (if enabled)
+    reorder: Option<RefCell<DragReorder>>,
+    /// Time of the last auto-scroll while dragging
+    scrolled: Cell<Option<Instant>>,
+}
+
+impl ListView {
+    /// Create a new list view widget
+    ///
+    /// * `rows`: Rows of text
+    ///
+    /// The first row is selected.
+    pub fn new(rows: Vec<String>) -> Self {
+        let selection = RefCell::new(Selection::new(rows.len()));
+        ListView {
+            rows: RefCell::new(rows),
+            styled: RefCell::new(None),
+            selection,
+            page: Cell::new(0),
+            top: Cell::new(0),
+            reorder: None,
+            scrolled: Cell::new(None),
+        }
+    }
+
+    /// Create a new list view widget with styled rows
+    ///
+    /// * `rows`: Rows of styled text
+    ///
+    /// The first row is selected.
+    pub fn from_styled(rows: Vec<StyledText>) -> Self {
+        let text = rows.iter().map(|r| r.text().to_string()).collect();
+        let list = ListView::new(text);
+        list.styled.replace(Some(rows));
+        list
+    }
+
+    /// Set the selection mode
+    pub fn with_mode(mut self, mode: SelectMode) -> Self {
+        let selection = self.selection.into_inner().with_mode(mode);
+        self.selection = RefCell::new(selection);
+        self
+    }
+
+    /// Enable reordering rows
+    ///
+    /// Rows are moved by dragging them with the mouse, or with `Ctrl+Up` /
+    /// `Ctrl+Down`.  Each move produces an [Action::Reordered].
+    pub fn with_reorder(mut self, enable: bool) -> Self {
+        self.reorder = enable
+            .then(|| RefCell::new(DragReorder::new(self.rows.borrow().len())));
+        self
+    }
+
+    /// Get the rows
+    pub fn rows(&self) -> Ref<'_, [String]> {
+        Ref::map(self.rows.borrow(), Vec::as_slice)
+    }
+
+    /// Get the selected row
+    ///
+    /// In multiple mode, this is the cursor row.
+    pub fn selected(&self) -> Option<usize> {
+        self.selection.borrow().cursor()
+    }
+
+    /// Get all selected rows, in order
+    pub fn selections(&self) -> Vec<usize> {
+        self.selection.borrow().selections()
+    }
+
+    /// Select a row
+    ///
+    /// * `row`: Row to select (clamped to the last row)
+    pub fn select(&self, row: usize) {
+        self.selection.borrow_mut().move_to(row, false);
+    }
+
+    /// Move a row, keeping the selected rows selected
+    fn move_row(&self, from: usize, to: usize) -> Option<Action> {
+        move_item(&mut self.rows.borrow_mut(), from, to);
+        if let Some(styled) = self.styled.borrow_mut().as_mut() {
+            move_item(styled, from, to);
+        }
+        self.selection.borrow_mut().move_row(from, to);
+        Some(Action::Reordered(WidgetId::of(self), from, to))
+    }
+
+    /// Scroll by one row while a drag rests at an edge of the view
+    ///
+    /// The row is revealed by a wrapping [ScrollView], which follows the
+    /// [cursor row](Widget::cursor_row).
+    ///
+    /// [ScrollView]: struct.ScrollView.html
+    fn auto_scroll(
+        &self,
+        reorder: &mut DragReorder,
+        cells: &mut Cells,
+        top: u16,
+        height: u16,
+    ) {
+        if !reorder.is_dragging() {
+            self.scrolled.set(None);
+            return;
+        }
+        let now = cells.frame_time();
+        match self.scrolled.get() {
+            Some(at) if now < at + SCROLL_INTERVAL => cells.request_frame(),
+            _ => {
+                if reorder.tick(top, height).is_some() {
+                    self.scrolled.set(Some(now));
+                    cells.request_frame();
+                }
+            }
+        }
+    }
+
+    /// Get the style overlay of a row
+    ///
+    /// * `theme`: Theme for drawing
+    /// * `selection`: Row selection
+    /// * `reorder`: Drag reordering, if enabled
+    /// * `row`: Row index
+    fn row_overlay(
+        theme: &Theme,
+        selection: &Selection,
+        reorder: Option<&DragReorder>,
+        row: usize,
+    ) -> Option<StyleOverlay> {
+        let group = reorder
+            .and_then(|r| r.row_style(row))
+            .or_else(|| selection.row_style(row));
+        let overlay = group.map(|group| row_overlay(theme, group));
+        match reorder.and_then(|r| r.indicator()) {
+            Some(0) if row == 0 => {
+                Some(overlay.unwrap_or_default().with_background(theme.primary))
+            }
+            Some(ind) if ind == row + 1 => {
+                Some(overlay.unwrap_or_default().with_underline(true))
+            }
+            _ => overlay,
+        }
+    }
+}
+
+/// Get the style overlay for highlighting a row
+///
+/// * `theme`: Theme for drawing
+/// * `group`: Style group of the row
+///
+/// The background is the accent color of the group, so that the colors of
+/// the text are kept.
+fn row_overlay(theme: &Theme, group: StyleGroup) -> StyleOverlay {
+    StyleOverlay::default().with_background(theme.style(group).foreground())
+}
+
+impl PersistState for ListView {
+    /// Save the selected rows
+    fn save_state(&self) -> Option<StateBlob> {
+        let selection = self.selection.borrow();
+        let mut values = vec![selection.cursor()? as u64];
+        if selection.mode() == SelectMode::Multiple {
+            values.extend(selection.selections().iter().map(|r| *r as u64));
+        }
+        Some(StateBlob::new(STATE_VERSION).with_values(values))
+    }
+
+    /// Restore the selected rows
+    ///
+    /// Rows past the last row are clamped (for the cursor) or ignored.
+    fn restore_state(&self, blob: &StateBlob) {
+        if let (STATE_VERSION, [cursor, rows @ ..]) =
+            (blob.version(), blob.values())
+        {
+            let rows: Vec<usize> = rows
+                .iter()
+                .filter_map(|r| usize::try_from(*r).ok())
+                .collect();
+            let cursor = usize::try_from(*cursor).unwrap_or(usize::MAX);
+            let mut selection = self.selection.borrow_mut();
+            selection.set_selected(&rows);
+            selection.move_to(cursor, false);
+        }
+    }
+}
+
+impl Widget for ListView {
+    /// Get the persistent state
+    fn persist(&self) -> Option<&dyn PersistState> {
+        Some(self)
+    }
+
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        let w = self.rows.borrow().iter().map(|r| display_width(r)).max();
+        let w = w.unwrap_or(0).try_into().unwrap_or(u16::MAX);
+        LengthBound::new(w..)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        let rows = self.rows.borrow().len().try_into().unwrap_or(u16::MAX);
+        LengthBound::new(rows..=rows)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        let width = cells.width();
+        let height = cells.height();
+        self.page.set(height);
+        self.top.set(offset.row);
+        let mut reorder = self.reorder.as_ref().map(|r| r.borrow_mut());
+        if let Some(reorder) = reorder.as_deref_mut() {
+            self.auto_scroll(reorder, cells, offset.row, height);
+        }
+        let selection = self.selection.borrow();
+        let rows = self.rows.borrow();
+        let styled = self.styled.borrow();
+        let visible = rows.iter().enumerate().skip(offset.row.into());
+        for (row, (i, text)) in visible.take(height.into()).enumerate() {
+            let row = row as u16; // limited to u16 by take(height)
+            let overlay = Self::row_overlay(
+                cells.theme(),
+                &selection,
+                reorder.as_deref(),
+                i,
+            );
+            if let Some(overlay) = overlay {
+                cells.push_style_overlay(overlay)?;
+                cells.move_to(0, row)?;
+                cells.print_str(&" ".repeat(width.into()))?;
+            }
+            if let Some((col, span)) = visible_span(text, offset.col, width) {
+                cells.move_to(col, row)?;
+                match &*styled {
+                    Some(styled) => {
+                        // Byte index of the span, which is a slice of text
+                        let start =
+                            span.as_ptr() as usize - text.as_ptr() as usize;
+                        cells.print_spans(&styled[i], start, span, span)?;
+                    }
+                    None => cells.print_str(span)?,
+                }
+            }
+            if overlay.is_some() {
+                cells.pop_style_overlay()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether the list can accept focus
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    /// Get the selected row (or drag target), to keep it visible
+    fn cursor_row(&self) -> Option<u16> {
+        let target = self.reorder.as_ref().and_then(|r| r.borrow().target());
+        target
+            .or_else(|| self.selected())
+            .and_then(|row| row.try_into().ok())
+    }
+
+    /// Handle key events
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        if let (Some(reorder), Some(row)) = (&self.reorder, self.selected()) {
+            let mut reorder = reorder.borrow_mut();
+            let lifted = reorder.is_dragging();
+            if let Some((from, to)) = reorder.key_event(key, mods, row) {
+                return self.move_row(from, to);
+            }
+            if lifted && !reorder.is_dragging() {
+                return Some(Action::Redraw());
+            }
+        }
+        let page = usize::from(self.page.get());
+        self.selection
+            .borrow_mut()
+            .key_event(key, mods, page)
+            .then_some(Action::Redraw())
+    }
+
+    /// Handle mouse events
+    fn mouse_event(
+        &self,
+        mev: MouseEvent,
+        mods: ModKeys,
+        _dim: Dim,
+        pos: WidgetPos,
+    ) -> Option<Action> {
+        if let Some(reorder) = &self.reorder {
+            let mut reorder = reorder.borrow_mut();
+            let (lifted, target) = (reorder.is_dragging(), reorder.target());
+            let top = self.top.get();
+            let pointer = pos.row.saturating_sub(top);
+            if let Some((from, to)) = reorder.mouse_event(mev, pointer, top) {
+                return self.move_row(from, to);
+            }
+            if reorder.is_dragging() != lifted || reorder.target() != target {
+                return Some(Action::Redraw());
+            }
+        }
+        let row = usize::from(pos.row);
+        if mev != MouseEvent::ButtonDown(MouseButton::Left)
+            || row >= self.rows.borrow().len()
+        {
+            return None;
+        }
+        self.selection.borrow_mut().click(row, mods);
+        Some(Action::Redraw())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::to_text;
+    use crate::grid_area;
+    use crate::input::NavKey;
+
+    /// Create a list with a number of rows
+    fn list(len: usize) -> ListView {
+        ListView::new((0..len).map(|i| format!("item {}", i)).collect())
+    }
+
+    /// Press a navigation key
+    fn press(lv: &ListView, key: NavKey) -> Option<Action> {
+        lv.key_event(KeyPress::Navigation(key), ModKeys::EMPTY)
+    }
+
+    /// Get the style of the first cell of a row
+    fn style(screen: &crate::Screen, row: u16) -> crate::text::TextStyle {
+        screen.snapshot().cell(0, row).unwrap().style()
+    }
+
+    const DOWN: MouseEvent = MouseEvent::ButtonDown(MouseButton::Left);
+    const DRAG: MouseEvent = MouseEvent::Drag(Some(MouseButton::Left));
+    const UP: MouseEvent = MouseEvent::ButtonUp(MouseButton::Left);
+
+    /// Send a mouse event at a row
+    fn mouse(lv: &ListView, mev: MouseEvent, row: u16) -> Option<Action> {
+        let dim = Dim::new(6, 5);
+        lv.mouse_event(mev, ModKeys::EMPTY, dim, WidgetPos::new(1, row))
+    }
+
+    #[test]
+    fn navigation() -> Result<()> {
+        let lv = list(10);
+        assert_eq!(lv.height_bounds(&Theme::default(), 6).minimum(), 10);
+        assert_eq!(lv.selected(), Some(0));
+        assert_eq!(press(&lv, NavKey::Down), Some(Action::Redraw()));
+        assert_eq!(lv.selected(), Some(1));
+        let grid = grid_area!([lv])?;
+        let text = to_text(&grid, Dim::new(6, 4), &Theme::default())?;
+        assert_eq!(text, "item 0\nitem 1\nitem 2\nitem 3\n");
+        assert_eq!(press(&lv, NavKey::PageDown), Some(Action::Redraw()));
+        assert_eq!(lv.selected(), Some(5));
+        press(&lv, NavKey::End);
+        assert_eq!(lv.selected(), Some(9));
+        press(&lv, NavKey::Down);
+        assert_eq!(lv.selected(), Some(9));
+        press(&lv, NavKey::PageUp);
+        assert_eq!(lv.selected(), Some(5));
+        press(&lv, NavKey::Home);
+        assert_eq!(lv.selected(), Some(0));
+        assert_eq!(press(&lv, NavKey::Left), None);
+        Ok(())
+    }
+
+    #[test]
+    fn click() {
+        let lv = list(3);
+        let dim = Dim::new(6, 3);
+        let down = MouseEvent::ButtonDown(MouseButton::Left);
+        let pos = |row| WidgetPos::new(1, row);
+        assert_eq!(
+            lv.mouse_event(down, ModKeys::EMPTY, dim, pos(2)),
+            Some(Action::Redraw())
+        );
+        assert_eq!(lv.selected(), Some(2));
+        assert_eq!(lv.mouse_event(down, ModKeys::EMPTY, dim, pos(5)), None);
+        assert_eq!(lv.selected(), Some(2));
+    }
+
+    #[test]
+    fn styled_selection() -> Result<()> {
+        use crate::text::{Color, Intensity};
+        use crate::Screen;
+        let theme = Theme::default();
+        let base = theme.style(StyleGroup::Enabled);
+        let bg = theme.focused;
+        let red = base.with_foreground(Color::Red(Intensity::Bright));
+        let green = base.with_foreground(Color::Green(Intensity::Bright));
+        let row = |a: &str, b: &str| {
+            StyledText::new().span(a, red).plain("-").span(b, green)
+        };
+        let lv = ListView::from_styled(vec![row("a", "b"), row("c", "d")]);
+        let mut screen = Screen::headless(Dim::new(4, 2), theme);
+        let grid = grid_area!([lv])?;
+        screen.step_with_events(&grid, [])?;
+        assert_eq!(screen.text(), "a-b \nc-d \n");
+        let style =
+            |col, row| screen.snapshot().cell(col, row).unwrap().style();
+        // Selected row keeps its colors, with the selection background
+        assert_eq!(style(0, 0), red.with_background(bg));
+        assert_eq!(style(1, 0), base.with_background(bg));
+        assert_eq!(style(2, 0), green.with_background(bg));
+        assert_eq!(style(3, 0), base.with_background(bg));
+        assert_eq!(style(0, 1), red);
+        assert_eq!(style(1, 1), base);
+        assert_eq!(style(2, 1), green);
+        assert_eq!(style(3, 1), base);
+        Ok(())
+    }
+
+    #[test]
+    fn persist() {
+        let lv = list(10).with_mode(SelectMode::Multiple);
+        lv.selection.borrow_mut().set_selected(&[2, 3, 7]);
+        lv.select(5);
+        let blob = lv.save_state().unwrap();
+        assert_eq!(blob.values(), [5, 2, 3, 7]);
+        let restored = list(10).with_mode(SelectMode::Multiple);
+        restored.restore_state(&blob);
+        assert_eq!(restored.selected(), Some(5));
+        assert_eq!(restored.selections(), [2, 3, 7]);
+        // Stale rows past the new end are clamped or ignored
+        let short = list(4).with_mode(SelectMode::Multiple);
+        short.restore_state(&blob);
+        assert_eq!(short.selected(), Some(3));
+        assert_eq!(short.selections(), [2, 3]);
+        let single = list(3);
+        single
+            .restore_state(&StateBlob::new(STATE_VERSION).with_values(vec![9]));
+        assert_eq!(single.selected(), Some(2));
+        // Empty lists and mismatched blobs are ignored
+        let empty = list(0);
+        empty.restore_state(&blob);
+        assert_eq!(empty.selected(), None);
+        assert_eq!(empty.save_state(), None);
+        single.restore_state(&StateBlob::new(STATE_VERSION));
+        single.restore_state(&StateBlob::new(0).with_values(vec![0]));
+        assert_eq!(single.selected(), Some(2));
+    }
+
+    #[test]
+    fn reorder_drag() -> Result<()> {
+        use crate::Screen;
+        let lv = list(5).with_reorder(true);
+        let id = WidgetId::of(&lv);
+        assert_eq!(mouse(&lv, DOWN, 1), Some(Action::Redraw()));
+        assert_eq!(mouse(&lv, DRAG, 1), None);
+        assert_eq!(mouse(&lv, DRAG, 3), Some(Action::Redraw()));
+        assert_eq!(lv.cursor_row(), Some(3));
+        // Lifted row, with an indicator below the row it would follow
+        let theme = Theme::default();
+        let mut screen = Screen::headless(Dim::new(6, 5), theme.clone());
+        let grid = grid_area!([lv])?;
+        screen.render(&grid)?;
+        assert_eq!(style(&screen, 1).background(), theme.interacting);
+        assert!(style(&screen, 3).appearance().underline());
+        assert!(!style(&screen, 2).appearance().underline());
+        assert!(!style(&screen, 4).appearance().underline());
+        assert_eq!(mouse(&lv, UP, 3), Some(Action::Reordered(id, 1, 3)));
+        let rows = ["item 0", "item 2", "item 3", "item 1", "item 4"];
+        assert_eq!(&lv.rows()[..], rows);
+        assert_eq!(lv.selected(), Some(3));
+        // Moving to the top tints the first row
+        mouse(&lv, DOWN, 2);
+        mouse(&lv, DRAG, 0);
+        screen.render(&grid)?;
+        assert_eq!(style(&screen, 0).background(), theme.primary);
+        assert_eq!(mouse(&lv, UP, 0), Some(Action::Reordered(id, 2, 0)));
+        assert_eq!(lv.rows()[0], "item 3");
+        Ok(())
+    }
+
+    #[test]
+    fn reorder_cancel() {
+        let lv = list(4).with_reorder(true);
+        let esc = KeyPress::Navigation(NavKey::Esc);
+        mouse(&lv, DOWN, 0);
+        mouse(&lv, DRAG, 2);
+        assert_eq!(lv.cursor_row(), Some(2));
+        assert_eq!(lv.key_event(esc, ModKeys::EMPTY), Some(Action::Redraw()));
+        assert_eq!(lv.cursor_row(), Some(0));
+        assert_eq!(mouse(&lv, DRAG, 3), None);
+        assert_eq!(mouse(&lv, UP, 3), None);
+        let rows = ["item 0", "item 1", "item 2", "item 3"];
+        assert_eq!(&lv.rows()[..], rows);
+        assert_eq!(lv.selected(), Some(0));
+        // Esc is not handled without a drag
+        assert_eq!(lv.key_event(esc, ModKeys::EMPTY), None);
+    }
+
+    #[test]
+    fn reorder_keys() {
+        let lv = list(3).with_reorder(true);
+        let id = WidgetId::of(&lv);
+        let down = KeyPress::Navigation(NavKey::Down);
+        let up = KeyPress::Navigation(NavKey::Up);
+        assert_eq!(
+            lv.key_event(down, ModKeys::CONTROL),
+            Some(Action::Reordered(id, 0, 1))
+        );
+        assert_eq!(lv.selected(), Some(1));
+        assert_eq!(&lv.rows()[..], ["item 1", "item 0", "item 2"]);
+        assert_eq!(
+            lv.key_event(up, ModKeys::CONTROL),
+            Some(Action::Reordered(id, 1, 0))
+        );
+        assert_eq!(lv.selected(), Some(0));
+        assert_eq!(lv.key_event(up, ModKeys::CONTROL), None);
+        assert_eq!(&lv.rows()[..], ["item 0", "item 1", "item 2"]);
+        // Without reordering, nothing is moved
+        let lv = list(3);
+        assert_eq!(lv.key_event(down, ModKeys::CONTROL), None);
+        assert_eq!(&lv.rows()[..], ["item 0", "item 1", "item 2"]);
+    }
+
+    #[test]
+    fn reorder_multiple() {
+        let lv = list(5).with_mode(SelectMode::Multiple).with_reorder(true);
+        lv.selection.borrow_mut().set_selected(&[1, 3]);
+        lv.select(1);
+        let down = KeyPress::Navigation(NavKey::Down);
+        lv.key_event(down, ModKeys::CONTROL);
+        assert_eq!(lv.rows()[2], "item 1");
+        assert_eq!(lv.selected(), Some(2));
+        assert_eq!(lv.selections(), [2, 3]);
+        mouse(&lv, DOWN, 3);
+        mouse(&lv, DRAG, 0);
+        mouse(&lv, UP, 0);
+        assert_eq!(lv.rows()[0], "item 3");
+        assert_eq!(lv.selected(), Some(0));
+        assert_eq!(lv.selections(), [0, 3]);
+    }
+
+    #[test]
+    fn reorder_auto_scroll() -> Result<()> {
+        use crate::input::Event;
+        use crate::layout::ScreenPos;
+        use crate::Screen;
+        let sv = list(10).with_reorder(true).into_scroll_view();
+        let grid = grid_area!([sv])?;
+        let mut screen = Screen::headless(Dim::new(8, 4), Theme::default());
+        let mut now = Instant::now();
+        screen.set_time(now);
+        let ev = |mev, row| {
+            Event::Mouse(mev, ModKeys::EMPTY, ScreenPos::new(1, row))
+        };
+        // Drag the second row to the bottom row of the view
+        let events = [ev(DOWN, 1), ev(DRAG, 2), ev(DRAG, 3)];
+        screen.step_with_events(&grid, events)?;
+        let lv = sv.wrapped();
+        assert_eq!(lv.cursor_row(), Some(4));
+        assert!(screen.next_frame().is_some());
+        // Not scrolled again until the interval elapses
+        screen.render(&grid)?;
+        assert_eq!(lv.cursor_row(), Some(4));
+        while screen.next_frame().is_some() {
+            now += SCROLL_INTERVAL;
+            screen.set_time(now);
+            screen.render(&grid)?;
+        }
+        assert_eq!(lv.cursor_row(), Some(9));
+        assert!(screen.text().ends_with("item 9 ░\n"), "{}", screen.text());
+        let actions = screen.step_with_events(&grid, [ev(UP, 3)])?;
+        assert_eq!(actions, [Action::Reordered(WidgetId::of(lv), 1, 9)]);
+        assert_eq!(lv.rows()[9], "item 1");
+        // Scrolling back up
+        let events = [ev(DOWN, 2), ev(DRAG, 1), ev(DRAG, 0)];
+        screen.step_with_events(&grid, events)?;
+        while screen.next_frame().is_some() {
+            now += SCROLL_INTERVAL;
+            screen.set_time(now);
+            screen.render(&grid)?;
+        }
+        assert_eq!(lv.cursor_row(), Some(0));
+        assert!(screen.text().starts_with("item 0"), "{}", screen.text());
+        Ok(())
+    }
+
+    #[test]
+    fn scroll_view() -> Result<()> {
+        let sv = list(10).into_scroll_view();
+        let grid = grid_area!([sv])?;
+        let theme = Theme::default();
+        let dim = Dim::new(7, 3);
+        to_text(&grid, dim, &theme)?;
+        for _ in 0..4 {
+            sv.key_event(KeyPress::Navigation(NavKey::Down), ModKeys::EMPTY);
+        }
+        assert_eq!(sv.wrapped().selected(), Some(4));
+        let text = to_text(&grid, dim, &theme)?;
+        assert!(text.starts_with("item 2"), "{:?}", text);
+        assert!(text.contains("item 4"), "{:?}", text);
+        Ok(())
+    }
+}
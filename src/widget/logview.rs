@@ -0,0 +1,333 @@
+// logview.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::layout::{Cells, ContentPos, LengthBound};
+use crate::text::{display_width, truncate_end, StyleGroup, Theme};
+use crate::{Result, Widget};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+/// Severity of a log line
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Severity {
+    /// Informational
+    #[default]
+    Info,
+    /// Warning
+    Warning,
+    /// Error
+    Error,
+}
+
+/// One line of a log
+#[derive(Debug)]
+struct LogLine {
+    /// Line text
+    text: String,
+    /// Line severity
+    severity: Severity,
+    /// Number of wrapped rows, if known
+    rows: Cell<Option<u16>>,
+}
+
+/// Log view widget
+///
+/// Lines are appended while the widget is in use, such as the output of a
+/// build.  Once the capacity is reached, the oldest lines are dropped.
+///
+/// The log is as tall as its lines, so it should be wrapped in a
+/// [ScrollView].  The scroll view follows the end of the log as lines are
+/// appended, unless it has been scrolled up.
+///
+/// Each line has a [Severity], drawn with a style group from the theme:
+/// `Enabled` for info, `Primary` for warnings and `Error` for errors.
+///
+/// [ScrollView]: struct.ScrollView.html
+#[derive(Debug)]
+pub struct LogView {
+    /// Lines of the log
+    lines: RefCell<VecDeque<LogLine>>,
+    /// Maximum number of lines
+    capacity: usize,
+    /// Wrap long lines (instead of truncating)
+    wrap: bool,
+    /// Width of wrapped rows
+    wrap_width: Cell<u16>,
+    /// Style groups for each severity
+    styles: [StyleGroup; 3],
+    /// Rows removed from the front since last taken
+    removed: Cell<usize>,
+}
+
+impl Severity {
+    /// Get the index of a severity
+    fn index(self) -> usize {
+        match self {
+            Severity::Info => 0,
+            Severity::Warning => 1,
+            Severity::Error => 2,
+        }
+    }
+}
+
+impl LogView {
+    /// Create a new log view widget
+    ///
+    /// * `capacity`: Maximum number of lines
+    pub fn new(capacity: usize) -> Self {
+        LogView {
+            lines: RefCell::new(VecDeque::new()),
+            capacity,
+            wrap: false,
+            wrap_width: Cell::new(0),
+            styles: [
+                StyleGroup::Enabled,
+                StyleGroup::Primary,
+                StyleGroup::Error,
+            ],
+            removed: Cell::new(0),
+        }
+    }
+
+    /// Set whether long lines are wrapped
+    ///
+    /// By default, long lines are truncated with an ellipsis.
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Set the style group for lines of a severity
+    pub fn with_severity_style(
+        mut self,
+        severity: Severity,
+        group: StyleGroup,
+    ) -> Self {
+        self.styles[severity.index()] = group;
+        self
+    }
+
+    /// Get the number of lines
+    pub fn len(&self) -> usize {
+        self.lines.borrow().len()
+    }
+
+    /// Check if there are no lines
+    pub fn is_empty(&self) -> bool {
+        self.lines.borrow().is_empty()
+    }
+
+    /// Append an info line
+    pub fn push_line(&self, line: &str) {
+        self.push(Severity::Info, line);
+    }
+
+    /// Append a line with a severity
+    ///
+    /// Text after any `\n` is appended as more lines.
+    pub fn push(&self, severity: Severity, line: &str) {
+        {
+            let mut lines = self.lines.borrow_mut();
+            for text in line.split('\n') {
+                lines.push_back(LogLine {
+                    text: text.to_string(),
+                    severity,
+                    rows: Cell::new(None),
+                });
+            }
+        }
+        self.evict();
+    }
+
+    /// Remove all lines
+    pub fn clear(&self) {
+        let rows = self.total_rows();
+        self.lines.borrow_mut().clear();
+        self.removed.set(self.removed.get() + rows);
+    }
+
+    /// Drop lines past the capacity
+    fn evict(&self) {
+        let mut lines = self.lines.borrow_mut();
+        let excess = lines.len().saturating_sub(self.capacity);
+        let rows: usize = lines
+            .drain(..excess)
+            .map(|line| usize::from(line.rows.get().unwrap_or(1)))
+            .sum();
+        self.removed.set(self.removed.get() + rows);
+    }
+
+    /// Get the number of rows of one line
+    fn line_rows(&self, theme: &Theme, line: &LogLine) -> u16 {
+        if !self.wrap {
+            return 1;
+        }
+        match line.rows.get() {
+            Some(rows) => rows,
+            None => {
+                let width = self.wrap_width.get();
+                let rows = theme.wrap(&line.text, width).len().max(1);
+                let rows = rows.try_into().unwrap_or(u16::MAX);
+                line.rows.set(Some(rows));
+                rows
+            }
+        }
+    }
+
+    /// Get the total number of rows, from the last layout
+    fn total_rows(&self) -> usize {
+        let lines = self.lines.borrow();
+        lines
+            .iter()
+            .map(|line| usize::from(line.rows.get().unwrap_or(1)))
+            .sum()
+    }
+}
+
+impl Widget for LogView {
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        LengthBound::new(1..)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, theme: &Theme, width: u16) -> LengthBound {
+        if self.wrap && width != self.wrap_width.get() {
+            self.wrap_width.set(width);
+            for line in self.lines.borrow().iter() {
+                line.rows.set(None);
+            }
+        }
+        let lines = self.lines.borrow();
+        let rows: usize = lines
+            .iter()
+            .map(|l| usize::from(self.line_rows(theme, l)))
+            .sum();
+        let rows = rows.try_into().unwrap_or(u16::MAX);
+        LengthBound::new(rows..=rows)
+    }
+
+    /// Take the number of rows removed from the top
+    fn take_rows_removed(&self) -> u16 {
+        self.removed.take().try_into().unwrap_or(u16::MAX)
+    }
+
+    /// Follow the end of the log in a wrapping scroll view
+    fn tail(&self) -> bool {
+        true
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        let width = cells.width();
+        let height = cells.height();
+        let theme = cells.theme().clone();
+        let normal = theme.style(self.style_group());
+        let lines = self.lines.borrow();
+        let mut skip = offset.row;
+        let mut row = 0;
+        for line in lines.iter() {
+            if row >= height {
+                break;
+            }
+            let rows = self.line_rows(&theme, line);
+            if skip >= rows {
+                skip -= rows;
+                continue;
+            }
+            let group = self.styles[line.severity.index()];
+            if group != self.style_group() {
+                cells.set_style(theme.style(group))?;
+            }
+            let texts = if self.wrap {
+                theme.wrap(&line.text, self.wrap_width.get())
+            } else {
+                vec![truncate_end(&line.text, usize::from(width)).into()]
+            };
+            for text in texts.iter().skip(usize::from(skip)) {
+                if row >= height {
+                    break;
+                }
+                if display_width(text) > 0 {
+                    cells.move_to(0, row)?;
+                    cells.print_str(text)?;
+                }
+                row += 1;
+            }
+            skip = 0;
+            if group != self.style_group() {
+                cells.set_style(normal)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::to_text;
+    use crate::grid_area;
+    use crate::layout::Dim;
+
+    #[test]
+    fn capacity() {
+        let log = LogView::new(3);
+        log.push_line("one");
+        log.push(Severity::Warning, "two\nthree");
+        assert_eq!(log.take_rows_removed(), 0);
+        log.push(Severity::Error, "four");
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.take_rows_removed(), 1);
+        let theme = Theme::default();
+        assert_eq!(log.height_bounds(&theme, 10), LengthBound::new(3..=3));
+        log.clear();
+        assert!(log.is_empty());
+        assert_eq!(log.take_rows_removed(), 3);
+    }
+
+    #[test]
+    fn wrap() -> Result<()> {
+        let theme = Theme::default();
+        let log = LogView::new(10);
+        log.push_line("a long line");
+        log.push_line("b");
+        let grid = grid_area!([log])?;
+        let text = to_text(&grid, Dim::new(6, 2), &theme)?;
+        assert_eq!(text, "a lon…\nb     \n");
+        let log = LogView::new(10).with_wrap(true);
+        log.push_line("a long line");
+        log.push_line("b");
+        assert_eq!(log.height_bounds(&theme, 6), LengthBound::new(3..=3));
+        let grid = grid_area!([log])?;
+        let text = to_text(&grid, Dim::new(6, 3), &theme)?;
+        assert_eq!(text, "a long\nline  \nb     \n");
+        Ok(())
+    }
+
+    #[test]
+    fn tail() -> Result<()> {
+        let log = LogView::new(100).into_scroll_view();
+        let grid = grid_area!([log])?;
+        let theme = Theme::default();
+        let dim = Dim::new(4, 2);
+        for i in 0..5 {
+            log.wrapped().push_line(&i.to_string());
+        }
+        let text = to_text(&grid, dim, &theme)?;
+        assert!(text.starts_with("3"), "{:?}", text);
+        log.wrapped().push_line("5");
+        let text = to_text(&grid, dim, &theme)?;
+        assert!(text.starts_with("4"), "{:?}", text);
+        // Scrolled up, the view stays in place
+        log.scroll_to_top();
+        log.wrapped().push_line("6");
+        let text = to_text(&grid, dim, &theme)?;
+        assert!(text.starts_with("0"), "{:?}", text);
+        log.scroll_to_bottom();
+        let text = to_text(&grid, dim, &theme)?;
+        assert!(text.starts_with("5"), "{:?}", text);
+        Ok(())
+    }
+}
@@ -0,0 +1,512 @@
+// menubar.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{
+    Action, FocusEvent, KeyPress, ModKeys, MouseButton, MouseEvent, NavKey,
+};
+use crate::layout::{
+    BBox, Cells, ContentPos, Dim, GridArea, GridItem, LengthBound, WidgetPos,
+};
+use crate::text::{display_width, StyleGroup, Theme};
+use crate::{Popup, Result, Widget};
+use std::cell::Cell;
+
+/// Menu of a menu bar
+struct Menu {
+    /// Menu title
+    title: String,
+    /// Item labels and action codes
+    items: Vec<(String, u32)>,
+}
+
+/// Dropdown list of the open menu
+///
+/// This holds the menus, so that they can be navigated from the dropdown
+/// popup or the bar.
+#[derive(Default)]
+struct Dropdown {
+    /// All menus
+    menus: Vec<Menu>,
+    /// Index of open menu
+    open: Cell<Option<usize>>,
+    /// Index of current menu title
+    current: Cell<usize>,
+    /// Index of selected item in open menu
+    item: Cell<usize>,
+}
+
+/// Menu bar widget
+///
+/// A one-row bar of menu titles.  Clicking a title, or pressing `Enter` or
+/// `Down` while the bar is focused, opens a dropdown list of its items.
+///
+/// The dropdown is drawn as a [Popup] below the title, or above it if there
+/// is no room below.  Within it, `Up` and `Down` select an item, `Left` and
+/// `Right` open the neighboring menus, and `Esc` closes it.  Choosing an
+/// item closes the dropdown and returns [Action::Custom] with the item's
+/// code.
+///
+/// ```no_run
+/// # use semtext::input::Action;
+/// # use semtext::widget::{Label, MenuBar};
+/// # use semtext::{grid_area, Screen};
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut screen = Screen::new()?;
+/// let menu = MenuBar::new()
+///     .with_menu("File", &[("Open", 1), ("Quit", 2)])
+///     .with_menu("Help", &[("About", 3)]);
+/// let body = Label::new("Hello");
+/// let grid = grid_area!([menu][body])?;
+/// loop {
+///     let dropdown = menu.dropdown_area()?;
+///     let popup = menu.popup(&dropdown);
+///     match screen.step_with_popup(&grid, popup.as_ref()).await? {
+///         Action::Quit() | Action::Custom(2) => break,
+///         Action::Dismissed() => menu.close(),
+///         _ => (),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [Action::Custom]: ../input/enum.Action.html#variant.Custom
+/// [Popup]: ../struct.Popup.html
+#[derive(Default)]
+pub struct MenuBar {
+    /// Dropdown list (with menus)
+    dropdown: Dropdown,
+    /// Keyboard focus flag
+    focused: Cell<bool>,
+    /// Index of title under the pointer
+    hovered: Cell<Option<usize>>,
+    /// Bar bounding box, from last draw
+    anchor: Cell<BBox>,
+    /// Screen bounding box, from last draw
+    screen: Cell<BBox>,
+}
+
+impl Menu {
+    /// Get the width of the title, with padding
+    fn title_width(&self) -> u16 {
+        let w = display_width(&self.title).saturating_add(2);
+        w.try_into().unwrap_or(u16::MAX)
+    }
+
+    /// Get the width of the dropdown, with padding
+    fn items_width(&self) -> u16 {
+        let w = self.items.iter().map(|(label, _)| display_width(label));
+        let w = w.max().unwrap_or(0).saturating_add(2);
+        w.try_into().unwrap_or(u16::MAX)
+    }
+
+    /// Get the number of items
+    fn items_height(&self) -> u16 {
+        self.items.len().try_into().unwrap_or(u16::MAX)
+    }
+}
+
+impl Dropdown {
+    /// Open a menu
+    fn open(&self, m: usize) {
+        let open = self.menus.get(m).is_some_and(|m| !m.items.is_empty());
+        self.open.set(open.then_some(m));
+        self.current.set(m);
+        self.item.set(0);
+    }
+
+    /// Open the next or previous menu
+    fn open_next(&self, forward: bool) {
+        let len = self.menus.len();
+        let m = self.current.get();
+        let m = if forward { m + 1 } else { m + len - 1 };
+        self.open(m % len);
+    }
+
+    /// Select an item of the open menu, closing it
+    fn select(&self, i: usize) -> Option<Action> {
+        let m = self.open.get()?;
+        let (_, code) = self.menus[m].items.get(i)?;
+        self.open.set(None);
+        Some(Action::Custom(*code))
+    }
+
+    /// Get the open menu
+    fn menu(&self) -> Option<&Menu> {
+        self.open.get().map(|m| &self.menus[m])
+    }
+}
+
+impl Widget for Dropdown {
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        let w = self.menu().map_or(0, Menu::items_width);
+        LengthBound::new(w..)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        let rows = self.menu().map_or(0, Menu::items_height);
+        LengthBound::new(rows..=rows)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+        let menu = match self.menu() {
+            Some(menu) => menu,
+            None => return Ok(()),
+        };
+        let width = usize::from(cells.width());
+        let theme = cells.theme().clone();
+        let normal = theme.style(self.style_group());
+        let items = menu.items.iter().enumerate();
+        for (i, (label, _)) in items.take(cells.height().into()) {
+            let row = i as u16; // limited to u16 by take(height)
+            let selected = i == self.item.get();
+            if selected {
+                cells.set_style(theme.style(StyleGroup::Focused))?;
+            }
+            let pad = width.saturating_sub(display_width(label) + 1);
+            cells.move_to(0, row)?;
+            cells.print_str(&format!(" {}{}", label, " ".repeat(pad)))?;
+            if selected {
+                cells.set_style(normal)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether the dropdown can accept focus
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    /// Handle key events
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        let menu = self.menu()?;
+        let item = self.item.get();
+        match (key, mods) {
+            (KeyPress::Navigation(NavKey::Up), ModKeys::EMPTY) => {
+                self.item.set(item.saturating_sub(1));
+            }
+            (KeyPress::Navigation(NavKey::Down), ModKeys::EMPTY) => {
+                self.item.set((item + 1).min(menu.items.len() - 1));
+            }
+            (KeyPress::Navigation(NavKey::Left), ModKeys::EMPTY) => {
+                self.open_next(false);
+            }
+            (KeyPress::Navigation(NavKey::Right), ModKeys::EMPTY) => {
+                self.open_next(true);
+            }
+            (KeyPress::Navigation(NavKey::Enter), ModKeys::EMPTY) => {
+                return self.select(item);
+            }
+            (KeyPress::Navigation(NavKey::Esc), _) => self.open.set(None),
+            _ => return None,
+        }
+        Some(Action::Redraw())
+    }
+
+    /// Handle mouse events
+    fn mouse_event(
+        &self,
+        mev: MouseEvent,
+        _mods: ModKeys,
+        _dim: Dim,
+        pos: WidgetPos,
+    ) -> Option<Action> {
+        let menu = self.menu()?;
+        let row = usize::from(pos.row);
+        match mev {
+            MouseEvent::Drag(None)
+                if row != self.item.get() && row < menu.items.len() =>
+            {
+                self.item.set(row);
+                Some(Action::Redraw())
+            }
+            MouseEvent::ButtonUp(MouseButton::Left) => self.select(row),
+            _ => None,
+        }
+    }
+}
+
+impl MenuBar {
+    /// Create a new menu bar widget
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a menu
+    ///
+    /// * `title`: Menu title
+    /// * `items`: Item labels, with a code for the [Action::Custom] returned
+    ///   when chosen
+    ///
+    /// [Action::Custom]: ../input/enum.Action.html#variant.Custom
+    pub fn with_menu(mut self, title: &str, items: &[(&str, u32)]) -> Self {
+        let title = title.to_string();
+        let items = items
+            .iter()
+            .map(|(label, code)| (label.to_string(), *code))
+            .collect();
+        self.dropdown.menus.push(Menu { title, items });
+        self
+    }
+
+    /// Check if a dropdown is open
+    pub fn is_open(&self) -> bool {
+        self.dropdown.open.get().is_some()
+    }
+
+    /// Close the open dropdown
+    ///
+    /// This should be called when the dropdown popup is dismissed.
+    pub fn close(&self) {
+        self.dropdown.open.set(None);
+    }
+
+    /// Get a grid area containing the dropdown list
+    pub fn dropdown_area(&self) -> Result<GridArea<'_>> {
+        GridArea::new(&[GridItem::Widget(&self.dropdown)], 1)
+    }
+
+    /// Get a popup for the open dropdown
+    ///
+    /// * `area`: Grid area from [dropdown_area](MenuBar::dropdown_area)
+    ///
+    /// ## Return
+    ///
+    /// Popup below (or above) the open menu title, or `None` if closed.
+    pub fn popup<'p, 'a>(
+        &self,
+        area: &'p GridArea<'a>,
+    ) -> Option<Popup<'p, 'a>> {
+        let bbox = self.dropdown_bbox()?;
+        Some(Popup::new(area, bbox).with_dismiss(true))
+    }
+
+    /// Get the screen bounding box of the open dropdown
+    fn dropdown_bbox(&self) -> Option<BBox> {
+        let m = self.dropdown.open.get()?;
+        let menu = &self.dropdown.menus[m];
+        let (col, _) = self.title_spans()[m];
+        let width = menu.items_width();
+        let height = menu.items_height();
+        let bar = self.anchor.get();
+        let screen = self.screen.get();
+        let col = bar
+            .left()
+            .saturating_add(col)
+            .min(screen.right().saturating_sub(width));
+        let below = bar.bottom().saturating_add(height) <= screen.bottom();
+        let row = if below || bar.top() < screen.top() + height {
+            bar.bottom()
+        } else {
+            bar.top() - height
+        };
+        Some(BBox::new(col, row, width, height))
+    }
+
+    /// Get the column and width of each title
+    fn title_spans(&self) -> Vec<(u16, u16)> {
+        let mut col = 0u16;
+        self.dropdown
+            .menus
+            .iter()
+            .map(|menu| {
+                let width = menu.title_width();
+                let span = (col, width);
+                col = col.saturating_add(width);
+                span
+            })
+            .collect()
+    }
+
+    /// Get the title at a column
+    fn title_at(&self, col: u16) -> Option<usize> {
+        self.title_spans()
+            .iter()
+            .position(|(c, w)| col >= *c && col < c.saturating_add(*w))
+    }
+
+    /// Get the style group of a title
+    fn title_group(&self, m: usize) -> StyleGroup {
+        if self.dropdown.open.get() == Some(m) {
+            StyleGroup::Interacted
+        } else if self.hovered.get() == Some(m) {
+            StyleGroup::Hovered
+        } else if self.focused.get() && self.dropdown.current.get() == m {
+            StyleGroup::Focused
+        } else {
+            self.style_group()
+        }
+    }
+}
+
+impl Widget for MenuBar {
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        let spans = self.title_spans();
+        let w = spans
+            .iter()
+            .fold(0, |w: u16, (_, tw)| w.saturating_add(*tw));
+        LengthBound::new(w..)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        LengthBound::new(1..=1)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+        self.anchor.set(cells.screen_clip());
+        self.screen.set(cells.screen_bbox());
+        let theme = cells.theme().clone();
+        let normal = theme.style(self.style_group());
+        cells.move_to(0, 0)?;
+        for (m, menu) in self.dropdown.menus.iter().enumerate() {
+            let group = self.title_group(m);
+            if group != self.style_group() {
+                cells.set_style(theme.style(group))?;
+            }
+            cells.print_str(&format!(" {} ", menu.title))?;
+            if group != self.style_group() {
+                cells.set_style(normal)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether the menu bar can accept focus
+    fn focusable(&self) -> bool {
+        !self.dropdown.menus.is_empty()
+    }
+
+    /// Handle focus event
+    fn focus(&self, fev: FocusEvent) -> Option<Action> {
+        match fev {
+            FocusEvent::Offer => self.focused.set(true),
+            FocusEvent::Take => self.focused.set(false),
+            FocusEvent::HoverOutside if self.hovered.get().is_some() => {
+                self.hovered.set(None)
+            }
+            _ => return None,
+        }
+        Some(Action::Redraw())
+    }
+
+    /// Handle key events
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        let len = self.dropdown.menus.len();
+        let m = self.dropdown.current.get();
+        match (key, mods) {
+            (KeyPress::Navigation(NavKey::Left), ModKeys::EMPTY) => {
+                self.dropdown.current.set((m + len - 1) % len);
+            }
+            (KeyPress::Navigation(NavKey::Right), ModKeys::EMPTY) => {
+                self.dropdown.current.set((m + 1) % len);
+            }
+            (KeyPress::Navigation(NavKey::Enter), ModKeys::EMPTY)
+            | (KeyPress::Navigation(NavKey::Down), ModKeys::EMPTY) => {
+                self.dropdown.open(m);
+            }
+            _ => return None,
+        }
+        Some(Action::Redraw())
+    }
+
+    /// Handle mouse events
+    fn mouse_event(
+        &self,
+        mev: MouseEvent,
+        _mods: ModKeys,
+        _dim: Dim,
+        pos: WidgetPos,
+    ) -> Option<Action> {
+        let title = self.title_at(pos.col);
+        match mev {
+            MouseEvent::Drag(None) if title != self.hovered.get() => {
+                self.hovered.set(title);
+                Some(Action::Redraw())
+            }
+            MouseEvent::ButtonDown(MouseButton::Left) => {
+                self.dropdown.open(title?);
+                Some(Action::Redraw())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::to_text;
+    use crate::grid_area;
+    use crate::widget::Label;
+
+    /// Create a menu bar for testing
+    fn menu_bar() -> MenuBar {
+        MenuBar::new()
+            .with_menu("File", &[("Open", 1), ("Quit", 2)])
+            .with_menu("Help", &[("About", 3)])
+    }
+
+    /// Key press of a navigation key
+    fn nav(key: NavKey) -> KeyPress {
+        KeyPress::Navigation(key)
+    }
+
+    #[test]
+    fn bounds() {
+        let theme = Theme::default();
+        let menu = menu_bar();
+        assert_eq!(menu.width_bounds(&theme), LengthBound::new(12..));
+        assert_eq!(menu.height_bounds(&theme, 12), LengthBound::new(1..=1));
+    }
+
+    #[test]
+    fn keys() {
+        let menu = menu_bar();
+        let dropdown = &menu.dropdown;
+        assert!(menu.key_event(nav(NavKey::Right), ModKeys::EMPTY).is_some());
+        menu.key_event(nav(NavKey::Enter), ModKeys::EMPTY);
+        assert_eq!(dropdown.open.get(), Some(1));
+        dropdown.key_event(nav(NavKey::Right), ModKeys::EMPTY);
+        assert_eq!(dropdown.open.get(), Some(0));
+        dropdown.key_event(nav(NavKey::Down), ModKeys::EMPTY);
+        dropdown.key_event(nav(NavKey::Down), ModKeys::EMPTY);
+        assert_eq!(dropdown.item.get(), 1);
+        let action = dropdown.key_event(nav(NavKey::Enter), ModKeys::EMPTY);
+        assert_eq!(action, Some(Action::Custom(2)));
+        assert!(!menu.is_open());
+        menu.key_event(nav(NavKey::Down), ModKeys::EMPTY);
+        assert!(menu.is_open());
+        dropdown.key_event(nav(NavKey::Esc), ModKeys::EMPTY);
+        assert!(!menu.is_open());
+    }
+
+    #[test]
+    fn dropdown() -> Result<()> {
+        let theme = Theme::default();
+        let menu = menu_bar();
+        let body = Label::new("body");
+        let grid = grid_area!([menu][body])?;
+        let text = to_text(&grid, Dim::new(14, 2), &theme)?;
+        assert!(text.starts_with(" File  Help   \n"), "{:?}", text);
+        let dropdown = menu.dropdown_area()?;
+        assert!(menu.popup(&dropdown).is_none());
+        menu.dropdown.open(1);
+        assert_eq!(menu.dropdown_bbox(), Some(BBox::new(6, 1, 7, 1)));
+        // Shifted left at the right edge of the screen
+        to_text(&grid, Dim::new(10, 2), &theme)?;
+        assert_eq!(menu.dropdown_bbox(), Some(BBox::new(3, 1, 7, 1)));
+        // Opened upward at the bottom of the screen
+        let grid = grid_area!([body][menu])?;
+        to_text(&grid, Dim::new(14, 2), &theme)?;
+        assert_eq!(menu.dropdown_bbox(), Some(BBox::new(6, 0, 7, 1)));
+        Ok(())
+    }
+}
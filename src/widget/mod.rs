@@ -3,15 +3,76 @@
 // Copyright (c) 2020  Douglas P Lau
 //
 //! User Interface Widgets
+//!
+//! [Border], [Button], [Label] and [Spacer] are always available.  Other
+//! widgets are enabled by features:
+//!
+//! * `widgets-extra`: `Art`, `Badge`, `KeyValue`, `ListView`, `LogView`,
+//!   `MenuBar`, `PathLabel`, `Rule`, `ScrollView`, `Text` and `Tree`
+//! * `forms`: `CheckBox`, `Form` and `TextInput`
 
+#[cfg(feature = "widgets-extra")]
+mod art;
+#[cfg(feature = "widgets-extra")]
+mod badge;
 mod border;
 mod button;
+#[cfg(feature = "forms")]
+mod checkbox;
+#[cfg(feature = "forms")]
+mod form;
+#[cfg(feature = "widgets-extra")]
+mod keyvalue;
 mod label;
+#[cfg(feature = "widgets-extra")]
+mod listview;
+#[cfg(feature = "widgets-extra")]
+mod logview;
+#[cfg(feature = "widgets-extra")]
+mod menubar;
+#[cfg(feature = "widgets-extra")]
+mod pathlabel;
+#[cfg(feature = "widgets-extra")]
+mod rule;
+#[cfg(feature = "widgets-extra")]
 mod scrollview;
 mod spacer;
+#[cfg(feature = "widgets-extra")]
+mod text;
+#[cfg(feature = "forms")]
+mod textinput;
+#[cfg(feature = "widgets-extra")]
+mod tree;
 
+#[cfg(feature = "widgets-extra")]
+pub use art::Art;
+#[cfg(feature = "widgets-extra")]
+pub use badge::Badge;
 pub use border::{Border, BorderStyle};
 pub use button::Button;
+#[cfg(feature = "forms")]
+pub use checkbox::CheckBox;
+#[cfg(feature = "forms")]
+pub use form::{FieldKind, FieldValue, Form, FormValues};
+#[cfg(feature = "widgets-extra")]
+pub use keyvalue::KeyValue;
 pub use label::Label;
-pub use scrollview::{ScrollBar, ScrollView};
+#[cfg(feature = "widgets-extra")]
+pub use listview::ListView;
+#[cfg(feature = "widgets-extra")]
+pub use logview::{LogView, Severity};
+#[cfg(feature = "widgets-extra")]
+pub use menubar::MenuBar;
+#[cfg(feature = "widgets-extra")]
+pub use pathlabel::PathLabel;
+#[cfg(feature = "widgets-extra")]
+pub use rule::{Orientation, Rule};
+#[cfg(feature = "widgets-extra")]
+pub use scrollview::{Easing, ScrollBar, ScrollCommand, ScrollView};
 pub use spacer::Spacer;
+#[cfg(feature = "widgets-extra")]
+pub use text::Text;
+#[cfg(feature = "forms")]
+pub use textinput::TextInput;
+#[cfg(feature = "widgets-extra")]
+pub use tree::{Tree, TreeNode};
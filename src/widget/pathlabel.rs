@@ -0,0 +1,263 @@
+// pathlabel.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::layout::{Cells, ContentPos, LengthBound};
+use crate::text::{display_width, truncate_end, Theme};
+use crate::{Result, Widget};
+use std::cell::RefCell;
+use std::path::{Component, Path, PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Minimum width of a path label
+const MIN_WIDTH: u16 = 8;
+
+/// Placeholder for abbreviated or dropped components
+const ELLIPSIS: &str = "…";
+
+/// Path label widget
+///
+/// Displays a file system path, fit into the available width by:
+///
+/// 1. Substituting `~` for the home directory
+/// 2. Abbreviating ancestor directories, starting with the outermost
+/// 3. Replacing ancestor directories with a single `…`
+///
+/// The final component (file name) is kept intact unless nothing else
+/// remains to shorten.
+///
+/// ```rust
+/// use semtext::widget::PathLabel;
+///
+/// let path = PathLabel::new("/usr/share/doc/semtext/README.md");
+/// path.set_path("/usr/share/doc/semtext/CHANGELOG.md");
+/// ```
+pub struct PathLabel {
+    /// Path to display
+    path: RefCell<PathBuf>,
+    /// Home directory
+    home: Option<PathBuf>,
+}
+
+/// Split a path into display components
+///
+/// An absolute path starts with an empty component, and a path within the
+/// home directory starts with `~`.
+fn path_parts(path: &Path, home: Option<&Path>) -> Vec<String> {
+    let mut parts = vec![];
+    let mut path = path;
+    if let Some(rest) = home.and_then(|home| path.strip_prefix(home).ok()) {
+        parts.push("~".to_string());
+        path = rest;
+    }
+    for comp in path.components() {
+        match comp {
+            Component::RootDir => parts.push(String::new()),
+            _ => parts.push(comp.as_os_str().to_string_lossy().into_owned()),
+        }
+    }
+    parts
+}
+
+/// Join display components into a path string
+fn join_parts(parts: &[String]) -> String {
+    match parts {
+        [root] if root.is_empty() => "/".to_string(),
+        _ => parts.join("/"),
+    }
+}
+
+/// Abbreviate a component to its first grapheme
+fn abbreviate(part: &str) -> Option<String> {
+    let first = part.graphemes(true).next()?;
+    let abbr = format!("{}{}", first, ELLIPSIS);
+    (display_width(&abbr) < display_width(part)).then_some(abbr)
+}
+
+/// Fit path components into a display width
+fn fit_parts(mut parts: Vec<String>, width: usize) -> String {
+    let fits = |parts: &[String]| display_width(&join_parts(parts)) <= width;
+    if fits(&parts) {
+        return join_parts(&parts);
+    }
+    let name = match parts.pop() {
+        Some(name) => name,
+        None => return String::new(),
+    };
+    // Leading root or home component is never abbreviated
+    let first = match parts.first() {
+        Some(root) if root.is_empty() || root == "~" => 1,
+        _ => 0,
+    };
+    for i in first..parts.len() {
+        if let Some(abbr) = abbreviate(&parts[i]) {
+            parts[i] = abbr;
+            let mut trial = parts.clone();
+            trial.push(name.clone());
+            if fits(&trial) {
+                return join_parts(&trial);
+            }
+        }
+    }
+    for i in first..parts.len() {
+        let mut trial = parts[..first].to_vec();
+        trial.push(ELLIPSIS.to_string());
+        trial.extend_from_slice(&parts[i + 1..]);
+        trial.push(name.clone());
+        if fits(&trial) {
+            return join_parts(&trial);
+        }
+    }
+    let trial = [ELLIPSIS.to_string(), name.clone()];
+    if !parts.is_empty() && fits(&trial) {
+        return join_parts(&trial);
+    }
+    truncate_end(&name, width)
+}
+
+impl PathLabel {
+    /// Create a new path label widget
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        let path = RefCell::new(path.into());
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        Self { path, home }
+    }
+
+    /// Set the home directory (replaced with `~`)
+    ///
+    /// By default, the `HOME` environment variable is used.
+    pub fn with_home<P: Into<PathBuf>>(mut self, home: Option<P>) -> Self {
+        self.home = home.map(|h| h.into());
+        self
+    }
+
+    /// Get the path
+    pub fn path(&self) -> PathBuf {
+        self.path.borrow().clone()
+    }
+
+    /// Set the path
+    pub fn set_path<P: Into<PathBuf>>(&self, path: P) {
+        *self.path.borrow_mut() = path.into();
+    }
+
+    /// Get the path fit into a display width
+    fn fit(&self, width: usize) -> String {
+        let path = self.path.borrow();
+        fit_parts(path_parts(&path, self.home.as_deref()), width)
+    }
+}
+
+impl Widget for PathLabel {
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        let path = self.path.borrow();
+        let parts = path_parts(&path, self.home.as_deref());
+        let w = display_width(&join_parts(&parts)) as u16;
+        LengthBound::new(w.min(MIN_WIDTH)..)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        LengthBound::new(1..=1)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        if offset.row == 0 {
+            let text = self.fit(usize::from(cells.width()));
+            cells.move_to(0, 0)?;
+            cells.print_str(&text)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fit(path: &str, width: usize) -> String {
+        PathLabel::new(path)
+            .with_home(Some("/home/user"))
+            .fit(width)
+    }
+
+    #[test]
+    fn parts() {
+        let home = Some(Path::new("/home/user"));
+        assert_eq!(path_parts(Path::new("/usr/lib"), home), ["", "usr", "lib"]);
+        assert_eq!(path_parts(Path::new("/home/user/src"), home), ["~", "src"]);
+        assert_eq!(
+            path_parts(Path::new("src/lib.rs"), home),
+            ["src", "lib.rs"]
+        );
+        assert_eq!(join_parts(&path_parts(Path::new("/"), home)), "/");
+    }
+
+    #[test]
+    fn home() {
+        let path = "/home/user/projects/semtext/src/screen.rs";
+        assert_eq!(fit(path, 60), "~/projects/semtext/src/screen.rs");
+        assert_eq!(fit(path, 28), "~/p…/semtext/src/screen.rs");
+        assert_eq!(fit(path, 21), "~/p…/s…/src/screen.rs");
+        assert_eq!(fit(path, 20), "~/p…/s…/s…/screen.rs");
+        assert_eq!(fit(path, 16), "~/…/s…/screen.rs");
+        assert_eq!(fit(path, 13), "~/…/screen.rs");
+        assert_eq!(fit(path, 12), "…/screen.rs");
+        assert_eq!(fit(path, 9), "screen.rs");
+        assert_eq!(fit(path, 6), "scree…");
+    }
+
+    #[test]
+    fn absolute() {
+        let path = "/usr/share/doc/semtext/README.md";
+        assert_eq!(fit(path, 32), "/usr/share/doc/semtext/README.md");
+        assert_eq!(fit(path, 31), "/u…/share/doc/semtext/README.md");
+        assert_eq!(fit(path, 22), "/u…/s…/d…/s…/README.md");
+        assert_eq!(fit(path, 21), "/…/s…/d…/s…/README.md");
+        assert_eq!(fit(path, 18), "/…/d…/s…/README.md");
+        assert_eq!(fit(path, 15), "/…/s…/README.md");
+        assert_eq!(fit(path, 12), "/…/README.md");
+    }
+
+    #[test]
+    fn wide() {
+        let path = "/home/user/書類/プロジェクト/報告書.txt";
+        assert_eq!(fit(path, 30), "~/書類/プロジェクト/報告書.txt");
+        assert_eq!(fit(path, 29), "~/書…/プロジェクト/報告書.txt");
+        assert_eq!(fit(path, 20), "~/書…/プ…/報告書.txt");
+        assert_eq!(fit(path, 18), "~/…/プ…/報告書.txt");
+        assert_eq!(fit(path, 17), "~/…/報告書.txt");
+        assert_eq!(fit(path, 10), "報告書.txt");
+        assert_eq!(fit(path, 9), "報告書.t…");
+    }
+
+    #[test]
+    fn long_name() {
+        let path = "/tmp/a_file_name_which_is_longer_than_everything";
+        assert_eq!(fit(path, 20), "a_file_name_which_i…");
+        assert_eq!(fit(path, 5), "a_fi…");
+    }
+
+    #[test]
+    fn all_widths() {
+        let paths = [
+            "/home/user/projects/semtext/src/screen.rs",
+            "/usr/share/doc/semtext/README.md",
+            "/home/user/書類/プロジェクト/報告書.txt",
+            "/tmp/a_file_name_which_is_longer_than_everything",
+            "relative/path/to/file.txt",
+        ];
+        for path in paths {
+            let name = Path::new(path).file_name().unwrap().to_string_lossy();
+            for width in 5..=60 {
+                let text = fit(path, width);
+                assert!(display_width(&text) <= width, "{} {}", text, width);
+                if display_width(&name) <= width {
+                    assert!(text.ends_with(&*name), "{} {}", text, width);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+// rule.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::layout::{Cells, ContentPos, LengthBound};
+use crate::text::{display_width, truncate_end, Outline, Theme};
+use crate::{Result, Widget};
+
+/// Orientation of a rule
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Orientation {
+    /// Horizontal line, one row tall
+    #[default]
+    Horizontal,
+    /// Vertical line, one column wide
+    Vertical,
+}
+
+/// Rule widget
+///
+/// A horizontal or vertical line for separating rows or columns of a grid.
+/// Rules are decorative, so they are not hit-testable.
+///
+/// End caps can be enabled with [with_caps], which join the ends of the line
+/// into adjacent borders: `├` `┤` for horizontal rules, or `┬` `┴` for
+/// vertical rules.
+///
+/// ```rust
+/// use semtext::widget::{Orientation, Rule};
+///
+/// let rule = Rule::new(Orientation::Horizontal).with_label("Section");
+/// ```
+///
+/// [with_caps]: struct.Rule.html#method.with_caps
+#[derive(Debug, Default)]
+pub struct Rule {
+    /// Line orientation
+    orientation: Orientation,
+    /// Line outline
+    outline: Outline,
+    /// End caps flag
+    caps: bool,
+    /// Centered label
+    label: Option<String>,
+}
+
+/// Get the end cap characters for an outline
+fn end_caps(
+    outline: Outline,
+    orientation: Orientation,
+) -> Option<(char, char)> {
+    use Orientation::*;
+    use Outline::*;
+    match (outline, orientation) {
+        (Light(_, _), Horizontal) => Some(('├', '┤')),
+        (Light(_, _), Vertical) => Some(('┬', '┴')),
+        (Heavy(_), Horizontal) => Some(('┣', '┫')),
+        (Heavy(_), Vertical) => Some(('┳', '┻')),
+        (Double, Horizontal) => Some(('╠', '╣')),
+        (Double, Vertical) => Some(('╦', '╩')),
+        _ => None,
+    }
+}
+
+impl Rule {
+    /// Create a new rule widget
+    pub fn new(orientation: Orientation) -> Self {
+        Rule {
+            orientation,
+            ..Default::default()
+        }
+    }
+
+    /// Set the outline style of the line
+    pub fn with_outline(mut self, outline: Outline) -> Self {
+        self.outline = outline;
+        self
+    }
+
+    /// Set whether end caps are drawn
+    ///
+    /// Caps are only available for `Light`, `Heavy` and `Double` outlines.
+    pub fn with_caps(mut self, caps: bool) -> Self {
+        self.caps = caps;
+        self
+    }
+
+    /// Set a label, centered within a horizontal rule
+    ///
+    /// Labels are not drawn on vertical rules.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Get the line character
+    fn line(&self) -> char {
+        match self.orientation {
+            Orientation::Horizontal => self.outline.top(),
+            Orientation::Vertical => self.outline.left(),
+        }
+    }
+
+    /// Get the line characters for a length
+    fn chars(&self, len: u16) -> Vec<char> {
+        let mut chars = vec![self.line(); usize::from(len)];
+        if self.caps && len > 1 {
+            if let Some((start, end)) = end_caps(self.outline, self.orientation)
+            {
+                chars[0] = start;
+                chars[usize::from(len) - 1] = end;
+            }
+        }
+        chars
+    }
+
+    /// Draw the label (horizontal only)
+    fn draw_label(&self, cells: &mut Cells) -> Result<()> {
+        if let Some(label) = &self.label {
+            let width = usize::from(cells.width());
+            // Leave room for a line character and space on each side
+            let label = truncate_end(label, width.saturating_sub(4));
+            let lw = display_width(&label);
+            if lw > 0 {
+                let col = (width - lw) / 2;
+                cells.move_to(col as u16 - 1, 0)?;
+                cells.print_char(' ')?;
+                cells.print_str(&label)?;
+                cells.print_char(' ')?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Widget for Rule {
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        match self.orientation {
+            Orientation::Horizontal => LengthBound::new(1..),
+            Orientation::Vertical => LengthBound::new(1..=1),
+        }
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        match self.orientation {
+            Orientation::Horizontal => LengthBound::new(1..=1),
+            Orientation::Vertical => LengthBound::new(1..),
+        }
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+        match self.orientation {
+            Orientation::Horizontal => {
+                let chars = self.chars(cells.width());
+                cells.move_to(0, 0)?;
+                cells.print_str(&chars.iter().collect::<String>())?;
+                self.draw_label(cells)?;
+            }
+            Orientation::Vertical => {
+                let chars = self.chars(cells.height());
+                for (row, ch) in chars.into_iter().enumerate() {
+                    cells.move_to(0, row as u16)?;
+                    cells.print_char(ch)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether the widget is hit-testable
+    fn hit_testable(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::to_text;
+    use crate::grid_area;
+    use crate::layout::Dim;
+    use crate::text::Stroke;
+
+    #[test]
+    fn horizontal() -> Result<()> {
+        let theme = Theme::default();
+        let rule = Rule::new(Orientation::Horizontal).with_caps(true);
+        let grid = grid_area!([rule])?;
+        assert_eq!(to_text(&grid, Dim::new(5, 1), &theme)?, "├───┤\n");
+        let rule = Rule::new(Orientation::Horizontal)
+            .with_outline(Outline::Heavy(Stroke::Solid))
+            .with_label("Section");
+        let grid = grid_area!([rule])?;
+        let text = to_text(&grid, Dim::new(15, 1), &theme)?;
+        assert_eq!(text, "━━━ Section ━━━\n");
+        let text = to_text(&grid, Dim::new(8, 1), &theme)?;
+        assert_eq!(text, "━ Sec… ━\n");
+        Ok(())
+    }
+
+    #[test]
+    fn vertical() -> Result<()> {
+        let theme = Theme::default();
+        let rule = Rule::new(Orientation::Vertical)
+            .with_outline(Outline::Double)
+            .with_caps(true)
+            .with_label("ignored");
+        assert_eq!(rule.width_bounds(&theme), LengthBound::new(1..=1));
+        let grid = grid_area!([rule])?;
+        assert_eq!(to_text(&grid, Dim::new(1, 3), &theme)?, "╦\n║\n╩\n");
+        Ok(())
+    }
+}
@@ -2,11 +2,26 @@
 //
 // Copyright (c) 2020-2021  Douglas P Lau
 //
-use crate::input::{Action, FocusEvent, ModKeys, MouseEvent};
-use crate::layout::{BBox, Cells, Dim, LengthBound, Pos};
+use crate::input::{
+    Action, FocusEvent, KeyPress, ModKeys, MouseButton, MouseEvent, NavKey,
+    WidgetKeyMap,
+};
+use crate::layout::{
+    BBox, Cells, ContentPos, Dim, LengthBound, Pos, WidgetPos,
+};
 use crate::text::{StyleGroup, Theme};
-use crate::{Result, Widget};
+use crate::{PersistState, Result, StateBlob, Widget};
 use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// Duration of smooth scrolling animations
+const SMOOTH_DURATION: Duration = Duration::from_millis(120);
+
+/// Minimum offset change (rows or columns) to animate
+const SMOOTH_MIN: u16 = 2;
+
+/// Version of saved state representation
+const STATE_VERSION: u32 = 1;
 
 /// Scroll view state
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -32,6 +47,73 @@ pub enum ScrollBar {
     VerticalAndHorizontal(u16, u16),
 }
 
+/// Easing function for smooth scrolling
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Easing {
+    /// Constant speed
+    Linear,
+    /// Quadratic deceleration
+    #[default]
+    EaseOutQuad,
+}
+
+/// Scroll view commands, bound to keys with a [WidgetKeyMap]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScrollCommand {
+    /// Scroll up one row
+    Up,
+    /// Scroll down one row
+    Down,
+    /// Scroll left one column
+    Left,
+    /// Scroll right one column
+    Right,
+    /// Scroll up one page
+    PageUp,
+    /// Scroll down one page
+    PageDown,
+    /// Scroll to the top
+    Top,
+    /// Scroll to the bottom
+    Bottom,
+}
+
+/// Offset animation
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Animation {
+    /// Starting offset
+    from: Pos,
+    /// Target offset
+    to: Pos,
+    /// Start time, from the first frame drawn
+    start: Option<Instant>,
+    /// Offset shown on the last frame drawn
+    shown: Pos,
+    /// Easing function
+    easing: Easing,
+}
+
+/// Areas of a scroll view
+///
+/// When space is short, scroll bars are dropped in favor of content, until
+/// there is only room for a bar:
+///
+/// * Width of 3 or more: content and vertical bar
+/// * Width of 2: content only
+/// * Width of 1: vertical bar only, as a position indicator
+/// * Width of 0: nothing
+///
+/// The horizontal bar is handled the same way using the height.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Viewport {
+    /// Content dimensions
+    content: Dim,
+    /// Vertical scroll bar area
+    v_bar: Option<BBox>,
+    /// Horizontal scroll bar area
+    h_bar: Option<BBox>,
+}
+
 /// Vertical scroll bar widget
 struct VerticalScrollBar {
     /// Scroll view rows
@@ -62,8 +144,157 @@ pub struct ScrollView<W: Widget> {
     h_bar: Option<HorizontalScrollBar>,
     /// Offset within wrapped widget
     offset: Cell<Pos>,
+    /// Minimum wrapped widget dimensions, from layout
+    content: Cell<Dim>,
+    /// View dimensions (excluding scroll bars), from last draw
+    view: Cell<Dim>,
+    /// Columns scrolled by horizontal wheel events
+    col_step: u16,
+    /// Key bindings
+    keymap: WidgetKeyMap<ScrollCommand>,
+    /// Easing for smooth scrolling (`None` to disable)
+    easing: Option<Easing>,
+    /// Current offset animation
+    animation: Cell<Option<Animation>>,
     /// Widget state
     state: Cell<State>,
+    /// Position within thumb where it was pressed, for dragging
+    grab: Cell<Option<u16>>,
+    /// Maximum row offset from the last draw, for tailing
+    last_max: Cell<u16>,
+}
+
+impl Easing {
+    /// Apply easing to a fraction of elapsed time (0 to 1)
+    fn ease(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOutQuad => t * (2.0 - t),
+        }
+    }
+}
+
+/// Interpolate between two values
+fn lerp(from: u16, to: u16, t: f32) -> u16 {
+    let delta = f32::from(to) - f32::from(from);
+    (f32::from(from) + delta * t).round() as u16
+}
+
+impl ScrollCommand {
+    /// Get the default key map
+    ///
+    /// Arrow keys scroll by one row or column, page keys by one page, and
+    /// `Home` / `End` to the top / bottom.  `Left` and `Right` also scroll
+    /// with the `Shift` modifier.
+    pub fn keymap() -> WidgetKeyMap<Self> {
+        let nav = |key, cmd, km: WidgetKeyMap<Self>| {
+            km.with(KeyPress::Navigation(key), ModKeys::EMPTY, cmd)
+        };
+        let shift = |key, cmd, km: WidgetKeyMap<Self>| {
+            km.with(KeyPress::Navigation(key), ModKeys::SHIFT, cmd)
+        };
+        let km = WidgetKeyMap::new();
+        let km = nav(NavKey::Up, ScrollCommand::Up, km);
+        let km = nav(NavKey::Down, ScrollCommand::Down, km);
+        let km = nav(NavKey::Left, ScrollCommand::Left, km);
+        let km = nav(NavKey::Right, ScrollCommand::Right, km);
+        let km = shift(NavKey::Left, ScrollCommand::Left, km);
+        let km = shift(NavKey::Right, ScrollCommand::Right, km);
+        let km = nav(NavKey::PageUp, ScrollCommand::PageUp, km);
+        let km = nav(NavKey::PageDown, ScrollCommand::PageDown, km);
+        let km = nav(NavKey::Home, ScrollCommand::Top, km);
+        nav(NavKey::End, ScrollCommand::Bottom, km)
+    }
+}
+
+impl Animation {
+    /// Get the animated offset at a point in time
+    fn offset(&self, now: Instant) -> Pos {
+        let elapsed = self.start.map_or(Duration::ZERO, |start| {
+            now.saturating_duration_since(start)
+        });
+        let t = elapsed.as_secs_f32() / SMOOTH_DURATION.as_secs_f32();
+        let t = self.easing.ease(t.min(1.0));
+        let col = lerp(self.from.col, self.to.col, t);
+        let row = lerp(self.from.row, self.to.row, t);
+        Pos::new(col, row)
+    }
+
+    /// Check if the animation is finished
+    fn is_done(&self, now: Instant) -> bool {
+        self.start.is_some_and(|start| {
+            now.saturating_duration_since(start) >= SMOOTH_DURATION
+        })
+    }
+}
+
+/// Get the span of a scroll bar thumb
+///
+/// * `bar`: Length of scroll bar, which is also the viewport length
+/// * `content`: Length of wrapped widget content
+/// * `offset`: Offset within content
+///
+/// The thumb length is proportional to the viewport, and its travel along
+/// the bar maps linearly onto the scrollable range.
+///
+/// ## Return
+///
+/// Start (inclusive) and end (exclusive) of the thumb.
+fn thumb_span(bar: u16, content: u16, offset: u16) -> (u16, u16) {
+    if content <= bar {
+        return (0, bar);
+    }
+    let (bar32, content32) = (u32::from(bar), u32::from(content));
+    // limited to bar length, since bar < content
+    let len = (bar32 * bar32 / content32).max(1) as u16;
+    let travel = u32::from(bar - len);
+    let range = content32 - bar32;
+    let offset = u32::from(offset).min(range);
+    // limited to travel, since offset <= range
+    let start = ((offset * travel + range / 2) / range) as u16;
+    (start, start + len)
+}
+
+/// Get the offset after clicking a scroll bar
+///
+/// * `bar`: Length of scroll bar, which is also the viewport length
+/// * `content`: Length of wrapped widget content
+/// * `offset`: Offset within content
+/// * `click`: Clicked position on bar
+///
+/// Clicking before or after the thumb scrolls by one page.
+fn thumb_click(bar: u16, content: u16, offset: u16, click: u16) -> u16 {
+    let (start, end) = thumb_span(bar, content, offset);
+    let max = content.saturating_sub(bar);
+    let page = bar.max(1);
+    if click < start {
+        offset.saturating_sub(page)
+    } else if click >= end {
+        offset.saturating_add(page).min(max)
+    } else {
+        offset
+    }
+}
+
+/// Get the offset for a dragged scroll bar thumb
+///
+/// * `bar`: Length of scroll bar, which is also the viewport length
+/// * `content`: Length of wrapped widget content
+/// * `start`: Requested start of thumb (can be negative)
+///
+/// This is the inverse of [thumb_span], limited to the scrollable range.
+fn thumb_drag(bar: u16, content: u16, start: i32) -> u16 {
+    let (first, end) = thumb_span(bar, content, 0);
+    let travel = bar - (end - first);
+    let range = u32::from(content.saturating_sub(bar));
+    if travel == 0 || range == 0 {
+        return 0;
+    }
+    // limited to travel by clamp
+    let start = start.clamp(0, i32::from(travel)) as u32;
+    let travel = u32::from(travel);
+    // limited to range, since start <= travel
+    ((start * range + travel / 2) / travel) as u16
 }
 
 impl VerticalScrollBar {
@@ -77,17 +308,12 @@ impl VerticalScrollBar {
         }
     }
 
-    /// Get the start and end rows of the thumb
+    /// Get the start and end (exclusive) rows of the thumb
     ///
     /// * `bar_height`: Scroll bar height
     /// * `offset`: Offset within wrapped widget
     fn thumb_rows(&self, bar_height: u16, offset: Pos) -> (u16, u16) {
-        let height = self.height.get();
-        let tfrac = f32::from(bar_height) / f32::from(height);
-        let start = (f32::from(offset.row) * tfrac).ceil() as u16;
-        let trows = (height / bar_height * bar_height).min(1);
-        let end = start + trows;
-        (start, end)
+        thumb_span(bar_height, self.height.get(), offset.row)
     }
 
     /// Adjust row offset based on a click
@@ -96,13 +322,18 @@ impl VerticalScrollBar {
     /// * `offset`: Offset within wrapped widget
     /// * `crow`: Clicked row
     fn thumb_offset(&self, bar_height: u16, offset: Pos, crow: u16) -> Pos {
-        let (start, end) = self.thumb_rows(bar_height, offset);
-        let mut row = offset.row;
-        if crow < start {
-            row -= 1;
-        } else if crow > end {
-            row += 1;
-        }
+        let height = self.height.get();
+        let row = thumb_click(bar_height, height, offset.row, crow);
+        Pos::new(offset.col, row)
+    }
+
+    /// Get row offset for a dragged thumb
+    ///
+    /// * `bar_height`: Scroll bar height
+    /// * `offset`: Offset within wrapped widget
+    /// * `start`: Requested start row of thumb
+    fn drag_offset(&self, bar_height: u16, offset: Pos, start: i32) -> Pos {
+        let row = thumb_drag(bar_height, self.height.get(), start);
         Pos::new(offset.col, row)
     }
 }
@@ -124,15 +355,15 @@ impl Widget for VerticalScrollBar {
     }
 
     /// Draw the widget
-    fn draw(&self, cells: &mut Cells, pos: Pos) -> Result<()> {
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
         debug_assert!(cells.height() > 0);
         let height = self.height.get();
         let bar_height = cells.height();
         if bar_height <= height {
-            let (start, end) = self.thumb_rows(bar_height, pos);
+            let (start, end) = self.thumb_rows(bar_height, offset.0);
             for row in 0..bar_height {
                 cells.move_to(0, row)?;
-                if row < start || row > end {
+                if row < start || row >= end {
                     cells.print_char('▓')?;
                 } else {
                     cells.print_char('░')?;
@@ -150,17 +381,12 @@ impl HorizontalScrollBar {
         Self { cols, state, width }
     }
 
-    /// Get the start and end columns of the thumb
+    /// Get the start and end (exclusive) columns of the thumb
     ///
     /// * `bar_width`: Scroll bar width
     /// * `offset`: Offset within wrapped widget
     fn thumb_cols(&self, bar_width: u16, offset: Pos) -> (u16, u16) {
-        let width = self.width.get();
-        let tfrac = f32::from(bar_width) / f32::from(width);
-        let start = (f32::from(offset.col) * tfrac).ceil() as u16;
-        let tcols = (width / bar_width * bar_width).min(1);
-        let end = start + tcols;
-        (start, end)
+        thumb_span(bar_width, self.width.get(), offset.col)
     }
 
     /// Adjust column offset based on a click
@@ -169,13 +395,18 @@ impl HorizontalScrollBar {
     /// * `offset`: Offset within wrapped widget
     /// * `ccol`: Clicked column
     fn thumb_offset(&self, bar_width: u16, offset: Pos, ccol: u16) -> Pos {
-        let (start, end) = self.thumb_cols(bar_width, offset);
-        let mut col = offset.col;
-        if ccol < start {
-            col -= 1;
-        } else if ccol > end {
-            col += 1;
-        }
+        let width = self.width.get();
+        let col = thumb_click(bar_width, width, offset.col, ccol);
+        Pos::new(col, offset.row)
+    }
+
+    /// Get column offset for a dragged thumb
+    ///
+    /// * `bar_width`: Scroll bar width
+    /// * `offset`: Offset within wrapped widget
+    /// * `start`: Requested start column of thumb
+    fn drag_offset(&self, bar_width: u16, offset: Pos, start: i32) -> Pos {
+        let col = thumb_drag(bar_width, self.width.get(), start);
         Pos::new(col, offset.row)
     }
 }
@@ -187,15 +418,14 @@ impl Widget for HorizontalScrollBar {
     }
 
     /// Draw the widget
-    fn draw(&self, cells: &mut Cells, pos: Pos) -> Result<()> {
-        assert_eq!(pos, Pos::default(), "FIXME");
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
         let width = self.width.get();
         let bar_width = cells.width();
         if bar_width <= width {
             cells.move_to(0, 0)?;
-            let (start, end) = self.thumb_cols(bar_width, pos);
+            let (start, end) = self.thumb_cols(bar_width, offset.0);
             for col in 0..bar_width {
-                if col < start || col > end {
+                if col < start || col >= end {
                     cells.print_char('▓')?;
                 } else {
                     cells.print_char('░')?;
@@ -212,13 +442,29 @@ impl<W: Widget> ScrollView<W> {
         let v_bar = Some(VerticalScrollBar::new(8));
         let h_bar = None;
         let offset = Cell::new(Pos::default());
+        let content = Cell::new(Dim::default());
+        let view = Cell::new(Dim::default());
+        let col_step = 1;
+        let keymap = ScrollCommand::keymap();
+        let easing = None;
+        let animation = Cell::new(None);
         let state = Cell::new(State::Enabled);
+        let grab = Cell::new(None);
+        let last_max = Cell::new(0);
         Self {
             wrapped,
             v_bar,
             h_bar,
             offset,
+            content,
+            view,
+            col_step,
+            keymap,
+            easing,
+            animation,
             state,
+            grab,
+            last_max,
         }
     }
 
@@ -241,11 +487,254 @@ impl<W: Widget> ScrollView<W> {
         self
     }
 
+    /// Enable or disable smooth scrolling
+    ///
+    /// When enabled, large offset changes are animated over several frames
+    /// using the default [Easing].
+    pub fn with_smooth_scrolling(mut self, enable: bool) -> Self {
+        self.easing = enable.then(Easing::default);
+        self
+    }
+
+    /// Enable smooth scrolling with an easing function
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = Some(easing);
+        self
+    }
+
+    /// Set the number of columns scrolled by horizontal wheel events
+    ///
+    /// The default step is 1 column.
+    pub fn with_column_step(mut self, step: u16) -> Self {
+        self.col_step = step;
+        self
+    }
+
+    /// Set the key bindings
+    ///
+    /// The default bindings are from [ScrollCommand::keymap].
+    pub fn with_keymap(mut self, keymap: WidgetKeyMap<ScrollCommand>) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
     /// Get the wrapped widget
     pub fn wrapped(&self) -> &W {
         &self.wrapped
     }
 
+    /// Get the scroll offset within the wrapped widget
+    ///
+    /// While smooth scrolling, this is the target offset.
+    pub fn offset(&self) -> Pos {
+        let max = self.max_offset();
+        let Pos { col, row } = self.offset.get();
+        Pos::new(col.min(max.col), row.min(max.row))
+    }
+
+    /// Set the scroll offset within the wrapped widget
+    ///
+    /// The offset is limited to the content extent from the last layout,
+    /// and takes effect on the next draw, without animation.  If the
+    /// content later shrinks past the offset, it is limited again on draw,
+    /// keeping the end of the content at the edge of the view.
+    pub fn set_offset(&self, offset: Pos) {
+        let max = self.max_offset();
+        self.animation.set(None);
+        self.offset
+            .set(Pos::new(offset.col.min(max.col), offset.row.min(max.row)));
+    }
+
+    /// Scroll to the top of the wrapped widget
+    pub fn scroll_to_top(&self) {
+        self.set_offset(Pos::new(self.offset().col, 0));
+    }
+
+    /// Scroll to the bottom of the wrapped widget
+    ///
+    /// The offset is limited on the next draw, so content added before then
+    /// is also revealed.
+    pub fn scroll_to_bottom(&self) {
+        self.animation.set(None);
+        self.offset.set(Pos::new(self.offset().col, u16::MAX));
+    }
+
+    /// Get the maximum offset, from the last layout and draw
+    fn max_offset(&self) -> Pos {
+        let view = self.view.get();
+        let content = self.content.get();
+        Pos::new(
+            content.width.saturating_sub(view.width),
+            content.height.saturating_sub(view.height),
+        )
+    }
+
+    /// Get the areas of the scroll view
+    ///
+    /// * `dim`: Dimensions of the scroll view
+    fn viewport(&self, dim: Dim) -> Viewport {
+        let mut content = dim;
+        let mut v_bar = false;
+        let mut h_bar = false;
+        if dim.is_empty() {
+            content = Dim::default();
+        } else if self.v_bar.is_some() && dim.width == 1 {
+            content = Dim::new(0, dim.height);
+            v_bar = true;
+        } else if self.h_bar.is_some() && dim.height == 1 {
+            content = Dim::new(dim.width, 0);
+            h_bar = true;
+        } else {
+            if self.v_bar.is_some() && dim.width > 2 {
+                content.width -= 1;
+                v_bar = true;
+            }
+            if self.h_bar.is_some() && dim.height > 2 {
+                content.height -= 1;
+                h_bar = true;
+            }
+        }
+        let v_rows = dim.height - u16::from(h_bar && !content.is_empty());
+        let h_cols = dim.width - u16::from(v_bar && !content.is_empty());
+        Viewport {
+            content,
+            v_bar: v_bar.then(|| BBox::new(dim.width - 1, 0, 1, v_rows)),
+            h_bar: h_bar.then(|| BBox::new(0, dim.height - 1, h_cols, 1)),
+        }
+    }
+
+    /// Adjust the offset for rows removed from the top of the content
+    ///
+    /// The same content stays visible, unless it was removed.
+    fn adjust_removed(&self) {
+        let removed = self.wrapped.take_rows_removed();
+        if removed == 0 {
+            return;
+        }
+        let up = |pos: Pos| Pos::new(pos.col, pos.row.saturating_sub(removed));
+        self.offset.set(up(self.offset.get()));
+        if let Some(mut anim) = self.animation.get() {
+            anim.from = up(anim.from);
+            anim.to = up(anim.to);
+            self.animation.set(Some(anim));
+        }
+    }
+
+    /// Limit the offset to the content extent
+    ///
+    /// This is needed when content shrinks, or a stale offset is restored.
+    fn clamp_offset(&self, view: Dim) {
+        let content = self.content.get();
+        let max_col = content.width.saturating_sub(view.width);
+        let max_row = content.height.saturating_sub(view.height);
+        let Pos { col, row } = self.offset.get();
+        if col > max_col || row > max_row {
+            self.offset
+                .set(Pos::new(col.min(max_col), row.min(max_row)));
+            self.animation.set(None);
+        }
+    }
+
+    /// Get the offset to draw at a frame time
+    ///
+    /// An animation starts on the first frame drawn after it was created.
+    fn current_offset(&self, now: Instant) -> Pos {
+        match self.animation.get() {
+            Some(mut anim) if !anim.is_done(now) => {
+                anim.start = Some(anim.start.unwrap_or(now));
+                anim.shown = anim.offset(now);
+                self.animation.set(Some(anim));
+                anim.shown
+            }
+            Some(_) => {
+                self.animation.set(None);
+                self.offset.get()
+            }
+            None => self.offset.get(),
+        }
+    }
+
+    /// Get the offset shown on the last frame
+    ///
+    /// While animating, this differs from the target offset.
+    fn shown_offset(&self) -> Pos {
+        self.animation
+            .get()
+            .map_or_else(|| self.offset.get(), |anim| anim.shown)
+    }
+
+    /// Scroll to a target offset
+    ///
+    /// With smooth scrolling, large changes start an animation from the
+    /// currently shown offset, replacing any animation in progress.
+    fn scroll_to(&self, to: Pos) {
+        let from = self.shown_offset();
+        let far = from.col.abs_diff(to.col) >= SMOOTH_MIN
+            || from.row.abs_diff(to.row) >= SMOOTH_MIN;
+        let animation = match self.easing {
+            Some(easing) if far => Some(Animation {
+                from,
+                to,
+                start: None,
+                shown: from,
+                easing,
+            }),
+            _ => None,
+        };
+        self.animation.set(animation);
+        self.offset.set(to);
+    }
+
+    /// Execute a command
+    ///
+    /// Offsets are limited to the content extent, from layout.  Horizontal
+    /// commands are ignored without a horizontal scroll bar.
+    fn command(&self, cmd: ScrollCommand) -> Option<Action> {
+        if matches!(cmd, ScrollCommand::Left | ScrollCommand::Right) {
+            self.h_bar.as_ref()?;
+        }
+        let view = self.view.get();
+        let Pos {
+            col: max_col,
+            row: max_row,
+        } = self.max_offset();
+        let page = view.height.max(1);
+        let Pos { col, row } = self.offset.get();
+        let to = match cmd {
+            ScrollCommand::Up => Pos::new(col, row.saturating_sub(1)),
+            ScrollCommand::Down => Pos::new(col, row.saturating_add(1)),
+            ScrollCommand::Left => Pos::new(col.saturating_sub(1), row),
+            ScrollCommand::Right => Pos::new(col.saturating_add(1), row),
+            ScrollCommand::PageUp => Pos::new(col, row.saturating_sub(page)),
+            ScrollCommand::PageDown => Pos::new(col, row.saturating_add(page)),
+            ScrollCommand::Top => Pos::new(col, 0),
+            ScrollCommand::Bottom => Pos::new(col, max_row),
+        };
+        let to = Pos::new(to.col.min(max_col), to.row.min(max_row));
+        if to == self.offset.get() {
+            return None;
+        }
+        self.scroll_to(to);
+        Some(Action::Redraw())
+    }
+
+    /// Scroll to keep the cursor row of the wrapped widget visible
+    fn reveal_cursor(&self) {
+        let (row, view) = match (self.wrapped.cursor_row(), self.view.get()) {
+            (Some(row), view) if !view.is_empty() => (row, view),
+            _ => return,
+        };
+        let Pos { col, row: top } = self.offset.get();
+        let top = if row < top {
+            row
+        } else if row >= top.saturating_add(view.height) {
+            row + 1 - view.height
+        } else {
+            return;
+        };
+        self.scroll_to(Pos::new(col, top));
+    }
+
     /// Set the widget state
     fn set_state(&self, st: State) -> Option<Action> {
         let mut action = None;
@@ -268,89 +757,212 @@ impl<W: Widget> ScrollView<W> {
         action
     }
 
+    /// Press a scroll bar
+    ///
+    /// * `span`: Thumb span before the press
+    /// * `click`: Clicked position on bar
+    ///
+    /// Pressing on the thumb grabs it for dragging.
+    fn press_bar(&self, span: (u16, u16), click: u16) {
+        let (start, end) = span;
+        let grab = (start..end).contains(&click).then(|| click - start);
+        self.grab.set(grab);
+        self.set_state(State::Held);
+    }
+
     /// Handle mouse button down events
     fn mouse_button_down(
         &self,
         mev: MouseEvent,
         mods: ModKeys,
-        mut dim: Dim,
-        pos: Pos,
+        dim: Dim,
+        pos: WidgetPos,
     ) -> Option<Action> {
-        if let Some(v_bar) = &self.v_bar {
-            debug_assert!(dim.width > 0);
-            if pos.col >= dim.width - 1 {
-                dim = Dim::new(1, dim.height);
+        let vp = self.viewport(dim);
+        if let (Some(v_bar), Some(bx)) = (&self.v_bar, vp.v_bar) {
+            if pos.col >= bx.left() {
                 let offset = self.offset.get();
-                self.offset
-                    .set(v_bar.thumb_offset(dim.height, offset, pos.row));
-                self.set_state(State::Held);
+                let span = v_bar.thumb_rows(bx.height(), offset);
+                let to = v_bar.thumb_offset(bx.height(), offset, pos.row);
+                self.scroll_to(to);
+                self.press_bar(span, pos.row);
                 // Don't set horizontal scroll bar to Held state
                 if let Some(h_bar) = &self.h_bar {
                     h_bar.state.set(State::Focused);
                 }
                 return Some(Action::Redraw());
-            } else {
-                dim = Dim::new(dim.width - 1, dim.height);
             }
         }
-        if let Some(h_bar) = &self.h_bar {
-            debug_assert!(dim.height > 0);
-            if pos.row >= dim.height - 1 {
-                dim = Dim::new(dim.width, 1);
+        if let (Some(h_bar), Some(bx)) = (&self.h_bar, vp.h_bar) {
+            if pos.row >= bx.top() {
                 let offset = self.offset.get();
-                self.offset
-                    .set(h_bar.thumb_offset(dim.width, offset, pos.col));
-                self.set_state(State::Held);
+                let span = h_bar.thumb_cols(bx.width(), offset);
+                let to = h_bar.thumb_offset(bx.width(), offset, pos.col);
+                self.scroll_to(to);
+                self.press_bar(span, pos.col);
                 // Don't set vertical scroll bar to Held state
                 if let Some(v_bar) = &self.v_bar {
                     v_bar.state.set(State::Focused);
                 }
                 return Some(Action::Redraw());
-            } else {
-                dim = Dim::new(dim.width, dim.height - 1);
             }
         }
+        if vp.content.is_empty() {
+            return None;
+        }
+        self.wrapped_mouse_event(mev, mods, vp.content, pos)
+    }
+
+    /// Drag a grabbed scroll bar thumb
+    ///
+    /// Only the position along the held bar matters, so the drag continues
+    /// when the pointer strays off of it.
+    fn drag_thumb(&self, dim: Dim, pos: WidgetPos) -> Option<Action> {
+        let grab = i32::from(self.grab.get()?);
+        let vp = self.viewport(dim);
+        let offset = self.offset.get();
+        let to = match (&self.v_bar, vp.v_bar, &self.h_bar, vp.h_bar) {
+            (Some(v_bar), Some(bx), _, _)
+                if v_bar.state.get() == State::Held =>
+            {
+                let start = i32::from(pos.row) - grab;
+                v_bar.drag_offset(bx.height(), offset, start)
+            }
+            (_, _, Some(h_bar), Some(bx))
+                if h_bar.state.get() == State::Held =>
+            {
+                let start = i32::from(pos.col) - grab;
+                h_bar.drag_offset(bx.width(), offset, start)
+            }
+            _ => return None,
+        };
+        if to == offset {
+            return None;
+        }
+        // Dragging follows the pointer without animation
+        self.animation.set(None);
+        self.offset.set(to);
+        Some(Action::Redraw())
+    }
+
+    /// Forward a mouse event to the wrapped widget
+    ///
+    /// * `content`: Dimensions of content area
+    /// * `pos`: Position within scroll view
+    fn wrapped_mouse_event(
+        &self,
+        mev: MouseEvent,
+        mods: ModKeys,
+        content: Dim,
+        pos: WidgetPos,
+    ) -> Option<Action> {
+        let pos = ContentPos::from_widget(pos, self.shown_offset());
+        let dim = self.content.get();
+        let dim = Dim::new(
+            dim.width.max(content.width),
+            dim.height.max(content.height),
+        );
         self.wrapped
-            .mouse_event(mev, mods, dim, self.offset.get() + pos)
+            .mouse_event(mev, mods, dim, WidgetPos::from_content(pos))
     }
 
     /// Handle scroll down events
+    ///
+    /// The wheel scrolls by one row, or one column with `Shift`.
     fn scroll_down(&self, mods: ModKeys, dim: Dim) -> Option<Action> {
-        if let (Some(v_bar), ModKeys::Empty) = (&self.v_bar, mods) {
-            let offset = self.offset.get();
-            let row = dim.height - 1;
-            self.offset.set(v_bar.thumb_offset(dim.height, offset, row));
-            return Some(Action::Redraw());
+        if dim.is_empty() {
+            return None;
         }
-        if let (Some(h_bar), ModKeys::Shift) = (&self.h_bar, mods) {
-            let offset = self.offset.get();
-            let col = dim.width - 1;
-            self.offset.set(h_bar.thumb_offset(dim.width, offset, col));
-            return Some(Action::Redraw());
+        match (&self.v_bar, &self.h_bar, mods) {
+            (Some(_), _, m) if m.is_empty() => {
+                self.command(ScrollCommand::Down)
+            }
+            (_, Some(_), m) if m.contains(ModKeys::SHIFT) => {
+                self.command(ScrollCommand::Right)
+            }
+            _ => None,
         }
-        None
     }
 
     /// Handle scroll up events
+    ///
+    /// The wheel scrolls by one row, or one column with `Shift`.
     fn scroll_up(&self, mods: ModKeys, dim: Dim) -> Option<Action> {
-        if let (Some(v_bar), ModKeys::Empty) = (&self.v_bar, mods) {
-            let offset = self.offset.get();
-            self.offset.set(v_bar.thumb_offset(dim.height, offset, 0));
-            return Some(Action::Redraw());
+        if dim.is_empty() {
+            return None;
+        }
+        match (&self.v_bar, &self.h_bar, mods) {
+            (Some(_), _, m) if m.is_empty() => self.command(ScrollCommand::Up),
+            (_, Some(_), m) if m.contains(ModKeys::SHIFT) => {
+                self.command(ScrollCommand::Left)
+            }
+            _ => None,
+        }
+    }
+
+    /// Scroll horizontally by the column step
+    ///
+    /// * `right`: Scroll right (`true`) or left (`false`)
+    fn scroll_horizontal(&self, right: bool) -> Option<Action> {
+        self.h_bar.as_ref()?;
+        let view = self.view.get();
+        let content = self.content.get();
+        let max_col = content.width.saturating_sub(view.width);
+        let offset = self.offset.get();
+        let col = if right {
+            offset.col.saturating_add(self.col_step).min(max_col)
+        } else {
+            offset.col.saturating_sub(self.col_step)
+        };
+        if col == offset.col {
+            return None;
         }
-        if let (Some(h_bar), ModKeys::Shift) = (&self.h_bar, mods) {
-            let offset = self.offset.get();
-            self.offset.set(h_bar.thumb_offset(dim.width, offset, 0));
-            return Some(Action::Redraw());
+        self.scroll_to(Pos::new(col, offset.row));
+        Some(Action::Redraw())
+    }
+}
+
+impl<W: Widget> PersistState for ScrollView<W> {
+    /// Save the scroll offset and the state of the wrapped widget
+    fn save_state(&self) -> Option<StateBlob> {
+        let Pos { col, row } = self.offset.get();
+        let values = vec![u64::from(row), u64::from(col)];
+        let blob = StateBlob::new(STATE_VERSION).with_values(values);
+        match self.wrapped.persist().and_then(|p| p.save_state()) {
+            Some(nested) => Some(blob.with_nested(nested)),
+            None => Some(blob),
+        }
+    }
+
+    /// Restore the scroll offset and the state of the wrapped widget
+    ///
+    /// The offset is limited to the content extent on the next draw.
+    fn restore_state(&self, blob: &StateBlob) {
+        if let (STATE_VERSION, [row, col]) = (blob.version(), blob.values()) {
+            if let (Some(nested), Some(p)) =
+                (blob.nested(), self.wrapped.persist())
+            {
+                p.restore_state(nested);
+            }
+            let row = u16::try_from(*row).unwrap_or(u16::MAX);
+            let col = u16::try_from(*col).unwrap_or(u16::MAX);
+            self.offset.set(Pos::new(col, row));
+            self.animation.set(None);
         }
-        None
     }
 }
 
 impl<W: Widget> Widget for ScrollView<W> {
+    /// Get the persistent state
+    fn persist(&self) -> Option<&dyn PersistState> {
+        Some(self)
+    }
+
     /// Get the width bounds
     fn width_bounds(&self, theme: &Theme) -> LengthBound {
         let mut bounds = self.wrapped.width_bounds(theme);
+        let content = self.content.get();
+        self.content.set(Dim::new(bounds.minimum(), content.height));
         if let Some(v_bar) = &self.v_bar {
             bounds = bounds + v_bar.width_bounds(theme);
         }
@@ -366,6 +978,8 @@ impl<W: Widget> Widget for ScrollView<W> {
     /// Get the height bounds
     fn height_bounds(&self, theme: &Theme, width: u16) -> LengthBound {
         let mut bounds = self.wrapped.height_bounds(theme, width);
+        let content = self.content.get();
+        self.content.set(Dim::new(content.width, bounds.minimum()));
         if let Some(h_bar) = &self.h_bar {
             bounds = bounds + h_bar.height_bounds(theme, width);
         }
@@ -379,43 +993,83 @@ impl<W: Widget> Widget for ScrollView<W> {
     }
 
     /// Draw the widget
-    fn draw(&self, cells: &mut Cells, offset: Pos) -> Result<()> {
-        assert_eq!(offset, Pos::default(), "FIXME");
-        let offset = self.offset.get();
-        let mut width = cells.width();
-        let mut height = cells.height();
-        debug_assert!(width > 0);
-        debug_assert!(height > 0);
-        if self.v_bar.is_some() {
-            width -= 1;
-        }
-        if self.h_bar.is_some() {
-            height -= 1;
-        }
-        let width_bounds = self.wrapped.width_bounds(cells.theme());
-        let height_bounds = self
-            .wrapped
-            .height_bounds(cells.theme(), width_bounds.minimum());
-        if height_bounds.minimum() <= height && width_bounds.minimum() <= width
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        assert_eq!(offset, ContentPos::default(), "FIXME");
+        let at_end = self.offset.get().row >= self.last_max.get();
+        self.adjust_removed();
+        let vp = self.viewport(Dim::new(cells.width(), cells.height()));
+        self.clamp_offset(vp.content);
+        let max_row =
+            self.content.get().height.saturating_sub(vp.content.height);
+        if self.wrapped.tail() && at_end {
+            let Pos { col, row } = self.offset.get();
+            if row != max_row {
+                self.offset.set(Pos::new(col, max_row));
+                self.animation.set(None);
+            }
+        }
+        self.last_max.set(max_row);
+        let offset = ContentPos::from_widget(
+            WidgetPos::default(),
+            self.current_offset(cells.frame_time()),
+        );
+        if self.animation.get().is_some() {
+            cells.request_frame();
+        }
+        self.view.set(vp.content);
+        // Use dimensions from layout, in case the theme has changed
+        let content = self.content.get();
+        if content.height <= vp.content.height
+            && content.width <= vp.content.width
         {
             self.set_state(State::Disabled);
         }
         let w_style = cells.theme().style(self.wrapped.style_group());
-        if let Some(v_bar) = &self.v_bar {
+        let w_style = w_style.with_override(self.wrapped.style_override());
+        if let (Some(v_bar), Some(bx)) = (&self.v_bar, vp.v_bar) {
             let style = cells.theme().style(v_bar.style_group());
-            cells.clip(Some(BBox::new(width, 0, 1, height)));
+            cells.push_clip(bx);
             cells.set_style(style)?;
-            v_bar.draw(cells, offset)?;
+            let res = v_bar.draw(cells, offset);
+            cells.pop_clip();
+            res?;
         }
-        if let Some(h_bar) = &self.h_bar {
+        if let (Some(h_bar), Some(bx)) = (&self.h_bar, vp.h_bar) {
             let style = cells.theme().style(h_bar.style_group());
-            cells.clip(Some(BBox::new(0, height, width, 1)));
+            cells.push_clip(bx);
             cells.set_style(style)?;
-            h_bar.draw(cells, offset)?;
+            let res = h_bar.draw(cells, offset);
+            cells.pop_clip();
+            res?;
+        }
+        if vp.content.is_empty() {
+            return Ok(());
         }
-        cells.clip(Some(BBox::new(0, 0, width, height)));
+        let Dim { width, height } = vp.content;
+        let cursor = self.wrapped.cursor_row();
+        cells.push_clip(BBox::new(0, 0, width, height));
         cells.set_style(w_style)?;
-        self.wrapped.draw(cells, offset)
+        let res = self.wrapped.draw(cells, offset);
+        cells.pop_clip();
+        // Cursor moved while drawing, such as by auto-scrolling a drag
+        if self.wrapped.cursor_row() != cursor {
+            self.reveal_cursor();
+            cells.request_frame();
+        }
+        res
+    }
+
+    /// Check whether the scroll view can accept focus
+    ///
+    /// A scroll view with nothing to scroll is focusable only if its
+    /// wrapped widget is.
+    fn focusable(&self) -> bool {
+        self.state.get() != State::Disabled || self.wrapped.focusable()
+    }
+
+    /// Take a bell requested by the wrapped widget
+    fn take_bell(&self) -> bool {
+        self.wrapped.take_bell()
     }
 
     /// Handle focus event
@@ -427,20 +1081,55 @@ impl<W: Widget> Widget for ScrollView<W> {
             (_, Disabled) => Some(Disabled),
             (Offer, _) => Some(Focused),
             (Take, _) => Some(Enabled),
-            (HoverOutside, Held) | (HoverInside, Held) => Some(Focused),
             _ => None,
         }
         .and_then(|st| self.set_state(st));
         self.wrapped.focus(fev).or(act)
     }
 
+    /// Get the position of the terminal cursor, if within the view
+    fn cursor(&self) -> Option<WidgetPos> {
+        let pos = self.wrapped.cursor()?;
+        let view = self.view.get();
+        (pos.col < view.width && pos.row < view.height).then_some(pos)
+    }
+
+    /// Handle key events
+    ///
+    /// A focusable wrapped widget gets keys first, and its cursor row is
+    /// kept visible.  Other keys are translated to a [ScrollCommand]
+    /// through the key map; keys which are not bound are passed to a
+    /// wrapped widget which is not focusable.
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        let focusable = self.wrapped.focusable();
+        if focusable {
+            if let Some(action) = self.wrapped.key_event(key, mods) {
+                self.reveal_cursor();
+                return Some(action);
+            }
+        }
+        if self.state.get() == State::Disabled {
+            return None;
+        }
+        match self.keymap.lookup(key, mods) {
+            Some(cmd) => self.command(cmd),
+            None if !focusable => self.wrapped.key_event(key, mods),
+            None => None,
+        }
+    }
+
+    /// Handle key releases
+    fn key_release(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        self.wrapped.key_release(key, mods)
+    }
+
     /// Handle mouse events
     fn mouse_event(
         &self,
         mev: MouseEvent,
         mods: ModKeys,
         dim: Dim,
-        pos: Pos,
+        pos: WidgetPos,
     ) -> Option<Action> {
         let state = self.state.get();
         match (mev, state) {
@@ -448,15 +1137,588 @@ impl<W: Widget> Widget for ScrollView<W> {
             (MouseEvent::ButtonDown(_), _) => {
                 self.mouse_button_down(mev, mods, dim, pos)
             }
-            (MouseEvent::ButtonUp(_), _) => self.wrapped.mouse_event(
-                mev,
-                mods,
-                dim,
-                self.offset.get() + pos,
-            ),
+            (MouseEvent::Drag(Some(MouseButton::Left)), State::Held) => {
+                self.drag_thumb(dim, pos)
+            }
+            (MouseEvent::ButtonUp(_), State::Held) => {
+                self.grab.set(None);
+                self.set_state(State::Focused)
+            }
+            (MouseEvent::ButtonUp(_), _) | (MouseEvent::Drag(Some(_)), _) => {
+                let vp = self.viewport(dim);
+                if vp.content.is_empty() {
+                    return None;
+                }
+                self.wrapped_mouse_event(mev, mods, vp.content, pos)
+            }
             (MouseEvent::ScrollDown(), _) => self.scroll_down(mods, dim),
             (MouseEvent::ScrollUp(), _) => self.scroll_up(mods, dim),
+            (MouseEvent::ScrollLeft(), _) => self.scroll_horizontal(false),
+            (MouseEvent::ScrollRight(), _) => self.scroll_horizontal(true),
             _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::widget::Label;
+
+    #[test]
+    fn page_down_golden() {
+        let sv =
+            ScrollView::new(Label::new("text")).with_smooth_scrolling(true);
+        let start = Instant::now();
+        sv.scroll_to(Pos::new(0, 20));
+        let rows: Vec<u16> = (0..=7)
+            .map(|i| start + Duration::from_millis(i * 20))
+            .map(|now| sv.current_offset(now).row)
+            .collect();
+        assert_eq!(rows, [0, 6, 11, 15, 18, 19, 20, 20]);
+        assert_eq!(sv.animation.get(), None);
+    }
+
+    #[test]
+    fn linear() {
+        let sv =
+            ScrollView::new(Label::new("text")).with_easing(Easing::Linear);
+        let start = Instant::now();
+        sv.scroll_to(Pos::new(0, 12));
+        // Animation starts on the first frame
+        assert_eq!(sv.current_offset(start), Pos::new(0, 0));
+        let now = start + Duration::from_millis(60);
+        assert_eq!(sv.current_offset(now), Pos::new(0, 6));
+    }
+
+    #[test]
+    fn retarget_mid_flight() {
+        let sv =
+            ScrollView::new(Label::new("text")).with_smooth_scrolling(true);
+        let start = Instant::now();
+        sv.scroll_to(Pos::new(0, 20));
+        assert_eq!(sv.current_offset(start), Pos::new(0, 0));
+        let now = start + Duration::from_millis(40);
+        assert_eq!(sv.current_offset(now), Pos::new(0, 11));
+        // New target starts from the shown position
+        sv.scroll_to(Pos::new(0, 0));
+        assert_eq!(sv.current_offset(now), Pos::new(0, 11));
+        let now = now + SMOOTH_DURATION;
+        assert_eq!(sv.current_offset(now), Pos::new(0, 0));
+    }
+
+    #[test]
+    fn small_change_instant() {
+        let sv =
+            ScrollView::new(Label::new("text")).with_smooth_scrolling(true);
+        let start = Instant::now();
+        sv.scroll_to(Pos::new(0, 1));
+        assert_eq!(sv.animation.get(), None);
+        assert_eq!(sv.current_offset(start), Pos::new(0, 1));
+    }
+
+    #[test]
+    fn disabled() {
+        let sv = ScrollView::new(Label::new("text"));
+        let start = Instant::now();
+        sv.scroll_to(Pos::new(0, 20));
+        assert_eq!(sv.current_offset(start), Pos::new(0, 20));
+    }
+
+    #[test]
+    fn thumb_size() {
+        // Content fits: thumb fills the bar
+        assert_eq!(thumb_span(10, 8, 0), (0, 10));
+        // Half the content visible: half the bar
+        assert_eq!(thumb_span(10, 20, 0), (0, 5));
+        assert_eq!(thumb_span(10, 20, 5), (3, 8));
+        assert_eq!(thumb_span(10, 20, 10), (5, 10));
+        // Very long content: minimum of one cell
+        assert_eq!(thumb_span(10, 1000, 0), (0, 1));
+        assert_eq!(thumb_span(10, 1000, 990), (9, 10));
+        // Stale offset past the end stays on the bar
+        assert_eq!(thumb_span(10, 1000, 5000), (9, 10));
+    }
+
+    #[test]
+    fn thumb_travel() {
+        let starts: Vec<u16> =
+            (0..=20).map(|offset| thumb_span(8, 28, offset).0).collect();
+        assert_eq!(starts.first(), Some(&0));
+        assert_eq!(starts.last(), Some(&6));
+        assert!(starts.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn thumb_click_page() {
+        // Thumb at rows 3..8
+        assert_eq!(thumb_click(10, 20, 5, 0), 0);
+        assert_eq!(thumb_click(10, 20, 5, 4), 5);
+        assert_eq!(thumb_click(10, 20, 5, 9), 10);
+        // Paging is limited to the scrollable range
+        assert_eq!(thumb_click(10, 30, 15, 9), 20);
+        assert_eq!(thumb_click(10, 30, 15, 0), 5);
+        // No underflow at the top
+        assert_eq!(thumb_click(10, 1000, 0, 0), 0);
+        assert_eq!(thumb_click(10, 1000, 0, 5), 10);
+    }
+
+    #[test]
+    fn thumb_drag_inverse() {
+        for offset in 0..=20 {
+            let (start, _) = thumb_span(8, 28, offset);
+            let back = thumb_drag(8, 28, i32::from(start));
+            assert_eq!(thumb_span(8, 28, back).0, start);
+        }
+        assert_eq!(thumb_drag(8, 28, -5), 0);
+        assert_eq!(thumb_drag(8, 28, 50), 20);
+        assert_eq!(thumb_drag(8, 6, 3), 0);
+    }
+
+    #[test]
+    fn drag() {
+        let rows: Vec<String> = (0..30).map(|i| i.to_string()).collect();
+        let sv = ScrollView::new(Label::new(&rows.join("\n")));
+        let theme = Theme::default();
+        sv.width_bounds(&theme);
+        sv.height_bounds(&theme, 2);
+        // Bar is 10 rows, with a 3 row thumb
+        let dim = Dim::new(3, 10);
+        let left = MouseButton::Left;
+        let down = MouseEvent::ButtonDown(left);
+        let drag = MouseEvent::Drag(Some(left));
+        let up = MouseEvent::ButtonUp(left);
+        let mods = ModKeys::EMPTY;
+        sv.mouse_event(down, mods, dim, WidgetPos::new(2, 1));
+        assert_eq!(sv.offset.get(), Pos::new(0, 0));
+        assert_eq!(sv.state.get(), State::Held);
+        // Off the bar, thumb dragged to the bottom
+        let act = sv.mouse_event(drag, mods, dim, WidgetPos::new(0, 8));
+        assert_eq!(act, Some(Action::Redraw()));
+        assert_eq!(sv.offset.get(), Pos::new(0, 20));
+        sv.mouse_event(drag, mods, dim, WidgetPos::new(2, 4));
+        assert_eq!(sv.offset.get(), Pos::new(0, 9));
+        sv.mouse_event(up, mods, dim, WidgetPos::new(0, 0));
+        assert_eq!(sv.state.get(), State::Focused);
+        assert_eq!(sv.mouse_event(drag, mods, dim, WidgetPos::new(2, 0)), None);
+        // Pressing the track pages without grabbing the thumb
+        sv.mouse_event(down, mods, dim, WidgetPos::new(2, 0));
+        assert_eq!(sv.offset.get(), Pos::new(0, 0));
+        assert_eq!(sv.mouse_event(drag, mods, dim, WidgetPos::new(2, 9)), None);
+    }
+
+    /// Create a scroll view with 10x10 view of 20x30 content
+    fn scroll_view() -> ScrollView<Label> {
+        let sv = ScrollView::new(Label::new("text"));
+        sv.view.set(Dim::new(10, 10));
+        sv.content.set(Dim::new(20, 30));
+        sv
+    }
+
+    #[test]
+    fn keys_default() {
+        let sv = scroll_view();
+        let down = KeyPress::Navigation(NavKey::Down);
+        let up = KeyPress::Navigation(NavKey::Up);
+        assert_eq!(sv.key_event(up, ModKeys::EMPTY), None);
+        assert_eq!(sv.key_event(down, ModKeys::EMPTY), Some(Action::Redraw()));
+        assert_eq!(sv.offset.get(), Pos::new(0, 1));
+        let page_down = KeyPress::Navigation(NavKey::PageDown);
+        sv.key_event(page_down, ModKeys::EMPTY);
+        assert_eq!(sv.offset.get(), Pos::new(0, 11));
+        sv.key_event(page_down, ModKeys::EMPTY);
+        assert_eq!(sv.offset.get(), Pos::new(0, 20));
+        assert_eq!(sv.key_event(page_down, ModKeys::EMPTY), None);
+        let right = KeyPress::Navigation(NavKey::Right);
+        // No horizontal scroll bar
+        assert_eq!(sv.key_event(right, ModKeys::EMPTY), None);
+        assert_eq!(sv.key_event(down, ModKeys::SHIFT), None);
+        let home = KeyPress::Navigation(NavKey::Home);
+        assert_eq!(sv.key_event(home, ModKeys::EMPTY), Some(Action::Redraw()));
+        assert_eq!(sv.offset.get(), Pos::new(0, 0));
+        let end = KeyPress::Navigation(NavKey::End);
+        sv.key_event(end, ModKeys::EMPTY);
+        assert_eq!(sv.offset.get(), Pos::new(0, 20));
+    }
+
+    #[test]
+    fn keys_horizontal() {
+        let sv =
+            scroll_view().with_bars(ScrollBar::VerticalAndHorizontal(8, 8));
+        sv.view.set(Dim::new(10, 10));
+        sv.content.set(Dim::new(20, 30));
+        let right = KeyPress::Navigation(NavKey::Right);
+        let left = KeyPress::Navigation(NavKey::Left);
+        assert_eq!(sv.key_event(left, ModKeys::EMPTY), None);
+        sv.key_event(right, ModKeys::EMPTY);
+        assert_eq!(sv.offset.get(), Pos::new(1, 0));
+        sv.key_event(right, ModKeys::SHIFT);
+        assert_eq!(sv.offset.get(), Pos::new(2, 0));
+        for _ in 0..20 {
+            sv.key_event(right, ModKeys::EMPTY);
+        }
+        assert_eq!(sv.offset.get(), Pos::new(10, 0));
+        sv.key_event(left, ModKeys::SHIFT);
+        assert_eq!(sv.offset.get(), Pos::new(9, 0));
+    }
+
+    #[test]
+    fn keys_rebound() {
+        let j = KeyPress::Character('j');
+        let k = KeyPress::Character('k');
+        let keymap = WidgetKeyMap::new()
+            .with(j, ModKeys::EMPTY, ScrollCommand::Down)
+            .with(k, ModKeys::EMPTY, ScrollCommand::Up);
+        let sv = scroll_view().with_keymap(keymap);
+        let down = KeyPress::Navigation(NavKey::Down);
+        assert_eq!(sv.key_event(down, ModKeys::EMPTY), None);
+        assert_eq!(sv.key_event(j, ModKeys::EMPTY), Some(Action::Redraw()));
+        assert_eq!(sv.key_event(j, ModKeys::EMPTY), Some(Action::Redraw()));
+        assert_eq!(sv.offset.get(), Pos::new(0, 2));
+        assert_eq!(sv.key_event(k, ModKeys::EMPTY), Some(Action::Redraw()));
+        assert_eq!(sv.offset.get(), Pos::new(0, 1));
+    }
+
+    /// Create a scroll view around a long label, laid out with bounds
+    fn long_view(bars: ScrollBar) -> ScrollView<Label> {
+        let text = "a long label which wraps onto many rows of text";
+        let sv = ScrollView::new(Label::new(text)).with_bars(bars);
+        let theme = Theme::default();
+        sv.width_bounds(&theme);
+        sv.height_bounds(&theme, 12);
+        sv
+    }
+
+    #[test]
+    fn degrade_vertical() {
+        let sv = long_view(ScrollBar::Vertical(4));
+        let vp = sv.viewport(Dim::new(0, 5));
+        assert_eq!(vp.content, Dim::default());
+        assert_eq!((vp.v_bar, vp.h_bar), (None, None));
+        let vp = sv.viewport(Dim::new(1, 5));
+        assert_eq!(vp.content, Dim::new(0, 5));
+        assert_eq!(vp.v_bar, Some(BBox::new(0, 0, 1, 5)));
+        let vp = sv.viewport(Dim::new(2, 5));
+        assert_eq!(vp.content, Dim::new(2, 5));
+        assert_eq!(vp.v_bar, None);
+        let vp = sv.viewport(Dim::new(3, 5));
+        assert_eq!(vp.content, Dim::new(2, 5));
+        assert_eq!(vp.v_bar, Some(BBox::new(2, 0, 1, 5)));
+    }
+
+    #[test]
+    fn degrade_horizontal() {
+        let sv = long_view(ScrollBar::Horizontal(4));
+        assert_eq!(sv.viewport(Dim::new(5, 0)).content, Dim::default());
+        let vp = sv.viewport(Dim::new(5, 1));
+        assert_eq!(vp.content, Dim::new(5, 0));
+        assert_eq!(vp.h_bar, Some(BBox::new(0, 0, 5, 1)));
+        let vp = sv.viewport(Dim::new(5, 2));
+        assert_eq!(vp.content, Dim::new(5, 2));
+        assert_eq!(vp.h_bar, None);
+        let vp = sv.viewport(Dim::new(5, 3));
+        assert_eq!(vp.content, Dim::new(5, 2));
+        assert_eq!(vp.h_bar, Some(BBox::new(0, 2, 5, 1)));
+    }
+
+    #[test]
+    fn degrade_both() {
+        let sv = long_view(ScrollBar::VerticalAndHorizontal(4, 4));
+        let vp = sv.viewport(Dim::new(1, 1));
+        assert_eq!(vp.content, Dim::new(0, 1));
+        assert_eq!((vp.v_bar, vp.h_bar), (Some(BBox::new(0, 0, 1, 1)), None));
+        let vp = sv.viewport(Dim::new(3, 1));
+        assert_eq!((vp.v_bar, vp.h_bar), (None, Some(BBox::new(0, 0, 3, 1))));
+        let vp = sv.viewport(Dim::new(2, 3));
+        assert_eq!(vp.content, Dim::new(2, 2));
+        assert_eq!((vp.v_bar, vp.h_bar), (None, Some(BBox::new(0, 2, 2, 1))));
+        let vp = sv.viewport(Dim::new(3, 3));
+        assert_eq!(vp.content, Dim::new(2, 2));
+        assert_eq!(vp.v_bar, Some(BBox::new(2, 0, 1, 2)));
+        assert_eq!(vp.h_bar, Some(BBox::new(0, 2, 2, 1)));
+    }
+
+    #[test]
+    fn degrade_mouse() {
+        use crate::input::MouseButton;
+        let events = [
+            MouseEvent::ButtonDown(MouseButton::Left),
+            MouseEvent::ButtonUp(MouseButton::Left),
+            MouseEvent::ScrollDown(),
+            MouseEvent::ScrollUp(),
+        ];
+        let bars = [
+            ScrollBar::Vertical(4),
+            ScrollBar::Horizontal(4),
+            ScrollBar::VerticalAndHorizontal(4, 4),
+        ];
+        for bars in bars {
+            for width in 0..=3 {
+                for height in 0..=3 {
+                    let sv = long_view(bars);
+                    let dim = Dim::new(width, height);
+                    for mev in events {
+                        for mods in [ModKeys::EMPTY, ModKeys::SHIFT] {
+                            for (col, row) in [(0, 0), (2, 2)] {
+                                let pos = WidgetPos::new(col, row);
+                                sv.mouse_event(mev, mods, dim, pos);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        // Zero size ignores events
+        let sv = long_view(ScrollBar::Vertical(4));
+        let mev = MouseEvent::ButtonDown(MouseButton::Left);
+        let dim = Dim::new(0, 3);
+        let pos = WidgetPos::default();
+        assert_eq!(sv.mouse_event(mev, ModKeys::EMPTY, dim, pos), None);
+        // Bar only still adjusts the offset
+        let dim = Dim::new(1, 3);
+        let pos = WidgetPos::new(0, 2);
+        let act = sv.mouse_event(mev, ModKeys::EMPTY, dim, pos);
+        assert_eq!(act, Some(Action::Redraw()));
+        assert_eq!(sv.offset.get(), Pos::new(0, 1));
+    }
+
+    /// Widget which records mouse event positions
+    #[derive(Default)]
+    struct Probe {
+        last: Cell<Option<(Dim, WidgetPos)>>,
+    }
+
+    impl Widget for Probe {
+        fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+            LengthBound::new(6..=6)
+        }
+
+        fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+            LengthBound::new(40..=40)
+        }
+
+        fn mouse_event(
+            &self,
+            _mev: MouseEvent,
+            _mods: ModKeys,
+            dim: Dim,
+            pos: WidgetPos,
+        ) -> Option<Action> {
+            self.last.set(Some((dim, pos)));
+            None
+        }
+    }
+
+    #[test]
+    fn border_mouse_pos() {
+        use crate::input::MouseButton;
+        let theme = Theme::default();
+        let sv = Probe::default().into_border().into_scroll_view();
+        let width = sv.width_bounds(&theme).minimum();
+        sv.height_bounds(&theme, width);
+        assert_eq!(sv.content.get(), Dim::new(8, 42));
+        sv.scroll_to(Pos::new(0, 20));
+        let dim = Dim::new(width, 10);
+        let mev = MouseEvent::ButtonDown(MouseButton::Left);
+        sv.mouse_event(mev, ModKeys::EMPTY, dim, WidgetPos::new(3, 2));
+        let probe = sv.wrapped.wrapped();
+        assert_eq!(
+            probe.last.get(),
+            Some((Dim::new(6, 40), WidgetPos::new(2, 21)))
+        );
+        // Clicks on the border are clamped into the wrapped widget
+        let mev = MouseEvent::ButtonUp(MouseButton::Left);
+        sv.mouse_event(mev, ModKeys::EMPTY, dim, WidgetPos::new(0, 0));
+        assert_eq!(
+            probe.last.get(),
+            Some((Dim::new(6, 40), WidgetPos::new(0, 19)))
+        );
+    }
+
+    #[test]
+    fn evict_keeps_view() -> Result<()> {
+        use crate::export::to_text;
+        use crate::grid_area;
+        use crate::widget::Text;
+        let text = Text::default().with_capacity_lines(1000);
+        text.extend_lines((0..1000).map(|i| format!("line {}", i)));
+        let sv = ScrollView::new(text).with_bars(ScrollBar::Vertical(1));
+        sv.scroll_to(Pos::new(0, 100));
+        let grid = grid_area!([sv])?;
+        let dim = Dim::new(10, 20);
+        let theme = Theme::default();
+        let before = to_text(&grid, dim, &theme)?;
+        assert!(before.starts_with("line 100"));
+        assert!(before.contains("line 119"));
+        sv.wrapped()
+            .extend_lines((1000..1050).map(|i| format!("line {}", i)));
+        assert_eq!(sv.wrapped().lines_range(0..1), ["line 50"]);
+        let after = to_text(&grid, dim, &theme)?;
+        assert_eq!(sv.offset.get(), Pos::new(0, 50));
+        assert_eq!(sv.wrapped().lines_range(50..70)[0], "line 100");
+        // Scroll bar thumb may move, but content does not
+        let content = |t: &str| -> Vec<String> {
+            t.lines().map(|l| l[..9].to_string()).collect()
+        };
+        assert_eq!(content(&after), content(&before));
+        Ok(())
+    }
+
+    #[test]
+    fn wheel_horizontal() {
+        let sv = ScrollView::new(Label::new("text"))
+            .with_bars(ScrollBar::VerticalAndHorizontal(1, 1))
+            .with_column_step(4);
+        sv.view.set(Dim::new(10, 10));
+        sv.content.set(Dim::new(20, 30));
+        let dim = Dim::new(11, 11);
+        let pos = WidgetPos::new(2, 2);
+        let right = MouseEvent::ScrollRight();
+        let left = MouseEvent::ScrollLeft();
+        let redraw = Some(Action::Redraw());
+        assert_eq!(sv.mouse_event(left, ModKeys::EMPTY, dim, pos), None);
+        assert_eq!(sv.mouse_event(right, ModKeys::EMPTY, dim, pos), redraw);
+        assert_eq!(sv.offset.get(), Pos::new(4, 0));
+        sv.mouse_event(right, ModKeys::EMPTY, dim, pos);
+        sv.mouse_event(right, ModKeys::EMPTY, dim, pos);
+        // Clamped to the content width
+        assert_eq!(sv.offset.get(), Pos::new(10, 0));
+        assert_eq!(sv.mouse_event(right, ModKeys::EMPTY, dim, pos), None);
+        assert_eq!(sv.mouse_event(left, ModKeys::EMPTY, dim, pos), redraw);
+        assert_eq!(sv.offset.get(), Pos::new(6, 0));
+        // No horizontal bar
+        let sv = scroll_view();
+        assert_eq!(sv.mouse_event(right, ModKeys::EMPTY, dim, pos), None);
+        assert_eq!(sv.offset.get(), Pos::new(0, 0));
+    }
+
+    #[test]
+    fn persist() -> Result<()> {
+        use crate::text::Theme;
+        use crate::widget::Text;
+        use crate::{grid_area, Screen};
+        let lines: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let sv = ScrollView::new(Text::new(&lines.join("\n")));
+        let grid = grid_area!([sv])?;
+        let mut screen = Screen::headless(Dim::new(4, 4), Theme::default());
+        screen.render(&grid)?;
+        let blob = StateBlob::new(STATE_VERSION).with_values(vec![10, 0]);
+        sv.restore_state(&blob);
+        screen.render(&grid)?;
+        assert_eq!(sv.save_state(), Some(blob));
+        // Stale offset past the content is clamped on draw
+        sv.restore_state(
+            &StateBlob::new(STATE_VERSION).with_values(vec![500, 9]),
+        );
+        screen.render(&grid)?;
+        assert_eq!(sv.offset.get(), Pos::new(0, 16));
+        // Mismatched shape is ignored
+        sv.restore_state(&StateBlob::new(STATE_VERSION).with_values(vec![1]));
+        sv.restore_state(&StateBlob::new(0).with_values(vec![1, 1]));
+        assert_eq!(sv.offset.get(), Pos::new(0, 16));
+        Ok(())
+    }
+
+    #[test]
+    fn persist_wrapped() -> Result<()> {
+        use crate::text::Theme;
+        use crate::widget::ListView;
+        use crate::{grid_area, Screen};
+        let rows = (0..20).map(|i| i.to_string()).collect();
+        let sv = ScrollView::new(ListView::new(rows));
+        sv.wrapped.select(7);
+        let grid = grid_area!([(#1 sv)])?;
+        let mut screen = Screen::headless(Dim::new(4, 4), Theme::default());
+        screen.render(&grid)?;
+        let session = screen.export_session(&grid);
+        let nested = session.get(1).and_then(|b| b.nested());
+        assert_eq!(nested.map(|b| b.values()), Some(&[7][..]));
+        let rows = (0..20).map(|i| i.to_string()).collect();
+        let sv = ScrollView::new(ListView::new(rows));
+        let grid = grid_area!([(#1 sv)])?;
+        screen.import_session(session);
+        screen.render(&grid)?;
+        assert_eq!(sv.wrapped.selected(), Some(7));
+        Ok(())
+    }
+
+    #[test]
+    fn set_offset() -> Result<()> {
+        use crate::text::Theme;
+        use crate::widget::Text;
+        use crate::{grid_area, Screen};
+        let lines: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let sv = ScrollView::new(Text::new(&lines.join("\n")));
+        let grid = grid_area!([sv])?;
+        let mut screen = Screen::headless(Dim::new(4, 4), Theme::default());
+        screen.render(&grid)?;
+        sv.set_offset(Pos::new(3, 5));
+        assert_eq!(sv.offset(), Pos::new(0, 5));
+        screen.render(&grid)?;
+        assert!(screen.text().starts_with("5  "), "{:?}", screen.text());
+        sv.set_offset(Pos::new(0, 100));
+        assert_eq!(sv.offset(), Pos::new(0, 16));
+        sv.scroll_to_top();
+        assert_eq!(sv.offset(), Pos::new(0, 0));
+        // Lines added before the next draw are revealed
+        sv.scroll_to_bottom();
+        sv.wrapped().push_line("20".to_string());
+        screen.render(&grid)?;
+        assert_eq!(sv.offset(), Pos::new(0, 17));
+        assert!(screen.text().starts_with("17 "), "{:?}", screen.text());
+        Ok(())
+    }
+
+    #[test]
+    fn smooth_frames() -> Result<()> {
+        use crate::input::{Event, KeyPress, ModKeys, NavKey};
+        use crate::text::Theme;
+        use crate::widget::Text;
+        use crate::{grid_area, Screen};
+        let lines: Vec<String> = (0..40).map(|i| i.to_string()).collect();
+        let sv = ScrollView::new(Text::new(&lines.join("\n")))
+            .with_easing(Easing::Linear);
+        let grid = grid_area!([sv])?;
+        let mut screen = Screen::headless(Dim::new(4, 4), Theme::default());
+        let start = Instant::now();
+        screen.set_time(start);
+        screen.step_with_events(&grid, [])?;
+        assert_eq!(screen.next_frame(), None);
+        let key = |k| Event::Key(KeyPress::Navigation(k), ModKeys::EMPTY);
+        screen.step_with_events(&grid, [key(NavKey::Tab)])?;
+        screen.step_with_events(&grid, [key(NavKey::PageDown)])?;
+        // Frames are scheduled until the animation is done
+        let mut rows = vec![];
+        while let Some(at) = screen.next_frame() {
+            assert!(at > start);
+            screen.set_time(
+                start + Duration::from_millis(30) * rows.len() as u32,
+            );
+            screen.step_with_events(&grid, [])?;
+            let text = screen.text();
+            rows.push(text.split_whitespace().next().map(String::from));
+        }
+        let rows: Vec<_> = rows.iter().flatten().map(String::as_str).collect();
+        assert_eq!(rows, ["0", "1", "2", "3", "4"]);
+        assert_eq!(sv.offset(), Pos::new(0, 4));
+        Ok(())
+    }
+
+    #[test]
+    fn label_offset() -> Result<()> {
+        use crate::text::Theme;
+        use crate::{grid_area, Screen};
+        let lines: Vec<String> =
+            (0..50).map(|i| format!("line {}", i)).collect();
+        let sv = ScrollView::new(Label::new(&lines.join("\n")));
+        let grid = grid_area!([sv])?;
+        let mut screen = Screen::headless(Dim::new(8, 4), Theme::default());
+        screen.render(&grid)?;
+        assert!(screen.text().starts_with("line 0 "), "{:?}", screen.text());
+        sv.set_offset(Pos::new(0, 10));
+        screen.render(&grid)?;
+        assert!(screen.text().starts_with("line 10"), "{:?}", screen.text());
+        assert!(screen.text().contains("line 13"), "{:?}", screen.text());
+        Ok(())
+    }
+}
@@ -2,8 +2,8 @@
 //
 // Copyright (c) 2020-2021  Douglas P Lau
 //
-use crate::layout::{Cells, LengthBound, Pos};
-use crate::text::{Glyph, IntoGlyph, Theme};
+use crate::layout::{Cells, ContentPos, Dim, Length, LengthBound};
+use crate::text::{Glyph, IntoGlyph, TextStyle, Theme};
 use crate::{Result, Widget};
 use std::ops::RangeBounds;
 
@@ -13,15 +13,27 @@ use std::ops::RangeBounds;
 /// widgets.  By default it draws nothing, but a fill glyph may be provided
 /// using [with_fill].
 ///
+/// Spacers are decorative, so they are not hit-testable unless enabled with
+/// [with_hit_test].
+///
 /// [with_fill]: struct.Spacer.html#method.with_fill
+/// [with_hit_test]: struct.Spacer.html#method.with_hit_test
 #[derive(Default)]
 pub struct Spacer {
     /// Width bounds
     width_bounds: LengthBound,
     /// Height bounds
     height_bounds: LengthBound,
+    /// Relative width
+    width_len: Option<Length>,
+    /// Relative height
+    height_len: Option<Length>,
     /// Fill character
     fill: Option<Glyph>,
+    /// Hit-testable flag
+    hit_test: bool,
+    /// Style override
+    style: Option<TextStyle>,
 }
 
 impl Spacer {
@@ -38,6 +50,23 @@ impl Spacer {
         R: RangeBounds<u16>,
     {
         self.width_bounds = LengthBound::new(col);
+        self.width_len = None;
+        self
+    }
+
+    /// Set column spacing to a [Length]
+    ///
+    /// Relative lengths are resolved against the width of the containing
+    /// area.
+    ///
+    /// ```rust
+    /// use semtext::layout::Length;
+    /// use semtext::widget::Spacer;
+    ///
+    /// let margin = Spacer::default().with_columns_len(Length::Percent(5));
+    /// ```
+    pub fn with_columns_len(mut self, len: Length) -> Self {
+        self.width_len = Some(len);
         self
     }
 
@@ -54,6 +83,16 @@ impl Spacer {
         R: RangeBounds<u16>,
     {
         self.height_bounds = LengthBound::new(row);
+        self.height_len = None;
+        self
+    }
+
+    /// Set row spacing to a [Length]
+    ///
+    /// Relative lengths are resolved against the height of the containing
+    /// area.
+    pub fn with_rows_len(mut self, len: Length) -> Self {
+        self.height_len = Some(len);
         self
     }
 
@@ -62,9 +101,26 @@ impl Spacer {
         self.fill = Some(fill.into_glyph()?);
         Ok(self)
     }
+
+    /// Set whether the spacer is hit-testable
+    pub fn with_hit_test(mut self, enable: bool) -> Self {
+        self.hit_test = enable;
+        self
+    }
+
+    /// Set a style, overriding the theme colors
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
 }
 
 impl Widget for Spacer {
+    /// Get the style override
+    fn style_override(&self) -> Option<TextStyle> {
+        self.style
+    }
+
     /// Get the width bounds
     fn width_bounds(&self, _theme: &Theme) -> LengthBound {
         self.width_bounds
@@ -75,11 +131,43 @@ impl Widget for Spacer {
         self.height_bounds
     }
 
+    /// Get the width bounds within a containing area
+    fn width_bounds_in(&self, _theme: &Theme, area: Dim) -> LengthBound {
+        match self.width_len {
+            Some(len) => {
+                let cols = len.resolve(area.width);
+                LengthBound::new(cols..=cols)
+            }
+            None => self.width_bounds,
+        }
+    }
+
+    /// Get the height bounds within a containing area
+    fn height_bounds_in(
+        &self,
+        _theme: &Theme,
+        _width: u16,
+        area: Dim,
+    ) -> LengthBound {
+        match self.height_len {
+            Some(len) => {
+                let rows = len.resolve(area.height);
+                LengthBound::new(rows..=rows)
+            }
+            None => self.height_bounds,
+        }
+    }
+
     /// Draw the widget
-    fn draw(&self, cells: &mut Cells, _offset: Pos) -> Result<()> {
+    fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
         if let Some(fill) = &self.fill {
             cells.fill(fill)?;
         }
         Ok(())
     }
+
+    /// Check whether the widget is hit-testable
+    fn hit_testable(&self) -> bool {
+        self.hit_test
+    }
 }
@@ -0,0 +1,194 @@
+// text.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::layout::{Cells, ContentPos, LengthBound};
+use crate::text::{display_width, visible_span, Theme};
+use crate::{Result, Widget};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// Text widget
+///
+/// Text is displayed one line per row, without wrapping.  Lines can be
+/// appended while the widget is in use, for following a growing file or the
+/// output of a subprocess.
+///
+/// With a line capacity, the oldest lines are dropped as new ones are
+/// appended.  A wrapping [ScrollView] is adjusted so that the visible lines
+/// stay in place.
+///
+/// [ScrollView]: struct.ScrollView.html
+#[derive(Debug, Default)]
+pub struct Text {
+    /// Lines of text
+    lines: RefCell<VecDeque<String>>,
+    /// Maximum number of lines
+    capacity: Option<usize>,
+    /// Width of widest line
+    width: Cell<u16>,
+    /// Lines removed from the front since last taken
+    removed: Cell<usize>,
+}
+
+/// Get the display width of a line
+fn line_width(line: &str) -> u16 {
+    display_width(line).try_into().unwrap_or(u16::MAX)
+}
+
+impl Text {
+    /// Create a new text widget
+    ///
+    /// * `text`: Initial text, split into lines at `\n`
+    pub fn new(text: &str) -> Self {
+        let txt = Text::default();
+        if !text.is_empty() {
+            txt.extend_lines(text.lines().map(String::from));
+        }
+        txt
+    }
+
+    /// Set the maximum number of lines
+    ///
+    /// When lines are appended past the capacity, the oldest are dropped.
+    pub fn with_capacity_lines(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self.evict();
+        self.update_width();
+        self
+    }
+
+    /// Get the number of lines
+    pub fn len(&self) -> usize {
+        self.lines.borrow().len()
+    }
+
+    /// Check if there are no lines
+    pub fn is_empty(&self) -> bool {
+        self.lines.borrow().is_empty()
+    }
+
+    /// Append one line
+    pub fn push_line(&self, line: String) {
+        self.extend_lines(std::iter::once(line));
+    }
+
+    /// Append lines in bulk
+    ///
+    /// Lines past the capacity are dropped only once all have been
+    /// appended.
+    pub fn extend_lines(&self, lines: impl IntoIterator<Item = String>) {
+        let mut width = self.width.get();
+        {
+            let mut ln = self.lines.borrow_mut();
+            for line in lines {
+                width = width.max(line_width(&line));
+                ln.push_back(line);
+            }
+        }
+        self.width.set(width);
+        if self.evict() > 0 {
+            self.update_width();
+        }
+    }
+
+    /// Get a range of lines
+    ///
+    /// The range is clamped to the lines present.
+    pub fn lines_range(&self, range: Range<usize>) -> Vec<String> {
+        let lines = self.lines.borrow();
+        let end = range.end.min(lines.len());
+        let start = range.start.min(end);
+        lines.range(start..end).cloned().collect()
+    }
+
+    /// Drop lines past the capacity
+    ///
+    /// ## Return
+    ///
+    /// Number of lines dropped.
+    fn evict(&self) -> usize {
+        let mut lines = self.lines.borrow_mut();
+        let excess = match self.capacity {
+            Some(cap) => lines.len().saturating_sub(cap),
+            None => 0,
+        };
+        lines.drain(..excess);
+        self.removed.set(self.removed.get() + excess);
+        excess
+    }
+
+    /// Update the width of the widest line
+    fn update_width(&self) {
+        let lines = self.lines.borrow();
+        let width = lines.iter().map(|l| line_width(l)).max().unwrap_or(0);
+        self.width.set(width);
+    }
+}
+
+impl Widget for Text {
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        LengthBound::new(self.width.get()..)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        let rows = self.len().try_into().unwrap_or(u16::MAX);
+        LengthBound::new(rows..=rows)
+    }
+
+    /// Take the number of rows removed from the top
+    fn take_rows_removed(&self) -> u16 {
+        self.removed.take().try_into().unwrap_or(u16::MAX)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        let width = cells.width();
+        let height = usize::from(cells.height());
+        let lines = self.lines.borrow();
+        let lines = lines.iter().skip(usize::from(offset.row));
+        for (row, line) in lines.take(height).enumerate() {
+            let row = row as u16; // limited to u16 by take(height)
+            if let Some((col, span)) = visible_span(line, offset.col, width) {
+                cells.move_to(col, row)?;
+                cells.print_str(span)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn extend() {
+        let text = Text::new("one\ntwo");
+        text.extend_lines(["three", "four"].map(String::from));
+        assert_eq!(text.len(), 4);
+        assert_eq!(text.lines_range(1..3), ["two", "three"]);
+        assert_eq!(text.lines_range(3..10), ["four"]);
+        let theme = Theme::default();
+        assert_eq!(text.width_bounds(&theme).minimum(), 5);
+        assert_eq!(text.height_bounds(&theme, 5), LengthBound::new(4..=4));
+        assert_eq!(text.take_rows_removed(), 0);
+    }
+
+    #[test]
+    fn capacity() {
+        let text = Text::new("a long line\nb").with_capacity_lines(3);
+        text.extend_lines(["c", "d"].map(String::from));
+        assert_eq!(text.lines_range(0..3), ["b", "c", "d"]);
+        // Width shrinks when the widest line is dropped
+        assert_eq!(text.width_bounds(&Theme::default()).minimum(), 1);
+        assert_eq!(text.take_rows_removed(), 1);
+        assert_eq!(text.take_rows_removed(), 0);
+        text.push_line("e".to_string());
+        assert_eq!(text.lines_range(0..3), ["c", "d", "e"]);
+        assert_eq!(text.take_rows_removed(), 1);
+    }
+}
@@ -0,0 +1,640 @@
+// textinput.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{
+    Action, FocusEvent, InputFilter, InputRules, KeyPress, ModKeys,
+    MouseButton, MouseEvent, NavKey, WidgetId,
+};
+use crate::layout::{Cells, ContentPos, Dim, LengthBound, WidgetPos};
+use crate::text::{
+    column_to_index, display_width, index_to_column, visible_span, StyleGroup,
+    StyleOverlay, Theme,
+};
+use crate::{PersistState, Result, StateBlob, Widget};
+use std::cell::{Cell, RefCell};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Version of saved state representation
+const STATE_VERSION: u32 = 1;
+
+/// Tab width for column conversion (text never contains tabs)
+const NO_TABS: u16 = 1;
+
+/// Text input state
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    /// Input disabled
+    Disabled,
+    /// Input enabled
+    Enabled,
+    /// Input focused
+    Focused,
+}
+
+/// Single-line text input widget
+///
+/// The cursor is moved with Left / Right / Home / End, and Backspace /
+/// Delete remove the grapheme before / after it.  When the text is wider
+/// than the widget, it scrolls horizontally to keep the cursor visible.
+///
+/// Each edit produces an [Action::Changed] with the id of the widget.
+/// Typed and pasted text can be limited by a maximum length and a character
+/// filter; when any of it is rejected, the bell is rung.
+pub struct TextInput {
+    /// Current text
+    text: RefCell<String>,
+    /// Cursor byte index (at a grapheme boundary)
+    cursor: Cell<usize>,
+    /// Column scroll offset
+    scroll: Cell<u16>,
+    /// Mask character for password entry
+    mask: Option<char>,
+    /// Input state
+    state: Cell<State>,
+    /// Rules for inserted text
+    rules: InputRules,
+    /// Bell requested by rejected input
+    bell: Cell<bool>,
+}
+
+/// Get the byte index of the previous grapheme boundary
+fn prev_boundary(text: &str, idx: usize) -> usize {
+    text[..idx]
+        .grapheme_indices(true)
+        .next_back()
+        .map_or(0, |(i, _)| i)
+}
+
+/// Get the byte index of the next grapheme boundary
+fn next_boundary(text: &str, idx: usize) -> usize {
+    text[idx..]
+        .graphemes(true)
+        .next()
+        .map_or(idx, |g| idx + g.len())
+}
+
+impl TextInput {
+    /// Create a new text input widget
+    ///
+    /// * `text`: Initial text; the cursor is placed at the end
+    pub fn new(text: &str) -> Self {
+        TextInput {
+            text: RefCell::new(single_line(text)),
+            cursor: Cell::new(single_line(text).len()),
+            scroll: Cell::new(0),
+            mask: None,
+            state: Cell::new(State::Enabled),
+            rules: InputRules::default(),
+            bell: Cell::new(false),
+        }
+    }
+
+    /// Mask the displayed text, for password entry
+    ///
+    /// * `mask`: Character drawn for each grapheme
+    pub fn with_mask(mut self, mask: char) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Limit the length of typed and pasted text
+    ///
+    /// * `max_length`: Maximum length in grapheme clusters
+    ///
+    /// Text set with [set_text](TextInput::set_text) is not limited.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.rules = self.rules.with_max_length(max_length);
+        self
+    }
+
+    /// Filter typed and pasted characters
+    ///
+    /// * `filter`: Filter of accepted characters
+    pub fn with_filter(mut self, filter: InputFilter) -> Self {
+        self.rules = self.rules.with_filter(filter);
+        self
+    }
+
+    /// Get the number of grapheme clusters remaining before the maximum
+    /// length
+    ///
+    /// ## Return
+    ///
+    /// `None` if the length is not limited.
+    pub fn remaining(&self) -> Option<usize> {
+        self.rules.remaining(&self.text.borrow())
+    }
+
+    /// Paste text at the cursor
+    ///
+    /// * `text`: Pasted text
+    ///
+    /// Line breaks are removed, rejected characters dropped, and the rest
+    /// truncated to the remaining length.  The bell is requested if any of
+    /// the text was rejected.
+    ///
+    /// ## Return
+    ///
+    /// [Action::Changed] if any text was inserted.
+    pub fn paste(&self, text: &str) -> Option<Action> {
+        if self.state.get() == State::Disabled {
+            return None;
+        }
+        self.insert(&single_line(text))
+    }
+
+    /// Get the current text
+    pub fn text(&self) -> String {
+        self.text.borrow().clone()
+    }
+
+    /// Set the text
+    ///
+    /// The cursor is placed at the end.  Setting the text does not produce
+    /// an [Action::Changed].
+    pub fn set_text(&self, text: &str) {
+        let text = single_line(text);
+        self.cursor.set(text.len());
+        *self.text.borrow_mut() = text;
+    }
+
+    /// Get the cursor byte index
+    pub fn cursor(&self) -> usize {
+        self.cursor.get()
+    }
+
+    /// Disable the input
+    pub fn disable(&self) {
+        self.state.set(State::Disabled);
+    }
+
+    /// Enable the input
+    pub fn enable(&self) {
+        if self.state.get() == State::Disabled {
+            self.state.set(State::Enabled);
+        }
+    }
+
+    /// Get the text as displayed, with its cursor byte index
+    fn display(&self) -> (String, usize) {
+        let text = self.text.borrow();
+        let cursor = self.cursor.get();
+        match self.mask {
+            Some(mask) => {
+                let before = text[..cursor].graphemes(true).count();
+                let count = before + text[cursor..].graphemes(true).count();
+                let masked: String = std::iter::repeat_n(mask, count).collect();
+                (masked, before * mask.len_utf8())
+            }
+            None => (text.clone(), cursor),
+        }
+    }
+
+    /// Convert a byte index in the displayed text to one in the text
+    fn text_index(&self, idx: usize) -> usize {
+        match self.mask {
+            Some(mask) => {
+                let count = idx / mask.len_utf8();
+                let text = self.text.borrow();
+                text.grapheme_indices(true)
+                    .nth(count)
+                    .map_or(text.len(), |(i, _)| i)
+            }
+            None => idx,
+        }
+    }
+
+    /// Scroll so that the cursor is visible
+    ///
+    /// * `width`: Width of the widget
+    fn scroll_to_cursor(&self, width: u16) {
+        let (display, cursor) = self.display();
+        let col = index_to_column(&display, cursor, 0, NO_TABS);
+        let next = next_boundary(&display, cursor);
+        let cursor_width =
+            (display_width(&display[cursor..next]) as u16).max(1);
+        let scroll = self.scroll.get();
+        if col < scroll {
+            self.scroll.set(col);
+        } else if col + cursor_width > scroll + width {
+            self.scroll.set((col + cursor_width).saturating_sub(width));
+        }
+    }
+
+    /// Move the cursor
+    fn move_cursor(&self, to: usize) -> Option<Action> {
+        if to == self.cursor.get() {
+            return None;
+        }
+        self.cursor.set(to);
+        Some(Action::Redraw())
+    }
+
+    /// Insert text at the cursor, applying the input rules
+    fn insert(&self, input: &str) -> Option<Action> {
+        let cursor = self.cursor.get();
+        let mut text = self.text.borrow_mut();
+        let ins = self.rules.insert(&text, cursor, input);
+        if ins.rejected {
+            self.bell.set(true);
+        }
+        if ins.text.is_empty() {
+            return None;
+        }
+        text.insert_str(cursor, &ins.text);
+        // A combining character can join the preceding grapheme
+        let after = cursor + ins.text.len();
+        self.cursor
+            .set(next_boundary(&text, prev_boundary(&text, after)));
+        Some(Action::Changed(WidgetId::of(self)))
+    }
+
+    /// Remove text between two byte indices
+    fn remove(&self, start: usize, end: usize) -> Option<Action> {
+        if start == end {
+            return None;
+        }
+        self.text.borrow_mut().replace_range(start..end, "");
+        self.cursor.set(start);
+        Some(Action::Changed(WidgetId::of(self)))
+    }
+}
+
+/// Make text into a single line
+///
+/// Line breaks are removed, and tabs replaced with spaces.
+fn single_line(text: &str) -> String {
+    text.chars()
+        .filter(|c| *c != '\n' && *c != '\r')
+        .map(|c| if c == '\t' { ' ' } else { c })
+        .collect()
+}
+
+impl PersistState for TextInput {
+    /// Save the text and cursor
+    fn save_state(&self) -> Option<StateBlob> {
+        let values = vec![self.cursor.get() as u64];
+        let blob = StateBlob::new(STATE_VERSION).with_values(values);
+        Some(blob.with_text(&self.text.borrow()))
+    }
+
+    /// Restore the text and cursor
+    ///
+    /// A cursor which is not at a grapheme boundary is moved to the end.
+    fn restore_state(&self, blob: &StateBlob) {
+        if let (STATE_VERSION, [cursor]) = (blob.version(), blob.values()) {
+            self.set_text(blob.text());
+            let text = self.text.borrow();
+            let cursor = usize::try_from(*cursor).unwrap_or(usize::MAX);
+            if text.grapheme_indices(true).any(|(i, _)| i == cursor) {
+                self.cursor.set(cursor);
+            }
+        }
+    }
+}
+
+impl Widget for TextInput {
+    /// Get the style group
+    fn style_group(&self) -> StyleGroup {
+        match self.state.get() {
+            State::Disabled => StyleGroup::Disabled,
+            State::Enabled => StyleGroup::Enabled,
+            State::Focused => StyleGroup::Focused,
+        }
+    }
+
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        LengthBound::new(1..)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        LengthBound::new(1..=1)
+    }
+
+    /// Get the persistent state
+    fn persist(&self) -> Option<&dyn PersistState> {
+        Some(self)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, _offset: ContentPos) -> Result<()> {
+        let width = cells.width();
+        self.scroll_to_cursor(width);
+        let scroll = self.scroll.get();
+        let (display, cursor) = self.display();
+        if let Some((col, span)) = visible_span(&display, scroll, width) {
+            cells.move_to(col, 0)?;
+            cells.print_str(span)?;
+        }
+        if self.state.get() == State::Focused {
+            let col = index_to_column(&display, cursor, scroll, NO_TABS);
+            let next = next_boundary(&display, cursor);
+            let under = if next > cursor {
+                &display[cursor..next]
+            } else {
+                " "
+            };
+            cells.push_style_overlay(
+                StyleOverlay::default().with_reverse(true),
+            )?;
+            cells.move_to(col, 0)?;
+            cells.print_str(under)?;
+            cells.pop_style_overlay()?;
+        }
+        Ok(())
+    }
+
+    /// Get the position of the terminal cursor, while focused
+    fn cursor(&self) -> Option<WidgetPos> {
+        if self.state.get() != State::Focused {
+            return None;
+        }
+        let (display, cursor) = self.display();
+        let col = index_to_column(&display, cursor, self.scroll.get(), NO_TABS);
+        Some(WidgetPos::new(col, 0))
+    }
+
+    /// Check whether the input can accept focus
+    fn focusable(&self) -> bool {
+        self.state.get() != State::Disabled
+    }
+
+    /// Take a bell requested by rejected input
+    fn take_bell(&self) -> bool {
+        self.bell.replace(false)
+    }
+
+    /// Handle focus event
+    fn focus(&self, fev: FocusEvent) -> Option<Action> {
+        let state = match (fev, self.state.get()) {
+            (_, State::Disabled) => return None,
+            (FocusEvent::Offer, _) => State::Focused,
+            (FocusEvent::Take, _) => State::Enabled,
+            _ => return None,
+        };
+        if state == self.state.get() {
+            return None;
+        }
+        self.state.set(state);
+        Some(Action::Redraw())
+    }
+
+    /// Handle key events
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        if self.state.get() == State::Disabled {
+            return None;
+        }
+        let cursor = self.cursor.get();
+        let len = self.text.borrow().len();
+        match (key, mods) {
+            (KeyPress::Navigation(nav), ModKeys::EMPTY) => match nav {
+                NavKey::Left => {
+                    self.move_cursor(prev_boundary(&self.text.borrow(), cursor))
+                }
+                NavKey::Right => {
+                    self.move_cursor(next_boundary(&self.text.borrow(), cursor))
+                }
+                NavKey::Home => self.move_cursor(0),
+                NavKey::End => self.move_cursor(len),
+                NavKey::Backspace => {
+                    let start = prev_boundary(&self.text.borrow(), cursor);
+                    self.remove(start, cursor)
+                }
+                NavKey::Delete => {
+                    let end = next_boundary(&self.text.borrow(), cursor);
+                    self.remove(cursor, end)
+                }
+                _ => None,
+            },
+            (KeyPress::Character(ch), ModKeys::EMPTY | ModKeys::SHIFT)
+                if !ch.is_control() =>
+            {
+                let mut buf = [0; 4];
+                self.insert(ch.encode_utf8(&mut buf))
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle mouse events
+    ///
+    /// Clicking moves the cursor to the nearest grapheme boundary.
+    fn mouse_event(
+        &self,
+        mev: MouseEvent,
+        _mods: ModKeys,
+        _dim: Dim,
+        pos: WidgetPos,
+    ) -> Option<Action> {
+        if mev != MouseEvent::ButtonDown(MouseButton::Left)
+            || self.state.get() == State::Disabled
+        {
+            return None;
+        }
+        let (display, _) = self.display();
+        let (idx, _) =
+            column_to_index(&display, pos.col, self.scroll.get(), NO_TABS);
+        self.move_cursor(self.text_index(idx))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::to_text;
+    use crate::grid_area;
+
+    /// Type some text
+    fn type_text(input: &TextInput, text: &str) {
+        for ch in text.chars() {
+            input.key_event(KeyPress::Character(ch), ModKeys::EMPTY);
+        }
+    }
+
+    /// Press a sequence of navigation keys
+    fn press(input: &TextInput, keys: &[NavKey]) {
+        for key in keys {
+            input.key_event(KeyPress::Navigation(*key), ModKeys::EMPTY);
+        }
+    }
+
+    #[test]
+    fn cursor_pos() {
+        let input = TextInput::new("日本");
+        assert_eq!(Widget::cursor(&input), None);
+        input.focus(FocusEvent::Offer);
+        assert_eq!(Widget::cursor(&input), Some(WidgetPos::new(4, 0)));
+        press(&input, &[NavKey::Left]);
+        assert_eq!(Widget::cursor(&input), Some(WidgetPos::new(2, 0)));
+    }
+
+    #[test]
+    fn editing() {
+        let input = TextInput::new("");
+        type_text(&input, "hello");
+        assert_eq!(input.text(), "hello");
+        press(&input, &[NavKey::Left, NavKey::Left, NavKey::Backspace]);
+        assert_eq!(input.text(), "helo");
+        assert_eq!(input.cursor(), 2);
+        press(&input, &[NavKey::Home, NavKey::Delete]);
+        assert_eq!(input.text(), "elo");
+        type_text(&input, "H");
+        press(&input, &[NavKey::End]);
+        type_text(&input, "!");
+        assert_eq!(input.text(), "Helo!");
+        let id = WidgetId::of(&input);
+        let ch = KeyPress::Character('x');
+        assert_eq!(
+            input.key_event(ch, ModKeys::EMPTY),
+            Some(Action::Changed(id))
+        );
+        // Nothing to delete at the end
+        let del = KeyPress::Navigation(NavKey::Delete);
+        assert_eq!(input.key_event(del, ModKeys::EMPTY), None);
+        input.set_text("a\nb");
+        assert_eq!(input.text(), "ab");
+        assert_eq!(input.cursor(), 2);
+    }
+
+    #[test]
+    fn graphemes() {
+        let input = TextInput::new("a👍🏽b");
+        press(&input, &[NavKey::Left, NavKey::Left]);
+        assert_eq!(input.cursor(), 1);
+        press(&input, &[NavKey::Delete]);
+        assert_eq!(input.text(), "ab");
+        // Combining mark joins the preceding grapheme
+        type_text(&input, "e\u{301}");
+        assert_eq!(input.text(), "ae\u{301}b");
+        assert_eq!(input.cursor(), 4);
+        press(&input, &[NavKey::Backspace]);
+        assert_eq!(input.text(), "ab");
+    }
+
+    #[test]
+    fn scrolling() -> Result<()> {
+        let input = TextInput::new("abcdefghij");
+        let grid = grid_area!([input])?;
+        let theme = Theme::default();
+        // Cursor at the end needs a column
+        assert_eq!(to_text(&grid, Dim::new(5, 1), &theme)?, "ghij \n");
+        press(&input, &[NavKey::Home, NavKey::Right]);
+        assert_eq!(to_text(&grid, Dim::new(5, 1), &theme)?, "bcdef\n");
+        press(&input, &[NavKey::Left]);
+        assert_eq!(to_text(&grid, Dim::new(5, 1), &theme)?, "abcde\n");
+        // Wide glyph at the cursor is never cut off
+        let input = TextInput::new("abcd日");
+        press(&input, &[NavKey::Left]);
+        let grid = grid_area!([input])?;
+        assert_eq!(to_text(&grid, Dim::new(4, 1), &theme)?, "cd日\n");
+        Ok(())
+    }
+
+    #[test]
+    fn mask() -> Result<()> {
+        let input = TextInput::new("pässwörd").with_mask('*');
+        let grid = grid_area!([input])?;
+        let theme = Theme::default();
+        assert_eq!(to_text(&grid, Dim::new(10, 1), &theme)?, "********  \n");
+        assert_eq!(input.text(), "pässwörd");
+        let click = MouseEvent::ButtonDown(MouseButton::Left);
+        let dim = Dim::new(10, 1);
+        input.mouse_event(click, ModKeys::EMPTY, dim, WidgetPos::new(2, 0));
+        assert_eq!(input.cursor(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn focus() {
+        let input = TextInput::new("x");
+        assert_eq!(input.style_group(), StyleGroup::Enabled);
+        assert_eq!(input.focus(FocusEvent::Offer), Some(Action::Redraw()));
+        assert_eq!(input.style_group(), StyleGroup::Focused);
+        input.disable();
+        assert_eq!(input.focus(FocusEvent::Offer), None);
+        type_text(&input, "y");
+        assert_eq!(input.text(), "x");
+    }
+
+    /// Type some text, counting bell requests
+    fn type_bells(input: &TextInput, text: &str) -> usize {
+        text.chars()
+            .filter(|ch| {
+                input.key_event(KeyPress::Character(*ch), ModKeys::EMPTY);
+                input.take_bell()
+            })
+            .count()
+    }
+
+    #[test]
+    fn max_length() {
+        let input = TextInput::new("").with_max_length(4);
+        assert_eq!(input.remaining(), Some(4));
+        assert_eq!(type_bells(&input, "a👍🏽b"), 0);
+        assert_eq!(input.remaining(), Some(1));
+        assert_eq!(type_bells(&input, "cde"), 2);
+        assert_eq!(input.text(), "a👍🏽bc");
+        assert_eq!(input.remaining(), Some(0));
+        // Editing keys are never rejected
+        press(&input, &[NavKey::Home, NavKey::Delete]);
+        assert!(!input.take_bell());
+        assert_eq!(input.remaining(), Some(1));
+        assert_eq!(type_bells(&input, "x"), 0);
+        assert_eq!(input.text(), "x👍🏽bc");
+        assert_eq!(TextInput::new("").remaining(), None);
+    }
+
+    #[test]
+    fn paste_mixed() {
+        let input = TextInput::new("12")
+            .with_max_length(6)
+            .with_filter(InputFilter::Numeric);
+        press(&input, &[NavKey::Left]);
+        let id = WidgetId::of(&input);
+        assert_eq!(input.paste("a3 4\n5b6789"), Some(Action::Changed(id)));
+        assert_eq!(input.text(), "134562");
+        assert_eq!(input.cursor(), 5);
+        assert!(input.take_bell());
+        assert!(!input.take_bell());
+        assert_eq!(input.remaining(), Some(0));
+        // Nothing fits
+        assert_eq!(input.paste("7"), None);
+        assert!(input.take_bell());
+        assert_eq!(input.text(), "134562");
+    }
+
+    #[test]
+    fn custom_filter() {
+        let input =
+            TextInput::new("").with_filter(InputFilter::custom(|c| c != 'x'));
+        assert_eq!(type_bells(&input, "axbxc"), 2);
+        assert_eq!(input.text(), "abc");
+        assert_eq!(input.remaining(), None);
+        assert_eq!(
+            input.paste("xyz"),
+            Some(Action::Changed(WidgetId::of(&input)))
+        );
+        assert!(input.take_bell());
+        assert_eq!(input.text(), "abcyz");
+    }
+
+    #[test]
+    fn persist() {
+        let input = TextInput::new("saved");
+        press(&input, &[NavKey::Left]);
+        let blob = input.save_state().unwrap();
+        let restored = TextInput::new("");
+        restored.restore_state(&blob);
+        assert_eq!(restored.text(), "saved");
+        assert_eq!(restored.cursor(), 4);
+        // Cursor inside a grapheme is moved to the end
+        let blob = StateBlob::new(STATE_VERSION)
+            .with_values(vec![2])
+            .with_text("👍");
+        restored.restore_state(&blob);
+        assert_eq!(restored.cursor(), 4);
+    }
+}
@@ -0,0 +1,827 @@
+// tree.rs
+//
+// Copyright (c) 2022  Douglas P Lau
+//
+use crate::input::{
+    Action, KeyPress, ModKeys, MouseButton, MouseEvent, NavKey,
+};
+use crate::layout::{Cells, ContentPos, Dim, LengthBound, WidgetPos};
+use crate::text::{display_width, visible_span, StyleGroup, Theme};
+use crate::{PersistState, Result, StateBlob, Widget};
+use std::cell::RefCell;
+
+/// Marker for a collapsed node
+const COLLAPSED: &str = "▸ ";
+
+/// Marker for an expanded node
+const EXPANDED: &str = "▾ ";
+
+/// Label of placeholder row shown while children are loading
+const LOADING: &str = "Loading…";
+
+/// Version of saved state representation
+const STATE_VERSION: u32 = 1;
+
+/// Node of a tree
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TreeNode {
+    /// Label of node
+    label: String,
+    /// Children of node
+    children: Children,
+}
+
+/// Children of a node
+#[derive(Clone, Debug, PartialEq)]
+enum Children {
+    /// Children are loaded
+    Loaded(Vec<TreeNode>),
+    /// Children will be fetched on first expansion
+    Lazy,
+}
+
+/// Node state within a tree
+#[derive(Clone, Debug)]
+struct Node {
+    /// Label of node
+    label: String,
+    /// Children of node
+    children: NodeChildren,
+    /// Node is expanded
+    expanded: bool,
+}
+
+/// Children of a node within a tree
+#[derive(Clone, Debug)]
+enum NodeChildren {
+    /// Children are loaded
+    Loaded(Vec<Node>),
+    /// Children will be fetched on first expansion
+    Lazy,
+    /// Children are being fetched
+    Loading,
+}
+
+/// Kind of visible row
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum RowKind {
+    /// Node without children
+    Leaf,
+    /// Collapsed node
+    Collapsed,
+    /// Expanded node
+    Expanded,
+    /// Placeholder for children being loaded
+    Loading,
+}
+
+/// Visible row of a tree
+#[derive(Clone, Debug, PartialEq)]
+struct Row {
+    /// Path of child indices from the roots
+    path: Vec<usize>,
+    /// Kind of row
+    kind: RowKind,
+    /// Text, including indentation guides and marker
+    text: String,
+    /// Column of expansion marker
+    marker: u16,
+}
+
+/// Callback to fetch the children of a lazy node
+type Loader = Box<dyn FnMut(&[usize], &str) -> Option<Vec<TreeNode>>>;
+
+/// Tree view widget
+///
+/// Visible nodes are drawn one per row, with indentation guides and
+/// expansion markers.  A node is selected with the Up / Down keys or by
+/// clicking.  Left / Right collapse and expand nodes, as do Enter and
+/// clicking on the marker.
+///
+/// Nodes created with [TreeNode::lazy] have their children fetched by a
+/// loader callback when first expanded.  If the loader returns `None`, a
+/// placeholder row is shown until the children are provided with
+/// [set_children](Tree::set_children).
+///
+/// Nodes are identified by a path of child indices from the roots.
+pub struct Tree {
+    /// Root nodes
+    roots: RefCell<Vec<Node>>,
+    /// Path of selected node
+    selected: RefCell<Vec<usize>>,
+    /// Visible rows (`None` when expansion changes)
+    rows: RefCell<Option<Vec<Row>>>,
+    /// Loader for lazy children
+    loader: RefCell<Option<Loader>>,
+}
+
+impl Default for Children {
+    fn default() -> Self {
+        Children::Loaded(vec![])
+    }
+}
+
+impl TreeNode {
+    /// Create a new tree node without children
+    pub fn new(label: &str) -> Self {
+        TreeNode {
+            label: label.to_string(),
+            children: Children::default(),
+        }
+    }
+
+    /// Create a tree node with children fetched on first expansion
+    pub fn lazy(label: &str) -> Self {
+        TreeNode {
+            label: label.to_string(),
+            children: Children::Lazy,
+        }
+    }
+
+    /// Add a child node
+    pub fn with_child(mut self, child: TreeNode) -> Self {
+        match &mut self.children {
+            Children::Loaded(children) => children.push(child),
+            _ => self.children = Children::Loaded(vec![child]),
+        }
+        self
+    }
+
+    /// Add child nodes
+    pub fn with_children(self, children: Vec<TreeNode>) -> Self {
+        children
+            .into_iter()
+            .fold(self, |node, c| node.with_child(c))
+    }
+
+    /// Get the label
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+impl From<TreeNode> for Node {
+    fn from(node: TreeNode) -> Self {
+        let children = match node.children {
+            Children::Loaded(children) => NodeChildren::Loaded(
+                children.into_iter().map(Node::from).collect(),
+            ),
+            Children::Lazy => NodeChildren::Lazy,
+        };
+        Node {
+            label: node.label,
+            children,
+            expanded: false,
+        }
+    }
+}
+
+impl Node {
+    /// Get the kind of row for the node
+    fn kind(&self) -> RowKind {
+        match (&self.children, self.expanded) {
+            (NodeChildren::Loaded(c), _) if c.is_empty() => RowKind::Leaf,
+            (_, false) => RowKind::Collapsed,
+            (_, true) => RowKind::Expanded,
+        }
+    }
+}
+
+/// Find a node by path
+fn node_mut<'a>(nodes: &'a mut [Node], path: &[usize]) -> Option<&'a mut Node> {
+    let (first, rest) = path.split_first()?;
+    let node = nodes.get_mut(*first)?;
+    if rest.is_empty() {
+        return Some(node);
+    }
+    match &mut node.children {
+        NodeChildren::Loaded(children) => node_mut(children, rest),
+        _ => None,
+    }
+}
+
+/// Collect paths of expanded nodes, parents before children
+fn expanded_paths(
+    nodes: &[Node],
+    path: &mut Vec<usize>,
+    paths: &mut Vec<Vec<usize>>,
+) {
+    for (i, node) in nodes.iter().enumerate() {
+        path.push(i);
+        if node.expanded {
+            paths.push(path.clone());
+        }
+        if let NodeChildren::Loaded(children) = &node.children {
+            expanded_paths(children, path, paths);
+        }
+        path.pop();
+    }
+}
+
+/// Decode paths saved as a length followed by indices
+fn decode_paths(values: &[u64]) -> Vec<Vec<usize>> {
+    let mut paths = vec![];
+    let mut values = values.iter().map(|v| usize::try_from(*v).ok());
+    while let Some(Some(len)) = values.next() {
+        let path: Option<Vec<usize>> = values.by_ref().take(len).collect();
+        match path {
+            Some(path) if path.len() == len => paths.push(path),
+            _ => break,
+        }
+    }
+    paths
+}
+
+/// Get the indentation guides for a row
+///
+/// * `more`: Whether each ancestor below the roots has following siblings
+/// * `last`: Row is the last of its siblings
+/// * `depth`: Depth of the row (0 for roots)
+fn guides(more: &[bool], last: bool, depth: usize) -> String {
+    let mut text = String::new();
+    if depth > 0 {
+        for m in more {
+            text.push_str(if *m { "│ " } else { "  " });
+        }
+        text.push_str(if last { "└ " } else { "├ " });
+    }
+    text
+}
+
+/// Flatten visible nodes into rows
+///
+/// * `nodes`: Sibling nodes
+/// * `path`: Path of parent node
+/// * `more`: Ancestor guides of the siblings
+/// * `rows`: Visible rows
+fn flatten(
+    nodes: &[Node],
+    path: &mut Vec<usize>,
+    more: &mut Vec<bool>,
+    rows: &mut Vec<Row>,
+) {
+    let depth = path.len();
+    for (i, node) in nodes.iter().enumerate() {
+        let last = i + 1 == nodes.len();
+        path.push(i);
+        let mut text = guides(more, last, depth);
+        let marker = display_width(&text) as u16;
+        let kind = node.kind();
+        match kind {
+            RowKind::Collapsed => text.push_str(COLLAPSED),
+            RowKind::Expanded => text.push_str(EXPANDED),
+            _ => (),
+        }
+        text.push_str(&node.label);
+        rows.push(Row {
+            path: path.clone(),
+            kind,
+            text,
+            marker,
+        });
+        if node.expanded {
+            if depth > 0 {
+                more.push(!last);
+            }
+            match &node.children {
+                NodeChildren::Loaded(children) => {
+                    flatten(children, path, more, rows)
+                }
+                NodeChildren::Loading => {
+                    let mut text = guides(more, true, depth + 1);
+                    let marker = display_width(&text) as u16;
+                    text.push_str(LOADING);
+                    rows.push(Row {
+                        path: path.clone(),
+                        kind: RowKind::Loading,
+                        text,
+                        marker,
+                    });
+                }
+                NodeChildren::Lazy => (),
+            }
+            if depth > 0 {
+                more.pop();
+            }
+        }
+        path.pop();
+    }
+}
+
+impl Tree {
+    /// Create a new tree widget
+    ///
+    /// * `roots`: Root nodes
+    ///
+    /// All nodes are initially collapsed, with the first root selected.
+    pub fn new(roots: Vec<TreeNode>) -> Self {
+        let selected = if roots.is_empty() { vec![] } else { vec![0] };
+        let roots = roots.into_iter().map(Node::from).collect();
+        Tree {
+            roots: RefCell::new(roots),
+            selected: RefCell::new(selected),
+            rows: RefCell::new(None),
+            loader: RefCell::new(None),
+        }
+    }
+
+    /// Set the loader for lazy children
+    ///
+    /// The loader is called with the path and label of a lazy node when it
+    /// is first expanded.
+    pub fn with_loader<F>(self, loader: F) -> Self
+    where
+        F: FnMut(&[usize], &str) -> Option<Vec<TreeNode>> + 'static,
+    {
+        *self.loader.borrow_mut() = Some(Box::new(loader));
+        self
+    }
+
+    /// Get the path of the selected node
+    ///
+    /// The selected node may be hidden within a collapsed ancestor.
+    pub fn selected(&self) -> Vec<usize> {
+        self.selected.borrow().clone()
+    }
+
+    /// Select a node
+    pub fn select(&self, path: &[usize]) {
+        *self.selected.borrow_mut() = path.to_vec();
+    }
+
+    /// Check if a node is expanded
+    pub fn is_expanded(&self, path: &[usize]) -> bool {
+        let mut roots = self.roots.borrow_mut();
+        node_mut(&mut roots, path).is_some_and(|n| n.expanded)
+    }
+
+    /// Expand or collapse a node
+    ///
+    /// Expanding a lazy node fetches its children with the loader.
+    pub fn set_expanded(&self, path: &[usize], expanded: bool) {
+        let lazy = {
+            let mut roots = self.roots.borrow_mut();
+            let node = match node_mut(&mut roots, path) {
+                Some(node) => node,
+                None => return,
+            };
+            node.expanded = expanded;
+            match (&node.children, expanded) {
+                (NodeChildren::Lazy, true) => {
+                    node.children = NodeChildren::Loading;
+                    Some(node.label.clone())
+                }
+                _ => None,
+            }
+        };
+        *self.rows.borrow_mut() = None;
+        if let Some(label) = lazy {
+            let children = match &mut *self.loader.borrow_mut() {
+                Some(loader) => loader(path, &label),
+                None => Some(vec![]),
+            };
+            if let Some(children) = children {
+                self.set_children(path, children);
+            }
+        }
+    }
+
+    /// Set the children of a node
+    ///
+    /// This completes loading of a lazy node.
+    pub fn set_children(&self, path: &[usize], children: Vec<TreeNode>) {
+        let mut roots = self.roots.borrow_mut();
+        if let Some(node) = node_mut(&mut roots, path) {
+            let children = children.into_iter().map(Node::from).collect();
+            node.children = NodeChildren::Loaded(children);
+            *self.rows.borrow_mut() = None;
+        }
+    }
+
+    /// Check if a node exists
+    fn contains(&self, path: &[usize]) -> bool {
+        let mut roots = self.roots.borrow_mut();
+        node_mut(&mut roots, path).is_some()
+    }
+
+    /// Call a function with the visible rows
+    fn with_rows<R>(&self, f: impl FnOnce(&[Row]) -> R) -> R {
+        let mut rows = self.rows.borrow_mut();
+        let rows = rows.get_or_insert_with(|| {
+            let mut rows = vec![];
+            flatten(&self.roots.borrow(), &mut vec![], &mut vec![], &mut rows);
+            rows
+        });
+        f(rows)
+    }
+
+    /// Get the index of the visible row of the selected node
+    ///
+    /// When the selected node is hidden, this is its nearest visible
+    /// ancestor.
+    fn selected_row(&self, rows: &[Row]) -> Option<usize> {
+        let selected = self.selected.borrow();
+        rows.iter()
+            .enumerate()
+            .filter(|(_, r)| r.kind != RowKind::Loading)
+            .filter(|(_, r)| selected.starts_with(&r.path))
+            .max_by_key(|(_, r)| r.path.len())
+            .map(|(i, _)| i)
+    }
+
+    /// Move the selection up or down by visible rows
+    fn move_selection(&self, down: bool) -> Option<Action> {
+        let path = self.with_rows(|rows| {
+            let row = self.selected_row(rows)?;
+            let mut candidates: Box<dyn Iterator<Item = &Row>> = if down {
+                Box::new(rows[row + 1..].iter())
+            } else {
+                Box::new(rows[..row].iter().rev())
+            };
+            candidates
+                .find(|r| r.kind != RowKind::Loading)
+                .map(|r| r.path.clone())
+        })?;
+        self.select(&path);
+        Some(Action::Redraw())
+    }
+
+    /// Get the path and kind of the selected visible row
+    fn selected_visible(&self) -> Option<(Vec<usize>, RowKind)> {
+        self.with_rows(|rows| {
+            let row = &rows[self.selected_row(rows)?];
+            Some((row.path.clone(), row.kind))
+        })
+    }
+
+    /// Handle a left key press
+    fn left(&self) -> Option<Action> {
+        let (path, kind) = self.selected_visible()?;
+        if kind == RowKind::Expanded {
+            self.set_expanded(&path, false);
+        } else if path.len() > 1 {
+            self.select(&path[..path.len() - 1]);
+        } else {
+            return None;
+        }
+        Some(Action::Redraw())
+    }
+
+    /// Handle a right key press
+    fn right(&self) -> Option<Action> {
+        let (path, kind) = self.selected_visible()?;
+        match kind {
+            RowKind::Collapsed => self.set_expanded(&path, true),
+            RowKind::Expanded => {
+                let mut child = path.clone();
+                child.push(0);
+                let loaded = self.with_rows(|rows| {
+                    rows.iter()
+                        .any(|r| r.path == child && r.kind != RowKind::Loading)
+                });
+                if !loaded {
+                    return None;
+                }
+                self.select(&child);
+            }
+            _ => return None,
+        }
+        Some(Action::Redraw())
+    }
+
+    /// Toggle expansion of the selected visible row
+    fn toggle(&self) -> Option<Action> {
+        let (path, kind) = self.selected_visible()?;
+        match kind {
+            RowKind::Collapsed => self.set_expanded(&path, true),
+            RowKind::Expanded => self.set_expanded(&path, false),
+            _ => return None,
+        }
+        Some(Action::Redraw())
+    }
+}
+
+impl PersistState for Tree {
+    /// Save the selected path and expanded nodes
+    fn save_state(&self) -> Option<StateBlob> {
+        let mut paths = vec![self.selected()];
+        expanded_paths(&self.roots.borrow(), &mut vec![], &mut paths);
+        let mut values = vec![];
+        for path in paths {
+            values.push(path.len() as u64);
+            values.extend(path.iter().map(|i| *i as u64));
+        }
+        Some(StateBlob::new(STATE_VERSION).with_values(values))
+    }
+
+    /// Restore the selected path and expanded nodes
+    ///
+    /// Paths to nodes which no longer exist are ignored.
+    fn restore_state(&self, blob: &StateBlob) {
+        if blob.version() != STATE_VERSION {
+            return;
+        }
+        let mut paths = decode_paths(blob.values()).into_iter();
+        let selected = paths.next();
+        for path in paths {
+            self.set_expanded(&path, true);
+        }
+        if let Some(path) = selected.filter(|p| self.contains(p)) {
+            self.select(&path);
+        }
+    }
+}
+
+impl Widget for Tree {
+    /// Get the persistent state
+    fn persist(&self) -> Option<&dyn PersistState> {
+        Some(self)
+    }
+
+    /// Get the width bounds
+    fn width_bounds(&self, _theme: &Theme) -> LengthBound {
+        let w = self.with_rows(|rows| {
+            rows.iter()
+                .map(|r| display_width(&r.text))
+                .max()
+                .unwrap_or(0)
+        });
+        LengthBound::new(w as u16..)
+    }
+
+    /// Get the height bounds
+    fn height_bounds(&self, _theme: &Theme, _width: u16) -> LengthBound {
+        let rows = self.with_rows(|rows| rows.len());
+        let rows = rows.try_into().unwrap_or(u16::MAX);
+        LengthBound::new(rows..=rows)
+    }
+
+    /// Draw the widget
+    fn draw(&self, cells: &mut Cells, offset: ContentPos) -> Result<()> {
+        let width = cells.width();
+        let height = usize::from(cells.height());
+        let normal = cells.theme().style(self.style_group());
+        let focused = cells.theme().style(StyleGroup::Focused);
+        self.with_rows(|rows| {
+            let selected = self.selected_row(rows);
+            let visible = rows.iter().enumerate().skip(usize::from(offset.row));
+            for (row, (i, r)) in visible.take(height).enumerate() {
+                let row = row as u16; // limited to u16 by take(height)
+                let span = visible_span(&r.text, offset.col, width);
+                if let Some((col, span)) = span {
+                    if selected == Some(i) {
+                        cells.set_style(focused)?;
+                    }
+                    cells.move_to(col, row)?;
+                    cells.print_str(span)?;
+                    if selected == Some(i) {
+                        cells.set_style(normal)?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Check whether the tree can accept focus
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    /// Get the row of the selected node, to keep it visible
+    fn cursor_row(&self) -> Option<u16> {
+        let row = self.with_rows(|rows| self.selected_row(rows))?;
+        row.try_into().ok()
+    }
+
+    /// Handle key events
+    fn key_event(&self, key: KeyPress, mods: ModKeys) -> Option<Action> {
+        match (key, mods) {
+            (KeyPress::Navigation(nav), ModKeys::EMPTY) => match nav {
+                NavKey::Up => self.move_selection(false),
+                NavKey::Down => self.move_selection(true),
+                NavKey::Left => self.left(),
+                NavKey::Right => self.right(),
+                NavKey::Enter => self.toggle(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Handle mouse events
+    fn mouse_event(
+        &self,
+        mev: MouseEvent,
+        _mods: ModKeys,
+        _dim: Dim,
+        pos: WidgetPos,
+    ) -> Option<Action> {
+        if mev != MouseEvent::ButtonDown(MouseButton::Left) {
+            return None;
+        }
+        let (path, kind, marker) = self.with_rows(|rows| {
+            let row = rows.get(usize::from(pos.row))?;
+            Some((row.path.clone(), row.kind, row.marker))
+        })?;
+        let on_marker = pos.col >= marker && pos.col < marker + 2;
+        match kind {
+            RowKind::Loading => return None,
+            RowKind::Collapsed if on_marker => self.set_expanded(&path, true),
+            RowKind::Expanded if on_marker => self.set_expanded(&path, false),
+            _ => self.select(&path),
+        }
+        Some(Action::Redraw())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+
+    /// Three-level fixture
+    fn fixture() -> Tree {
+        let a = TreeNode::new("a")
+            .with_child(TreeNode::new("a1").with_child(TreeNode::new("a1x")))
+            .with_child(TreeNode::new("a2"));
+        let b = TreeNode::new("b").with_children(vec![
+            TreeNode::new("b1"),
+            TreeNode::new("b2")
+                .with_child(TreeNode::new("b2x"))
+                .with_child(TreeNode::new("b2y")),
+        ]);
+        Tree::new(vec![a, b, TreeNode::new("c")])
+    }
+
+    /// Get the text of visible rows
+    fn texts(tree: &Tree) -> Vec<String> {
+        tree.with_rows(|rows| rows.iter().map(|r| r.text.clone()).collect())
+    }
+
+    /// Press a sequence of navigation keys
+    fn press(tree: &Tree, keys: &[NavKey]) {
+        for key in keys {
+            tree.key_event(KeyPress::Navigation(*key), ModKeys::EMPTY);
+        }
+    }
+
+    #[test]
+    fn flatten_collapsed() {
+        let tree = fixture();
+        assert_eq!(texts(&tree), ["▸ a", "▸ b", "c"]);
+        let theme = Theme::default();
+        assert_eq!(tree.height_bounds(&theme, 10), LengthBound::new(3..=3));
+        assert_eq!(tree.width_bounds(&theme).minimum(), 3);
+    }
+
+    #[test]
+    fn flatten_guides() {
+        let tree = fixture();
+        for path in [&[0][..], &[0, 0], &[1], &[1, 1]] {
+            tree.set_expanded(path, true);
+        }
+        assert_eq!(
+            texts(&tree),
+            [
+                "▾ a",
+                "├ ▾ a1",
+                "│ └ a1x",
+                "└ a2",
+                "▾ b",
+                "├ b1",
+                "└ ▾ b2",
+                "  ├ b2x",
+                "  └ b2y",
+                "c",
+            ]
+        );
+        let paths: Vec<Vec<usize>> = tree
+            .with_rows(|rows| rows.iter().map(|r| r.path.clone()).collect());
+        assert_eq!(paths[2], [0, 0, 0]);
+        assert_eq!(paths[8], [1, 1, 1]);
+        assert_eq!(paths[9], [2]);
+        let theme = Theme::default();
+        assert_eq!(tree.height_bounds(&theme, 10), LengthBound::new(10..=10));
+        // Flattening is recomputed on collapse
+        tree.set_expanded(&[0], false);
+        assert_eq!(texts(&tree)[..2], ["▸ a", "▾ b"]);
+    }
+
+    #[test]
+    fn keyboard_navigation() {
+        use NavKey::*;
+        let tree = fixture();
+        press(&tree, &[Down, Right]);
+        assert_eq!(tree.selected(), [1]);
+        assert!(tree.is_expanded(&[1]));
+        press(&tree, &[Right, Down]);
+        assert_eq!(tree.selected(), [1, 1]);
+        press(&tree, &[Enter, Down, Down]);
+        assert_eq!(tree.selected(), [1, 1, 1]);
+        // Down at the last row moves to the next root
+        press(&tree, &[Down]);
+        assert_eq!(tree.selected(), [2]);
+        press(&tree, &[Down]);
+        assert_eq!(tree.selected(), [2]);
+        press(&tree, &[Up, Left]);
+        assert_eq!(tree.selected(), [1, 1]);
+        press(&tree, &[Left]);
+        assert!(!tree.is_expanded(&[1, 1]));
+        press(&tree, &[Left, Left]);
+        assert_eq!(tree.selected(), [1]);
+        assert!(!tree.is_expanded(&[1]));
+        press(&tree, &[Up, Up]);
+        assert_eq!(tree.selected(), [0]);
+    }
+
+    #[test]
+    fn state_survives_collapse() {
+        let tree = fixture();
+        tree.set_expanded(&[1], true);
+        tree.set_expanded(&[1, 1], true);
+        tree.select(&[1, 1, 0]);
+        tree.set_expanded(&[1], false);
+        // Hidden selection is shown on the collapsed ancestor
+        assert_eq!(tree.with_rows(|rows| tree.selected_row(rows)), Some(1));
+        assert_eq!(tree.selected(), [1, 1, 0]);
+        tree.key_event(KeyPress::Navigation(NavKey::Enter), ModKeys::EMPTY);
+        assert!(tree.is_expanded(&[1, 1]));
+        assert_eq!(tree.selected(), [1, 1, 0]);
+        assert_eq!(tree.with_rows(|rows| tree.selected_row(rows)), Some(4));
+    }
+
+    #[test]
+    fn lazy_children() {
+        let calls = Rc::new(RefCell::new(vec![]));
+        let c = Rc::clone(&calls);
+        let tree = Tree::new(vec![TreeNode::lazy("dir"), TreeNode::lazy("x")])
+            .with_loader(move |path, label| {
+                c.borrow_mut().push((path.to_vec(), label.to_string()));
+                match label {
+                    "dir" => None,
+                    _ => Some(vec![TreeNode::new("y")]),
+                }
+            });
+        assert_eq!(texts(&tree), ["▸ dir", "▸ x"]);
+        tree.set_expanded(&[0], true);
+        assert_eq!(texts(&tree), ["▾ dir", "└ Loading…", "▸ x"]);
+        // Placeholder is skipped by navigation
+        press(&tree, &[NavKey::Down]);
+        assert_eq!(tree.selected(), [1]);
+        tree.set_children(&[0], vec![TreeNode::new("f1"), TreeNode::new("f2")]);
+        assert_eq!(texts(&tree), ["▾ dir", "├ f1", "└ f2", "▸ x"]);
+        tree.set_expanded(&[1], true);
+        tree.set_expanded(&[1], false);
+        tree.set_expanded(&[1], true);
+        assert_eq!(texts(&tree)[3..], ["▾ x", "└ y"]);
+        assert_eq!(
+            *calls.borrow(),
+            [(vec![0], "dir".to_string()), (vec![1], "x".to_string())]
+        );
+    }
+
+    #[test]
+    fn click() {
+        let tree = fixture();
+        tree.set_expanded(&[0], true);
+        let down = MouseEvent::ButtonDown(MouseButton::Left);
+        let dim = Dim::new(10, 10);
+        // Click on label selects
+        tree.mouse_event(down, ModKeys::EMPTY, dim, WidgetPos::new(4, 1));
+        assert_eq!(tree.selected(), [0, 0]);
+        assert!(!tree.is_expanded(&[0, 0]));
+        // Click on marker expands
+        tree.mouse_event(down, ModKeys::EMPTY, dim, WidgetPos::new(2, 1));
+        assert!(tree.is_expanded(&[0, 0]));
+        tree.mouse_event(down, ModKeys::EMPTY, dim, WidgetPos::new(0, 0));
+        assert!(!tree.is_expanded(&[0]));
+        assert_eq!(texts(&tree), ["▸ a", "▸ b", "c"]);
+        let none =
+            tree.mouse_event(down, ModKeys::EMPTY, dim, WidgetPos::new(0, 5));
+        assert_eq!(none, None);
+    }
+
+    #[test]
+    fn persist() {
+        let tree = fixture();
+        tree.set_expanded(&[1], true);
+        tree.set_expanded(&[1, 1], true);
+        tree.select(&[1, 1, 0]);
+        let blob = tree.save_state().unwrap();
+        assert_eq!(blob.values(), [3, 1, 1, 0, 1, 1, 2, 1, 1]);
+        let restored = fixture();
+        restored.restore_state(&blob);
+        assert_eq!(restored.selected(), [1, 1, 0]);
+        assert_eq!(texts(&restored), texts(&tree));
+        // Stale paths past the current nodes are ignored
+        let tree = Tree::new(vec![TreeNode::new("a"), TreeNode::new("b")]);
+        tree.restore_state(&blob);
+        assert_eq!(tree.selected(), [0]);
+        assert_eq!(texts(&tree), ["a", "b"]);
+        // Truncated values are ignored
+        let blob = StateBlob::new(STATE_VERSION).with_values(vec![2, 1, 9, 5]);
+        restored.restore_state(&blob);
+        assert_eq!(restored.selected(), [1, 1, 0]);
+    }
+}
@@ -0,0 +1,93 @@
+use semtext::layout::{Cells, ContentPos, Dim};
+use semtext::text::{
+    Color, CustomStyle, Intensity, StyleGroup, TextStyle, Theme,
+};
+use semtext::{grid_area, Error, Screen, Widget};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Allocator which counts allocations
+struct Counting;
+
+thread_local! {
+    /// Allocations made by the current thread
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Get the number of allocations made by the current thread
+fn allocations() -> usize {
+    ALLOCATIONS.with(|a| a.get())
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[test]
+fn custom_lookup_no_alloc() {
+    let mut theme = Theme::default();
+    let style =
+        TextStyle::default().with_foreground(Color::Green(Intensity::Bright));
+    theme.define_custom("diff-added", style);
+    let handle = theme.custom_handle("diff-added").unwrap();
+    let before = allocations();
+    for _ in 0..100 {
+        assert_eq!(theme.custom_style(handle), style);
+        assert_eq!(theme.style(StyleGroup::Custom(handle)), style);
+        assert_eq!(theme.custom("diff-added"), style);
+    }
+    assert_eq!(allocations(), before);
+}
+
+/// Widget which looks up a custom style while drawing
+struct Added {
+    /// Custom style handle
+    handle: CustomStyle,
+    /// Allocations made by the lookups, once drawn
+    allocs: Cell<Option<usize>>,
+}
+
+impl Widget for Added {
+    fn draw(
+        &self,
+        cells: &mut Cells,
+        _offset: ContentPos,
+    ) -> Result<(), Error> {
+        let before = allocations();
+        let mut style = TextStyle::default();
+        for _ in 0..100 {
+            style = cells.theme().style(StyleGroup::Custom(self.handle));
+        }
+        self.allocs.set(Some(allocations() - before));
+        cells.set_style(style)?;
+        cells.print_text("+", ContentPos::default())
+    }
+}
+
+#[test]
+fn custom_draw_no_alloc() -> Result<(), Error> {
+    let mut theme = Theme::default();
+    let style =
+        TextStyle::default().with_foreground(Color::Green(Intensity::Bright));
+    let handle = theme.define_custom("diff-added", style);
+    let mut screen = Screen::headless(Dim::new(4, 1), theme);
+    let added = Added {
+        handle,
+        allocs: Cell::new(None),
+    };
+    let grid = grid_area!([added])?;
+    screen.step_with_events(&grid, [])?;
+    assert_eq!(added.allocs.get(), Some(0));
+    assert_eq!(screen.text(), "+   \n");
+    Ok(())
+}
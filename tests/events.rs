@@ -0,0 +1,54 @@
+use semtext::input::{
+    Action, Event, KeyPress, ModKeys, MouseButton, MouseEvent, NavKey, WidgetId,
+};
+use semtext::layout::{Dim, ScreenPos};
+use semtext::text::Theme;
+use semtext::widget::{Button, Label};
+use semtext::{grid_area, Screen, Widget};
+
+/// Mouse click events at a position
+fn click(col: u16, row: u16) -> [Event; 2] {
+    let pos = ScreenPos::new(col, row);
+    [
+        Event::Mouse(
+            MouseEvent::ButtonDown(MouseButton::Left),
+            ModKeys::EMPTY,
+            pos,
+        ),
+        Event::Mouse(
+            MouseEvent::ButtonUp(MouseButton::Left),
+            ModKeys::EMPTY,
+            pos,
+        ),
+    ]
+}
+
+#[test]
+fn click_buttons() {
+    let mut screen = Screen::headless(Dim::new(8, 1), Theme::default());
+    let a = Button::new(Label::new("A"));
+    let b = Button::new(Label::new("B"));
+    let grid = grid_area!([a b]).unwrap();
+    let mut events = click(6, 0).to_vec();
+    events.extend(click(1, 0));
+    let actions = screen.step_with_events(&grid, events).unwrap();
+    let activated = |w: &dyn Widget| Action::Activated(WidgetId::of(w));
+    assert_eq!(actions, [activated(&b), activated(&a)]);
+    assert_eq!(screen.text(), " A   B  \n");
+}
+
+#[test]
+fn keys_and_resize() {
+    let mut screen = Screen::headless(Dim::new(5, 2), Theme::default());
+    let a = Label::new("Hello");
+    let grid = grid_area!([a]).unwrap();
+    let esc = Event::Key(KeyPress::Navigation(NavKey::Esc), ModKeys::EMPTY);
+    let resize = Event::Resize(Dim::new(7, 1));
+    let actions = screen.step_with_events(&grid, [resize, esc]).unwrap();
+    assert_eq!(actions, [Action::Quit()]);
+    assert_eq!(screen.text(), "Hello  \n");
+    screen.set_notify_resize(true);
+    let resize = Event::Resize(Dim::new(6, 1));
+    let actions = screen.step_with_events(&grid, [resize]).unwrap();
+    assert_eq!(actions, [Action::Resize(Dim::new(6, 1))]);
+}
@@ -0,0 +1,27 @@
+use std::process::Command;
+
+/// Feature sets which must build on their own
+const FEATURES: &[&str] = &[
+    "",
+    "textwrap",
+    "markdown",
+    "widgets-extra",
+    "forms",
+    "serde",
+];
+
+#[test]
+fn feature_subsets() {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let target = concat!(env!("CARGO_TARGET_TMPDIR"), "/features");
+    for features in FEATURES {
+        let status = Command::new(&cargo)
+            .args(["check", "--lib", "--quiet", "--no-default-features"])
+            .args(["--features", features])
+            .args(["--target-dir", target])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .status()
+            .expect("cargo check");
+        assert!(status.success(), "features: {:?}", features);
+    }
+}
@@ -0,0 +1,7 @@
+#[test]
+fn grid_area_macro() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/grid-valid.rs");
+    t.compile_fail("tests/ui/grid-ragged.rs");
+    t.compile_fail("tests/ui/grid-ragged-first.rs");
+}
@@ -0,0 +1,10 @@
+use semtext::grid_area;
+use semtext::widget::Label;
+
+fn main() {
+    let a = Label::new("A");
+    let _ = grid_area!(
+        [a]
+        [a .]
+    );
+}
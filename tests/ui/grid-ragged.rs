@@ -0,0 +1,12 @@
+use semtext::grid_area;
+use semtext::widget::Label;
+
+fn main() {
+    let a = Label::new("A");
+    let b = Label::new("B");
+    let _ = grid_area!(
+        [a a b]
+        [a a b]
+        [. b]
+    );
+}
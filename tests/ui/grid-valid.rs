@@ -0,0 +1,14 @@
+use semtext::widget::{Label, Spacer};
+use semtext::{grid_area, Widget};
+
+fn main() {
+    let a = Label::new("Label").into_button();
+    let b = Spacer::default();
+    grid_area!(
+        [. a a]
+        [b a a]
+        [b . .]
+    )
+    .unwrap();
+    grid_area!([a]).unwrap();
+}